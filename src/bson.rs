@@ -31,6 +31,8 @@ use serde_json::{json, Value};
 
 pub use crate::document::Document;
 use crate::{
+    display::{DisplayOptions, Formatted},
+    extjson,
     oid::{self, ObjectId},
     spec::{BinarySubtype, ElementType},
     Decimal128,
@@ -51,6 +53,12 @@ pub enum Bson {
     Boolean(bool),
     /// Null value
     Null,
+    /// Regular expression
+    RegularExpression(Regex),
+    /// JavaScript code
+    JavaScriptCode(String),
+    /// JavaScript code w/ scope
+    JavaScriptCodeWithScope(JavaScriptCodeWithScope),
     /// 32-bit signed integer
     Int32(i32),
     /// 64-bit signed integer
@@ -63,10 +71,33 @@ pub enum Bson {
     Timestamp(Timestamp),
     /// Binary data
     Binary(Binary),
+    /// [ObjectId](http://dochub.mongodb.org/core/objectids)
+    ObjectId(oid::ObjectId),
     /// UTC datetime
     DateTime(crate::DateTime),
+    /// Symbol (Deprecated)
+    Symbol(String),
     /// [128-bit decimal floating point](https://github.com/mongodb/specifications/blob/master/source/bson-decimal128/decimal128.rst)
     Decimal128(Decimal128),
+    /// Undefined value (Deprecated)
+    Undefined,
+    /// Max key
+    MaxKey,
+    /// Min key
+    MinKey,
+    /// DBPointer (Deprecated)
+    DbPointer(DbPointer),
+    /// An element whose type tag is not recognized by this crate, kept as its undecoded value
+    /// bytes. These are only produced when decoding with
+    /// [`DeserializerOptions::unknown_elements`](crate::DeserializerOptions::unknown_elements)
+    /// enabled or with a [codec](crate::codec) registered for the tag, and are written back out
+    /// unchanged.
+    Unknown {
+        /// The element type tag.
+        tag: u8,
+        /// The raw bytes of the value, not including the tag or key.
+        bytes: Vec<u8>,
+    },
 }
 
 /// Alias for `Vec<Bson>`.
@@ -79,37 +110,8 @@ impl Default for Bson {
 }
 
 impl Display for Bson {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Bson::Double(f) => write!(fmt, "{}", f),
-            Bson::String(ref s) => write!(fmt, "\"{}\"", s),
-            Bson::Array(ref vec) => {
-                fmt.write_str("[")?;
-
-                let mut first = true;
-                for bson in vec {
-                    if !first {
-                        fmt.write_str(", ")?;
-                    }
-
-                    write!(fmt, "{}", bson)?;
-                    first = false;
-                }
-
-                fmt.write_str("]")
-            }
-            Bson::Document(ref doc) => write!(fmt, "{}", doc),
-            Bson::Boolean(b) => write!(fmt, "{}", b),
-            Bson::Null => write!(fmt, "null"),
-            Bson::Int32(i) => write!(fmt, "{}", i),
-            Bson::Int64(i) => write!(fmt, "{}", i),
-            Bson::UInt32(i) => write!(fmt, "{}", i),
-            Bson::UInt64(i) => write!(fmt, "{}", i),
-            Bson::Timestamp(ref x) => write!(fmt, "{}", x),
-            Bson::Binary(ref x) => write!(fmt, "{}", x),
-            Bson::DateTime(date_time) => write!(fmt, "DateTime(\"{}\")", date_time),
-            Bson::Decimal128(ref d) => write!(fmt, "{}", d),
-        }
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.display_with_options(Default::default()), fmt)
     }
 }
 
@@ -126,14 +128,30 @@ impl Debug for Bson {
             Bson::Document(ref doc) => Debug::fmt(doc, fmt),
             Bson::Boolean(b) => fmt.debug_tuple("Boolean").field(&b).finish(),
             Bson::Null => write!(fmt, "Null"),
+            Bson::RegularExpression(ref regex) => Debug::fmt(regex, fmt),
+            Bson::JavaScriptCode(ref code) => {
+                fmt.debug_tuple("JavaScriptCode").field(code).finish()
+            }
+            Bson::JavaScriptCodeWithScope(ref code) => Debug::fmt(code, fmt),
             Bson::Int32(i) => fmt.debug_tuple("Int32").field(&i).finish(),
             Bson::Int64(i) => fmt.debug_tuple("Int64").field(&i).finish(),
             Bson::UInt32(i) => fmt.debug_tuple("UInt32").field(&i).finish(),
             Bson::UInt64(i) => fmt.debug_tuple("UInt64").field(&i).finish(),
             Bson::Timestamp(ref t) => Debug::fmt(t, fmt),
             Bson::Binary(ref b) => Debug::fmt(b, fmt),
+            Bson::ObjectId(ref id) => Debug::fmt(id, fmt),
             Bson::DateTime(ref date_time) => Debug::fmt(date_time, fmt),
+            Bson::Symbol(ref sym) => fmt.debug_tuple("Symbol").field(sym).finish(),
             Bson::Decimal128(ref d) => Debug::fmt(d, fmt),
+            Bson::Undefined => write!(fmt, "Undefined"),
+            Bson::MinKey => write!(fmt, "MinKey"),
+            Bson::MaxKey => write!(fmt, "MaxKey"),
+            Bson::DbPointer(ref p) => Debug::fmt(p, fmt),
+            Bson::Unknown { tag, ref bytes } => fmt
+                .debug_struct("Unknown")
+                .field("tag", &tag)
+                .field("bytes", bytes)
+                .finish(),
         }
     }
 }
@@ -174,6 +192,24 @@ impl From<bool> for Bson {
     }
 }
 
+impl From<Regex> for Bson {
+    fn from(regex: Regex) -> Bson {
+        Bson::RegularExpression(regex)
+    }
+}
+
+impl From<JavaScriptCodeWithScope> for Bson {
+    fn from(code_with_scope: JavaScriptCodeWithScope) -> Bson {
+        Bson::JavaScriptCodeWithScope(code_with_scope)
+    }
+}
+
+impl From<DbPointer> for Bson {
+    fn from(p: DbPointer) -> Bson {
+        Bson::DbPointer(p)
+    }
+}
+
 impl From<Binary> for Bson {
     fn from(binary: Binary) -> Bson {
         Bson::Binary(binary)
@@ -186,6 +222,18 @@ impl From<Timestamp> for Bson {
     }
 }
 
+impl From<oid::ObjectId> for Bson {
+    fn from(a: oid::ObjectId) -> Bson {
+        Bson::ObjectId(a)
+    }
+}
+
+impl From<Decimal128> for Bson {
+    fn from(d: Decimal128) -> Bson {
+        Bson::Decimal128(d)
+    }
+}
+
 impl<T> From<&T> for Bson
 where
     T: Clone + Into<Bson>,
@@ -298,117 +346,118 @@ impl From<Bson> for Value {
 impl Bson {
     /// Converts the Bson value into its [relaxed extended JSON representation](https://docs.mongodb.com/manual/reference/mongodb-extended-json/).
     ///
-    /// Note: If this method is called on a case which contains a `Decimal128` value, it will panic.
+    /// # Panics
+    ///
+    /// Panics if the value contains a type that has no extended JSON representation. Use
+    /// [`Bson::try_into_relaxed_extjson`] to handle that case instead.
     pub fn into_relaxed_extjson(self) -> Value {
-        match self {
-            Bson::Double(v) if v.is_nan() => {
-                let s = if v.is_sign_negative() { "-NaN" } else { "NaN" };
-
-                json!({ "$numberDouble": s })
-            }
-            Bson::Double(v) if v.is_infinite() => {
-                let s = if v.is_sign_negative() {
-                    "-Infinity"
-                } else {
-                    "Infinity"
-                };
-
-                json!({ "$numberDouble": s })
-            }
-            Bson::Double(v) => json!(v),
-            Bson::String(v) => json!(v),
-            Bson::Array(v) => json!(v),
-            Bson::Document(v) => {
-                Value::Object(v.into_iter().map(|(k, v)| (k, Value::from(v))).collect())
-            }
-            Bson::Boolean(v) => json!(v),
-            Bson::Null => Value::Null,
-            Bson::Int32(v) => v.into(),
-            Bson::Int64(v) => v.into(),
-            Bson::UInt32(v) => v.into(),
-            Bson::UInt64(v) => v.into(),
-            Bson::Timestamp(Timestamp { time, increment }) => json!({
-                "$timestamp": {
-                    "t": time,
-                    "i": increment,
-                }
-            }),
-            Bson::Binary(Binary { subtype, ref bytes }) => {
-                let tval: u8 = From::from(subtype);
-                json!({
-                    "$binary": {
-                        "base64": base64::encode(bytes),
-                        "subType": hex::encode([tval]),
-                    }
-                })
-            }
-            Bson::DateTime(v) if v.timestamp_millis() >= 0 && v.to_chrono().year() <= 99999 => {
-                json!({
-                    "$date": v.to_rfc3339_string(),
-                })
-            }
-            Bson::DateTime(v) => json!({
-                "$date": { "$numberLong": v.timestamp_millis().to_string() },
-            }),
-            Bson::Decimal128(_) => panic!("Decimal128 extended JSON not implemented yet."),
-        }
+        self.try_into_relaxed_extjson()
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
     /// Converts the Bson value into its [canonical extended JSON representation](https://docs.mongodb.com/manual/reference/mongodb-extended-json/).
     ///
-    /// Note: extended json encoding for `Decimal128` values is not supported. If this method is
-    /// called on a case which contains a `Decimal128` value, it will panic.
+    /// # Panics
+    ///
+    /// Panics if the value contains a type that has no extended JSON representation. Use
+    /// [`Bson::try_into_canonical_extjson`] to handle that case instead.
     pub fn into_canonical_extjson(self) -> Value {
-        match self {
-            Bson::Int32(i) => json!({ "$numberInt": i.to_string() }),
-            Bson::Int64(i) => json!({ "$numberLong": i.to_string() }),
-            Bson::UInt32(i) => json!({ "$numberUInt32": i.to_string() }),
-            Bson::UInt64(i) => json!({ "$numberUInt64": i.to_string() }),
-            Bson::Double(f) if f.is_normal() => {
-                let mut s = f.to_string();
-                if f.fract() == 0.0 {
-                    s.push_str(".0");
-                }
+        self.try_into_canonical_extjson()
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 
-                json!({ "$numberDouble": s })
-            }
-            Bson::Double(f) if f == 0.0 => {
-                let s = if f.is_sign_negative() { "-0.0" } else { "0.0" };
+    /// Converts the Bson value into its [relaxed extended JSON representation](https://docs.mongodb.com/manual/reference/mongodb-extended-json/),
+    /// returning an error that names the key path of the first value that cannot be represented.
+    pub fn try_into_relaxed_extjson(self) -> extjson::ser::Result<Value> {
+        self.try_into_extjson(false)
+    }
 
-                json!({ "$numberDouble": s })
-            }
-            Bson::DateTime(date) => {
-                json!({ "$date": { "$numberLong": date.timestamp_millis().to_string() } })
-            }
-            Bson::Array(arr) => {
-                Value::Array(arr.into_iter().map(Bson::into_canonical_extjson).collect())
-            }
-            Bson::Document(arr) => Value::Object(
-                arr.into_iter()
-                    .map(|(k, v)| (k, v.into_canonical_extjson()))
-                    .collect(),
+    /// Converts the Bson value into its [canonical extended JSON representation](https://docs.mongodb.com/manual/reference/mongodb-extended-json/),
+    /// returning an error that names the key path of the first value that cannot be represented.
+    pub fn try_into_canonical_extjson(self) -> extjson::ser::Result<Value> {
+        self.try_into_extjson(true)
+    }
+
+    fn try_into_extjson(self, canonical: bool) -> extjson::ser::Result<Value> {
+        let value = match self {
+            Bson::Array(v) => Value::Array(
+                v.into_iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        v.try_into_extjson(canonical)
+                            .map_err(|e| e.with_parent_key(&i.to_string()))
+                    })
+                    .collect::<extjson::ser::Result<_>>()?,
             ),
-            other => other.into_relaxed_extjson(),
-        }
+            Bson::Document(v) => Value::Object(
+                v.into_iter()
+                    .map(|(k, v)| {
+                        let v = v
+                            .try_into_extjson(canonical)
+                            .map_err(|e| e.with_parent_key(&k))?;
+                        Ok((k, v))
+                    })
+                    .collect::<extjson::ser::Result<_>>()?,
+            ),
+            Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope { code, scope }) => {
+                let scope = Bson::Document(scope)
+                    .try_into_extjson(canonical)
+                    .map_err(|e| e.with_parent_key("$scope"))?;
+                json!({
+                    "$code": code,
+                    "$scope": scope,
+                })
+            }
+            other => extjson::ser::raw_scalar(&other)
+                .and_then(|value| extjson::ser::scalar_to_extjson(value, canonical))
+                .expect("documents, arrays and code with scope are handled above"),
+        };
+        Ok(value)
     }
 
-    /// Get the `ElementType` of this value.
-    pub fn element_type(&self) -> ElementType {
-        match *self {
+    /// Returns a value that displays this value in shell syntax as configured by `options`.
+    /// See the [`display`](crate::display) module for the format.
+    pub fn display_with_options(&self, options: DisplayOptions) -> Formatted<'_> {
+        Formatted::bson(self, options)
+    }
+
+    /// Get the `ElementType` of this value, or `None` if this is a [`Bson::Unknown`], which by
+    /// definition has no `ElementType`. Use [`Bson::tag`] to get the type byte of any value.
+    pub fn element_type(&self) -> Option<ElementType> {
+        let element_type = match *self {
             Bson::Double(..) => ElementType::Double,
             Bson::String(..) => ElementType::String,
             Bson::Array(..) => ElementType::Array,
             Bson::Document(..) => ElementType::EmbeddedDocument,
             Bson::Boolean(..) => ElementType::Boolean,
             Bson::Null => ElementType::Null,
+            Bson::RegularExpression(..) => ElementType::RegularExpression,
+            Bson::JavaScriptCode(..) => ElementType::JavaScriptCode,
+            Bson::JavaScriptCodeWithScope(..) => ElementType::JavaScriptCodeWithScope,
             Bson::Int32(..) => ElementType::Int32,
             Bson::Int64(..) => ElementType::Int64,
             Bson::UInt32(..) => ElementType::UInt32,
             Bson::UInt64(..) => ElementType::UInt64,
             Bson::Timestamp(..) => ElementType::Timestamp,
             Bson::Binary(..) => ElementType::Binary,
+            Bson::ObjectId(..) => ElementType::ObjectId,
             Bson::DateTime(..) => ElementType::DateTime,
+            Bson::Symbol(..) => ElementType::Symbol,
             Bson::Decimal128(..) => ElementType::Decimal128,
+            Bson::Undefined => ElementType::Undefined,
+            Bson::MaxKey => ElementType::MaxKey,
+            Bson::MinKey => ElementType::MinKey,
+            Bson::DbPointer(..) => ElementType::DbPointer,
+            Bson::Unknown { .. } => return None,
+        };
+        Some(element_type)
+    }
+
+    /// Get the type tag this value is written with.
+    pub fn tag(&self) -> u8 {
+        match *self {
+            Bson::Unknown { tag, .. } => tag,
+            _ => self.element_type().expect("only unknown elements have no element type") as u8,
         }
     }
 
@@ -416,8 +465,23 @@ impl Bson {
     /// This function mainly used for [extended JSON format](https://docs.mongodb.com/manual/reference/mongodb-extended-json/).
     // TODO RUST-426: Investigate either removing this from the serde implementation or unifying
     // with the extended JSON implementation.
-    pub(crate) fn into_extended_document(self) -> Document {
-        match self {
+    pub(crate) fn into_extended_document(self) -> extjson::ser::Result<Document> {
+        let doc = match self {
+            Bson::RegularExpression(Regex { pattern, options }) => {
+                doc! {
+                    "$regularExpression": {
+                        "pattern": pattern,
+                        "options": options,
+                    }
+                }
+            }
+            Bson::JavaScriptCode(code) => doc! {
+                "$code": code,
+            },
+            Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope { code, scope }) => doc! {
+                "$code": code,
+                "$scope": scope,
+            },
             Bson::Timestamp(Timestamp { time, increment }) => {
                 doc! {
                     "$timestamp": {
@@ -435,6 +499,9 @@ impl Bson {
                     }
                 }
             }
+            Bson::ObjectId(ref v) => doc! {
+                "$oid": v.to_string(),
+            },
             Bson::DateTime(v) if v.timestamp_millis() >= 0 && v.to_chrono().year() <= 9999 => {
                 doc! {
                     "$date": v.to_rfc3339_string(),
@@ -443,8 +510,40 @@ impl Bson {
             Bson::DateTime(v) => doc! {
                 "$date": { "$numberLong": v.timestamp_millis().to_string() },
             },
-            _ => panic!("Attempted conversion of invalid data type: {}", self),
-        }
+            Bson::Symbol(v) => doc! {
+                "$symbol": v,
+            },
+            Bson::Undefined => doc! {
+                "$undefined": true,
+            },
+            Bson::MinKey => doc! {
+                "$minKey": 1,
+            },
+            Bson::MaxKey => doc! {
+                "$maxKey": 1,
+            },
+            Bson::DbPointer(DbPointer { namespace, id }) => doc! {
+                "$dbPointer": {
+                    "$ref": namespace,
+                    "$id": {
+                        "$oid": id.to_string(),
+                    },
+                }
+            },
+            Bson::Unknown { tag, ref bytes } => doc! {
+                "$unknown": {
+                    "tag": hex::encode([tag]),
+                    "base64": base64::encode(bytes),
+                }
+            },
+            other => {
+                let element_type = other
+                    .element_type()
+                    .expect("unknown elements are handled above");
+                return Err(extjson::ser::Error::unsupported_type(element_type));
+            }
+        };
+        Ok(doc)
     }
 
     pub(crate) fn from_extended_document(doc: Document) -> Bson {
@@ -456,6 +555,14 @@ impl Bson {
         keys.sort_unstable();
 
         match keys.as_slice() {
+            ["$oid"] => {
+                if let Ok(oid) = doc.get_str("$oid") {
+                    if let Ok(oid) = ObjectId::parse_str(oid) {
+                        return Bson::ObjectId(oid);
+                    }
+                }
+            }
+
             ["$numberInt"] => {
                 if let Ok(i) = doc.get_str("$numberInt") {
                     if let Ok(i) = i.parse() {
@@ -472,6 +579,22 @@ impl Bson {
                 }
             }
 
+            ["$numberUInt32"] => {
+                if let Ok(i) = doc.get_str("$numberUInt32") {
+                    if let Ok(i) = i.parse() {
+                        return Bson::UInt32(i);
+                    }
+                }
+            }
+
+            ["$numberUInt64"] => {
+                if let Ok(i) = doc.get_str("$numberUInt64") {
+                    if let Ok(i) = i.parse() {
+                        return Bson::UInt64(i);
+                    }
+                }
+            }
+
             ["$numberDouble"] => match doc.get_str("$numberDouble") {
                 Ok("Infinity") => return Bson::Double(std::f64::INFINITY),
                 Ok("-Infinity") => return Bson::Double(std::f64::NEG_INFINITY),
@@ -484,6 +607,14 @@ impl Bson {
                 _ => {}
             },
 
+            ["$numberDecimal"] => {
+                if let Ok(d) = doc.get_str("$numberDecimal") {
+                    if let Ok(d) = d.parse() {
+                        return Bson::Decimal128(d);
+                    }
+                }
+            }
+
             ["$numberDecimalBytes"] => {
                 if let Ok(bytes) = doc.get_binary_generic("$numberDecimalBytes") {
                     if let Ok(b) = bytes.clone().try_into() {
@@ -492,6 +623,41 @@ impl Bson {
                 }
             }
 
+            ["$regularExpression"] => {
+                if let Ok(regex) = doc.get_document("$regularExpression") {
+                    if let Ok(pattern) = regex.get_str("pattern") {
+                        if let Ok(options) = regex.get_str("options") {
+                            return Bson::RegularExpression(Regex::new(pattern, options));
+                        }
+                    }
+                }
+            }
+
+            ["$options", "$regex"] => {
+                if let Ok(pattern) = doc.get_str("$regex") {
+                    if let Ok(options) = doc.get_str("$options") {
+                        return Bson::RegularExpression(Regex::new(pattern, options));
+                    }
+                }
+            }
+
+            ["$code"] => {
+                if let Ok(code) = doc.get_str("$code") {
+                    return Bson::JavaScriptCode(code.to_string());
+                }
+            }
+
+            ["$code", "$scope"] => {
+                if let Ok(code) = doc.get_str("$code") {
+                    if let Ok(scope) = doc.get_document("$scope") {
+                        return Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope {
+                            code: code.to_string(),
+                            scope: scope.clone(),
+                        });
+                    }
+                }
+            }
+
             ["$binary"] => {
                 if let Some(binary) = Binary::from_extended_doc(&doc) {
                     return Bson::Binary(binary);
@@ -538,6 +704,44 @@ impl Bson {
                 }
             }
 
+            ["$symbol"] => {
+                if let Ok(symbol) = doc.get_str("$symbol") {
+                    return Bson::Symbol(symbol.to_string());
+                }
+            }
+
+            ["$undefined"] => {
+                if let Ok(true) = doc.get_bool("$undefined") {
+                    return Bson::Undefined;
+                }
+            }
+
+            ["$minKey"] => {
+                if let Some(Bson::Int32(1)) | Some(Bson::Int64(1)) = doc.get("$minKey") {
+                    return Bson::MinKey;
+                }
+            }
+
+            ["$maxKey"] => {
+                if let Some(Bson::Int32(1)) | Some(Bson::Int64(1)) = doc.get("$maxKey") {
+                    return Bson::MaxKey;
+                }
+            }
+
+            ["$dbPointer"] => {
+                if let Ok(pointer) = doc.get_document("$dbPointer") {
+                    if let Some(pointer) = DbPointer::from_extended_doc(pointer) {
+                        return Bson::DbPointer(pointer);
+                    }
+                }
+            }
+
+            ["$unknown"] => {
+                if let Some((tag, bytes)) = unknown_from_extended_doc(&doc) {
+                    return Bson::Unknown { tag, bytes };
+                }
+            }
+
             _ => {}
         };
 
@@ -655,6 +859,22 @@ impl Bson {
         }
     }
 
+    /// If `Bson` is `ObjectId`, return its value. Returns `None` otherwise
+    pub fn as_object_id(&self) -> Option<oid::ObjectId> {
+        match *self {
+            Bson::ObjectId(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// If `Bson` is `ObjectId`, return a mutable reference to its value. Returns `None` otherwise
+    pub fn as_object_id_mut(&mut self) -> Option<&mut oid::ObjectId> {
+        match *self {
+            Bson::ObjectId(ref mut v) => Some(v),
+            _ => None,
+        }
+    }
+
     /// If `Bson` is `DateTime`, return its value. Returns `None` otherwise
     pub fn as_datetime(&self) -> Option<&crate::DateTime> {
         match *self {
@@ -672,6 +892,54 @@ impl Bson {
         }
     }
 
+    /// If `Bson` is `RegularExpression`, return a reference to it. Returns `None` otherwise
+    pub fn as_regex(&self) -> Option<&Regex> {
+        match *self {
+            Bson::RegularExpression(ref regex) => Some(regex),
+            _ => None,
+        }
+    }
+
+    /// If `Bson` is `JavaScriptCode`, return a reference to its code. Returns `None` otherwise
+    pub fn as_javascript(&self) -> Option<&str> {
+        match *self {
+            Bson::JavaScriptCode(ref code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// If `Bson` is `JavaScriptCodeWithScope`, return a reference to it. Returns `None` otherwise
+    pub fn as_javascript_with_scope(&self) -> Option<&JavaScriptCodeWithScope> {
+        match *self {
+            Bson::JavaScriptCodeWithScope(ref code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// If `Bson` is `Symbol`, return its value. Returns `None` otherwise
+    pub fn as_symbol(&self) -> Option<&str> {
+        match *self {
+            Bson::Symbol(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// If `Bson` is `DbPointer`, return a reference to it. Returns `None` otherwise
+    pub fn as_db_pointer(&self) -> Option<&DbPointer> {
+        match *self {
+            Bson::DbPointer(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// If `Bson` is `Unknown`, return its tag and value bytes. Returns `None` otherwise
+    pub fn as_unknown(&self) -> Option<(u8, &[u8])> {
+        match *self {
+            Bson::Unknown { tag, ref bytes } => Some((tag, bytes)),
+            _ => None,
+        }
+    }
+
     /// If `Bson` is `Timestamp`, return its value. Returns `None` otherwise
     pub fn as_timestamp(&self) -> Option<Timestamp> {
         match *self {
@@ -680,6 +948,14 @@ impl Bson {
         }
     }
 
+    /// If `Bson` is `Decimal128`, return its value. Returns `None` otherwise
+    pub fn as_decimal128(&self) -> Option<Decimal128> {
+        match *self {
+            Bson::Decimal128(d) => Some(d),
+            _ => None,
+        }
+    }
+
     /// If `Bson` is `Null`, return its value. Returns `None` otherwise
     pub fn as_null(&self) -> Option<()> {
         match *self {
@@ -725,6 +1001,155 @@ impl Timestamp {
     }
 }
 
+/// Represents a BSON regular expression value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regex {
+    /// The regex pattern to match.
+    pub pattern: String,
+
+    /// The options for the regex.
+    ///
+    /// Options are identified by characters, which must be stored in alphabetical order. Valid
+    /// options are 'i' for case insensitive matching, 'l' to make \w, \W, etc. locale dependent,
+    /// 'm' for multiline matching, 's' for dotall mode ('.' matches everything), 'u' to make \w,
+    /// \W, etc. match unicode and 'x' for verbose mode.
+    pub options: String,
+}
+
+impl Regex {
+    /// Creates a regex from `pattern` and `options`, sorting the options into the order the
+    /// BSON spec requires and dropping repeated ones. The options are not otherwise checked; use
+    /// [`Regex::try_new`] to reject ones the spec does not define.
+    pub fn new(pattern: impl AsRef<str>, options: impl AsRef<str>) -> Self {
+        Self {
+            pattern: pattern.as_ref().to_string(),
+            options: Self::normalize_options(options.as_ref()),
+        }
+    }
+
+    /// Creates a regex like [`Regex::new`], failing if the pattern or options contain a null
+    /// byte or the options contain a character other than `i`, `l`, `m`, `s`, `u` and `x`.
+    pub fn try_new(pattern: impl AsRef<str>, options: impl AsRef<str>) -> Result<Self, RegexError> {
+        let regex = Self::new(pattern, options);
+        if regex.pattern.contains('\0') || regex.options.contains('\0') {
+            return Err(RegexError::NullByte);
+        }
+        if let Some(option) = regex.options.chars().find(|c| !"ilmsux".contains(*c)) {
+            return Err(RegexError::UnknownOption { option });
+        }
+        Ok(regex)
+    }
+
+    pub(crate) fn normalize_options(options: &str) -> String {
+        let mut chars: Vec<_> = options.chars().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        chars.into_iter().collect()
+    }
+}
+
+impl Display for Regex {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "/{}/{}", self.pattern, self.options)
+    }
+}
+
+/// Errors that can occur when creating a [`Regex`] with [`Regex::try_new`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum RegexError {
+    /// The pattern or options contain a null byte, which cannot be stored in a BSON cstring.
+    NullByte,
+
+    /// The options contain a character the BSON spec does not define.
+    UnknownOption { option: char },
+}
+
+impl Display for RegexError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegexError::NullByte => fmt.write_str("regex pattern or options contain a null byte"),
+            RegexError::UnknownOption { option } => {
+                write!(fmt, "unknown regex option {:?}", option)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+/// Represents a BSON code with scope value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaScriptCodeWithScope {
+    /// The JavaScript code.
+    pub code: String,
+
+    /// The variables the code is evaluated with.
+    pub scope: Document,
+}
+
+impl Display for JavaScriptCodeWithScope {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.code)
+    }
+}
+
+/// Represents a DBPointer. (Deprecated)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbPointer {
+    /// The namespace of the collection the pointed-to document lives in.
+    pub namespace: String,
+
+    /// The id of the pointed-to document.
+    pub id: oid::ObjectId,
+}
+
+impl DbPointer {
+    /// Reads the body of an extended `$dbPointer` document, whose `$id` may still be in its
+    /// `{ "$oid": <hex> }` form.
+    pub(crate) fn from_extended_doc(doc: &Document) -> Option<Self> {
+        if doc.len() != 2 {
+            return None;
+        }
+        let namespace = doc.get_str("$ref").ok()?;
+        let id = match doc.get("$id")? {
+            Bson::ObjectId(id) => *id,
+            Bson::Document(id) => match Bson::from_extended_document(id.clone()) {
+                Bson::ObjectId(id) => id,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(Self {
+            namespace: namespace.to_string(),
+            id,
+        })
+    }
+}
+
+/// Reads an extended `$unknown` document. Like `$binary`, the body is either
+/// `{ "tag": <hex>, "base64": <base64> }` or, from non-human-readable serializers,
+/// `{ "tag": <i32>, "bytes": <bytes> }`.
+fn unknown_from_extended_doc(doc: &Document) -> Option<(u8, Vec<u8>)> {
+    let body = doc.get_document("$unknown").ok()?;
+    if body.len() != 2 {
+        return None;
+    }
+
+    if let Ok(bytes) = body.get_str("base64") {
+        let bytes = base64::decode(bytes).ok()?;
+        let tag = hex::decode(body.get_str("tag").ok()?).ok()?;
+        if tag.len() == 1 {
+            Some((tag[0], bytes))
+        } else {
+            None
+        }
+    } else {
+        let bytes = body.get_binary_generic("bytes").ok()?;
+        let tag = u8::try_from(body.get_i32("tag").ok()?).ok()?;
+        Some((tag, bytes.clone()))
+    }
+}
 
 /// Represents a BSON binary value.
 #[derive(Debug, Clone, PartialEq)]
@@ -736,17 +1161,6 @@ pub struct Binary {
     pub bytes: Vec<u8>,
 }
 
-impl Display for Binary {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            fmt,
-            "Binary({:#x}, {})",
-            u8::from(self.subtype),
-            base64::encode(&self.bytes)
-        )
-    }
-}
-
 impl Binary {
     pub(crate) fn from_extended_doc(doc: &Document) -> Option<Self> {
         let binary_doc = doc.get_document("$binary").ok()?;
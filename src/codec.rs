@@ -0,0 +1,731 @@
+//! Custom element types.
+//!
+//! Tags that this crate does not assign to one of its own [`ElementType`]s can be given a
+//! meaning by implementing [`ElementCodec`] and registering the implementation in an
+//! [`ElementCodecRegistry`]. The registry is consulted by:
+//!
+//! - the binary decoders, through [`DeserializerOptions::codecs`](crate::DeserializerOptions::codecs),
+//!   which use it to find the size of each custom element and reject malformed values;
+//! - the binary encoders, through [`SerializerOptions::codecs`](crate::SerializerOptions::codecs),
+//!   which reject malformed values before writing them;
+//! - the extended JSON writer, through
+//!   [`WriterOptions::codecs`](crate::extjson::ser::WriterOptions::codecs), and
+//!   [`ElementCodecRegistry::bson_from_extjson`], which use the codec's own extended JSON form.
+//!
+//! Custom elements are held as [`Bson::Unknown`] / [`RawBson::Unknown`];
+//! [`ElementCodec::encode_bson`] and [`ElementCodec::decode_bson`] convert between those and the
+//! codec's value type.
+//!
+//! ```rust
+//! use bson::{
+//!     codec::{ElementCodec, ElementCodecRegistry},
+//!     de,
+//!     doc,
+//!     spec::ElementSize,
+//!     Document,
+//!     DeserializerOptions,
+//! };
+//! use serde_json::json;
+//!
+//! /// A point in time with nanosecond precision.
+//! #[derive(Debug, PartialEq)]
+//! struct PreciseTime {
+//!     secs: i64,
+//!     nanos: u32,
+//! }
+//!
+//! struct PreciseTimeCodec;
+//!
+//! impl ElementCodec for PreciseTimeCodec {
+//!     type Value = PreciseTime;
+//!
+//!     fn tag(&self) -> u8 {
+//!         0x80
+//!     }
+//!
+//!     fn size(&self) -> ElementSize {
+//!         ElementSize::Fixed(12)
+//!     }
+//!
+//!     fn extjson_key(&self) -> &str {
+//!         "$preciseTime"
+//!     }
+//!
+//!     fn encode(&self, value: &PreciseTime) -> Vec<u8> {
+//!         let mut bytes = value.secs.to_le_bytes().to_vec();
+//!         bytes.extend_from_slice(&value.nanos.to_le_bytes());
+//!         bytes
+//!     }
+//!
+//!     fn decode(&self, bytes: &[u8]) -> de::Result<PreciseTime> {
+//!         let mut secs = [0; 8];
+//!         let mut nanos = [0; 4];
+//!         secs.copy_from_slice(&bytes[..8]);
+//!         nanos.copy_from_slice(&bytes[8..]);
+//!         Ok(PreciseTime {
+//!             secs: i64::from_le_bytes(secs),
+//!             nanos: u32::from_le_bytes(nanos),
+//!         })
+//!     }
+//!
+//!     fn to_extjson(&self, value: &PreciseTime) -> serde_json::Value {
+//!         json!({ "secs": value.secs.to_string(), "nanos": value.nanos })
+//!     }
+//!
+//!     fn parse_extjson(&self, body: serde_json::Value) -> de::Result<PreciseTime> {
+//!         let secs = body["secs"].as_str().and_then(|s| s.parse().ok());
+//!         let nanos = body["nanos"].as_u64().map(|n| n as u32);
+//!         match (secs, nanos) {
+//!             (Some(secs), Some(nanos)) => Ok(PreciseTime { secs, nanos }),
+//!             _ => Err(serde::de::Error::custom("invalid $preciseTime")),
+//!         }
+//!     }
+//! }
+//!
+//! let mut codecs = ElementCodecRegistry::new();
+//! codecs.register(PreciseTimeCodec)?;
+//!
+//! let time = PreciseTime { secs: 1, nanos: 5 };
+//! let doc = doc! { "t": PreciseTimeCodec.encode_bson(&time) };
+//! let mut bytes = Vec::new();
+//! doc.to_writer(&mut bytes)?;
+//!
+//! let options = DeserializerOptions::builder().codecs(codecs.clone()).build();
+//! let read = Document::from_reader_with_options(bytes.as_slice(), options)?;
+//! assert_eq!(PreciseTimeCodec.decode_bson(read.get("t").unwrap())?, time);
+//!
+//! let json = json!({ "t": { "$preciseTime": { "secs": "1", "nanos": 5 } } });
+//! assert_eq!(codecs.bson_from_extjson(json)?, doc.into());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{collections::BTreeMap, convert::TryFrom, fmt, panic::RefUnwindSafe, sync::Arc};
+
+use serde::de::Error as _;
+
+use crate::{
+    de,
+    extjson,
+    raw::RawBson,
+    spec::{ElementSize, ElementType},
+    Bson,
+    Document,
+};
+
+/// The extended JSON keys this crate itself gives a meaning to, which codecs cannot use.
+const RESERVED_EXTJSON_KEYS: &[&str] = &[
+    "$oid",
+    "$date",
+    "$numberInt",
+    "$numberLong",
+    "$numberUInt32",
+    "$numberUInt64",
+    "$numberDouble",
+    "$numberDecimal",
+    "$numberDecimalBytes",
+    "$binary",
+    "$uuid",
+    "$regularExpression",
+    "$code",
+    "$scope",
+    "$timestamp",
+    "$symbol",
+    "$dbPointer",
+    "$minKey",
+    "$maxKey",
+    "$undefined",
+    "$unknown",
+];
+
+/// Errors that can occur when registering an [`ElementCodec`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The tag is `0x00` or is assigned to one of this crate's own element types.
+    #[non_exhaustive]
+    ReservedTag { tag: u8 },
+
+    /// Another codec is already registered for the tag.
+    #[non_exhaustive]
+    DuplicateTag { tag: u8 },
+
+    /// The extended JSON key does not start with `$`, or is already used by this crate or by
+    /// another registered codec.
+    #[non_exhaustive]
+    InvalidExtJsonKey { key: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ReservedTag { tag } => {
+                write!(f, "{:#04x} is reserved and cannot be used by a codec", tag)
+            }
+            Error::DuplicateTag { tag } => {
+                write!(f, "a codec is already registered for {:#04x}", tag)
+            }
+            Error::InvalidExtJsonKey { key } => {
+                write!(
+                    f,
+                    "\"{}\" cannot be used as the extended JSON key of a codec",
+                    key
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Alias for `Result<T, codec::Error>`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The encoding of a custom element type.
+///
+/// Codecs are shared by every clone of the options they are registered with, so they must be
+/// thread-safe and unwind-safe like the rest of those options.
+pub trait ElementCodec: Send + Sync + RefUnwindSafe + 'static {
+    /// The type of the values of this element type.
+    type Value;
+
+    /// The type tag written before the key of each element. This must be neither `0x00` nor a
+    /// tag of one of this crate's own [`ElementType`]s.
+    fn tag(&self) -> u8;
+
+    /// The size of the values of this element type.
+    fn size(&self) -> ElementSize;
+
+    /// The key of the single-entry object representing a value in extended JSON, e.g.
+    /// `"$preciseTime"`.
+    fn extjson_key(&self) -> &str;
+
+    /// Encode a value as the bytes following the key of its element. For
+    /// [`ElementSize::LengthPrefixed`] values, this includes the length.
+    fn encode(&self, value: &Self::Value) -> Vec<u8>;
+
+    /// Decode a value from the bytes following the key of its element. The number of bytes has
+    /// already been checked against [`ElementCodec::size`].
+    fn decode(&self, bytes: &[u8]) -> de::Result<Self::Value>;
+
+    /// The body of the object representing a value in extended JSON.
+    fn to_extjson(&self, value: &Self::Value) -> serde_json::Value;
+
+    /// Parse a value from the body of the object representing it in extended JSON.
+    fn parse_extjson(&self, body: serde_json::Value) -> de::Result<Self::Value>;
+
+    /// Encode a value as a [`Bson`].
+    fn encode_bson(&self, value: &Self::Value) -> Bson {
+        Bson::Unknown {
+            tag: self.tag(),
+            bytes: self.encode(value),
+        }
+    }
+
+    /// Decode a value from a [`Bson`], which must be an element of this type.
+    fn decode_bson(&self, bson: &Bson) -> de::Result<Self::Value> {
+        match bson.as_unknown() {
+            Some((tag, bytes)) if tag == self.tag() => {
+                check_size(self.size(), bytes)?;
+                self.decode(bytes)
+            }
+            _ => Err(de::Error::custom(format!(
+                "expected element of type {:#04x}, instead got {:?}",
+                self.tag(),
+                bson
+            ))),
+        }
+    }
+
+    /// Decode a value from a [`RawBson`], which must be an element of this type.
+    fn decode_raw_bson(&self, bson: RawBson<'_>) -> de::Result<Self::Value> {
+        match bson.as_unknown() {
+            Some((tag, bytes)) if tag == self.tag() => {
+                check_size(self.size(), bytes)?;
+                self.decode(bytes)
+            }
+            _ => Err(de::Error::custom(format!(
+                "expected element of type {:#04x}, instead got {:?}",
+                self.tag(),
+                bson
+            ))),
+        }
+    }
+}
+
+/// Check that `bytes` is a value of the given size.
+fn check_size(size: ElementSize, bytes: &[u8]) -> de::Result<()> {
+    let expected = match size {
+        ElementSize::Fixed(length) => length,
+        ElementSize::LengthPrefixed => {
+            if bytes.len() < 4 {
+                return Err(de::Error::invalid_length(bytes.len(), &"at least 4 bytes"));
+            }
+            let mut length = [0; 4];
+            length.copy_from_slice(&bytes[..4]);
+            i32::from_le_bytes(length) as usize
+        }
+    };
+
+    if bytes.len() != expected {
+        return Err(de::Error::invalid_length(
+            bytes.len(),
+            &format!("{} bytes", expected).as_str(),
+        ));
+    }
+    Ok(())
+}
+
+/// Object-safe view of an [`ElementCodec`] that works on encoded values.
+trait ErasedCodec: Send + Sync + RefUnwindSafe {
+    fn element_size(&self) -> ElementSize;
+
+    fn key(&self) -> &str;
+
+    fn check(&self, bytes: &[u8]) -> de::Result<()>;
+
+    fn bytes_to_extjson(&self, bytes: &[u8]) -> de::Result<serde_json::Value>;
+
+    fn bytes_from_extjson(&self, body: serde_json::Value) -> de::Result<Vec<u8>>;
+}
+
+impl<C: ElementCodec> ErasedCodec for C {
+    fn element_size(&self) -> ElementSize {
+        self.size()
+    }
+
+    fn key(&self) -> &str {
+        self.extjson_key()
+    }
+
+    fn check(&self, bytes: &[u8]) -> de::Result<()> {
+        check_size(self.size(), bytes)?;
+        self.decode(bytes).map(drop)
+    }
+
+    fn bytes_to_extjson(&self, bytes: &[u8]) -> de::Result<serde_json::Value> {
+        check_size(self.size(), bytes)?;
+        Ok(self.to_extjson(&self.decode(bytes)?))
+    }
+
+    fn bytes_from_extjson(&self, body: serde_json::Value) -> de::Result<Vec<u8>> {
+        let bytes = self.encode(&self.parse_extjson(body)?);
+        check_size(self.size(), &bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// A set of [`ElementCodec`]s, keyed by tag.
+///
+/// Cloning a registry is cheap, as the codecs themselves are shared.
+#[derive(Clone, Default)]
+pub struct ElementCodecRegistry {
+    codecs: Arc<BTreeMap<u8, Arc<dyn ErasedCodec>>>,
+}
+
+impl fmt::Debug for ElementCodecRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.codecs
+                    .iter()
+                    .map(|(tag, codec)| (format!("{:#04x}", tag), codec.key())),
+            )
+            .finish()
+    }
+}
+
+impl ElementCodecRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a codec to the registry.
+    ///
+    /// Fails if the codec's tag is reserved or already registered, or if its extended JSON key
+    /// cannot be told apart from the other extended JSON forms.
+    pub fn register<C: ElementCodec>(&mut self, codec: C) -> Result<()> {
+        let tag = codec.tag();
+        if tag == 0 || ElementType::from(tag).is_some() {
+            return Err(Error::ReservedTag { tag });
+        }
+        if self.codecs.contains_key(&tag) {
+            return Err(Error::DuplicateTag { tag });
+        }
+
+        let key = codec.extjson_key();
+        if !key.starts_with('$')
+            || RESERVED_EXTJSON_KEYS.contains(&key)
+            || self.codecs.values().any(|c| c.key() == key)
+        {
+            return Err(Error::InvalidExtJsonKey {
+                key: key.to_string(),
+            });
+        }
+
+        Arc::make_mut(&mut self.codecs).insert(tag, Arc::new(codec));
+        Ok(())
+    }
+
+    /// Whether a codec is registered for the given tag.
+    pub fn contains(&self, tag: u8) -> bool {
+        self.codecs.contains_key(&tag)
+    }
+
+    /// Whether the registry is empty.
+    pub fn is_empty(&self) -> bool {
+        self.codecs.is_empty()
+    }
+
+    /// The size of the values of the element type with the given tag, if a codec is registered
+    /// for it.
+    pub(crate) fn size(&self, tag: u8) -> Option<ElementSize> {
+        self.codecs.get(&tag).map(|c| c.element_size())
+    }
+
+    /// Check an element value against the codec registered for its tag, if any.
+    pub(crate) fn check(&self, tag: u8, bytes: &[u8]) -> de::Result<()> {
+        match self.codecs.get(&tag) {
+            Some(codec) => codec.check(bytes),
+            None => Ok(()),
+        }
+    }
+
+    /// The extended JSON key and body of an element value, if a codec is registered for its tag.
+    pub(crate) fn to_extjson(
+        &self,
+        tag: u8,
+        bytes: &[u8],
+    ) -> Option<de::Result<(&str, serde_json::Value)>> {
+        let codec = self.codecs.get(&tag)?;
+        Some(
+            codec
+                .bytes_to_extjson(bytes)
+                .map(|body| (codec.key(), body)),
+        )
+    }
+
+    /// Convert extended JSON to [`Bson`] in the same way as `Bson::try_from`, additionally
+    /// reading the extended JSON form of each registered codec.
+    pub fn bson_from_extjson(&self, value: serde_json::Value) -> extjson::de::Result<Bson> {
+        match value {
+            serde_json::Value::Object(map) => {
+                if map.len() == 1 {
+                    let (key, body) = map.iter().next().unwrap();
+                    if let Some((tag, codec)) = self.codecs.iter().find(|(_, c)| c.key() == key) {
+                        let bytes = codec
+                            .bytes_from_extjson(body.clone())
+                            .map_err(extjson::de::Error::custom)?;
+                        return Ok(Bson::Unknown { tag: *tag, bytes });
+                    }
+                }
+
+                if map
+                    .keys()
+                    .any(|k| RESERVED_EXTJSON_KEYS.contains(&k.as_str()))
+                {
+                    return Bson::try_from(map);
+                }
+
+                map.into_iter()
+                    .map(|(k, v)| Ok((k, self.bson_from_extjson(v)?)))
+                    .collect::<extjson::de::Result<Document>>()
+                    .map(Bson::Document)
+            }
+            serde_json::Value::Array(values) => values
+                .into_iter()
+                .map(|v| self.bson_from_extjson(v))
+                .collect::<extjson::de::Result<_>>()
+                .map(Bson::Array),
+            other => Bson::try_from(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{ElementCodec, ElementCodecRegistry, Error};
+    use crate::{
+        de,
+        extjson::ser::{bson_to_writer, raw_document_to_writer, WriterOptions},
+        spec::ElementSize,
+        Bson,
+        DeserializerOptions,
+        Document,
+        RawDocument,
+        SerializerOptions,
+    };
+
+    /// Nanoseconds since the epoch, as a little-endian `i128`.
+    struct Nanos;
+
+    impl ElementCodec for Nanos {
+        type Value = i128;
+
+        fn tag(&self) -> u8 {
+            0x80
+        }
+
+        fn size(&self) -> ElementSize {
+            ElementSize::Fixed(16)
+        }
+
+        fn extjson_key(&self) -> &str {
+            "$nanos"
+        }
+
+        fn encode(&self, value: &i128) -> Vec<u8> {
+            value.to_le_bytes().to_vec()
+        }
+
+        fn decode(&self, bytes: &[u8]) -> de::Result<i128> {
+            let mut buf = [0; 16];
+            buf.copy_from_slice(bytes);
+            Ok(i128::from_le_bytes(buf))
+        }
+
+        fn to_extjson(&self, value: &i128) -> serde_json::Value {
+            json!(value.to_string())
+        }
+
+        fn parse_extjson(&self, body: serde_json::Value) -> de::Result<i128> {
+            body.as_str()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| serde::de::Error::custom("expected nanoseconds as a string"))
+        }
+    }
+
+    /// A length-prefixed list of ASCII tags, rejecting anything else.
+    struct Tags;
+
+    impl ElementCodec for Tags {
+        type Value = String;
+
+        fn tag(&self) -> u8 {
+            0x81
+        }
+
+        fn size(&self) -> ElementSize {
+            ElementSize::LengthPrefixed
+        }
+
+        fn extjson_key(&self) -> &str {
+            "$tags"
+        }
+
+        fn encode(&self, value: &String) -> Vec<u8> {
+            let mut bytes = ((value.len() + 4) as i32).to_le_bytes().to_vec();
+            bytes.extend_from_slice(value.as_bytes());
+            bytes
+        }
+
+        fn decode(&self, bytes: &[u8]) -> de::Result<String> {
+            match std::str::from_utf8(&bytes[4..]) {
+                Ok(s) if s.is_ascii() => Ok(s.to_string()),
+                _ => Err(serde::de::Error::custom("tags must be ASCII")),
+            }
+        }
+
+        fn to_extjson(&self, value: &String) -> serde_json::Value {
+            json!({ "list": value })
+        }
+
+        fn parse_extjson(&self, body: serde_json::Value) -> de::Result<String> {
+            body["list"]
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| serde::de::Error::custom("expected a list"))
+        }
+    }
+
+    fn registry() -> ElementCodecRegistry {
+        let mut codecs = ElementCodecRegistry::new();
+        codecs.register(Nanos).unwrap();
+        codecs.register(Tags).unwrap();
+        codecs
+    }
+
+    #[test]
+    fn options_are_unwind_safe() {
+        fn assert_unwind_safe<T: std::panic::UnwindSafe + std::panic::RefUnwindSafe>() {}
+
+        assert_unwind_safe::<ElementCodecRegistry>();
+        assert_unwind_safe::<DeserializerOptions>();
+        assert_unwind_safe::<SerializerOptions>();
+    }
+
+    #[test]
+    fn register() {
+        struct Custom(u8, &'static str);
+
+        impl ElementCodec for Custom {
+            type Value = ();
+
+            fn tag(&self) -> u8 {
+                self.0
+            }
+
+            fn size(&self) -> ElementSize {
+                ElementSize::Fixed(0)
+            }
+
+            fn extjson_key(&self) -> &str {
+                self.1
+            }
+
+            fn encode(&self, _: &()) -> Vec<u8> {
+                Vec::new()
+            }
+
+            fn decode(&self, _: &[u8]) -> de::Result<()> {
+                Ok(())
+            }
+
+            fn to_extjson(&self, _: &()) -> serde_json::Value {
+                json!(1)
+            }
+
+            fn parse_extjson(&self, _: serde_json::Value) -> de::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut codecs = registry();
+        assert!(codecs.contains(0x80));
+        assert!(!codecs.contains(0x82));
+        for &tag in &[0x00, 0x01, 0x14, 0x15, 0x7F, 0xFF] {
+            assert_eq!(
+                codecs.register(Custom(tag, "$custom")),
+                Err(Error::ReservedTag { tag })
+            );
+        }
+        assert_eq!(
+            codecs.register(Custom(0x80, "$custom")),
+            Err(Error::DuplicateTag { tag: 0x80 })
+        );
+        for &key in &["custom", "$date", "$unknown", "$nanos"] {
+            assert_eq!(
+                codecs.register(Custom(0x82, key)),
+                Err(Error::InvalidExtJsonKey {
+                    key: key.to_string()
+                })
+            );
+        }
+        codecs.register(Custom(0x82, "$custom")).unwrap();
+        assert_eq!(
+            format!("{:?}", codecs),
+            r#"{"0x80": "$nanos", "0x81": "$tags", "0x82": "$custom"}"#
+        );
+    }
+
+    #[test]
+    fn binary() {
+        let codecs = registry();
+        let doc = doc! {
+            "n": Nanos.encode_bson(&-5),
+            "a": [Tags.encode_bson(&"ab".to_string())],
+        };
+        let bytes = crate::to_vec(&doc).unwrap();
+
+        // a registered tag is decoded without enabling unknown elements
+        assert!(Document::from_reader(bytes.as_slice()).is_err());
+        let options = DeserializerOptions::builder()
+            .codecs(codecs.clone())
+            .build();
+        let read = Document::from_reader_with_options(bytes.as_slice(), options.clone()).unwrap();
+        assert_eq!(read, doc);
+        assert_eq!(Nanos.decode_bson(read.get("n").unwrap()).unwrap(), -5);
+        assert!(Tags.decode_bson(read.get("n").unwrap()).is_err());
+        assert_eq!(
+            crate::from_slice_with_options::<Document>(&bytes, options.clone()).unwrap(),
+            doc
+        );
+
+        let raw = RawDocument::new(&bytes).unwrap();
+        let (key, value) = raw.iter_with_options(&options).next().unwrap().unwrap();
+        assert_eq!(key, "n");
+        assert_eq!(Nanos.decode_raw_bson(value).unwrap(), -5);
+
+        // values rejected by their codec fail to decode and to encode
+        let bad = doc! { "t": Bson::Unknown { tag: 0x81, bytes: b"\x05\0\0\0\xFF".to_vec() } };
+        let bad_bytes = crate::to_vec(&bad).unwrap();
+        assert!(Document::from_reader_with_options(bad_bytes.as_slice(), options.clone()).is_err());
+        assert!(crate::from_slice_with_options::<Document>(&bad_bytes, options.clone()).is_err());
+        let err = RawDocument::new(&bad_bytes)
+            .unwrap()
+            .iter_with_options(&options)
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.key(), Some("t"));
+
+        let ser_options = SerializerOptions::builder().codecs(codecs).build();
+        assert!(crate::to_vec_with_options(&bad, ser_options.clone()).is_err());
+        assert!(bad
+            .to_writer_with_options(&mut Vec::new(), ser_options.clone())
+            .is_err());
+        let wrong_size = doc! { "n": Bson::Unknown { tag: 0x80, bytes: vec![0; 8] } };
+        assert!(crate::to_vec_with_options(&wrong_size, ser_options.clone()).is_err());
+        assert_eq!(
+            crate::to_vec_with_options(&doc, ser_options.clone()).unwrap(),
+            bytes
+        );
+        let mut written = Vec::new();
+        doc.to_writer_with_options(&mut written, ser_options)
+            .unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn extjson() {
+        let codecs = registry();
+        let doc = doc! {
+            "n": Nanos.encode_bson(&170141183460469231731687303715884105727),
+            "d": { "a": [Tags.encode_bson(&"ab".to_string()), 1] },
+        };
+        let canonical = json!({
+            "n": { "$nanos": "170141183460469231731687303715884105727" },
+            "d": { "a": [{ "$tags": { "list": "ab" } }, { "$numberInt": "1" }] },
+        });
+
+        let options = WriterOptions::builder()
+            .canonical(true)
+            .codecs(codecs.clone())
+            .build();
+        let mut json = Vec::new();
+        bson_to_writer(&mut json, &Bson::Document(doc.clone()), options.clone()).unwrap();
+        assert_eq!(json, serde_json::to_vec(&canonical).unwrap());
+
+        let bytes = crate::to_vec(&doc).unwrap();
+        let raw = RawDocument::new(&bytes).unwrap();
+        assert!(raw_document_to_writer(&mut Vec::new(), raw, Default::default()).is_err());
+        let mut json = Vec::new();
+        raw_document_to_writer(&mut json, raw, options).unwrap();
+        assert_eq!(json, serde_json::to_vec(&canonical).unwrap());
+
+        let mut pretty = Vec::new();
+        let options = WriterOptions::builder()
+            .canonical(true)
+            .pretty(true)
+            .codecs(codecs.clone())
+            .build();
+        raw_document_to_writer(&mut pretty, raw, options).unwrap();
+        assert_eq!(pretty, serde_json::to_vec_pretty(&canonical).unwrap());
+
+        assert_eq!(
+            codecs.bson_from_extjson(canonical).unwrap(),
+            Bson::Document(doc)
+        );
+        assert!(codecs
+            .bson_from_extjson(json!({ "n": { "$nanos": 1 } }))
+            .is_err());
+        assert_eq!(
+            codecs
+                .bson_from_extjson(json!({ "$nanos": "1", "x": 1 }))
+                .unwrap(),
+            Bson::Document(doc! { "$nanos": "1", "x": 1 })
+        );
+    }
+}
@@ -26,6 +26,20 @@ pub enum Error {
         element_type: u8,
     },
 
+    /// While decoding BSON bytes, an element of a type rejected by the [`DeserializerOptions`]
+    /// was encountered, see [`DeserializerOptions::reject_unsigned_integers`].
+    ///
+    /// [`DeserializerOptions`]: crate::DeserializerOptions
+    /// [`DeserializerOptions::reject_unsigned_integers`]: crate::DeserializerOptions::reject_unsigned_integers
+    #[non_exhaustive]
+    ElementTypeNotAllowed {
+        /// The key of the rejected element.
+        key: String,
+
+        /// The type tag of the rejected element.
+        element_type: u8,
+    },
+
     /// The end of the BSON input was reached too soon.
     EndOfStream,
 
@@ -63,6 +77,14 @@ impl fmt::Display for Error {
                 "unrecognized element type for key \"{}\": `{:#x}`",
                 key, element_type
             ),
+            Error::ElementTypeNotAllowed {
+                ref key,
+                element_type,
+            } => write!(
+                fmt,
+                "element type not allowed for key \"{}\": `{:#x}`",
+                key, element_type
+            ),
             Error::EndOfStream => fmt.write_str("end of stream"),
             Error::DeserializationError { ref message } => message.fmt(fmt),
         }
@@ -105,9 +127,19 @@ impl Bson {
             Bson::UInt64(i) => Unexpected::Unsigned(*i),
             Bson::Null => Unexpected::Unit,
             Bson::String(s) => Unexpected::Str(s.as_str()),
+            Bson::ObjectId(_) => Unexpected::Other("objectid"),
+            Bson::RegularExpression(_) => Unexpected::Other("regex"),
+            Bson::JavaScriptCode(_) => Unexpected::Other("javascript code"),
+            Bson::JavaScriptCodeWithScope(_) => Unexpected::Other("javascript code with scope"),
             Bson::Timestamp(_) => Unexpected::Other("timestamp"),
             Bson::DateTime(_) => Unexpected::Other("datetime"),
+            Bson::Symbol(_) => Unexpected::Other("symbol"),
             Bson::Decimal128(_) => Unexpected::Other("decimal128"),
+            Bson::Undefined => Unexpected::Other("undefined"),
+            Bson::MinKey => Unexpected::Other("minkey"),
+            Bson::MaxKey => Unexpected::Other("maxkey"),
+            Bson::DbPointer(_) => Unexpected::Other("dbpointer"),
+            Bson::Unknown { .. } => Unexpected::Other("unknown element"),
         }
     }
 }
@@ -33,11 +33,11 @@ pub use self::{
 use std::io::Read;
 
 use crate::{
-    bson::{Array, Binary, Bson, Document, Timestamp},
+    bson::{Array, Binary, Bson, DbPointer, Document, JavaScriptCodeWithScope, Regex, Timestamp},
     oid::{self, ObjectId},
     raw::RawBinary,
     ser::write_i32,
-    spec::{self, BinarySubtype},
+    spec::{self, BinarySubtype, ElementSize},
     Decimal128,
 };
 
@@ -180,6 +180,31 @@ fn read_f64<R: Read + ?Sized>(reader: &mut R) -> Result<f64> {
     Ok(f64::from_le_bytes(buf))
 }
 
+/// Reads the value bytes of an element whose type is unknown, given its size.
+fn read_unknown<R: Read + ?Sized>(reader: &mut R, size: ElementSize) -> Result<Vec<u8>> {
+    let bytes = match size {
+        ElementSize::Fixed(length) => {
+            let mut bytes = vec![0u8; length];
+            reader.read_exact(&mut bytes)?;
+            bytes
+        }
+        ElementSize::LengthPrefixed => {
+            let length = read_i32(reader)?;
+            if !(4..=MAX_BSON_SIZE).contains(&length) {
+                return Err(Error::invalid_length(
+                    length as usize,
+                    &format!("element length must be between 4 and {}", MAX_BSON_SIZE).as_str(),
+                ));
+            }
+            let mut bytes = vec![0u8; length as usize];
+            bytes[..4].copy_from_slice(&length.to_le_bytes());
+            reader.read_exact(&mut bytes[4..])?;
+            bytes
+        }
+    };
+    Ok(bytes)
+}
+
 /// Placeholder decoder for `Decimal128`. Reads 128 bits and just stores them, does no validation or
 /// parsing.
 #[inline]
@@ -189,7 +214,11 @@ fn read_f128<R: Read + ?Sized>(reader: &mut R) -> Result<Decimal128> {
     Ok(Decimal128 { bytes: buf })
 }
 
-fn deserialize_array<R: Read + ?Sized>(reader: &mut R, utf8_lossy: bool) -> Result<Array> {
+fn deserialize_array<R: Read + ?Sized>(
+    reader: &mut R,
+    utf8_lossy: bool,
+    options: &DeserializerOptions,
+) -> Result<Array> {
     let mut arr = Array::new();
     let length = read_i32(reader)?;
 
@@ -215,7 +244,7 @@ fn deserialize_array<R: Read + ?Sized>(reader: &mut R, utf8_lossy: bool) -> Resu
                     break;
                 }
 
-                let (_, val) = deserialize_bson_kvp(cursor, tag, utf8_lossy)?;
+                let (_, val) = deserialize_bson_kvp(cursor, tag, utf8_lossy, options)?;
                 arr.push(val)
             }
             Ok(())
@@ -229,18 +258,66 @@ pub(crate) fn deserialize_bson_kvp<R: Read + ?Sized>(
     reader: &mut R,
     tag: u8,
     utf8_lossy: bool,
+    options: &DeserializerOptions,
 ) -> Result<(String, Bson)> {
     use spec::ElementType;
     let key = read_cstring(reader)?;
 
+    if options.rejects(tag) {
+        return Err(Error::ElementTypeNotAllowed {
+            key,
+            element_type: tag,
+        });
+    }
+
     let val = match ElementType::from(tag) {
         Some(ElementType::Double) => Bson::Double(read_f64(reader)?),
         Some(ElementType::String) => read_string(reader, utf8_lossy).map(Bson::String)?,
-        Some(ElementType::EmbeddedDocument) => Document::from_reader(reader).map(Bson::Document)?,
-        Some(ElementType::Array) => deserialize_array(reader, utf8_lossy).map(Bson::Array)?,
+        Some(ElementType::EmbeddedDocument) => {
+            Document::decode(reader, utf8_lossy, options).map(Bson::Document)?
+        }
+        Some(ElementType::Array) => {
+            deserialize_array(reader, utf8_lossy, options).map(Bson::Array)?
+        }
         Some(ElementType::Binary) => Bson::Binary(Binary::from_reader(reader)?),
+        Some(ElementType::ObjectId) => Bson::ObjectId(ObjectId::from_reader(reader)?),
         Some(ElementType::Boolean) => Bson::Boolean(read_bool(reader)?),
         Some(ElementType::Null) => Bson::Null,
+        Some(ElementType::RegularExpression) => {
+            let pattern = read_cstring(reader)?;
+            let options = read_cstring(reader)?;
+            Bson::RegularExpression(Regex::new(pattern, options))
+        }
+        Some(ElementType::JavaScriptCode) => {
+            read_string(reader, utf8_lossy).map(Bson::JavaScriptCode)?
+        }
+        Some(ElementType::JavaScriptCodeWithScope) => {
+            let length = read_i32(reader)?;
+            if !(MIN_CODE_WITH_SCOPE_SIZE..=MAX_BSON_SIZE).contains(&length) {
+                return Err(Error::invalid_length(
+                    length as usize,
+                    &format!(
+                        "code with scope length must be between {} and {}",
+                        MIN_CODE_WITH_SCOPE_SIZE, MAX_BSON_SIZE
+                    )
+                    .as_str(),
+                ));
+            }
+
+            let mut code = String::new();
+            let mut scope = Document::new();
+            ensure_read_exactly(
+                reader,
+                (length as usize) - 4,
+                "code with scope length does not match contents",
+                |cursor| {
+                    code = read_string(cursor, utf8_lossy)?;
+                    scope = Document::decode(cursor, utf8_lossy, options)?;
+                    Ok(())
+                },
+            )?;
+            Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope { code, scope })
+        }
         Some(ElementType::Int32) => read_i32(reader).map(Bson::Int32)?,
         Some(ElementType::Int64) => read_i64(reader).map(Bson::Int64)?,
         Some(ElementType::UInt32) => read_u32(reader).map(Bson::UInt32)?,
@@ -251,13 +328,28 @@ pub(crate) fn deserialize_bson_kvp<R: Read + ?Sized>(
             let time = read_i64(reader)?;
             Bson::DateTime(crate::DateTime::from_millis(time))
         }
+        Some(ElementType::Symbol) => read_string(reader, utf8_lossy).map(Bson::Symbol)?,
         Some(ElementType::Decimal128) => read_f128(reader).map(Bson::Decimal128)?,
-        None => {
-            return Err(Error::UnrecognizedDocumentElementType {
-                key,
-                element_type: tag,
-            })
-        }
+        Some(ElementType::Undefined) => Bson::Undefined,
+        Some(ElementType::DbPointer) => Bson::DbPointer(DbPointer {
+            namespace: read_string(reader, utf8_lossy)?,
+            id: ObjectId::from_reader(reader)?,
+        }),
+        Some(ElementType::MaxKey) => Bson::MaxKey,
+        Some(ElementType::MinKey) => Bson::MinKey,
+        None => match options.unknown_element_size(tag) {
+            Some(size) => {
+                let bytes = read_unknown(reader, size)?;
+                options.codecs.check(tag, &bytes)?;
+                Bson::Unknown { tag, bytes }
+            }
+            None => {
+                return Err(Error::UnrecognizedDocumentElementType {
+                    key,
+                    element_type: tag,
+                })
+            }
+        },
     };
 
     Ok((key, val))
@@ -501,3 +593,35 @@ where
     let mut deserializer = raw::Deserializer::new(bytes, true);
     T::deserialize(&mut deserializer)
 }
+
+/// Deserialize an instance of type `T` from a slice of BSON bytes, using the provided options.
+///
+/// Of the options, only [`DeserializerOptions::unknown_elements`],
+/// [`DeserializerOptions::unknown_element_sizes`], [`DeserializerOptions::codecs`] and
+/// [`DeserializerOptions::reject_unsigned_integers`] apply to BSON bytes.
+///
+/// ```
+/// # use bson::{from_slice_with_options, spec::ElementSize, Bson, DeserializerOptions, RawBson};
+/// # use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct Peer<'a> {
+///     #[serde(borrow)]
+///     a: RawBson<'a>,
+/// }
+///
+/// let bytes = b"\x0d\x00\x00\x00\x80a\x00\x05\x00\x00\x00\x01\x00";
+/// let options = DeserializerOptions::builder()
+///     .unknown_elements(true)
+///     .unknown_element_size(0x80, ElementSize::LengthPrefixed)
+///     .build();
+/// let peer: Peer = from_slice_with_options(bytes, options)?;
+/// assert_eq!(peer.a.as_unknown(), Some((0x80, &b"\x05\x00\x00\x00\x01"[..])));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn from_slice_with_options<'de, T>(bytes: &'de [u8], options: DeserializerOptions) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let mut deserializer = raw::Deserializer::new_with_options(bytes, false, options);
+    T::deserialize(&mut deserializer)
+}
@@ -14,11 +14,12 @@ use serde::{
 use crate::{
     oid::ObjectId,
     raw::{RawBinary, RAW_ARRAY_NEWTYPE, RAW_BSON_NEWTYPE, RAW_DOCUMENT_NEWTYPE},
-    spec::{BinarySubtype, ElementType},
+    spec::{BinarySubtype, ElementSize, ElementType},
     uuid::UUID_NEWTYPE_NAME,
     Bson,
     DateTime,
     Decimal128,
+    DeserializerOptions,
     RawDocument,
     Timestamp,
 };
@@ -66,6 +67,12 @@ pub(crate) struct Deserializer<'de> {
     /// but given that there's no difference between deserializing an embedded document and a
     /// top level one, the distinction isn't necessary.
     current_type: ElementType,
+
+    /// The tag and size of the element currently being deserialized if its type is unknown, in
+    /// which case `current_type` is only a placeholder.
+    current_unknown: Option<(u8, ElementSize)>,
+
+    options: DeserializerOptions,
 }
 
 /// Enum used to determine what the type of document being deserialized is in
@@ -77,9 +84,27 @@ enum DocumentType {
 
 impl<'de> Deserializer<'de> {
     pub(crate) fn new(buf: &'de [u8], utf8_lossy: bool) -> Self {
+        Self::new_with_options(buf, utf8_lossy, Default::default())
+    }
+
+    pub(crate) fn new_with_options(
+        buf: &'de [u8],
+        utf8_lossy: bool,
+        options: DeserializerOptions,
+    ) -> Self {
         Self {
             bytes: BsonBuf::new(buf, utf8_lossy),
             current_type: ElementType::EmbeddedDocument,
+            current_unknown: None,
+            options,
+        }
+    }
+
+    /// Describe the type of the element currently being deserialized for use in error messages.
+    fn current_type_name(&self) -> String {
+        match self.current_unknown {
+            Some((tag, _)) => format!("unknown element type {:#04x}", tag),
+            None => format!("{:?}", self.current_type),
         }
     }
 
@@ -200,19 +225,72 @@ impl<'de> Deserializer<'de> {
 
     /// Deserialize the next element type and update `current_type` accordingly.
     /// Returns `None` if a null byte is read.
+    ///
+    /// If the type is unknown but can be passed through according to the options, `current_type`
+    /// is set to `Undefined` as a placeholder and `current_unknown` records the actual tag.
     fn deserialize_next_type(&mut self) -> Result<Option<ElementType>> {
         let tag = read_u8(&mut self.bytes)?;
         if tag == 0 {
             return Ok(None);
         }
 
-        let element_type = ElementType::from(tag)
-            .ok_or_else(|| Error::custom(format!("invalid element type: {}", tag)))?;
+        self.current_unknown = None;
+        let element_type = match ElementType::from(tag) {
+            Some(_) if self.options.rejects(tag) => {
+                return Err(Error::ElementTypeNotAllowed {
+                    key: self.deserialize_cstr()?.into_owned(),
+                    element_type: tag,
+                })
+            }
+            Some(element_type) => element_type,
+            None => match self.options.unknown_element_size(tag) {
+                Some(size) => {
+                    self.current_unknown = Some((tag, size));
+                    ElementType::Undefined
+                }
+                None => return Err(Error::custom(format!("invalid element type: {}", tag))),
+            },
+        };
 
         self.current_type = element_type;
         Ok(Some(element_type))
     }
 
+    /// Read the value of an element of unknown type and visit it as the serde data model
+    /// equivalent of its `$unknown` extended document.
+    fn deserialize_unknown<V>(
+        &mut self,
+        visitor: V,
+        hint: DeserializerHint,
+        tag: u8,
+        size: ElementSize,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let length = match size {
+            ElementSize::Fixed(length) => length,
+            ElementSize::LengthPrefixed => {
+                let mut len = self.bytes.slice(4)?;
+                let len = read_i32(&mut len)?;
+                if !(4..=MAX_BSON_SIZE).contains(&len) {
+                    return Err(Error::invalid_length(
+                        len as usize,
+                        &format!("element length must be between 4 and {}", MAX_BSON_SIZE).as_str(),
+                    ));
+                }
+                len as usize
+            }
+        };
+
+        let bytes = self.bytes.read_slice(length)?;
+        self.options.codecs.check(tag, bytes)?;
+        let mut deserializer = UnknownDeserializer::new(tag, bytes, hint);
+        visitor.visit_map(UnknownAccess {
+            deserializer: &mut deserializer,
+        })
+    }
+
     /// Deserialize the next element in the BSON, using the type of the element along with the
     /// provided hint to determine how to visit the data.
     fn deserialize_next<V>(&mut self, visitor: V, hint: DeserializerHint) -> Result<V::Value>
@@ -222,12 +300,17 @@ impl<'de> Deserializer<'de> {
         if let DeserializerHint::BinarySubtype(expected_st) = hint {
             if self.current_type != ElementType::Binary {
                 return Err(Error::custom(format!(
-                    "expected Binary with subtype {:?}, instead got {:?}",
-                    expected_st, self.current_type
+                    "expected Binary with subtype {:?}, instead got {}",
+                    expected_st,
+                    self.current_type_name()
                 )));
             }
         }
 
+        if let Some((tag, size)) = self.current_unknown {
+            return self.deserialize_unknown(visitor, hint, tag, size);
+        }
+
         match self.current_type {
             ElementType::Int32 => visitor.visit_i32(read_i32(&mut self.bytes)?),
             ElementType::Int64 => visitor.visit_i64(read_i64(&mut self.bytes)?),
@@ -240,6 +323,65 @@ impl<'de> Deserializer<'de> {
             },
             ElementType::Boolean => visitor.visit_bool(read_bool(&mut self.bytes)?),
             ElementType::Null => visitor.visit_unit(),
+            ElementType::RegularExpression => {
+                let mut de = RegexDeserializer::new(&mut *self);
+                visitor.visit_map(RegexAccess::new(&mut de))
+            }
+            ElementType::JavaScriptCode => {
+                let utf8_lossy = self.bytes.utf8_lossy;
+
+                match hint {
+                    DeserializerHint::RawBson => visitor.visit_map(RawBsonAccess::new(
+                        "$code",
+                        BsonContent::Str(self.bytes.read_borrowed_str()?),
+                    )),
+                    _ => {
+                        let code = read_string(&mut self.bytes, utf8_lossy)?;
+                        let doc = Bson::JavaScriptCode(code)
+                            .into_extended_document()
+                            .map_err(Error::custom)?;
+                        visitor.visit_map(MapDeserializer::new(doc))
+                    }
+                }
+            }
+            ElementType::JavaScriptCodeWithScope => {
+                let len = read_i32(&mut self.bytes)?;
+
+                if len < MIN_CODE_WITH_SCOPE_SIZE {
+                    return Err(Error::invalid_length(
+                        len.try_into().unwrap_or(0),
+                        &format!(
+                            "CodeWithScope to be at least {} bytes",
+                            MIN_CODE_WITH_SCOPE_SIZE
+                        )
+                        .as_str(),
+                    ));
+                } else if (self.bytes.bytes_remaining() as i32) < len - 4 {
+                    return Err(Error::invalid_length(
+                        len.try_into().unwrap_or(0),
+                        &format!(
+                            "CodeWithScope to be at most {} bytes",
+                            self.bytes.bytes_remaining()
+                        )
+                        .as_str(),
+                    ));
+                }
+
+                let mut d = CodeWithScopeDeserializer::new(&mut *self, hint, len - 4);
+                let out = visitor.visit_map(CodeWithScopeAccess::new(&mut d));
+
+                if d.length_remaining > 0 {
+                    return Err(Error::invalid_length(
+                        len.try_into().unwrap_or(0),
+                        &format!(
+                            "CodeWithScope length {} bytes greater than actual length",
+                            d.length_remaining
+                        )
+                        .as_str(),
+                    ));
+                }
+                out
+            }
             ElementType::EmbeddedDocument => {
                 self.deserialize_document(visitor, hint, DocumentType::EmbeddedDocument)
             }
@@ -280,6 +422,7 @@ impl<'de> Deserializer<'de> {
                     }
                 }
             }
+            ElementType::ObjectId => self.deserialize_objectid(visitor, hint),
             ElementType::DateTime => {
                 let dti = read_i64(&mut self.bytes)?;
                 let dt = DateTime::from_millis(dti);
@@ -299,6 +442,35 @@ impl<'de> Deserializer<'de> {
                 let d128 = read_f128(&mut self.bytes)?;
                 visitor.visit_map(Decimal128Access::new(d128))
             }
+            ElementType::Symbol => {
+                let utf8_lossy = self.bytes.utf8_lossy;
+
+                match hint {
+                    DeserializerHint::RawBson => visitor.visit_map(RawBsonAccess::new(
+                        "$symbol",
+                        BsonContent::Str(self.bytes.read_borrowed_str()?),
+                    )),
+                    _ => {
+                        let symbol = read_string(&mut self.bytes, utf8_lossy)?;
+                        let doc = Bson::Symbol(symbol)
+                            .into_extended_document()
+                            .map_err(Error::custom)?;
+                        visitor.visit_map(MapDeserializer::new(doc))
+                    }
+                }
+            }
+            ElementType::DbPointer => visitor.visit_map(DbPointerAccess::new(
+                &mut DbPointerDeserializer::new(&mut *self, hint),
+            )),
+            ElementType::Undefined => {
+                visitor.visit_map(RawBsonAccess::new("$undefined", BsonContent::Boolean(true)))
+            }
+            ElementType::MinKey => {
+                visitor.visit_map(RawBsonAccess::new("$minKey", BsonContent::Int32(1)))
+            }
+            ElementType::MaxKey => {
+                visitor.visit_map(RawBsonAccess::new("$maxKey", BsonContent::Int32(1)))
+            }
         }
     }
 }
@@ -339,7 +511,10 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
             ElementType::EmbeddedDocument => {
                 self.access_document(|access| visitor.visit_enum(access))
             }
-            t => Err(Error::custom(format!("expected enum, instead got {:?}", t))),
+            _ => Err(Error::custom(format!(
+                "expected enum, instead got {}",
+                self.current_type_name()
+            ))),
         }
     }
 
@@ -348,6 +523,7 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
         V: serde::de::Visitor<'de>,
     {
         match self.current_type {
+            ElementType::ObjectId => visitor.visit_borrowed_bytes(self.bytes.read_slice(12)?),
             _ => self.deserialize_any(visitor),
         }
     }
@@ -365,8 +541,8 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
             RAW_DOCUMENT_NEWTYPE => {
                 if self.current_type != ElementType::EmbeddedDocument {
                     return Err(serde::de::Error::custom(format!(
-                        "expected raw document, instead got {:?}",
-                        self.current_type
+                        "expected raw document, instead got {}",
+                        self.current_type_name()
                     )));
                 }
 
@@ -375,8 +551,8 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
             RAW_ARRAY_NEWTYPE => {
                 if self.current_type != ElementType::Array {
                     return Err(serde::de::Error::custom(format!(
-                        "expected raw array, instead got {:?}",
-                        self.current_type
+                        "expected raw array, instead got {}",
+                        self.current_type_name()
                     )));
                 }
 
@@ -1129,6 +1305,113 @@ enum BinaryDeserializationStage {
     Done,
 }
 
+/// A `MapAccess` providing access to an element of unknown type being deserialized.
+///
+/// If hinted to be raw BSON, this deserializes the serde data model equivalent
+/// of { "$unknown": { "tag": <u8>, "bytes": <borrowed bytes> } }.
+///
+/// Otherwise, this deserializes the serde data model equivalent of
+/// { "$unknown": { "tag": <hex string>, "base64": <base64 encoded data> } }.
+struct UnknownAccess<'d, 'de> {
+    deserializer: &'d mut UnknownDeserializer<'de>,
+}
+
+impl<'de, 'd> serde::de::MapAccess<'de> for UnknownAccess<'d, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        let field_name = match self.deserializer.stage {
+            UnknownDeserializationStage::TopLevel => "$unknown",
+            UnknownDeserializationStage::Tag => "tag",
+            UnknownDeserializationStage::Bytes => match self.deserializer.hint {
+                DeserializerHint::RawBson => "bytes",
+                _ => "base64",
+            },
+            UnknownDeserializationStage::Done => return Ok(None),
+        };
+
+        seed.deserialize(FieldDeserializer { field_name }).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.deserializer)
+    }
+}
+
+struct UnknownDeserializer<'a> {
+    tag: u8,
+    bytes: &'a [u8],
+    hint: DeserializerHint,
+    stage: UnknownDeserializationStage,
+}
+
+impl<'a> UnknownDeserializer<'a> {
+    fn new(tag: u8, bytes: &'a [u8], hint: DeserializerHint) -> Self {
+        Self {
+            tag,
+            bytes,
+            hint,
+            stage: UnknownDeserializationStage::TopLevel,
+        }
+    }
+}
+
+impl<'de> serde::de::Deserializer<'de> for &mut UnknownDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.stage {
+            UnknownDeserializationStage::TopLevel => {
+                self.stage = UnknownDeserializationStage::Tag;
+                visitor.visit_map(UnknownAccess { deserializer: self })
+            }
+            UnknownDeserializationStage::Tag => {
+                self.stage = UnknownDeserializationStage::Bytes;
+                match self.hint {
+                    DeserializerHint::RawBson => visitor.visit_u8(self.tag),
+                    _ => visitor.visit_string(hex::encode([self.tag])),
+                }
+            }
+            UnknownDeserializationStage::Bytes => {
+                self.stage = UnknownDeserializationStage::Done;
+                match self.hint {
+                    DeserializerHint::RawBson => visitor.visit_borrowed_bytes(self.bytes),
+                    _ => visitor.visit_string(base64::encode(self.bytes)),
+                }
+            }
+            UnknownDeserializationStage::Done => {
+                Err(Error::custom("unknown element fully deserialized already"))
+            }
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
+        bytes byte_buf map struct option unit newtype_struct
+        ignored_any unit_struct tuple_struct tuple enum identifier
+    }
+}
+
+enum UnknownDeserializationStage {
+    TopLevel,
+    Tag,
+    Bytes,
+    Done,
+}
+
 /// A `MapAccess` providing access to a BSON code with scope being deserialized.
 ///
 /// If hinted to be raw BSON, this deserializes the serde data model equivalent
@@ -1455,7 +1738,7 @@ impl<'de, 'a, 'b> serde::de::Deserializer<'de> for &'b mut RegexDeserializer<'de
                 }
             }
             RegexDeserializationStage::Done => {
-                Err(Error::custom("DbPointer fully deserialized already"))
+                Err(Error::custom("Regex fully deserialized already"))
             }
         }
     }
@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    collections::HashMap,
     convert::{TryFrom, TryInto},
     fmt,
     vec,
@@ -21,12 +22,13 @@ use serde::de::{
 use serde_bytes::ByteBuf;
 
 use crate::{
-    bson::{Binary, Bson, Timestamp},
+    bson::{Binary, Bson, DbPointer, JavaScriptCodeWithScope, Regex, Timestamp},
+    codec::ElementCodecRegistry,
     datetime::DateTime,
     document::{Document, IntoIter},
     oid::ObjectId,
     raw::RawBson,
-    spec::BinarySubtype,
+    spec::{BinarySubtype, ElementSize, ElementType},
     uuid::UUID_NEWTYPE_NAME,
     Decimal128,
 };
@@ -74,6 +76,7 @@ impl<'de> Visitor<'de> for ObjectIdVisitor {
         V: MapAccess<'de>,
     {
         match BsonVisitor.visit_map(&mut visitor)? {
+            Bson::ObjectId(oid) => Ok(oid),
             bson => {
                 let err = format!(
                     "expected map containing extended-JSON formatted ObjectId, instead found {}",
@@ -185,7 +188,7 @@ impl<'de> Visitor<'de> for BsonVisitor {
     where
         E: Error,
     {
-        convert_unsigned_to_signed(value as u64)
+        Ok(Bson::UInt32(value))
     }
 
     #[inline]
@@ -264,6 +267,11 @@ impl<'de> Visitor<'de> for BsonVisitor {
 
         while let Some(k) = visitor.next_key::<String>()? {
             match k.as_str() {
+                "$oid" => {
+                    let oid: ObjectId = visitor.next_value()?;
+                    return Ok(Bson::ObjectId(oid));
+                }
+
                 "$numberInt" => {
                     let string: String = visitor.next_value()?;
                     return Ok(Bson::Int32(string.parse().map_err(|_| {
@@ -310,10 +318,10 @@ impl<'de> Visitor<'de> for BsonVisitor {
                         "Infinity" => Bson::Double(std::f64::INFINITY),
                         "-Infinity" => Bson::Double(std::f64::NEG_INFINITY),
                         "NaN" => Bson::Double(std::f64::NAN),
-                        _ => Bson::Int64(string.parse().map_err(|_| {
+                        _ => Bson::Double(string.parse().map_err(|_| {
                             V::Error::invalid_value(
                                 Unexpected::Str(&string),
-                                &"64-bit signed integer as a string",
+                                &"double as a string",
                             )
                         })?),
                     };
@@ -328,6 +336,82 @@ impl<'de> Visitor<'de> for BsonVisitor {
                             .map_err(Error::custom)?,
                     ));
                 }
+                "$regularExpression" => {
+                    let re = visitor.next_value::<extjson::models::RegexBody>()?;
+                    return Ok(Bson::RegularExpression(Regex::new(re.pattern, re.options)));
+                }
+
+                "$code" => {
+                    let code = visitor.next_value::<String>()?;
+                    return match visitor.next_key::<String>()? {
+                        Some(key) if key == "$scope" => {
+                            let scope = visitor.next_value::<Document>()?;
+                            Ok(Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope {
+                                code,
+                                scope,
+                            }))
+                        }
+                        Some(key) => Err(Error::unknown_field(key.as_str(), &["$scope"])),
+                        None => Ok(Bson::JavaScriptCode(code)),
+                    };
+                }
+
+                "$scope" => {
+                    let scope = visitor.next_value::<Document>()?;
+                    return match visitor.next_key::<String>()? {
+                        Some(key) if key == "$code" => {
+                            let code = visitor.next_value::<String>()?;
+                            Ok(Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope {
+                                code,
+                                scope,
+                            }))
+                        }
+                        Some(key) => Err(Error::unknown_field(key.as_str(), &["$code"])),
+                        None => Err(Error::missing_field("$code")),
+                    };
+                }
+
+                "$symbol" => {
+                    let string: String = visitor.next_value()?;
+                    return Ok(Bson::Symbol(string));
+                }
+
+                "$dbPointer" => {
+                    let dbp = visitor.next_value::<extjson::models::DbPointerBody>()?;
+                    return Ok(Bson::DbPointer(DbPointer {
+                        namespace: dbp.ref_ns,
+                        id: dbp.id.parse().map_err(Error::custom)?,
+                    }));
+                }
+
+                "$minKey" => {
+                    let i = visitor.next_value::<u8>()?;
+                    return extjson::models::MinKey { value: i }
+                        .parse()
+                        .map_err(Error::custom);
+                }
+
+                "$maxKey" => {
+                    let i = visitor.next_value::<u8>()?;
+                    return extjson::models::MaxKey { value: i }
+                        .parse()
+                        .map_err(Error::custom);
+                }
+
+                "$undefined" => {
+                    let b = visitor.next_value::<bool>()?;
+                    return extjson::models::Undefined { value: b }
+                        .parse()
+                        .map_err(Error::custom);
+                }
+
+                "$unknown" => {
+                    let body = visitor.next_value::<extjson::models::UnknownBody>()?;
+                    return extjson::models::Unknown { body }
+                        .parse()
+                        .map_err(Error::custom);
+                }
+
                 "$timestamp" => {
                     let ts = visitor.next_value::<extjson::models::TimestampBody>()?;
                     return Ok(Bson::Timestamp(Timestamp {
@@ -346,10 +430,13 @@ impl<'de> Visitor<'de> for BsonVisitor {
                 }
 
                 "$numberDecimal" => {
-                    return Err(Error::custom(
-                        "deserializing decimal128 values from strings is not currently supported"
-                            .to_string(),
-                    ));
+                    let string: String = visitor.next_value()?;
+                    return Ok(Bson::Decimal128(string.parse().map_err(|_| {
+                        V::Error::invalid_value(
+                            Unexpected::Str(&string),
+                            &"decimal128 as a string",
+                        )
+                    })?));
                 }
 
                 "$numberDecimalBytes" => {
@@ -426,13 +513,41 @@ pub struct Deserializer {
 }
 
 /// Options used to configure a [`Deserializer`]. These can also be passed into
-/// [`crate::from_bson_with_options`] and [`crate::from_document_with_options`].
+/// [`crate::from_bson_with_options`] and [`crate::from_document_with_options`], and to the
+/// binary decoders [`crate::from_slice_with_options`], [`Document::from_reader_with_options`]
+/// and [`crate::RawDocument::iter_with_options`].
 #[derive(Debug, Clone, Default)]
 #[non_exhaustive]
 pub struct DeserializerOptions {
     /// Whether the [`Deserializer`] should present itself as human readable or not.
     /// The default is true.
     pub human_readable: Option<bool>,
+
+    /// Whether the binary decoders should keep elements with an unrecognized type tag as
+    /// [`Bson::Unknown`] / [`RawBson::Unknown`] instead of failing. An unknown element can only
+    /// be skipped over if its size is listed in [`DeserializerOptions::unknown_element_sizes`];
+    /// any other unknown element is still an error. This includes the last element of a
+    /// document, whose size is not inferred from the document's length, since nothing marks an
+    /// element as the last one before its value has been read. The default is false.
+    pub unknown_elements: bool,
+
+    /// The size of the value of each unrecognized element type, keyed by type tag. Only
+    /// consulted when [`DeserializerOptions::unknown_elements`] is enabled.
+    pub unknown_element_sizes: HashMap<u8, ElementSize>,
+
+    /// Codecs for custom element types. The binary decoders read elements whose tag has a codec
+    /// as [`Bson::Unknown`] / [`RawBson::Unknown`] whether or not
+    /// [`DeserializerOptions::unknown_elements`] is enabled, and fail if the codec rejects their
+    /// value.
+    pub codecs: ElementCodecRegistry,
+
+    /// Whether the binary decoders should reject the UInt32 (0x14) and UInt64 (0x15) element
+    /// types, which are specific to this crate, so that only input other BSON implementations
+    /// could have produced is accepted. Rejected elements are reported as
+    /// [`Error::ElementTypeNotAllowed`](crate::de::Error::ElementTypeNotAllowed), or as
+    /// [`ErrorKind::ElementTypeNotAllowed`](crate::raw::ErrorKind::ElementTypeNotAllowed) when
+    /// iterating a raw document. The default is false.
+    pub reject_unsigned_integers: bool,
 }
 
 impl DeserializerOptions {
@@ -442,6 +557,24 @@ impl DeserializerOptions {
             options: Default::default(),
         }
     }
+
+    /// Whether elements with the given type tag are rejected by the binary decoders.
+    pub(crate) fn rejects(&self, tag: u8) -> bool {
+        self.reject_unsigned_integers
+            && (tag == ElementType::UInt32 as u8 || tag == ElementType::UInt64 as u8)
+    }
+
+    /// The size of an element with the given unrecognized tag, or `None` if such an element
+    /// cannot be passed through.
+    pub(crate) fn unknown_element_size(&self, tag: u8) -> Option<ElementSize> {
+        if let Some(size) = self.codecs.size(tag) {
+            Some(size)
+        } else if self.unknown_elements {
+            self.unknown_element_sizes.get(&tag).copied()
+        } else {
+            None
+        }
+    }
 }
 
 /// Builder used to construct a [`DeserializerOptions`].
@@ -456,6 +589,30 @@ impl DeserializerOptionsBuilder {
         self
     }
 
+    /// Set the value for [`DeserializerOptions::unknown_elements`].
+    pub fn unknown_elements(mut self, val: bool) -> Self {
+        self.options.unknown_elements = val;
+        self
+    }
+
+    /// Add an entry to [`DeserializerOptions::unknown_element_sizes`].
+    pub fn unknown_element_size(mut self, tag: u8, size: ElementSize) -> Self {
+        self.options.unknown_element_sizes.insert(tag, size);
+        self
+    }
+
+    /// Set the value for [`DeserializerOptions::codecs`].
+    pub fn codecs(mut self, val: ElementCodecRegistry) -> Self {
+        self.options.codecs = val;
+        self
+    }
+
+    /// Set the value for [`DeserializerOptions::reject_unsigned_integers`].
+    pub fn reject_unsigned_integers(mut self, val: bool) -> Self {
+        self.options.reject_unsigned_integers = val;
+        self
+    }
+
     /// Consume this builder and produce a [`DeserializerOptions`].
     pub fn build(self) -> DeserializerOptions {
         self.options
@@ -562,14 +719,19 @@ impl<'de> de::Deserializer<'de> for Deserializer {
                 bytes,
             }) => visitor.visit_byte_buf(bytes),
             binary @ Bson::Binary(..) => visitor.visit_map(MapDeserializer {
-                iter: binary.into_extended_document().into_iter(),
+                iter: binary
+                    .into_extended_document()
+                    .map_err(crate::de::Error::custom)?
+                    .into_iter(),
                 value: None,
                 len: 2,
                 options: self.options,
             }),
             Bson::Decimal128(d) => visitor.visit_map(Decimal128Access::new(d)),
             _ => {
-                let doc = value.into_extended_document();
+                let doc = value
+                    .into_extended_document()
+                    .map_err(crate::de::Error::custom)?;
                 let len = doc.len();
                 visitor.visit_map(MapDeserializer {
                     iter: doc.into_iter(),
@@ -587,6 +749,9 @@ impl<'de> de::Deserializer<'de> for Deserializer {
         V: Visitor<'de>,
     {
         match self.value {
+            Some(Bson::ObjectId(oid)) if !self.is_human_readable() => {
+                visitor.visit_bytes(&oid.bytes())
+            }
             _ => self.deserialize_any(visitor),
         }
     }
@@ -996,6 +1161,41 @@ impl<'de> Deserialize<'de> for Timestamp {
     }
 }
 
+impl<'de> Deserialize<'de> for Regex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        match Bson::deserialize(deserializer)? {
+            Bson::RegularExpression(regex) => Ok(regex),
+            _ => Err(D::Error::custom("expecting Regex")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JavaScriptCodeWithScope {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        match Bson::deserialize(deserializer)? {
+            Bson::JavaScriptCodeWithScope(code_w_scope) => Ok(code_w_scope),
+            _ => Err(D::Error::custom("expecting JavaScriptCodeWithScope")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DbPointer {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        match Bson::deserialize(deserializer)? {
+            Bson::DbPointer(db_pointer) => Ok(db_pointer),
+            _ => Err(D::Error::custom("expecting DbPointer")),
+        }
+    }
+}
 
 impl<'de> Deserialize<'de> for Binary {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
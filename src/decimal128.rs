@@ -1,17 +1,60 @@
 //! [BSON Decimal128](https://github.com/mongodb/specifications/blob/master/source/bson-decimal128/decimal128.rst) data type representation
 
-use std::{convert::TryInto, fmt};
+use std::{
+    cmp::Ordering,
+    convert::{TryFrom, TryInto},
+    error,
+    fmt,
+    result,
+    str::FromStr,
+};
+
+/// The exponent bias of the IEEE 754-2008 decimal128 format.
+const EXPONENT_BIAS: i32 = 6176;
+/// The smallest exponent a finite decimal128 value can have.
+const EXPONENT_MIN: i32 = -6176;
+/// The largest exponent a finite decimal128 value can have.
+const EXPONENT_MAX: i32 = 6111;
+/// The maximum number of significant digits a decimal128 coefficient can hold.
+const MAX_DIGITS: usize = 34;
+/// The largest coefficient representable with 34 decimal digits.
+const MAX_COEFFICIENT: u128 = 9_999_999_999_999_999_999_999_999_999_999_999;
+
+const SIGN_MASK: u128 = 1 << 127;
+const COEFFICIENT_MASK: u128 = (1 << 113) - 1;
+const COMBINATION_NAN: u128 = 0b11111;
+const COMBINATION_INFINITY: u128 = 0b11110;
 
 /// Struct representing a BSON Decimal128 type.
 ///
-/// Currently, this type can only be used to round-trip through BSON. See
-/// [RUST-36](https://jira.mongodb.org/browse/RUST-36) to track the progress towards a complete implementation.
+/// Values are stored in the IEEE 754-2008 binary integer decimal (BID) encoding used by BSON, and
+/// can be parsed from and formatted to strings as described in the
+/// [decimal128 specification](https://github.com/mongodb/specifications/blob/master/source/bson-decimal128/decimal128.rst).
+///
+/// ```rust
+/// use bson::Decimal128;
+///
+/// let d: Decimal128 = "-1.00E-8".parse()?;
+/// assert_eq!(d.to_string(), "-1.00E-8");
+///
+/// let d: Decimal128 = "1e3".parse()?;
+/// assert_eq!(d.to_string(), "1E+3");
+/// # Ok::<(), bson::decimal128::Error>(())
+/// ```
 #[derive(Copy, Clone, PartialEq)]
 pub struct Decimal128 {
     /// BSON bytes containing the decimal128. Stored for round tripping.
     pub(crate) bytes: [u8; 128 / 8],
 }
 
+/// The decoded form of a [`Decimal128`], ignoring its sign.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Kind {
+    NaN,
+    Infinity,
+    Finite { coefficient: u128, exponent: i32 },
+}
+
 impl Decimal128 {
     /// Constructs a new `Decimal128` from the provided raw byte representation.
     pub fn from_bytes(bytes: [u8; 128 / 8]) -> Self {
@@ -29,16 +72,1231 @@ impl Decimal128 {
         let arr: [u8; 128 / 8] = bytes.try_into().map_err(E::custom)?;
         Ok(Decimal128 { bytes: arr })
     }
+
+    /// Returns true if this value is NaN.
+    pub fn is_nan(&self) -> bool {
+        self.kind() == Kind::NaN
+    }
+
+    /// Returns true if this value is positive or negative infinity.
+    pub fn is_infinite(&self) -> bool {
+        self.kind() == Kind::Infinity
+    }
+
+    /// Returns true if this value is neither NaN nor infinite.
+    pub fn is_finite(&self) -> bool {
+        matches!(self.kind(), Kind::Finite { .. })
+    }
+
+    /// Returns true if the sign bit of this value is set, including for `-0` and negative NaNs.
+    pub fn is_sign_negative(&self) -> bool {
+        self.bits() & SIGN_MASK != 0
+    }
+
+    fn bits(&self) -> u128 {
+        u128::from_le_bytes(self.bytes)
+    }
+
+    /// Builds a finite value from its parts. The coefficient and exponent must already be within
+    /// the ranges representable by the format.
+    pub(crate) fn from_parts(negative: bool, coefficient: u128, exponent: i32) -> Self {
+        debug_assert!(coefficient <= MAX_COEFFICIENT);
+        debug_assert!((EXPONENT_MIN..=EXPONENT_MAX).contains(&exponent));
+
+        let mut bits = coefficient | (((exponent + EXPONENT_BIAS) as u128) << 113);
+        if negative {
+            bits |= SIGN_MASK;
+        }
+        Self {
+            bytes: bits.to_le_bytes(),
+        }
+    }
+
+    fn special(negative: bool, combination: u128) -> Self {
+        let mut bits = combination << 122;
+        if negative {
+            bits |= SIGN_MASK;
+        }
+        Self {
+            bytes: bits.to_le_bytes(),
+        }
+    }
+
+    /// Decodes the BID representation of this value.
+    pub(crate) fn kind(&self) -> Kind {
+        let bits = self.bits();
+        let combination = (bits >> 122) & 0b11111;
+        if combination == COMBINATION_NAN {
+            return Kind::NaN;
+        }
+        if combination == COMBINATION_INFINITY {
+            return Kind::Infinity;
+        }
+
+        let (biased_exponent, coefficient) = if (bits >> 125) & 0b11 == 0b11 {
+            // The implied coefficient of this form always exceeds the maximum, so the value is
+            // non-canonical and treated as zero.
+            ((bits >> 111) & 0x3fff, 0)
+        } else {
+            let coefficient = bits & COEFFICIENT_MASK;
+            let coefficient = if coefficient > MAX_COEFFICIENT {
+                0
+            } else {
+                coefficient
+            };
+            ((bits >> 113) & 0x3fff, coefficient)
+        };
+
+        Kind::Finite {
+            coefficient,
+            exponent: biased_exponent as i32 - EXPONENT_BIAS,
+        }
+    }
+}
+
+impl FromStr for Decimal128 {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidString {
+            string: s.to_string(),
+        };
+
+        let (negative, unsigned) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        if unsigned.eq_ignore_ascii_case("nan") {
+            return Ok(Decimal128::special(negative, COMBINATION_NAN));
+        }
+        if unsigned.eq_ignore_ascii_case("inf") || unsigned.eq_ignore_ascii_case("infinity") {
+            return Ok(Decimal128::special(negative, COMBINATION_INFINITY));
+        }
+
+        let (significand, exponent_str) = match unsigned.find(|c| c == 'e' || c == 'E') {
+            Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+            None => (unsigned, None),
+        };
+
+        // Exponents far outside of the representable range are saturated rather than rejected
+        // here so that zeros can still be clamped and everything else reported as an overflow.
+        let mut exponent: i64 = match exponent_str {
+            Some(e) => {
+                let (exp_negative, digits) = match e.as_bytes().first() {
+                    Some(b'-') => (true, &e[1..]),
+                    Some(b'+') => (false, &e[1..]),
+                    _ => (false, e),
+                };
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid());
+                }
+                let magnitude = digits.bytes().fold(0i64, |acc, b| {
+                    (acc * 10 + i64::from(b - b'0')).min(i64::from(u32::MAX))
+                });
+                if exp_negative {
+                    -magnitude
+                } else {
+                    magnitude
+                }
+            }
+            None => 0,
+        };
+
+        let mut digits = Vec::with_capacity(significand.len());
+        let mut seen_point = false;
+        for b in significand.bytes() {
+            match b {
+                b'0'..=b'9' => {
+                    digits.push(b - b'0');
+                    if seen_point {
+                        exponent -= 1;
+                    }
+                }
+                b'.' if !seen_point => seen_point = true,
+                _ => return Err(invalid()),
+            }
+        }
+        if digits.is_empty() {
+            return Err(invalid());
+        }
+
+        let first_significant = digits
+            .iter()
+            .position(|d| *d != 0)
+            .unwrap_or(digits.len());
+        let mut significant = &digits[first_significant..];
+
+        // Only trailing zeros may be dropped to make the value fit, as anything else would
+        // require rounding.
+        if significant.len() > MAX_DIGITS {
+            let (kept, dropped) = significant.split_at(MAX_DIGITS);
+            if dropped.iter().any(|d| *d != 0) {
+                return Err(Error::Inexact);
+            }
+            exponent += dropped.len() as i64;
+            significant = kept;
+        }
+
+        let mut coefficient = significant
+            .iter()
+            .fold(0u128, |acc, d| acc * 10 + u128::from(*d));
+        let mut num_digits = significant.len();
+
+        if coefficient == 0 {
+            exponent = exponent.max(i64::from(EXPONENT_MIN)).min(i64::from(EXPONENT_MAX));
+        } else {
+            while exponent > i64::from(EXPONENT_MAX) && num_digits < MAX_DIGITS {
+                coefficient *= 10;
+                num_digits += 1;
+                exponent -= 1;
+            }
+            if exponent > i64::from(EXPONENT_MAX) {
+                return Err(Error::Overflow);
+            }
+
+            while exponent < i64::from(EXPONENT_MIN) && coefficient % 10 == 0 {
+                coefficient /= 10;
+                exponent += 1;
+            }
+            if exponent < i64::from(EXPONENT_MIN) {
+                return Err(Error::Inexact);
+            }
+        }
+
+        Ok(Decimal128::from_parts(
+            negative,
+            coefficient,
+            exponent as i32,
+        ))
+    }
 }
 
 impl fmt::Debug for Decimal128 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Decimal128(...)")
+        f.debug_tuple("Decimal128").field(&self.to_string()).finish()
     }
 }
 
 impl fmt::Display for Decimal128 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        let (coefficient, exponent) = match self.kind() {
+            Kind::NaN => return f.write_str("NaN"),
+            Kind::Infinity if self.is_sign_negative() => return f.write_str("-Infinity"),
+            Kind::Infinity => return f.write_str("Infinity"),
+            Kind::Finite {
+                coefficient,
+                exponent,
+            } => (coefficient, exponent),
+        };
+
+        let mut out = String::new();
+        if self.is_sign_negative() {
+            out.push('-');
+        }
+
+        let digits = coefficient.to_string();
+        let adjusted_exponent = exponent + (digits.len() as i32 - 1);
+
+        if exponent <= 0 && adjusted_exponent >= -6 {
+            // plain notation
+            let point = digits.len() as i32 + exponent;
+            if exponent == 0 {
+                out.push_str(&digits);
+            } else if point > 0 {
+                let (int, frac) = digits.split_at(point as usize);
+                out.push_str(int);
+                out.push('.');
+                out.push_str(frac);
+            } else {
+                out.push_str("0.");
+                out.push_str(&"0".repeat(-point as usize));
+                out.push_str(&digits);
+            }
+        } else {
+            // scientific notation
+            let (first, rest) = digits.split_at(1);
+            out.push_str(first);
+            if !rest.is_empty() {
+                out.push('.');
+                out.push_str(rest);
+            }
+            out.push('E');
+            if adjusted_exponent >= 0 {
+                out.push('+');
+            }
+            out.push_str(&adjusted_exponent.to_string());
+        }
+
+        f.pad(&out)
+    }
+}
+
+/// The rounding applied when a [`Decimal128`] operation has to discard digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RoundingMode {
+    /// Round to the nearest value, breaking ties towards the even neighbor.
+    HalfEven,
+    /// Round to the nearest value, breaking ties away from zero.
+    HalfUp,
+    /// Round towards zero.
+    Down,
+    /// Round away from zero.
+    Up,
+    /// Round towards positive infinity.
+    Ceiling,
+    /// Round towards negative infinity.
+    Floor,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for RoundingMode {
+    fn default() -> Self {
+        RoundingMode::HalfEven
+    }
+}
+
+impl Decimal128 {
+    /// Returns true if this value is a positive or negative zero.
+    pub fn is_zero(&self) -> bool {
+        matches!(self.kind(), Kind::Finite { coefficient: 0, .. })
+    }
+
+    /// Returns the canonical member of this value's cohort, with trailing zeros removed from the
+    /// coefficient, zeros written as `0` and any NaN payload dropped. The sign is preserved.
+    ///
+    /// ```rust
+    /// use bson::Decimal128;
+    ///
+    /// let d: Decimal128 = "1.2300E+5".parse()?;
+    /// assert_eq!(d.normalize().to_string(), "1.23E+5");
+    /// # Ok::<(), bson::decimal128::Error>(())
+    /// ```
+    pub fn normalize(&self) -> Self {
+        let negative = self.is_sign_negative();
+        match self.kind() {
+            Kind::NaN => Self::special(negative, COMBINATION_NAN),
+            Kind::Infinity => Self::special(negative, COMBINATION_INFINITY),
+            Kind::Finite { coefficient: 0, .. } => Self::from_parts(negative, 0, 0),
+            Kind::Finite {
+                mut coefficient,
+                mut exponent,
+            } => {
+                while coefficient % 10 == 0 && exponent < EXPONENT_MAX {
+                    coefficient /= 10;
+                    exponent += 1;
+                }
+                Self::from_parts(negative, coefficient, exponent)
+            }
+        }
+    }
+
+    /// Adds `rhs` to this value, rounding the result to 34 significant digits with
+    /// [`RoundingMode::HalfEven`]. Returns `None` if either operand is not finite or the result
+    /// overflows.
+    pub fn checked_add(self, rhs: Decimal128) -> Option<Decimal128> {
+        self.add_signed(rhs, rhs.is_sign_negative())
+    }
+
+    /// Subtracts `rhs` from this value, rounding the result to 34 significant digits with
+    /// [`RoundingMode::HalfEven`]. Returns `None` if either operand is not finite or the result
+    /// overflows.
+    pub fn checked_sub(self, rhs: Decimal128) -> Option<Decimal128> {
+        self.add_signed(rhs, !rhs.is_sign_negative())
+    }
+
+    /// Multiplies this value by `rhs`, rounding the result to 34 significant digits with
+    /// [`RoundingMode::HalfEven`]. Returns `None` if either operand is not finite or the result
+    /// overflows.
+    pub fn checked_mul(self, rhs: Decimal128) -> Option<Decimal128> {
+        let (c1, e1) = self.finite_parts()?;
+        let (c2, e2) = rhs.finite_parts()?;
+        let negative = self.is_sign_negative() != rhs.is_sign_negative();
+        let exponent = i64::from(e1) + i64::from(e2);
+
+        let (high, low) = mul_wide(c1, c2);
+        let (n, sticky, exponent) = if high == 0 {
+            (low, false, exponent)
+        } else {
+            // Keep a single digit beyond the 34 that fit so that rounding sees the exact position
+            // of the remainder.
+            let high_digits = num_digits(high);
+            let divisor = 10u128.pow(high_digits - 1);
+            (
+                high * 10u128.pow(35 - high_digits) + low / divisor,
+                low % divisor != 0,
+                exponent + i64::from(high_digits - 1),
+            )
+        };
+
+        round_finite(negative, n, sticky, exponent, RoundingMode::HalfEven)
+            .ok()
+            .map(|(d, _)| d)
+    }
+
+    /// Divides this value by `rhs`, rounding the result to 34 significant digits with
+    /// [`RoundingMode::HalfEven`]. Returns `None` if either operand is not finite, `rhs` is zero
+    /// or the result overflows.
+    pub fn checked_div(self, rhs: Decimal128) -> Option<Decimal128> {
+        let (c1, e1) = self.finite_parts()?;
+        let (c2, e2) = rhs.finite_parts()?;
+        if c2 == 0 {
+            return None;
+        }
+        let negative = self.is_sign_negative() != rhs.is_sign_negative();
+        let mut exponent = i64::from(e1) - i64::from(e2);
+
+        let (mut q, mut r) = (c1 / c2, c1 % c2);
+        while r != 0 && q <= MAX_COEFFICIENT {
+            q = q * 10 + (r * 10) / c2;
+            r = (r * 10) % c2;
+            exponent -= 1;
+        }
+
+        round_finite(negative, q, r != 0, exponent, RoundingMode::HalfEven)
+            .ok()
+            .map(|(d, _)| d)
+    }
+
+    /// Converts this value to an `i64`, rounding any fractional digits with `mode`.
+    pub fn to_i64_rounded(&self, mode: RoundingMode) -> Result<i64> {
+        i64::try_from(self.to_i128_rounded(mode)?).map_err(|_| Error::Overflow)
+    }
+
+    /// Converts this value to a `u64`, rounding any fractional digits with `mode`.
+    pub fn to_u64_rounded(&self, mode: RoundingMode) -> Result<u64> {
+        let (negative, magnitude, _) = self.integer_parts(mode)?;
+        u64::try_from(to_unsigned(negative, magnitude)?).map_err(|_| Error::Overflow)
+    }
+
+    /// Converts this value to an `i128`, rounding any fractional digits with `mode`.
+    pub fn to_i128_rounded(&self, mode: RoundingMode) -> Result<i128> {
+        let (negative, magnitude, _) = self.integer_parts(mode)?;
+        to_signed(negative, magnitude)
+    }
+
+    /// Converts an `i128` to a `Decimal128`, rounding values with more than 34 significant digits
+    /// with `mode`.
+    pub fn from_i128_rounded(value: i128, mode: RoundingMode) -> Self {
+        // An i128 has at most 39 digits, so rounding it can never overflow the exponent.
+        match round_finite(value < 0, value.wrapping_abs() as u128, false, 0, mode) {
+            Ok((d, _)) => d,
+            Err(_) => unreachable!("i128 values always fit in the decimal128 exponent range"),
+        }
+    }
+
+    /// Converts this value to the nearest `f64`. Values outside of the `f64` range become
+    /// infinite or zero.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    fn finite_parts(&self) -> Option<(u128, i32)> {
+        match self.kind() {
+            Kind::Finite {
+                coefficient,
+                exponent,
+            } => Some((coefficient, exponent)),
+            _ => None,
+        }
+    }
+
+    fn add_signed(self, rhs: Decimal128, rhs_negative: bool) -> Option<Decimal128> {
+        let (c1, e1) = self.finite_parts()?;
+        let (c2, e2) = rhs.finite_parts()?;
+        let lhs = (c1, i64::from(e1), self.is_sign_negative());
+        let rhs = (c2, i64::from(e2), rhs_negative);
+
+        // x is the operand with the larger exponent. It is scaled up as far as the intermediate
+        // precision allows; whatever is still not aligned in y only matters for rounding.
+        let ((mut x, mut exponent, x_negative), (y, y_exponent, y_negative)) = if e1 >= e2 {
+            (lhs, rhs)
+        } else {
+            (rhs, lhs)
+        };
+        while exponent > y_exponent && x < 10u128.pow(36) {
+            x *= 10;
+            exponent -= 1;
+        }
+        let (y, sticky) = if exponent > y_exponent {
+            match u32::try_from(exponent - y_exponent)
+                .ok()
+                .and_then(|shift| 10u128.checked_pow(shift))
+            {
+                Some(divisor) => (y / divisor, y % divisor != 0),
+                None => (0, y != 0),
+            }
+        } else {
+            (y, false)
+        };
+
+        let (negative, n, sticky) = if x_negative == y_negative {
+            (x_negative, x + y, sticky)
+        } else if sticky {
+            // x - (y + ε) lies strictly between x - y - 1 and x - y.
+            (x_negative, x - y - 1, true)
+        } else if x >= y {
+            (x_negative, x - y, false)
+        } else {
+            (y_negative, y - x, false)
+        };
+        // An exact zero is only negative when both operands were.
+        let negative = if n == 0 && !sticky {
+            x_negative && y_negative
+        } else {
+            negative
+        };
+
+        round_finite(negative, n, sticky, exponent, RoundingMode::HalfEven)
+            .ok()
+            .map(|(d, _)| d)
+    }
+
+    /// Returns the sign and the magnitude of this value rounded to an integer with `mode`, along
+    /// with whether any nonzero digits were discarded.
+    fn integer_parts(&self, mode: RoundingMode) -> Result<(bool, u128, bool)> {
+        let (coefficient, exponent) = self.finite_parts().ok_or(Error::NotFinite)?;
+        let negative = self.is_sign_negative();
+
+        if coefficient == 0 {
+            Ok((negative, 0, false))
+        } else if exponent >= 0 {
+            let magnitude = 10u128
+                .checked_pow(exponent as u32)
+                .and_then(|p| p.checked_mul(coefficient))
+                .ok_or(Error::Overflow)?;
+            Ok((negative, magnitude, false))
+        } else {
+            let (magnitude, inexact) =
+                round_digits(coefficient, false, exponent.wrapping_abs() as u32, negative, mode);
+            Ok((negative, magnitude, inexact))
+        }
+    }
+
+    /// Returns the sign and the magnitude of this value if it is an integer.
+    fn exact_integer_parts(&self) -> Result<(bool, u128)> {
+        match self.integer_parts(RoundingMode::Down)? {
+            (_, _, true) => Err(Error::Inexact),
+            (negative, magnitude, false) => Ok((negative, magnitude)),
+        }
+    }
+
+    /// Compares the numeric values of `self` and `other`, treating members of the same cohort and
+    /// zeros of either sign as equal.
+    pub(crate) fn value_cmp(&self, other: &Decimal128) -> Option<Ordering> {
+        let magnitude = match (self.kind(), other.kind()) {
+            (Kind::NaN, _) | (_, Kind::NaN) => return None,
+            (Kind::Infinity, Kind::Infinity) => Ordering::Equal,
+            (Kind::Infinity, _) => Ordering::Greater,
+            (_, Kind::Infinity) => Ordering::Less,
+            (
+                Kind::Finite {
+                    coefficient: c1,
+                    exponent: e1,
+                },
+                Kind::Finite {
+                    coefficient: c2,
+                    exponent: e2,
+                },
+            ) => cmp_magnitude((c1, e1), (c2, e2)),
+        };
+        if self.is_zero() && other.is_zero() {
+            return Some(Ordering::Equal);
+        }
+
+        Some(
+            match (self.is_sign_negative(), other.is_sign_negative()) {
+                (false, false) => magnitude,
+                (true, true) => magnitude.reverse(),
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+            },
+        )
+    }
+}
+
+/// Values are ordered numerically, with NaN unordered against everything but an identical NaN.
+///
+/// Because equality compares encodings, values that are numerically equal but encoded differently
+/// (`1.0` and `1.00`, or `0` and `-0`) are never reported as `Equal`. Such values are ordered as in
+/// the IEEE 754 `totalOrder` predicate: negative zero before positive zero, and otherwise by
+/// exponent. Compare [`Decimal128::normalize`]d values to ignore the trailing zeros.
+impl PartialOrd for Decimal128 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        match self.value_cmp(other)? {
+            Ordering::Equal => {}
+            ordering => return Some(ordering),
+        }
+
+        let negative = self.is_sign_negative();
+        if negative != other.is_sign_negative() {
+            return Some(if negative {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            });
+        }
+        let exponent = |d: &Decimal128| d.finite_parts().map_or(0, |(_, e)| e);
+        let ordering = exponent(self)
+            .cmp(&exponent(other))
+            .then_with(|| self.bits().cmp(&other.bits()));
+        Some(if negative {
+            ordering.reverse()
+        } else {
+            ordering
+        })
+    }
+}
+
+impl From<i32> for Decimal128 {
+    fn from(value: i32) -> Self {
+        Decimal128::from(i64::from(value))
+    }
+}
+
+impl From<u32> for Decimal128 {
+    fn from(value: u32) -> Self {
+        Decimal128::from(u64::from(value))
+    }
+}
+
+impl From<i64> for Decimal128 {
+    fn from(value: i64) -> Self {
+        Decimal128::from_parts(value < 0, u128::from(value.wrapping_abs() as u64), 0)
+    }
+}
+
+impl From<u64> for Decimal128 {
+    fn from(value: u64) -> Self {
+        Decimal128::from_parts(false, u128::from(value), 0)
+    }
+}
+
+/// Fails with [`Error::Inexact`] if the value has more than 34 significant digits. Use
+/// [`Decimal128::from_i128_rounded`] to round instead.
+impl TryFrom<i128> for Decimal128 {
+    type Error = Error;
+
+    fn try_from(value: i128) -> Result<Self> {
+        match round_finite(
+            value < 0,
+            value.wrapping_abs() as u128,
+            false,
+            0,
+            RoundingMode::HalfEven,
+        ) {
+            Ok((d, false)) => Ok(d),
+            _ => Err(Error::Inexact),
+        }
+    }
+}
+
+/// Converts to the shortest decimal representation that round trips back to the same `f64`, so
+/// `0.1` becomes `0.1` rather than the exact value of its binary approximation.
+impl From<f64> for Decimal128 {
+    fn from(value: f64) -> Self {
+        format!("{:e}", value)
+            .parse()
+            .expect("formatted f64 values are valid decimal128 strings")
+    }
+}
+
+/// Fails with [`Error::Inexact`] if the value has a fractional part, [`Error::Overflow`] if it is
+/// out of range and [`Error::NotFinite`] for NaN and infinities.
+impl TryFrom<Decimal128> for i64 {
+    type Error = Error;
+
+    fn try_from(d: Decimal128) -> Result<i64> {
+        let (negative, magnitude) = d.exact_integer_parts()?;
+        i64::try_from(to_signed(negative, magnitude)?).map_err(|_| Error::Overflow)
+    }
+}
+
+/// Fails with [`Error::Inexact`] if the value has a fractional part, [`Error::Overflow`] if it is
+/// out of range and [`Error::NotFinite`] for NaN and infinities.
+impl TryFrom<Decimal128> for u64 {
+    type Error = Error;
+
+    fn try_from(d: Decimal128) -> Result<u64> {
+        let (negative, magnitude) = d.exact_integer_parts()?;
+        u64::try_from(to_unsigned(negative, magnitude)?).map_err(|_| Error::Overflow)
+    }
+}
+
+/// Fails with [`Error::Inexact`] if the value has a fractional part, [`Error::Overflow`] if it is
+/// out of range and [`Error::NotFinite`] for NaN and infinities.
+impl TryFrom<Decimal128> for i128 {
+    type Error = Error;
+
+    fn try_from(d: Decimal128) -> Result<i128> {
+        let (negative, magnitude) = d.exact_integer_parts()?;
+        to_signed(negative, magnitude)
+    }
+}
+
+/// Fails with [`Error::Overflow`] if a finite value is outside of the `f64` range and
+/// [`Error::Inexact`] if it has more precision than an `f64` can round trip. NaN and infinities
+/// convert to their `f64` counterparts.
+impl TryFrom<Decimal128> for f64 {
+    type Error = Error;
+
+    fn try_from(d: Decimal128) -> Result<f64> {
+        let f = d.to_f64();
+        if d.is_finite() {
+            if f.is_infinite() {
+                return Err(Error::Overflow);
+            }
+            if Decimal128::from(f).value_cmp(&d) != Some(Ordering::Equal) {
+                return Err(Error::Inexact);
+            }
+        }
+        Ok(f)
+    }
+}
+
+fn num_digits(mut n: u128) -> u32 {
+    let mut digits = 1;
+    while n >= 10 {
+        n /= 10;
+        digits += 1;
+    }
+    digits
+}
+
+fn to_signed(negative: bool, magnitude: u128) -> Result<i128> {
+    if !negative {
+        i128::try_from(magnitude).map_err(|_| Error::Overflow)
+    } else if magnitude <= i128::MIN.wrapping_abs() as u128 {
+        Ok((magnitude as i128).wrapping_neg())
+    } else {
+        Err(Error::Overflow)
+    }
+}
+
+fn to_unsigned(negative: bool, magnitude: u128) -> Result<u128> {
+    if negative && magnitude != 0 {
+        Err(Error::Overflow)
+    } else {
+        Ok(magnitude)
+    }
+}
+
+/// Compares two nonnegative finite values given as coefficient and exponent.
+fn cmp_magnitude((c1, e1): (u128, i32), (c2, e2): (u128, i32)) -> Ordering {
+    match (c1 == 0, c2 == 0) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        (false, false) => {}
+    }
+
+    let adjusted1 = num_digits(c1) as i32 + e1;
+    let adjusted2 = num_digits(c2) as i32 + e2;
+    if adjusted1 != adjusted2 {
+        return adjusted1.cmp(&adjusted2);
+    }
+    // With equal adjusted exponents, scaling the shorter coefficient up to the length of the
+    // longer one always fits.
+    if e1 >= e2 {
+        (c1 * 10u128.pow((e1 - e2) as u32)).cmp(&c2)
+    } else {
+        c1.cmp(&(c2 * 10u128.pow((e2 - e1) as u32)))
+    }
+}
+
+/// Multiplies two coefficients, returning the product as `high * 10^34 + low`.
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const SPLIT: u128 = 100_000_000_000_000_000;
+    const BASE: u128 = MAX_COEFFICIENT + 1;
+
+    let (a1, a0) = (a / SPLIT, a % SPLIT);
+    let (b1, b0) = (b / SPLIT, b % SPLIT);
+    let cross = a1 * b0 + a0 * b1;
+    let low = a0 * b0 + (cross % SPLIT) * SPLIT;
+    let high = a1 * b1 + cross / SPLIT + low / BASE;
+    (high, low % BASE)
+}
+
+/// Divides `n` by `10^digits`, rounding the quotient with `mode`. `sticky` marks that nonzero
+/// digits below `n` were already discarded, i.e. that the true value is slightly larger than `n`.
+///
+/// Returns the quotient and whether any nonzero digits were dropped.
+fn round_digits(
+    n: u128,
+    sticky: bool,
+    digits: u32,
+    negative: bool,
+    mode: RoundingMode,
+) -> (u128, bool) {
+    if digits == 0 {
+        debug_assert!(!sticky);
+        return (n, false);
+    }
+
+    let (quotient, remainder, inexact) = match 10u128.checked_pow(digits) {
+        Some(divisor) => {
+            let remainder = n % divisor;
+            let position = match remainder.cmp(&(divisor / 2)) {
+                Ordering::Equal if sticky => Ordering::Greater,
+                ordering => ordering,
+            };
+            (n / divisor, position, remainder != 0 || sticky)
+        }
+        // Every u128 is below half of a divisor this large.
+        None => (0, Ordering::Less, n != 0 || sticky),
+    };
+
+    let round_up = inexact
+        && match mode {
+            RoundingMode::HalfEven => {
+                remainder == Ordering::Greater
+                    || (remainder == Ordering::Equal && quotient % 2 == 1)
+            }
+            RoundingMode::HalfUp => remainder != Ordering::Less,
+            RoundingMode::Down => false,
+            RoundingMode::Up => true,
+            RoundingMode::Ceiling => !negative,
+            RoundingMode::Floor => negative,
+        };
+
+    (if round_up { quotient + 1 } else { quotient }, inexact)
+}
+
+/// Rounds `n * 10^exponent` (plus a fraction of a unit if `sticky` is set) to a finite value,
+/// discarding digits beyond the 34 digit precision or below the minimum exponent.
+///
+/// Returns the value and whether it is inexact, or [`Error::Overflow`] if the exponent is too large.
+fn round_finite(
+    negative: bool,
+    n: u128,
+    sticky: bool,
+    exponent: i64,
+    mode: RoundingMode,
+) -> Result<(Decimal128, bool)> {
+    let mut drop = i64::from(num_digits(n).saturating_sub(MAX_DIGITS as u32));
+    if exponent + drop < i64::from(EXPONENT_MIN) {
+        drop = i64::from(EXPONENT_MIN) - exponent;
+    }
+
+    let (mut coefficient, inexact) = round_digits(
+        n,
+        sticky,
+        u32::try_from(drop).unwrap_or(u32::MAX),
+        negative,
+        mode,
+    );
+    let mut exponent = exponent + drop;
+    if coefficient > MAX_COEFFICIENT {
+        // Rounding carried into a 35th digit, which is always a zero.
+        coefficient /= 10;
+        exponent += 1;
+    }
+
+    if coefficient == 0 {
+        exponent = exponent.max(i64::from(EXPONENT_MIN)).min(i64::from(EXPONENT_MAX));
+    } else {
+        while exponent > i64::from(EXPONENT_MAX) && coefficient <= MAX_COEFFICIENT / 10 {
+            coefficient *= 10;
+            exponent -= 1;
+        }
+        if exponent > i64::from(EXPONENT_MAX) {
+            return Err(Error::Overflow);
+        }
+    }
+
+    Ok((
+        Decimal128::from_parts(negative, coefficient, exponent as i32),
+        inexact,
+    ))
+}
+
+/// Errors that can occur when parsing or converting a [`Decimal128`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The provided string is not a valid decimal128 literal.
+    #[non_exhaustive]
+    InvalidString { string: String },
+
+    /// The value cannot be represented without rounding.
+    Inexact,
+
+    /// The value is too large in magnitude to be represented.
+    Overflow,
+
+    /// The value is NaN or infinite and has no counterpart in the target type.
+    NotFinite,
+}
+
+/// Alias for `Result<T, decimal128::Error>`.
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidString { string } => {
+                write!(fmt, "invalid decimal128 string: \"{}\"", string)
+            }
+            Error::Inexact => fmt.write_str("decimal128 value cannot be represented exactly"),
+            Error::Overflow => fmt.write_str("decimal128 value is out of range"),
+            Error::NotFinite => fmt.write_str("decimal128 value is NaN or infinite"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cmp::Ordering,
+        convert::{TryFrom, TryInto},
+    };
+
+    use super::{Decimal128, Error, RoundingMode};
+    use crate::{Bson, Document};
+
+    fn decode(canonical_bson: &str) -> Document {
+        let bytes = hex::decode(canonical_bson).unwrap();
+        Document::from_reader(&mut bytes.as_slice()).unwrap()
+    }
+
+    fn encode(d: Decimal128) -> String {
+        let mut bytes = Vec::new();
+        doc! { "d": Bson::Decimal128(d) }.to_writer(&mut bytes).unwrap();
+        hex::encode_upper(bytes)
+    }
+
+    // (description, canonical_bson, canonical string) from the decimal128 spec tests
+    const VALID: &[(&str, &str, &str)] = &[
+        ("Special - Canonical NaN", "180000001364000000000000000000000000000000007C00", "NaN"),
+        ("Special - Canonical Positive Infinity", "180000001364000000000000000000000000000000007800", "Infinity"),
+        ("Special - Canonical Negative Infinity", "18000000136400000000000000000000000000000000F800", "-Infinity"),
+        ("Regular - Smallest", "18000000136400D204000000000000000000000000343000", "0.001234"),
+        ("Regular - Smallest with Trailing Zeros", "1800000013640040EF5A07000000000000000000002A3000", "0.00123400000"),
+        ("Regular - 0.1", "1800000013640001000000000000000000000000003E3000", "0.1"),
+        ("Regular - 0.1234567890123456789012345678901234", "18000000136400F2AF967ED05C82DE3297FF6FDE3CFC2F00", "0.1234567890123456789012345678901234"),
+        ("Regular - 0", "180000001364000000000000000000000000000000403000", "0"),
+        ("Regular - -0", "18000000136400000000000000000000000000000040B000", "-0"),
+        ("Regular - -0.0", "1800000013640000000000000000000000000000003EB000", "-0.0"),
+        ("Regular - 2", "180000001364000200000000000000000000000000403000", "2"),
+        ("Regular - 2.000", "18000000136400D0070000000000000000000000003A3000", "2.000"),
+        ("Regular - Largest", "18000000136400F2AF967ED05C82DE3297FF6FDE3C403000", "1234567890123456789012345678901234"),
+        ("Scientific - Tiniest", "18000000136400FFFFFFFF638E8D37C087ADBE09ED010000", "9.999999999999999999999999999999999E-6143"),
+        ("Scientific - Tiny", "180000001364000100000000000000000000000000000000", "1E-6176"),
+        ("Scientific - Negative Tiny", "180000001364000100000000000000000000000000008000", "-1E-6176"),
+        ("Scientific - Adjusted Exponent Limit", "18000000136400F2AF967ED05C82DE3297FF6FDE3CF02F00", "1.234567890123456789012345678901234E-7"),
+        ("Scientific - Fractional", "1800000013640064000000000000000000000000002CB000", "-1.00E-8"),
+        ("Scientific - 0 with Exponent", "180000001364000000000000000000000000000000205F00", "0E+6000"),
+        ("Scientific - 0 with Negative Exponent", "1800000013640000000000000000000000000000007A2B00", "0E-611"),
+        ("Scientific - No Decimal with Signed Exponent", "180000001364000100000000000000000000000000463000", "1E+3"),
+        ("Scientific - Trailing Zero", "180000001364001A04000000000000000000000000423000", "1.050E+4"),
+        ("Scientific - With Decimal", "180000001364006900000000000000000000000000423000", "1.05E+3"),
+        ("Scientific - Full", "18000000136400FFFFFFFFFFFFFFFFFFFFFFFFFFFF403000", "5192296858534827628530496329220095"),
+        ("Scientific - Large", "18000000136400000000000A5BC138938D44C64D31FE5F00", "1.000000000000000000000000000000000E+6144"),
+        ("Scientific - Largest", "18000000136400FFFFFFFF638E8D37C087ADBE09EDFF5F00", "9.999999999999999999999999999999999E+6144"),
+        ("Regular - Adjusted Exponent Limit", "18000000136400F2AF967ED05C82DE3297FF6FDE3CF22F00", "0.000001234567890123456789012345678901234"),
+        ("Clamped", "180000001364000A00000000000000000000000000FE5F00", "1.0E+6112"),
+    ];
+
+    // (description, bson, string) for encodings that are valid but lossy on output
+    const DEGENERATE_BSON: &[(&str, &str, &str)] = &[
+        ("Special - Negative NaN", "18000000136400000000000000000000000000000000FC00", "NaN"),
+        ("Special - Canonical SNaN", "180000001364000000000000000000000000000000007E00", "NaN"),
+        ("Special - Negative SNaN", "18000000136400000000000000000000000000000000FE00", "NaN"),
+        ("Special - NaN with a payload", "180000001364001200000000000000000000000000007E00", "NaN"),
+        ("Special - Invalid representation treated as 0", "180000001364000000000000000000000000000000106C00", "0"),
+        ("Special - Invalid representation treated as -0", "18000000136400DCBA9876543210DEADBEEF00000010EC00", "-0"),
+        ("Special - Invalid representation treated as 0E3", "18000000136400FFFFFFFFFFFFFFFFFFFFFFFFFFFF116C00", "0E+3"),
+    ];
+
+    // (description, string, canonical_bson) for strings that parse to a canonical value
+    const DEGENERATE_STRINGS: &[(&str, &str, &str)] = &[
+        ("Non-Canonical Parsing - Exponent Normalization", "-100E-10", "1800000013640064000000000000000000000000002CB000"),
+        ("Non-Canonical Parsing - Unsigned Positive Exponent", "1E3", "180000001364000100000000000000000000000000463000"),
+        ("Non-Canonical Parsing - Lowercase Exponent Identifier", "1e+3", "180000001364000100000000000000000000000000463000"),
+        ("Non-Canonical Parsing - Positive Sign", "+1234567890123456789012345678901234", "18000000136400F2AF967ED05C82DE3297FF6FDE3C403000"),
+        ("Non-Canonical Parsing - Leading Zeros", "0001E3", "180000001364000100000000000000000000000000463000"),
+        ("Non-Canonical Parsing - nan", "nan", "180000001364000000000000000000000000000000007C00"),
+        ("Non-Canonical Parsing - nAn", "nAn", "180000001364000000000000000000000000000000007C00"),
+        ("Non-Canonical Parsing - +infinity", "+infinity", "180000001364000000000000000000000000000000007800"),
+        ("Non-Canonical Parsing - infiniTY", "infiniTY", "180000001364000000000000000000000000000000007800"),
+        ("Non-Canonical Parsing - inf", "inf", "180000001364000000000000000000000000000000007800"),
+        ("Non-Canonical Parsing - -Inf", "-Inf", "18000000136400000000000000000000000000000000F800"),
+        ("Rounded Subnormal number", "10E-6177", "180000001364000100000000000000000000000000000000"),
+        ("Clamped", "1E6112", "180000001364000A00000000000000000000000000FE5F00"),
+        ("Clamped zero", "0E+8000", "180000001364000000000000000000000000000000FE5F00"),
+        ("Clamped negative zero", "-0E-8000", "180000001364000000000000000000000000000000008000"),
+        ("Exact rounding", "12345678901234567890123456789012340000", "18000000136400F2AF967ED05C82DE3297FF6FDE3C483000"),
+    ];
+
+    const PARSE_ERRORS: &[&str] = &[
+        "", "E02", "..1", ".", ".e", "+-32.4", " 1", "1 ", "1e", "E", "invalid", "i", "in", "-in",
+        "Na", "-Na", "1.23abc", "1.23abcE+02", "1.23E+0aabs2", "1E+", "1E--3",
+    ];
+
+    #[test]
+    fn valid() {
+        for (description, bson, string) in VALID {
+            let doc = decode(bson);
+            let d = match doc.get("d") {
+                Some(Bson::Decimal128(d)) => *d,
+                other => panic!("{}: expected decimal128, got {:?}", description, other),
+            };
+            assert_eq!(d.to_string(), *string, "{}", description);
+
+            let parsed: Decimal128 = string.parse().unwrap();
+            assert_eq!(encode(parsed), *bson, "{}", description);
+
+            let extjson = Bson::Document(doc.clone()).into_canonical_extjson();
+            assert_eq!(
+                extjson,
+                serde_json::json!({ "d": { "$numberDecimal": string } }),
+                "{}",
+                description
+            );
+            assert_eq!(
+                Bson::Document(doc.clone()).into_relaxed_extjson(),
+                extjson,
+                "{}",
+                description
+            );
+            let from_extjson: Bson = extjson.try_into().unwrap();
+            assert_eq!(from_extjson, Bson::Document(doc), "{}", description);
+        }
+    }
+
+    #[test]
+    fn degenerate() {
+        for (description, bson, string) in DEGENERATE_BSON {
+            let d = match decode(bson).get("d") {
+                Some(Bson::Decimal128(d)) => *d,
+                other => panic!("{}: expected decimal128, got {:?}", description, other),
+            };
+            assert_eq!(d.to_string(), *string, "{}", description);
+        }
+
+        for (description, string, bson) in DEGENERATE_STRINGS {
+            let parsed: Decimal128 = string.parse().unwrap();
+            assert_eq!(encode(parsed), *bson, "{}", description);
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        for s in PARSE_ERRORS {
+            assert!(
+                matches!(s.parse::<Decimal128>(), Err(Error::InvalidString { .. })),
+                "expected {:?} to fail to parse",
+                s
+            );
+        }
+
+        assert_eq!(
+            "100000000000000000000000000000000000000000000000000000000001".parse::<Decimal128>(),
+            Err(Error::Inexact)
+        );
+        assert_eq!("1E-6177".parse::<Decimal128>(), Err(Error::Inexact));
+        assert_eq!(
+            "1.000000000000000000000000000000000E+6145".parse::<Decimal128>(),
+            Err(Error::Overflow)
+        );
+        assert_eq!("1E+99999999999999".parse::<Decimal128>(), Err(Error::Overflow));
+    }
+
+    fn dec(s: &str) -> Decimal128 {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn integer_conversions() {
+        assert_eq!(Decimal128::from(i64::MIN).to_string(), "-9223372036854775808");
+        assert_eq!(Decimal128::from(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(i64::try_from(dec("-1.200E+3")), Ok(-1200));
+        assert_eq!(u64::try_from(dec("18446744073709551615")), Ok(u64::MAX));
+        assert_eq!(i128::try_from(dec("-1E+38")), Ok(-10i128.pow(38)));
+        assert_eq!(u64::try_from(dec("-0")), Ok(0));
+
+        assert_eq!(i64::try_from(dec("1.5")), Err(Error::Inexact));
+        assert_eq!(i64::try_from(dec("9223372036854775808")), Err(Error::Overflow));
+        assert_eq!(u64::try_from(dec("-1")), Err(Error::Overflow));
+        assert_eq!(i128::try_from(dec("1E+39")), Err(Error::Overflow));
+        assert_eq!(i64::try_from(dec("1E+6000")), Err(Error::Overflow));
+        assert_eq!(i64::try_from(dec("NaN")), Err(Error::NotFinite));
+        assert_eq!(i64::try_from(dec("-Infinity")), Err(Error::NotFinite));
+
+        let cases = [
+            ("2.5", RoundingMode::HalfEven, 2),
+            ("3.5", RoundingMode::HalfEven, 4),
+            ("-2.5", RoundingMode::HalfEven, -2),
+            ("2.5000000001", RoundingMode::HalfEven, 3),
+            ("2.5", RoundingMode::HalfUp, 3),
+            ("-2.5", RoundingMode::HalfUp, -3),
+            ("2.4", RoundingMode::HalfUp, 2),
+            ("-2.9", RoundingMode::Down, -2),
+            ("2.1", RoundingMode::Up, 3),
+            ("-2.1", RoundingMode::Ceiling, -2),
+            ("-2.1", RoundingMode::Floor, -3),
+            ("2.0", RoundingMode::Up, 2),
+            ("1E-6176", RoundingMode::Up, 1),
+            ("1E-6176", RoundingMode::HalfUp, 0),
+        ];
+        for (s, mode, expected) in cases.iter() {
+            assert_eq!(dec(s).to_i64_rounded(*mode), Ok(*expected), "{} {:?}", s, mode);
+            assert_eq!(dec(s).to_i128_rounded(*mode), Ok(i128::from(*expected)));
+        }
+        assert_eq!(dec("-0.4").to_u64_rounded(RoundingMode::HalfEven), Ok(0));
+        assert_eq!(
+            dec("-0.4").to_u64_rounded(RoundingMode::Floor),
+            Err(Error::Overflow)
+        );
+
+        assert_eq!(
+            Decimal128::try_from(i128::MIN),
+            Err(Error::Inexact),
+            "i128::MIN has 39 significant digits"
+        );
+        assert_eq!(
+            Decimal128::try_from(-10i128.pow(38)).unwrap().to_string(),
+            "-1.000000000000000000000000000000000E+38"
+        );
+        assert_eq!(
+            Decimal128::from_i128_rounded(i128::MAX, RoundingMode::HalfEven).to_string(),
+            "1.701411834604692317316873037158841E+38"
+        );
+        assert_eq!(
+            Decimal128::from_i128_rounded(i128::MAX, RoundingMode::Down).to_string(),
+            "1.701411834604692317316873037158841E+38"
+        );
+        assert_eq!(
+            Decimal128::from_i128_rounded(i128::MAX, RoundingMode::Up).to_string(),
+            "1.701411834604692317316873037158842E+38"
+        );
+    }
+
+    #[test]
+    fn float_conversions() {
+        assert_eq!(Decimal128::from(0.1).to_string(), "0.1");
+        assert_eq!(Decimal128::from(-1.5e300).to_string(), "-1.5E+300");
+        assert_eq!(Decimal128::from(-0.0).to_string(), "-0");
+        assert_eq!(Decimal128::from(5e-324).to_string(), "5E-324");
+        assert!(Decimal128::from(f64::NAN).is_nan());
+        assert_eq!(Decimal128::from(f64::NEG_INFINITY).to_string(), "-Infinity");
+
+        assert_eq!(dec("0.1").to_f64(), 0.1);
+        assert_eq!(dec("-1.00E-8").to_f64(), -1e-8);
+        assert_eq!(dec("1E+6000").to_f64(), f64::INFINITY);
+        assert_eq!(dec("-Infinity").to_f64(), f64::NEG_INFINITY);
+        assert!(dec("NaN").to_f64().is_nan());
+
+        assert_eq!(f64::try_from(dec("2.50")), Ok(2.5));
+        assert_eq!(f64::try_from(dec("Infinity")), Ok(f64::INFINITY));
+        assert_eq!(f64::try_from(dec("1E+309")), Err(Error::Overflow));
+        assert_eq!(f64::try_from(dec("1E-400")), Err(Error::Inexact));
+        assert_eq!(
+            f64::try_from(dec("0.1234567890123456789")),
+            Err(Error::Inexact)
+        );
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(dec("1.5") < dec("2"));
+        assert!(dec("-2") < dec("-1.5"));
+        assert!(dec("-Infinity") < dec("-1E+6144"));
+        assert!(dec("1E+6144") < dec("Infinity"));
+        assert!(dec("9.99") < dec("1E+1"));
+        assert!(dec("-1") < dec("0"));
+        assert_eq!(dec("NaN").partial_cmp(&dec("1")), None);
+        assert_eq!(dec("NaN").partial_cmp(&dec("NaN")), Some(Ordering::Equal));
+
+        // numerically equal values are ordered consistently with `==`
+        assert!(dec("1.00") < dec("1.0"));
+        assert!(dec("-1.0") < dec("-1.00"));
+        assert!(dec("-0") < dec("0"));
+        assert_eq!(
+            dec("1.00").normalize().partial_cmp(&dec("1.0").normalize()),
+            Some(Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn normalize() {
+        let cases = [
+            ("1.2300E+5", "1.23E+5"),
+            ("100", "1E+2"),
+            ("-0.000", "-0"),
+            ("0E+6000", "0"),
+            ("1.0E+6112", "1.0E+6112"),
+            ("1E-6176", "1E-6176"),
+            ("-Infinity", "-Infinity"),
+        ];
+        for (s, expected) in cases.iter() {
+            assert_eq!(dec(s).normalize().to_string(), *expected, "{}", s);
+        }
+        let nan_with_payload = Decimal128::from_bytes(
+            (0b11111u128 << 122 | 0x12).to_le_bytes(),
+        );
+        assert_eq!(nan_with_payload.normalize(), dec("NaN"));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let add = |a: &str, b: &str| dec(a).checked_add(dec(b)).map(|d| d.to_string());
+        let sub = |a: &str, b: &str| dec(a).checked_sub(dec(b)).map(|d| d.to_string());
+        let mul = |a: &str, b: &str| dec(a).checked_mul(dec(b)).map(|d| d.to_string());
+        let div = |a: &str, b: &str| dec(a).checked_div(dec(b)).map(|d| d.to_string());
+        let some = |s: &str| Some(s.to_string());
+
+        assert_eq!(add("1.1", "2.20"), some("3.30"));
+        assert_eq!(add("-1", "1"), some("0"));
+        assert_eq!(add("-0", "-0"), some("-0"));
+        assert_eq!(add("1E+40", "1"), some("1.000000000000000000000000000000000E+40"));
+        assert_eq!(
+            add("9999999999999999999999999999999999", "0.5"),
+            some("1.000000000000000000000000000000000E+34")
+        );
+        assert_eq!(
+            add("1234567890123456789012345678901234", "0.5"),
+            some("1234567890123456789012345678901234")
+        );
+        assert_eq!(
+            add("1234567890123456789012345678901234", "0.5000000000000000001"),
+            some("1234567890123456789012345678901235")
+        );
+        assert_eq!(
+            sub("1E+34", "0.6"),
+            some("9999999999999999999999999999999999")
+        );
+        assert_eq!(sub("1", "1.5"), some("-0.5"));
+        assert_eq!(add("9.999999999999999999999999999999999E+6144", "1E+6111"), None);
+        assert_eq!(add("1", "NaN"), None);
+
+        assert_eq!(mul("1.5", "-2.0"), some("-3.00"));
+        assert_eq!(mul("0", "-1E+10"), some("-0E+10"));
+        assert_eq!(
+            mul("1234567890123456789012345678901234", "1234567890123456789012345678901234"),
+            some("1.524157875323883675049535156256667E+66")
+        );
+        assert_eq!(
+            mul("9999999999999999999999999999999999", "9999999999999999999999999999999999"),
+            some("9.999999999999999999999999999999998E+67")
+        );
+        assert_eq!(mul("1E-6000", "1E-6000"), some("0E-6176"));
+        assert_eq!(mul("1E+6000", "1E+6000"), None);
+
+        assert_eq!(div("1", "4"), some("0.25"));
+        assert_eq!(div("1.00", "1"), some("1.00"));
+        assert_eq!(div("-10", "4"), some("-2.5"));
+        assert_eq!(div("1", "3"), some("0.3333333333333333333333333333333333"));
+        assert_eq!(div("2", "3"), some("0.6666666666666666666666666666666667"));
+        assert_eq!(div("0", "5"), some("0"));
+        assert_eq!(div("1", "0"), None);
+        assert_eq!(div("1E+6144", "1E-10"), None);
     }
 }
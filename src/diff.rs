@@ -0,0 +1,634 @@
+//! Structural differences between documents.
+//!
+//! [`Document::diff`] compares two versions of a document and lists the paths that were added,
+//! removed or changed, together with their old and new values. Embedded documents are compared
+//! field by field and arrays element by element, so a change deep inside a document is reported
+//! at its own path rather than as a change of the enclosing field.
+//!
+//! A [`DocumentDiff`] displays as a report with one line per change, and can be turned into an
+//! update document with [`DocumentDiff::to_update_document`].
+//!
+//! ```rust
+//! use bson::{diff::{ArrayDiffStrategy, DiffOptions}, doc};
+//!
+//! let old = doc! { "name": "a", "address": { "city": "x", "zip": 1 }, "tags": ["b", "c"] };
+//! let new = doc! { "name": "a", "address": { "city": "y" }, "tags": ["a", "b", "c"], "v": 2 };
+//!
+//! let diff = old.diff(&new);
+//! assert_eq!(
+//!     diff.to_string(),
+//!     "~ address.city: \"x\" -> \"y\"\n\
+//!      - address.zip: 1\n\
+//!      ~ tags.0: \"b\" -> \"a\"\n\
+//!      ~ tags.1: \"c\" -> \"b\"\n\
+//!      + tags.2: \"c\"\n\
+//!      + v: 2"
+//! );
+//!
+//! let options = DiffOptions::builder().arrays(ArrayDiffStrategy::Lcs).build();
+//! let diff = old.diff_with_options(&new, options);
+//! assert_eq!(
+//!     diff.to_string(),
+//!     "~ address.city: \"x\" -> \"y\"\n- address.zip: 1\n+ tags.0: \"a\"\n+ v: 2"
+//! );
+//! assert_eq!(
+//!     diff.to_update_document(),
+//!     doc! {
+//!         "$set": { "tags": ["a", "b", "c"], "address.city": "y", "v": 2 },
+//!         "$unset": { "address.zip": "" },
+//!     }
+//! );
+//! ```
+
+use std::{cmp::Ordering, fmt};
+
+use crate::{ordering, path::KeyPath, Array, Bson, Document};
+
+/// How [`Document::diff`] compares two arrays stored at the same path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArrayDiffStrategy {
+    /// Elements at the same index are compared with each other, and elements past the end of the
+    /// shorter array are reported as added or removed. An element inserted at the front of an
+    /// array shows up as a change of every element after it.
+    ByIndex,
+
+    /// The longest common subsequence of the two arrays is kept, and the elements outside of it
+    /// are reported as added or removed. Where elements are removed and added at the same place,
+    /// they are compared with each other pairwise instead. Removed elements are reported at their
+    /// index in the old array, and added or changed elements at their index in the new array.
+    ///
+    /// This takes time and memory proportional to the product of the lengths of the arrays.
+    Lcs,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for ArrayDiffStrategy {
+    fn default() -> Self {
+        ArrayDiffStrategy::ByIndex
+    }
+}
+
+/// Options used to configure how [`Document::diff`] compares documents.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct DiffOptions {
+    /// How arrays present in both documents are compared. The default value is
+    /// [`ArrayDiffStrategy::ByIndex`].
+    pub arrays: ArrayDiffStrategy,
+
+    /// Whether `Int32`, `Int64`, `UInt32`, `UInt64`, `Double` and `Decimal128` values are equal
+    /// if they hold the same number, rather than only if they also have the same type. NaN is
+    /// equal to NaN. The default value is false.
+    pub numeric_equality: bool,
+
+    /// Whether embedded documents with the same fields in a different order are equal. If not,
+    /// an embedded document whose common fields were reordered is reported as changed as a
+    /// whole. The order of the top-level fields is never compared, as an update cannot change
+    /// it. The default value is false.
+    pub ignore_key_order: bool,
+}
+
+impl DiffOptions {
+    /// Create a builder used to construct a new [`DiffOptions`].
+    pub fn builder() -> DiffOptionsBuilder {
+        DiffOptionsBuilder {
+            options: Default::default(),
+        }
+    }
+}
+
+/// A builder used to construct new [`DiffOptions`] structs.
+pub struct DiffOptionsBuilder {
+    options: DiffOptions,
+}
+
+impl DiffOptionsBuilder {
+    /// Set the value for [`DiffOptions::arrays`].
+    pub fn arrays(mut self, value: ArrayDiffStrategy) -> Self {
+        self.options.arrays = value;
+        self
+    }
+
+    /// Set the value for [`DiffOptions::numeric_equality`].
+    pub fn numeric_equality(mut self, value: bool) -> Self {
+        self.options.numeric_equality = value;
+        self
+    }
+
+    /// Set the value for [`DiffOptions::ignore_key_order`].
+    pub fn ignore_key_order(mut self, value: bool) -> Self {
+        self.options.ignore_key_order = value;
+        self
+    }
+
+    /// Consume this builder and produce a [`DiffOptions`].
+    pub fn build(self) -> DiffOptions {
+        self.options
+    }
+}
+
+/// A single difference between two documents.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Change {
+    /// A value is only present in the new document.
+    Added { path: KeyPath, value: Bson },
+
+    /// A value is only present in the old document.
+    Removed { path: KeyPath, value: Bson },
+
+    /// A value is present in both documents but differs.
+    Changed { path: KeyPath, old: Bson, new: Bson },
+}
+
+impl Change {
+    /// The path at which this change occurred.
+    pub fn path(&self) -> &KeyPath {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+
+    /// The value in the old document, if any.
+    pub fn old_value(&self) -> Option<&Bson> {
+        match self {
+            Change::Added { .. } => None,
+            Change::Removed { value, .. } | Change::Changed { old: value, .. } => Some(value),
+        }
+    }
+
+    /// The value in the new document, if any.
+    pub fn new_value(&self) -> Option<&Bson> {
+        match self {
+            Change::Removed { .. } => None,
+            Change::Added { value, .. } | Change::Changed { new: value, .. } => Some(value),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}: {}", path, value),
+            Change::Removed { path, value } => write!(f, "- {}: {}", path, value),
+            Change::Changed { path, old, new } => write!(f, "~ {}: {} -> {}", path, old, new),
+        }
+    }
+}
+
+/// The differences between two documents, as returned by [`Document::diff`].
+///
+/// The [`Display`](fmt::Display) implementation writes one line per change: `+ path: value` for
+/// an added value, `- path: value` for a removed value and `~ path: old -> new` for a changed
+/// value, with values in the syntax of the [`display`](crate::display) module.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DocumentDiff {
+    changes: Vec<Change>,
+
+    /// The arrays whose elements were removed or shifted, with their new values, which an update
+    /// has to replace as a whole.
+    rewritten: Vec<(KeyPath, Array)>,
+}
+
+impl DocumentDiff {
+    /// The changes, in the order of the fields of the old document followed by the fields only
+    /// present in the new document.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// The number of changes.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Whether the documents are equal.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// An update document that turns the old document into the new one, using `$set` for added
+    /// and changed values and `$unset` for removed ones.
+    ///
+    /// Paths are written in dotted notation with array indexes as segments, as the server
+    /// expects. Since the server cannot remove or insert array elements by path, an array from
+    /// which elements were removed or into which elements were inserted before its end is set
+    /// as a whole. Keys containing `.` are written unescaped, which the server does not accept
+    /// in update paths.
+    pub fn to_update_document(&self) -> Document {
+        let mut set = Document::new();
+        let mut unset = Document::new();
+        let mut rewritten: Vec<&KeyPath> = Vec::new();
+
+        for (path, array) in &self.rewritten {
+            if rewritten.iter().any(|r| is_prefix(r, path)) {
+                continue;
+            }
+            set.insert(path.segments().join("."), array.clone());
+            rewritten.push(path);
+        }
+
+        for change in &self.changes {
+            if rewritten.iter().any(|r| is_prefix(r, change.path())) {
+                continue;
+            }
+            let key = change.path().segments().join(".");
+            match change.new_value() {
+                Some(value) => {
+                    set.insert(key, value.clone());
+                }
+                None => {
+                    unset.insert(key, "");
+                }
+            }
+        }
+
+        let mut update = Document::new();
+        if !set.is_empty() {
+            update.insert("$set", set);
+        }
+        if !unset.is_empty() {
+            update.insert("$unset", unset);
+        }
+        update
+    }
+}
+
+impl fmt::Display for DocumentDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_prefix(prefix: &KeyPath, path: &KeyPath) -> bool {
+    path.segments().starts_with(prefix.segments())
+}
+
+/// Compare `old` to `new` as configured by `options`.
+pub(crate) fn diff_documents(
+    old: &Document,
+    new: &Document,
+    options: &DiffOptions,
+) -> DocumentDiff {
+    let mut differ = Differ {
+        options,
+        path: KeyPath::default(),
+        diff: DocumentDiff::default(),
+    };
+    differ.fields(old, new);
+    differ.diff
+}
+
+/// Whether two numbers are equal regardless of their types, or `None` if either value is not a
+/// number.
+fn numbers_equal(a: &Bson, b: &Bson) -> Option<bool> {
+    let ordering = ordering::compare_numbers(ordering::number(a)?, ordering::number(b)?);
+    Some(ordering == Ordering::Equal)
+}
+
+/// Whether the fields present in both documents appear in the same order.
+fn same_key_order(old: &Document, new: &Document) -> bool {
+    let old_keys = old.keys().filter(|k| new.contains_key(k.as_str()));
+    let new_keys = new.keys().filter(|k| old.contains_key(k.as_str()));
+    old_keys.eq(new_keys)
+}
+
+/// An edit of an array computed from its longest common subsequence.
+enum Edit {
+    Keep,
+    Remove(usize),
+    Add(usize),
+}
+
+struct Differ<'a> {
+    options: &'a DiffOptions,
+    path: KeyPath,
+    diff: DocumentDiff,
+}
+
+impl<'a> Differ<'a> {
+    fn equal(&self, old: &Bson, new: &Bson) -> bool {
+        match (old, new) {
+            (Bson::Document(old), Bson::Document(new)) => {
+                old.len() == new.len()
+                    && (self.options.ignore_key_order || same_key_order(old, new))
+                    && old.iter().all(|(key, old)| match new.get(key) {
+                        Some(new) => self.equal(old, new),
+                        None => false,
+                    })
+            }
+            (Bson::Array(old), Bson::Array(new)) => {
+                old.len() == new.len() && old.iter().zip(new).all(|(old, new)| self.equal(old, new))
+            }
+            _ if self.options.numeric_equality => {
+                numbers_equal(old, new).unwrap_or_else(|| old == new)
+            }
+            _ => old == new,
+        }
+    }
+
+    fn push(&mut self, change: impl FnOnce(KeyPath) -> Change) {
+        let change = change(self.path.clone());
+        self.diff.changes.push(change);
+    }
+
+    /// Run `f` with `segment` appended to the current path.
+    fn at(&mut self, segment: impl Into<String>, f: impl FnOnce(&mut Self)) {
+        self.path.push(segment);
+        f(self);
+        self.path.pop();
+    }
+
+    fn fields(&mut self, old: &Document, new: &Document) {
+        for (key, old_value) in old {
+            self.at(key.as_str(), |d| match new.get(key) {
+                Some(new_value) => d.values(old_value, new_value),
+                None => d.push(|path| Change::Removed {
+                    path,
+                    value: old_value.clone(),
+                }),
+            });
+        }
+        for (key, new_value) in new {
+            if !old.contains_key(key) {
+                self.at(key.as_str(), |d| {
+                    d.push(|path| Change::Added {
+                        path,
+                        value: new_value.clone(),
+                    })
+                });
+            }
+        }
+    }
+
+    fn values(&mut self, old: &Bson, new: &Bson) {
+        match (old, new) {
+            (Bson::Document(old_doc), Bson::Document(new_doc))
+                if self.options.ignore_key_order || same_key_order(old_doc, new_doc) =>
+            {
+                self.fields(old_doc, new_doc)
+            }
+            (Bson::Array(old), Bson::Array(new)) => match self.options.arrays {
+                ArrayDiffStrategy::ByIndex => self.arrays_by_index(old, new),
+                ArrayDiffStrategy::Lcs => self.arrays_by_lcs(old, new),
+            },
+            _ if self.equal(old, new) => {}
+            _ => self.push(|path| Change::Changed {
+                path,
+                old: old.clone(),
+                new: new.clone(),
+            }),
+        }
+    }
+
+    fn arrays_by_index(&mut self, old: &Array, new: &Array) {
+        if old.len() > new.len() {
+            self.rewrite(new);
+        }
+        for (i, (old, new)) in old.iter().zip(new).enumerate() {
+            self.at(i.to_string(), |d| d.values(old, new));
+        }
+        for (i, old) in old.iter().enumerate().skip(new.len()) {
+            self.at(i.to_string(), |d| {
+                d.push(|path| Change::Removed {
+                    path,
+                    value: old.clone(),
+                })
+            });
+        }
+        for (i, new) in new.iter().enumerate().skip(old.len()) {
+            self.at(i.to_string(), |d| {
+                d.push(|path| Change::Added {
+                    path,
+                    value: new.clone(),
+                })
+            });
+        }
+    }
+
+    fn arrays_by_lcs(&mut self, old: &Array, new: &Array) {
+        let edits = self.lcs_edits(old, new);
+
+        let mut removed = 0;
+        let mut inserted_before_end = false;
+        for edit in &edits {
+            match *edit {
+                Edit::Remove(_) => removed += 1,
+                Edit::Add(j) if j < old.len() => inserted_before_end = true,
+                _ => {}
+            }
+        }
+        if removed > 0 || inserted_before_end {
+            self.rewrite(new);
+        }
+
+        // each run of edits between kept elements is a gap, whose removed and added elements
+        // are paired up in order
+        for gap in edits.split(|edit| matches!(edit, Edit::Keep)) {
+            let removals = gap.iter().filter_map(|edit| match *edit {
+                Edit::Remove(i) => Some(i),
+                _ => None,
+            });
+            let mut additions = gap.iter().filter_map(|edit| match *edit {
+                Edit::Add(j) => Some(j),
+                _ => None,
+            });
+
+            for i in removals {
+                match additions.next() {
+                    Some(j) => self.at(j.to_string(), |d| d.values(&old[i], &new[j])),
+                    None => self.at(i.to_string(), |d| {
+                        d.push(|path| Change::Removed {
+                            path,
+                            value: old[i].clone(),
+                        })
+                    }),
+                }
+            }
+            for j in additions {
+                self.at(j.to_string(), |d| {
+                    d.push(|path| Change::Added {
+                        path,
+                        value: new[j].clone(),
+                    })
+                });
+            }
+        }
+    }
+
+    /// The edits that turn `old` into `new` while keeping their longest common subsequence.
+    fn lcs_edits(&self, old: &Array, new: &Array) -> Vec<Edit> {
+        // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+        let width = new.len() + 1;
+        let mut lengths = vec![0_usize; (old.len() + 1) * width];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lengths[i * width + j] = if self.equal(&old[i], &new[j]) {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+
+        let mut edits = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i == old.len() {
+                edits.push(Edit::Add(j));
+                j += 1;
+            } else if j == new.len() {
+                edits.push(Edit::Remove(i));
+                i += 1;
+            } else if lengths[i * width + j] == lengths[(i + 1) * width + j + 1] + 1
+                && self.equal(&old[i], &new[j])
+            {
+                edits.push(Edit::Keep);
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+                edits.push(Edit::Remove(i));
+                i += 1;
+            } else {
+                edits.push(Edit::Add(j));
+                j += 1;
+            }
+        }
+        edits
+    }
+
+    /// Record that the array at the current path must be replaced as a whole by an update.
+    fn rewrite(&mut self, new: &Array) {
+        self.diff.rewritten.push((self.path.clone(), new.clone()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArrayDiffStrategy, Change, DiffOptions};
+    use crate::{path::KeyPath, Bson, Decimal128};
+
+    #[test]
+    fn arrays() {
+        let old = doc! { "a": [1, { "x": 1 }, 3, 4], "b": [[1, 2], 3] };
+        let new = doc! { "a": [0, 1, { "x": 2 }, 4], "b": [[1, 2], 3, 5] };
+
+        let by_index = old.diff(&new);
+        assert_eq!(
+            by_index.to_string(),
+            "~ a.0: 1 -> 0\n\
+             ~ a.1: { \"x\": 1 } -> 1\n\
+             ~ a.2: 3 -> { \"x\": 2 }\n\
+             + b.2: 5"
+        );
+        assert_eq!(
+            by_index.to_update_document(),
+            doc! { "$set": { "a.0": 0, "a.1": 1, "a.2": { "x": 2 }, "b.2": 5 } }
+        );
+
+        let lcs = old.diff_with_options(
+            &new,
+            DiffOptions::builder()
+                .arrays(ArrayDiffStrategy::Lcs)
+                .build(),
+        );
+        assert_eq!(
+            lcs.changes(),
+            &[
+                Change::Added {
+                    path: KeyPath::from_segments(vec!["a", "0"]),
+                    value: Bson::Int32(0),
+                },
+                Change::Changed {
+                    path: KeyPath::from_segments(vec!["a", "2", "x"]),
+                    old: Bson::Int32(1),
+                    new: Bson::Int32(2),
+                },
+                Change::Removed {
+                    path: KeyPath::from_segments(vec!["a", "2"]),
+                    value: Bson::Int32(3),
+                },
+                Change::Added {
+                    path: KeyPath::from_segments(vec!["b", "2"]),
+                    value: Bson::Int32(5),
+                },
+            ]
+        );
+        assert_eq!(
+            lcs.to_update_document(),
+            doc! { "$set": { "a": [0, 1, { "x": 2 }, 4], "b.2": 5 } }
+        );
+
+        let shortened = doc! { "a": [1, { "x": 1 }], "b": [[1], 3] };
+        assert_eq!(
+            old.diff(&shortened).to_update_document(),
+            doc! { "$set": { "a": [1, { "x": 1 }], "b.0": [1] } }
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn numbers_and_key_order() {
+        let old = doc! {
+            "i": 1,
+            "l": 2_i64,
+            "d": 3.0,
+            "u": Bson::UInt64(u64::MAX),
+            "big": Bson::Int64(i64::MAX),
+            "nested": { "a": 1, "b": 2 },
+        };
+        let new = doc! {
+            "i": 1.0,
+            "l": Bson::UInt32(2),
+            "d": 3,
+            "u": Bson::UInt64(u64::MAX),
+            "big": i64::MAX as f64,
+            "nested": { "b": 2, "a": 1_i64 },
+        };
+
+        assert_eq!(
+            old.diff(&new)
+                .changes()
+                .iter()
+                .map(|c| c.path().to_string())
+                .collect::<Vec<_>>(),
+            vec!["i", "l", "d", "big", "nested"]
+        );
+
+        let options = DiffOptions::builder()
+            .numeric_equality(true)
+            .ignore_key_order(true)
+            .build();
+        let diff = old.diff_with_options(&new, options.clone());
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff.changes()[0].path().to_string(), "big");
+        assert_eq!(diff.changes()[0].old_value(), Some(&Bson::Int64(i64::MAX)));
+        let decimal = doc! { "n": "1.0".parse::<Decimal128>().unwrap(), "nan": f64::NAN };
+        assert!(decimal
+            .diff_with_options(&doc! { "n": 1, "nan": f64::NAN }, options.clone())
+            .is_empty());
+
+        let options = DiffOptions::builder().ignore_key_order(true).build();
+        assert_eq!(
+            old.diff_with_options(&new, options).to_update_document(),
+            doc! {
+                "$set": {
+                    "i": 1.0,
+                    "l": Bson::UInt32(2),
+                    "d": 3,
+                    "big": i64::MAX as f64,
+                    "nested.a": 1_i64,
+                },
+            }
+        );
+    }
+}
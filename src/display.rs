@@ -0,0 +1,460 @@
+//! Human-readable formatting of [`Bson`] values and [`Document`]s.
+//!
+//! The [`Display`](fmt::Display) implementations of [`Bson`] and [`Document`] write values in the
+//! syntax of the mongo shell, so that output can be pasted into the shell or read back with
+//! [`extjson::shell`](crate::extjson::shell):
+//!
+//! | Type                      | Output                                 |
+//! |---------------------------|----------------------------------------|
+//! | `Double`                  | `1.0`, `1e-7`, `NaN`, `-Infinity`      |
+//! | `Int32`                   | `1`                                    |
+//! | `Int64`                   | `NumberLong(1)`                        |
+//! | `UInt32` / `UInt64`       | `{ "$numberUInt32": "1" }`             |
+//! | `DateTime`                | `ISODate("2020-05-30T18:47:10.120Z")`  |
+//! | `Binary`                  | `BinData(0, "AQID")`, `UUID("…")`      |
+//! | `Timestamp`               | `Timestamp(1, 2)`                      |
+//! | `RegularExpression`       | `/^a/i`                                |
+//! | `JavaScriptCode`          | `{ "$code": "f()" }`                   |
+//! | `JavaScriptCodeWithScope` | `{ "$code": "f()", "$scope": { … } }`  |
+//! | `Decimal128`              | `NumberDecimal("1.5")`                 |
+//! | `ObjectId`                | `ObjectId("5ed2a84eb8bf0a6ef1ad4c5e")` |
+//! | `Symbol`                  | `{ "$symbol": "s" }`                   |
+//! | `MinKey` / `MaxKey`       | `MinKey`, `MaxKey`                     |
+//! | `Undefined`               | `undefined`                            |
+//! | `DbPointer`               | `DBPointer("db.c", ObjectId("…"))`     |
+//!
+//! The output depends only on the value, which makes it suitable for snapshot tests. The
+//! alternate flag (`{:#}`) spreads documents and arrays over multiple lines, indented by two
+//! spaces per level.
+//!
+//! ```rust
+//! # use bson::doc;
+//! let doc = doc! { "a": 1, "b": [2.0, "x"], "c": {} };
+//! assert_eq!(doc.to_string(), r#"{ "a": 1, "b": [2.0, "x"], "c": {} }"#);
+//! assert_eq!(
+//!     format!("{:#}", doc),
+//!     "{\n  \"a\": 1,\n  \"b\": [\n    2.0,\n    \"x\"\n  ],\n  \"c\": {}\n}"
+//! );
+//! ```
+//!
+//! [`DisplayOptions`] additionally allow long strings and binaries to be truncated, which keeps
+//! log lines short. Truncated output is marked with `...` and no longer parses as the original
+//! value.
+//!
+//! ```rust
+//! # use bson::{doc, display::DisplayOptions};
+//! let doc = doc! { "s": "abcdefgh" };
+//! let options = DisplayOptions::builder().max_string_length(3).build();
+//! assert_eq!(doc.display_with_options(options).to_string(), r#"{ "s": "abc..." }"#);
+//! ```
+
+use std::fmt::{self, Write};
+
+use crate::{
+    extjson::shell,
+    Binary,
+    Bson,
+    Document,
+    JavaScriptCodeWithScope,
+};
+
+/// Options used to configure how a [`Bson`] or [`Document`] is displayed.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct DisplayOptions {
+    /// Whether to spread documents and arrays over multiple lines, as the alternate flag (`{:#}`)
+    /// does. The default value is false.
+    pub pretty: bool,
+
+    /// The number of characters after which strings are truncated. Keys are never truncated.
+    /// The default value is `None`, which displays strings in full.
+    pub max_string_length: Option<usize>,
+
+    /// The number of bytes after which binary values are truncated. The default value is `None`,
+    /// which displays binaries in full.
+    pub max_binary_length: Option<usize>,
+}
+
+impl DisplayOptions {
+    /// Create a builder used to construct a new [`DisplayOptions`].
+    pub fn builder() -> DisplayOptionsBuilder {
+        DisplayOptionsBuilder {
+            options: Default::default(),
+        }
+    }
+}
+
+/// A builder used to construct new [`DisplayOptions`] structs.
+pub struct DisplayOptionsBuilder {
+    options: DisplayOptions,
+}
+
+impl DisplayOptionsBuilder {
+    /// Set the value for [`DisplayOptions::pretty`].
+    pub fn pretty(mut self, value: bool) -> Self {
+        self.options.pretty = value;
+        self
+    }
+
+    /// Set the value for [`DisplayOptions::max_string_length`].
+    pub fn max_string_length(mut self, value: impl Into<Option<usize>>) -> Self {
+        self.options.max_string_length = value.into();
+        self
+    }
+
+    /// Set the value for [`DisplayOptions::max_binary_length`].
+    pub fn max_binary_length(mut self, value: impl Into<Option<usize>>) -> Self {
+        self.options.max_binary_length = value.into();
+        self
+    }
+
+    /// Consume this builder and produce a [`DisplayOptions`].
+    pub fn build(self) -> DisplayOptions {
+        self.options
+    }
+}
+
+/// A [`Bson`] or [`Document`] paired with the [`DisplayOptions`] to display it with.
+///
+/// This is returned by [`Bson::display_with_options`] and [`Document::display_with_options`].
+#[derive(Clone, Debug)]
+pub struct Formatted<'a> {
+    value: Value<'a>,
+    options: DisplayOptions,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Value<'a> {
+    Bson(&'a Bson),
+    Document(&'a Document),
+}
+
+impl<'a> Formatted<'a> {
+    pub(crate) fn bson(bson: &'a Bson, options: DisplayOptions) -> Self {
+        Self {
+            value: Value::Bson(bson),
+            options,
+        }
+    }
+
+    pub(crate) fn document(doc: &'a Document, options: DisplayOptions) -> Self {
+        Self {
+            value: Value::Document(doc),
+            options,
+        }
+    }
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pretty = self.options.pretty || f.alternate();
+        let mut printer = Printer {
+            out: f,
+            options: &self.options,
+            pretty,
+            indent: 0,
+        };
+        match self.value {
+            Value::Bson(bson) => printer.bson(bson),
+            Value::Document(doc) => printer.document(doc),
+        }
+    }
+}
+
+struct Printer<'a, W> {
+    out: W,
+    options: &'a DisplayOptions,
+    pretty: bool,
+    indent: usize,
+}
+
+impl<W: Write> Printer<'_, W> {
+    fn bson(&mut self, bson: &Bson) -> fmt::Result {
+        match bson {
+            Bson::String(s) => self.string(s),
+            Bson::Array(array) => {
+                if array.is_empty() {
+                    return self.out.write_str("[]");
+                }
+                self.out.write_str("[")?;
+                self.indent += 1;
+                for (i, value) in array.iter().enumerate() {
+                    self.separator(i, false)?;
+                    self.bson(value)?;
+                }
+                self.indent -= 1;
+                self.close(']', false)
+            }
+            Bson::Document(doc) => self.document(doc),
+            Bson::Int32(i) => write!(self.out, "{}", i),
+            Bson::UInt32(i) => write!(self.out, "{{ \"$numberUInt32\": \"{}\" }}", i),
+            Bson::UInt64(i) => write!(self.out, "{{ \"$numberUInt64\": \"{}\" }}", i),
+            Bson::Binary(binary) => self.binary(binary),
+            Bson::JavaScriptCode(code) => {
+                self.out.write_str("{ \"$code\": ")?;
+                self.string(code)?;
+                self.out.write_str(" }")
+            }
+            Bson::Symbol(symbol) => {
+                self.out.write_str("{ \"$symbol\": ")?;
+                self.string(symbol)?;
+                self.out.write_str(" }")
+            }
+            Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope { code, scope }) => {
+                self.out.write_str("{")?;
+                self.indent += 1;
+                self.separator(0, true)?;
+                self.out.write_str("\"$code\": ")?;
+                self.string(code)?;
+                self.separator(1, true)?;
+                self.out.write_str("\"$scope\": ")?;
+                self.document(scope)?;
+                self.indent -= 1;
+                self.close('}', true)
+            }
+            _ => shell::write_bson(&mut self.out, bson),
+        }
+    }
+
+    fn document(&mut self, doc: &Document) -> fmt::Result {
+        if doc.is_empty() {
+            return self.out.write_str("{}");
+        }
+        self.out.write_str("{")?;
+        self.indent += 1;
+        for (i, (key, value)) in doc.iter().enumerate() {
+            self.separator(i, true)?;
+            shell::write_string(&mut self.out, key)?;
+            self.out.write_str(": ")?;
+            self.bson(value)?;
+        }
+        self.indent -= 1;
+        self.close('}', true)
+    }
+
+    fn binary(&mut self, binary: &Binary) -> fmt::Result {
+        match self.options.max_binary_length {
+            Some(max) if binary.bytes.len() > max => write!(
+                self.out,
+                "BinData({}, \"{}...\")",
+                u8::from(binary.subtype),
+                base64::encode(&binary.bytes[..max])
+            ),
+            _ => shell::write_binary(&mut self.out, binary),
+        }
+    }
+
+    fn string(&mut self, s: &str) -> fmt::Result {
+        match self.options.max_string_length {
+            Some(max) => match s.char_indices().nth(max) {
+                Some((end, _)) => {
+                    // Write the escaped prefix without its closing quote, then mark the cut.
+                    let mut escaped = String::new();
+                    shell::write_string(&mut escaped, &s[..end])?;
+                    escaped.pop();
+                    write!(self.out, "{}...\"", escaped)
+                }
+                None => shell::write_string(&mut self.out, s),
+            },
+            None => shell::write_string(&mut self.out, s),
+        }
+    }
+
+    /// Writes whatever precedes the `index`th element of the enclosing document or array. When
+    /// compact, documents are padded with spaces inside their braces and arrays are not.
+    fn separator(&mut self, index: usize, padded: bool) -> fmt::Result {
+        if index > 0 {
+            self.out.write_char(',')?;
+        }
+        if self.pretty {
+            self.newline()
+        } else if index > 0 || padded {
+            self.out.write_char(' ')
+        } else {
+            Ok(())
+        }
+    }
+
+    fn close(&mut self, bracket: char, padded: bool) -> fmt::Result {
+        if self.pretty {
+            self.newline()?;
+        } else if padded {
+            self.out.write_char(' ')?;
+        }
+        self.out.write_char(bracket)
+    }
+
+    fn newline(&mut self) -> fmt::Result {
+        self.out.write_char('\n')?;
+        for _ in 0..self.indent {
+            self.out.write_str("  ")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Binary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        shell::write_binary(f, self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DisplayOptions;
+    use crate::{
+        extjson::shell,
+        oid::ObjectId,
+        spec::BinarySubtype,
+        Binary,
+        Bson,
+        DateTime,
+        DbPointer,
+        Decimal128,
+        JavaScriptCodeWithScope,
+        Timestamp,
+    };
+
+    #[test]
+    fn display() {
+        let doc = doc! {
+            "_id": ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+            "double": 3.0,
+            "nan": f64::NAN,
+            "string": "line\nbreak \"quoted\"",
+            "array": [1, [], {}],
+            "long": 5i64,
+            "u32": 7u32,
+            "u64": u64::MAX,
+            "ts": Timestamp { time: 1, increment: 2 },
+            "bin": Binary { subtype: BinarySubtype::Md5, bytes: vec![1, 2, 3] },
+            "uuid": Binary { subtype: BinarySubtype::Uuid, bytes: vec![0xab; 16] },
+            "date": DateTime::from_millis(1_590_864_430_120),
+            "dec": Bson::Decimal128("-1.00E-8".parse::<Decimal128>().unwrap()),
+            "code": Bson::JavaScriptCode("f()".to_string()),
+            "code scope": JavaScriptCodeWithScope { code: "x".to_string(), scope: doc! { "x": 1 } },
+            "null": Bson::Null,
+            "bool": true,
+            "symbol": Bson::Symbol("s".to_string()),
+            "min": Bson::MinKey,
+            "max": Bson::MaxKey,
+            "undefined": Bson::Undefined,
+            "ptr": DbPointer {
+                namespace: "db.c".to_string(),
+                id: ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+            },
+        };
+
+        let printed = doc.to_string();
+        assert_eq!(
+            printed,
+            concat!(
+                r#"{ "_id": ObjectId("5ed2a84eb8bf0a6ef1ad4c5e"), "double": 3.0, "nan": NaN, "#,
+                r#""string": "line\nbreak \"quoted\"", "array": [1, [], {}], "#,
+                r#""long": NumberLong(5), "u32": { "$numberUInt32": "7" }, "#,
+                r#""u64": { "$numberUInt64": "18446744073709551615" }, "ts": Timestamp(1, 2), "#,
+                r#""bin": BinData(5, "AQID"), "uuid": UUID("abababab-abab-abab-abab-abababababab"), "#,
+                r#""date": ISODate("2020-05-30T18:47:10.120Z"), "#,
+                r#""dec": NumberDecimal("-1.00E-8"), "code": { "$code": "f()" }, "#,
+                r#""code scope": { "$code": "x", "$scope": { "x": 1 } }, "null": null, "bool": true, "#,
+                r#""symbol": { "$symbol": "s" }, "min": MinKey, "max": MaxKey, "#,
+                r#""undefined": undefined, "#,
+                r#""ptr": DBPointer("db.c", ObjectId("5ed2a84eb8bf0a6ef1ad4c5e")) }"#,
+            )
+        );
+        assert_eq!(Bson::Document(doc.clone()).to_string(), printed);
+
+        let parsed = shell::document_from_str(&printed).unwrap();
+        assert_eq!(parsed.to_string(), printed);
+
+        let pretty = format!("{:#}", doc);
+        assert_eq!(
+            shell::document_from_str(&pretty).unwrap().to_string(),
+            printed
+        );
+        assert_eq!(
+            doc.display_with_options(DisplayOptions::builder().pretty(true).build())
+                .to_string(),
+            pretty
+        );
+
+        assert_eq!(
+            Binary {
+                subtype: BinarySubtype::Generic,
+                bytes: vec![1, 2, 3]
+            }
+            .to_string(),
+            r#"BinData(0, "AQID")"#
+        );
+    }
+
+    #[test]
+    fn int64_extremes() {
+        assert_eq!(
+            Bson::Int64(i64::MIN).to_string(),
+            "NumberLong(\"-9223372036854775808\")"
+        );
+        assert_eq!(
+            doc! { "a": i64::MIN, "b": i64::MAX }.to_string(),
+            concat!(
+                r#"{ "a": NumberLong("-9223372036854775808"), "#,
+                r#""b": NumberLong("9223372036854775807") }"#,
+            )
+        );
+    }
+
+    #[test]
+    fn pretty() {
+        let doc = doc! { "a": { "b": [1, { "c": [] }], "d": {} }, "e": "f" };
+        assert_eq!(
+            format!("{:#}", doc),
+            concat!(
+                "{\n",
+                "  \"a\": {\n",
+                "    \"b\": [\n",
+                "      1,\n",
+                "      {\n",
+                "        \"c\": []\n",
+                "      }\n",
+                "    ],\n",
+                "    \"d\": {}\n",
+                "  },\n",
+                "  \"e\": \"f\"\n",
+                "}",
+            )
+        );
+        assert_eq!(format!("{:#}", Bson::Array(vec![])), "[]");
+        assert_eq!(format!("{:#}", Bson::Int32(1)), "1");
+    }
+
+    #[test]
+    fn truncation() {
+        let doc = doc! {
+            "short": "abc",
+            "long": "héllo wörld",
+            "quoted": "\"\"\"\"",
+            "bin": Binary { subtype: BinarySubtype::Generic, bytes: vec![0xff; 10] },
+            "uuid": Binary { subtype: BinarySubtype::Uuid, bytes: vec![0xab; 16] },
+            "keys are kept in full": [{ "nested": "truncated as well" }],
+        };
+        let options = DisplayOptions::builder()
+            .max_string_length(5)
+            .max_binary_length(3)
+            .build();
+        assert_eq!(
+            doc.display_with_options(options.clone()).to_string(),
+            concat!(
+                r#"{ "short": "abc", "long": "héllo...", "quoted": "\"\"\"\"", "#,
+                r#""bin": BinData(0, "////..."), "uuid": BinData(4, "q6ur..."), "#,
+                r#""keys are kept in full": [{ "nested": "trunc..." }] }"#,
+            )
+        );
+
+        let string = Bson::String("abcdef".to_string());
+        assert_eq!(
+            string.display_with_options(options).to_string(),
+            r#""abcde...""#
+        );
+        assert_eq!(string.to_string(), r#""abcdef""#);
+    }
+}
@@ -14,10 +14,20 @@ use serde::de::Error;
 
 use crate::{
     bson::{Array, Binary, Bson, Timestamp},
-    de::{deserialize_bson_kvp, ensure_read_exactly, read_i32, MIN_BSON_DOCUMENT_SIZE},
+    de::{
+        deserialize_bson_kvp,
+        ensure_read_exactly,
+        read_i32,
+        DeserializerOptions,
+        MIN_BSON_DOCUMENT_SIZE,
+    },
+    diff::{self, DiffOptions, DocumentDiff},
+    display::{DisplayOptions, Formatted},
+    merge::{self, ArrayMergeStrategy, MergeOptions},
     oid::ObjectId,
-    ser::{serialize_bson, write_i32},
-    spec::BinarySubtype,
+    path::{self, AsKeyPath},
+    ser::{serialize_bson, write_i32, SerializerOptions},
+    spec::{BinarySubtype, ElementType},
     Decimal128,
 };
 
@@ -71,21 +81,7 @@ impl Default for Document {
 
 impl Display for Document {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        fmt.write_str("{")?;
-
-        let mut first = true;
-        for (k, v) in self {
-            if first {
-                first = false;
-                fmt.write_str(" ")?;
-            } else {
-                fmt.write_str(", ")?;
-            }
-
-            write!(fmt, "\"{}\": {}", k, v)?;
-        }
-
-        write!(fmt, "{}}}", if !first { " " } else { "" })
+        Display::fmt(&self.display_with_options(Default::default()), fmt)
     }
 }
 
@@ -415,6 +411,24 @@ impl Document {
         }
     }
 
+    /// Get an object id value for this key if it exists and has the correct type.
+    pub fn get_object_id(&self, key: impl AsRef<str>) -> ValueAccessResult<ObjectId> {
+        match self.get(key) {
+            Some(&Bson::ObjectId(v)) => Ok(v),
+            Some(_) => Err(ValueAccessError::UnexpectedType),
+            None => Err(ValueAccessError::NotPresent),
+        }
+    }
+
+    /// Get a mutable reference to an object id value for this key if it exists and has the correct
+    /// type.
+    pub fn get_object_id_mut(&mut self, key: impl AsRef<str>) -> ValueAccessResult<&mut ObjectId> {
+        match self.get_mut(key) {
+            Some(&mut Bson::ObjectId(ref mut v)) => Ok(v),
+            Some(_) => Err(ValueAccessError::UnexpectedType),
+            None => Err(ValueAccessError::NotPresent),
+        }
+    }
 
     /// Get a reference to a UTC datetime value for this key if it exists and has the correct type.
     pub fn get_datetime(&self, key: impl AsRef<str>) -> ValueAccessResult<&crate::DateTime> {
@@ -487,6 +501,185 @@ impl Document {
         }
     }
 
+    /// Returns a reference to the value at the given path, which is either a string in dotted
+    /// notation such as `"a.b.0.c"` or a [`KeyPath`](path::KeyPath). See the [`path`] module for
+    /// the syntax.
+    ///
+    /// The error reports which segment of the path could not be resolved, and why.
+    pub fn get_path(&self, path: impl AsKeyPath) -> path::Result<&Bson> {
+        path::get(self, &path)
+    }
+
+    /// Returns a mutable reference to the value at the given path.
+    pub fn get_path_mut(&mut self, path: impl AsKeyPath) -> path::Result<&mut Bson> {
+        path::get_mut(self, &path)
+    }
+
+    /// Returns true if there is a value at the given path. Malformed paths contain no value.
+    pub fn contains_path(&self, path: impl AsKeyPath) -> bool {
+        self.get_path(path).is_ok()
+    }
+
+    /// Inserts a value at the given path, and returns the value it replaced, if any.
+    ///
+    /// Missing intermediate values are created as arrays if the segment following them is an
+    /// index, and as documents otherwise. Inserting at an index past the end of an array pads the
+    /// array with nulls. This fails if the path passes through a value that is neither a document
+    /// nor an array, applies a segment that is not an index to an array, or would pad an array
+    /// with more than 1,500,000 nulls.
+    ///
+    /// ```
+    /// # use bson::{doc, Bson};
+    /// let mut doc = doc! { "a": { "b": 1 } };
+    /// doc.insert_path("a.c.1.d", true)?;
+    /// assert_eq!(doc, doc! { "a": { "b": 1, "c": [Bson::Null, { "d": true }] } });
+    /// # Ok::<(), bson::path::Error>(())
+    /// ```
+    pub fn insert_path<BT: Into<Bson>>(
+        &mut self,
+        path: impl AsKeyPath,
+        val: BT,
+    ) -> path::Result<Option<Bson>> {
+        path::insert(self, &path, val.into())
+    }
+
+    /// Removes the value at the given path and returns it, or `None` if there is no such value.
+    /// Removing an element from an array shifts the elements after it.
+    ///
+    /// This fails if the path is malformed, passes through a value that is neither a document nor
+    /// an array, or applies a segment that is not an index to an array.
+    pub fn remove_path(&mut self, path: impl AsKeyPath) -> path::Result<Option<Bson>> {
+        path::remove(self, &path)
+    }
+
+    /// Get a floating point value at the given path if it exists and has the correct type.
+    pub fn get_path_f64(&self, path: impl AsKeyPath) -> path::Result<f64> {
+        path::get_typed(self, &path, ElementType::Double, Bson::as_f64)
+    }
+
+    /// Get a string slice at the given path if it exists and has the correct type.
+    pub fn get_path_str(&self, path: impl AsKeyPath) -> path::Result<&str> {
+        path::get_typed(self, &path, ElementType::String, Bson::as_str)
+    }
+
+    /// Get a reference to an array at the given path if it exists and has the correct type.
+    pub fn get_path_array(&self, path: impl AsKeyPath) -> path::Result<&Array> {
+        path::get_typed(self, &path, ElementType::Array, Bson::as_array)
+    }
+
+    /// Get a reference to a document at the given path if it exists and has the correct type.
+    pub fn get_path_document(&self, path: impl AsKeyPath) -> path::Result<&Document> {
+        path::get_typed(self, &path, ElementType::EmbeddedDocument, Bson::as_document)
+    }
+
+    /// Get a bool value at the given path if it exists and has the correct type.
+    pub fn get_path_bool(&self, path: impl AsKeyPath) -> path::Result<bool> {
+        path::get_typed(self, &path, ElementType::Boolean, Bson::as_bool)
+    }
+
+    /// Get an i32 value at the given path if it exists and has the correct type.
+    pub fn get_path_i32(&self, path: impl AsKeyPath) -> path::Result<i32> {
+        path::get_typed(self, &path, ElementType::Int32, Bson::as_i32)
+    }
+
+    /// Get an i64 value at the given path if it exists and has the correct type.
+    pub fn get_path_i64(&self, path: impl AsKeyPath) -> path::Result<i64> {
+        path::get_typed(self, &path, ElementType::Int64, Bson::as_i64)
+    }
+
+    /// Get a time stamp value at the given path if it exists and has the correct type.
+    pub fn get_path_timestamp(&self, path: impl AsKeyPath) -> path::Result<Timestamp> {
+        path::get_typed(self, &path, ElementType::Timestamp, Bson::as_timestamp)
+    }
+
+    /// Get an object id value at the given path if it exists and has the correct type.
+    pub fn get_path_object_id(&self, path: impl AsKeyPath) -> path::Result<ObjectId> {
+        path::get_typed(self, &path, ElementType::ObjectId, Bson::as_object_id)
+    }
+
+    /// Get a reference to a UTC datetime value at the given path if it exists and has the correct type.
+    pub fn get_path_datetime(&self, path: impl AsKeyPath) -> path::Result<&crate::DateTime> {
+        path::get_typed(self, &path, ElementType::DateTime, Bson::as_datetime)
+    }
+
+    /// Get a Decimal128 value at the given path if it exists and has the correct type.
+    pub fn get_path_decimal128(&self, path: impl AsKeyPath) -> path::Result<Decimal128> {
+        path::get_typed(self, &path, ElementType::Decimal128, Bson::as_decimal128)
+    }
+
+    /// Merges `other` into this document as configured by `options`, recursing into embedded
+    /// documents present in both. See the [`merge`](crate::merge) module for the details.
+    ///
+    /// ```
+    /// # use bson::doc;
+    /// let mut doc = doc! { "a": { "b": 1, "c": 2 }, "d": 3 };
+    /// doc.merge(doc! { "a": { "c": 4 }, "e": 5 }, Default::default());
+    /// assert_eq!(doc, doc! { "a": { "b": 1, "c": 4 }, "d": 3, "e": 5 });
+    /// ```
+    pub fn merge(&mut self, other: Document, options: MergeOptions) {
+        merge::merge_documents(self, other, &options)
+    }
+
+    /// Returns a copy of this document with `other` merged into it as configured by `options`.
+    /// See [`Document::merge`].
+    pub fn merged(&self, other: Document, options: MergeOptions) -> Document {
+        let mut merged = self.clone();
+        merged.merge(other, options);
+        merged
+    }
+
+    /// Applies a JSON merge patch (RFC 7386) to this document: fields of `patch` replace the
+    /// fields of the same name, embedded documents are patched recursively and a
+    /// [`Bson::Null`] field removes the field of the same name. See the
+    /// [`patch`](crate::patch) module for the other kind of patch.
+    ///
+    /// ```
+    /// # use bson::{doc, Bson};
+    /// let mut doc = doc! { "title": "a", "author": { "name": "b", "email": "c" }, "tags": [1] };
+    /// doc.apply_merge_patch(&doc! { "author": { "email": Bson::Null }, "tags": [2] });
+    /// assert_eq!(doc, doc! { "title": "a", "author": { "name": "b" }, "tags": [2] });
+    /// ```
+    pub fn apply_merge_patch(&mut self, patch: &Document) {
+        let options = MergeOptions::builder()
+            .arrays(ArrayMergeStrategy::Replace)
+            .null_removes(true)
+            .build();
+        merge::merge_documents(self, patch.clone(), &options)
+    }
+
+    /// Compares this document to `other` and returns the paths at which values were added,
+    /// removed or changed, using the default [`DiffOptions`]. See the [`diff`](crate::diff)
+    /// module for the details.
+    ///
+    /// ```
+    /// # use bson::doc;
+    /// let old = doc! { "a": { "b": 1 }, "c": 2 };
+    /// let diff = old.diff(&doc! { "a": { "b": 2 }, "d": 3 });
+    /// assert_eq!(diff.to_string(), "~ a.b: 1 -> 2\n- c: 2\n+ d: 3");
+    /// ```
+    pub fn diff(&self, other: &Document) -> DocumentDiff {
+        self.diff_with_options(other, Default::default())
+    }
+
+    /// Compares this document to `other` as configured by `options`. See [`Document::diff`].
+    pub fn diff_with_options(&self, other: &Document, options: DiffOptions) -> DocumentDiff {
+        diff::diff_documents(self, other, &options)
+    }
+
+    /// Returns a value that displays this document in shell syntax as configured by `options`.
+    /// See the [`display`](crate::display) module for the format.
+    ///
+    /// ```
+    /// use bson::{display::DisplayOptions, doc};
+    ///
+    /// let doc = doc! { "data": "0123456789" };
+    /// let options = DisplayOptions::builder().max_string_length(4).build();
+    /// assert_eq!(doc.display_with_options(options).to_string(), r#"{ "data": "0123..." }"#);
+    /// ```
+    pub fn display_with_options(&self, options: DisplayOptions) -> Formatted<'_> {
+        Formatted::document(self, options)
+    }
+
     /// Attempts to serialize the `Document` into a byte stream.
     ///
     /// While the method signature indicates an owned writer must be passed in, a mutable reference
@@ -504,13 +697,51 @@ impl Document {
     /// # }
     /// ```
     pub fn to_writer<W: Write>(&self, mut writer: W) -> crate::ser::Result<()> {
+        self.encode(&mut writer, &Default::default())
+    }
+
+    /// Attempt to encode the `Document` into a byte stream using the provided options.
+    ///
+    /// Unsigned integers are written according to [`SerializerOptions::unsigned_integers`], and
+    /// elements of custom types are checked against [`SerializerOptions::codecs`].
+    /// [`SerializerOptions::human_readable`] does not apply to BSON bytes and is ignored.
+    ///
+    /// ```
+    /// # fn main() -> bson::ser::Result<()> {
+    /// use bson::{doc, ser::UnsignedIntegerPolicy, Bson, Document, SerializerOptions};
+    ///
+    /// let doc = doc! { "small": Bson::UInt64(1), "large": Bson::UInt64(u64::MAX) };
+    /// let options = SerializerOptions::builder()
+    ///     .unsigned_integers(UnsignedIntegerPolicy::Int64OrDouble)
+    ///     .build();
+    /// let mut v = Vec::new();
+    /// doc.to_writer_with_options(&mut v, options)?;
+    ///
+    /// let read = Document::from_reader(v.as_slice()).unwrap();
+    /// assert_eq!(read, doc! { "small": 1, "large": u64::MAX as f64 });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_writer_with_options<W: Write>(
+        &self,
+        mut writer: W,
+        options: SerializerOptions,
+    ) -> crate::ser::Result<()> {
+        self.encode(&mut writer, &options)
+    }
+
+    pub(crate) fn encode<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        options: &SerializerOptions,
+    ) -> crate::ser::Result<()> {
         let mut buf = Vec::new();
         for (key, val) in self.into_iter() {
-            serialize_bson(&mut buf, key.as_ref(), val)?;
+            serialize_bson(&mut buf, key.as_ref(), val, options)?;
         }
 
         write_i32(
-            &mut writer,
+            writer,
             (buf.len() + mem::size_of::<i32>() + mem::size_of::<u8>()) as i32,
         )?;
         writer.write_all(&buf)?;
@@ -518,7 +749,11 @@ impl Document {
         Ok(())
     }
 
-    fn decode<R: Read + ?Sized>(reader: &mut R, utf_lossy: bool) -> crate::de::Result<Document> {
+    pub(crate) fn decode<R: Read + ?Sized>(
+        reader: &mut R,
+        utf_lossy: bool,
+        options: &DeserializerOptions,
+    ) -> crate::de::Result<Document> {
         let mut doc = Document::new();
 
         let length = read_i32(reader)?;
@@ -543,7 +778,7 @@ impl Document {
                         break;
                     }
 
-                    let (key, val) = deserialize_bson_kvp(cursor, tag, utf_lossy)?;
+                    let (key, val) = deserialize_bson_kvp(cursor, tag, utf_lossy, options)?;
                     doc.insert(key, val);
                 }
                 Ok(())
@@ -582,7 +817,39 @@ impl Document {
     /// # }
     /// ```
     pub fn from_reader<R: Read>(mut reader: R) -> crate::de::Result<Document> {
-        Self::decode(&mut reader, false)
+        Self::decode(&mut reader, false, &Default::default())
+    }
+
+    /// Attempts to deserialize a `Document` from a byte stream using the provided options.
+    ///
+    /// This makes it possible to keep elements of types this crate does not know as
+    /// [`Bson::Unknown`], see [`DeserializerOptions::unknown_elements`].
+    ///
+    /// ```
+    /// # use std::error::Error;
+    /// # fn main() -> std::result::Result<(), Box<dyn Error>> {
+    /// use bson::{spec::ElementSize, Bson, DeserializerOptions, Document};
+    ///
+    /// // { "a": <0x80 element holding 2 bytes>, "b": 1 }
+    /// let bytes = b"\x11\x00\x00\x00\x80a\x00\xab\xcd\x10b\x00\x01\x00\x00\x00\x00";
+    /// let options = DeserializerOptions::builder()
+    ///     .unknown_elements(true)
+    ///     .unknown_element_size(0x80, ElementSize::Fixed(2))
+    ///     .build();
+    /// let doc = Document::from_reader_with_options(&bytes[..], options)?;
+    /// assert_eq!(doc.get("a"), Some(&Bson::Unknown { tag: 0x80, bytes: vec![0xab, 0xcd] }));
+    ///
+    /// let mut written = Vec::new();
+    /// doc.to_writer(&mut written)?;
+    /// assert_eq!(written, bytes);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_reader_with_options<R: Read>(
+        mut reader: R,
+        options: DeserializerOptions,
+    ) -> crate::de::Result<Document> {
+        Self::decode(&mut reader, false, &options)
     }
 
     /// Attempt to deserialize a `Document` that may contain invalid UTF-8 strings from a byte
@@ -592,7 +859,7 @@ impl Document {
     /// in rare cases can contain invalidly truncated strings (<https://jira.mongodb.org/browse/SERVER-24007>).
     /// For most use cases, `Document::from_reader` can be used instead.
     pub fn from_reader_utf8_lossy<R: Read>(mut reader: R) -> crate::de::Result<Document> {
-        Self::decode(&mut reader, true)
+        Self::decode(&mut reader, true, &Default::default())
     }
 }
 
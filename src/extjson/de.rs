@@ -21,11 +21,40 @@
 //! Bson::try_from(invalid_ext_json).expect_err("5 should be a string");
 //! ```
 
-use std::convert::{TryFrom, TryInto};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt,
+    io,
+    result,
+};
 
-use serde::de::{Error as _, Unexpected};
+use serde::{
+    de::{
+        value::{BorrowedStrDeserializer, MapAccessDeserializer},
+        DeserializeOwned,
+        DeserializeSeed,
+        EnumAccess,
+        Error as _,
+        IntoDeserializer,
+        MapAccess,
+        SeqAccess,
+        Unexpected,
+        VariantAccess,
+        Visitor,
+    },
+    forward_to_deserialize_any,
+    Deserialize,
+    Deserializer as _,
+};
 
-use crate::{extjson::models, oid, Bson, Document};
+use crate::{
+    de::DeserializerOptions,
+    extjson::models,
+    oid,
+    uuid::UUID_NEWTYPE_NAME,
+    Bson,
+    Document,
+};
 
 #[derive(Clone, Debug)]
 #[non_exhaustive]
@@ -82,6 +111,10 @@ impl TryFrom<serde_json::Map<String, serde_json::Value>> for Bson {
     type Error = Error;
 
     fn try_from(obj: serde_json::Map<String, serde_json::Value>) -> Result<Self> {
+        if obj.contains_key("$oid") {
+            let oid: models::ObjectId = serde_json::from_value(obj.into())?;
+            return Ok(Bson::ObjectId(oid.parse()?));
+        }
 
         if obj.contains_key("$numberInt") {
             let int: models::Int32 = serde_json::from_value(obj.into())?;
@@ -93,6 +126,16 @@ impl TryFrom<serde_json::Map<String, serde_json::Value>> for Bson {
             return Ok(Bson::Int64(int.parse()?));
         }
 
+        if obj.contains_key("$numberUInt32") {
+            let int: models::UInt32 = serde_json::from_value(obj.into())?;
+            return Ok(Bson::UInt32(int.parse()?));
+        }
+
+        if obj.contains_key("$numberUInt64") {
+            let int: models::UInt64 = serde_json::from_value(obj.into())?;
+            return Ok(Bson::UInt64(int.parse()?));
+        }
+
         if obj.contains_key("$numberDouble") {
             let double: models::Double = serde_json::from_value(obj.into())?;
             return Ok(Bson::Double(double.parse()?));
@@ -108,6 +151,15 @@ impl TryFrom<serde_json::Map<String, serde_json::Value>> for Bson {
             return Ok(Bson::Binary(uuid.parse()?));
         }
 
+        if obj.contains_key("$regularExpression") {
+            let regex: models::Regex = serde_json::from_value(obj.into())?;
+            return Ok(regex.parse().into());
+        }
+
+        if obj.contains_key("$code") {
+            let code_w_scope: models::JavaScriptCodeWithScope = serde_json::from_value(obj.into())?;
+            return code_w_scope.parse();
+        }
 
         if obj.contains_key("$timestamp") {
             let ts: models::Timestamp = serde_json::from_value(obj.into())?;
@@ -120,7 +172,38 @@ impl TryFrom<serde_json::Map<String, serde_json::Value>> for Bson {
         }
 
         if obj.contains_key("$numberDecimal") {
-            return Err(Error::custom("decimal128 extjson support not implemented"));
+            let decimal: models::Decimal128 = serde_json::from_value(obj.into())?;
+            return Ok(Bson::Decimal128(decimal.parse()?));
+        }
+
+        if obj.contains_key("$minKey") {
+            let min_key: models::MinKey = serde_json::from_value(obj.into())?;
+            return min_key.parse();
+        }
+
+        if obj.contains_key("$maxKey") {
+            let max_key: models::MaxKey = serde_json::from_value(obj.into())?;
+            return max_key.parse();
+        }
+
+        if obj.contains_key("$undefined") {
+            let undefined: models::Undefined = serde_json::from_value(obj.into())?;
+            return undefined.parse();
+        }
+
+        if obj.contains_key("$symbol") {
+            let symbol: models::Symbol = serde_json::from_value(obj.into())?;
+            return Ok(Bson::Symbol(symbol.value));
+        }
+
+        if obj.contains_key("$dbPointer") {
+            let db_ptr: models::DbPointer = serde_json::from_value(obj.into())?;
+            return Ok(Bson::DbPointer(db_ptr.parse()?));
+        }
+
+        if obj.contains_key("$unknown") {
+            let unknown: models::Unknown = serde_json::from_value(obj.into())?;
+            return unknown.parse();
         }
 
         Ok(Bson::Document(obj.try_into()?))
@@ -142,11 +225,13 @@ impl TryFrom<serde_json::Value> for Bson {
                         Bson::Int64(i)
                     }
                 })
+                // Integers only representable as u64 are kept exact rather than rounded to a double.
+                .or_else(|| x.as_u64().map(Bson::UInt64))
                 .or_else(|| x.as_f64().map(Bson::from))
                 .ok_or_else(|| {
                     Error::invalid_value(
                         Unexpected::Other(format!("{}", x).as_str()),
-                        &"a number that could fit in i32, i64, or f64",
+                        &"a number that could fit in i32, i64, u64, or f64",
                     )
                 }),
             serde_json::Value::String(x) => Ok(x.into()),
@@ -178,3 +263,1122 @@ impl TryFrom<serde_json::Map<String, serde_json::Value>> for Document {
             .collect())
     }
 }
+
+/// Deserialize an instance of type `T` from a string of extended JSON.
+///
+/// Types are mapped the same way as by [`crate::from_slice`], without building an intermediate
+/// [`serde_json::Value`] or [`Bson`]. A JSON object is read as the extended JSON representation
+/// of a BSON type when its first key is one of `$oid`, `$date`, `$numberInt`, `$numberLong`,
+/// `$numberUInt32`, `$numberUInt64`, `$numberDouble`, `$numberDecimal`, `$binary`, `$uuid`,
+/// `$regularExpression`, `$code`, `$scope`, `$timestamp`, `$symbol`, `$dbPointer`, `$minKey`,
+/// `$maxKey`, `$undefined` or `$unknown`. JSON integers are read as 32-bit integers when they fit, then as 64-bit ones.
+pub fn from_str<'de, T>(s: &'de str) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let mut json = serde_json::Deserializer::from_str(s);
+    let value = T::deserialize(Deserializer::new(&mut json))?;
+    json.end()?;
+    Ok(value)
+}
+
+/// Deserialize an instance of type `T` from an I/O stream of extended JSON.
+///
+/// See [`from_str`] for how the input is mapped onto `T`.
+pub fn from_reader<R, T>(reader: R) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    let mut json = serde_json::Deserializer::from_reader(reader);
+    let value = T::deserialize(Deserializer::new(&mut json))?;
+    json.end()?;
+    Ok(value)
+}
+
+/// Deserializer that wraps a JSON deserializer and presents the extended JSON representations
+/// of BSON types to visitors the same way the raw BSON deserializer presents the types
+/// themselves.
+struct Deserializer<D> {
+    inner: D,
+}
+
+impl<D> Deserializer<D> {
+    fn new(inner: D) -> Self {
+        Self { inner }
+    }
+}
+
+/// The `deserialize_*` method originally called on a [`Deserializer`], replayed against the
+/// parsed value when an extended JSON wrapper is found.
+#[derive(Clone, Copy)]
+enum DeserializerHint {
+    None,
+    Bytes,
+    NewtypeStruct(&'static str),
+}
+
+impl<'de, D> serde::Deserializer<'de> for Deserializer<D>
+where
+    D: serde::Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> result::Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.inner
+            .deserialize_any(WrapVisitor::new(visitor, DeserializerHint::None))
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> result::Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.inner
+            .deserialize_any(WrapVisitor::new(visitor, DeserializerHint::Bytes))
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> result::Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> result::Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.inner
+            .deserialize_option(WrapVisitor::new(visitor, DeserializerHint::None))
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> result::Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match name {
+            UUID_NEWTYPE_NAME => self.inner.deserialize_any(WrapVisitor::new(
+                visitor,
+                DeserializerHint::NewtypeStruct(name),
+            )),
+            _ => self.inner.deserialize_newtype_struct(
+                name,
+                WrapVisitor::new(visitor, DeserializerHint::None),
+            ),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> result::Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.inner.deserialize_enum(
+            name,
+            variants,
+            WrapVisitor::new(visitor, DeserializerHint::None),
+        )
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> result::Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.inner.deserialize_ignored_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    forward_to_deserialize_any! {
+        bool char str string unit unit_struct seq tuple tuple_struct map struct identifier
+        i8 i16 i32 i64 u8 u16 u32 u64 f32 f64
+    }
+}
+
+struct WrapSeed<S>(S);
+
+impl<'de, S> DeserializeSeed<'de> for WrapSeed<S>
+where
+    S: DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> result::Result<S::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        self.0.deserialize(Deserializer::new(deserializer))
+    }
+}
+
+struct WrapVisitor<V> {
+    inner: V,
+    hint: DeserializerHint,
+}
+
+impl<V> WrapVisitor<V> {
+    fn new(inner: V, hint: DeserializerHint) -> Self {
+        Self { inner, hint }
+    }
+}
+
+/// Forwards `visit_*` methods whose argument needs no wrapping to the inner visitor.
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> result::Result<V::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, V> Visitor<'de> for WrapVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    forward_visit! {
+        visit_bool(bool);
+        visit_f64(f64);
+        visit_char(char);
+        visit_str(&str);
+        visit_borrowed_str(&'de str);
+        visit_string(String);
+        visit_bytes(&[u8]);
+        visit_borrowed_bytes(&'de [u8]);
+        visit_byte_buf(Vec<u8>);
+    }
+
+    fn visit_i64<E>(self, v: i64) -> result::Result<V::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match i32::try_from(v) {
+            Ok(i) => self.inner.visit_i32(i),
+            Err(_) => self.inner.visit_i64(v),
+        }
+    }
+
+    fn visit_u64<E>(self, v: u64) -> result::Result<V::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match i64::try_from(v) {
+            Ok(i) => self.visit_i64(i),
+            Err(_) => self.inner.visit_u64(v),
+        }
+    }
+
+    fn visit_none<E>(self) -> result::Result<V::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E>(self) -> result::Result<V::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> result::Result<V::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        self.inner.visit_some(Deserializer::new(deserializer))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> result::Result<V::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        self.inner
+            .visit_newtype_struct(Deserializer::new(deserializer))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> result::Result<V::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.inner.visit_seq(WrapSeqAccess(seq))
+    }
+
+    fn visit_enum<A>(self, data: A) -> result::Result<V::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        self.inner.visit_enum(WrapEnumAccess(data))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> result::Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let map = WrapMapAccess {
+            first_key: map.next_key_seed(KeySeed)?,
+            inner: map,
+            wrap_values: true,
+        };
+        let bson = match extended_value(map)? {
+            Ok(bson) => bson,
+            Err(map) => return self.inner.visit_map(map),
+        };
+
+        let options = DeserializerOptions::builder().human_readable(false).build();
+        let deserializer = crate::Deserializer::new_with_options(bson, options);
+        let result = match self.hint {
+            DeserializerHint::None => deserializer.deserialize_any(self.inner),
+            DeserializerHint::Bytes => deserializer.deserialize_bytes(self.inner),
+            DeserializerHint::NewtypeStruct(name) => {
+                deserializer.deserialize_newtype_struct(name, self.inner)
+            }
+        };
+        result.map_err(A::Error::custom)
+    }
+}
+
+/// Parses the extended JSON representation of a BSON type if the first key of `map` identifies
+/// one, otherwise returns `map` untouched.
+fn extended_value<'de, A>(
+    map: WrapMapAccess<'de, A>,
+) -> result::Result<result::Result<Bson, WrapMapAccess<'de, A>>, A::Error>
+where
+    A: MapAccess<'de>,
+{
+    fn parse<'de, A, T, U>(
+        mut map: WrapMapAccess<'de, A>,
+        parse: impl FnOnce(T) -> Result<U>,
+    ) -> result::Result<U, A::Error>
+    where
+        A: MapAccess<'de>,
+        T: Deserialize<'de>,
+    {
+        // The models expect nested wrappers such as `{ "$date": { "$numberLong": ... } }` to be
+        // left as maps.
+        map.wrap_values = false;
+        let model = T::deserialize(MapAccessDeserializer::new(map))?;
+        parse(model).map_err(A::Error::custom)
+    }
+
+    let key = match map.first_key {
+        Some(ref key) => key.as_str(),
+        None => return Ok(Err(map)),
+    };
+    let bson = match key {
+        "$oid" => Bson::ObjectId(parse(map, |m: models::ObjectId| m.parse())?),
+        "$numberInt" => Bson::Int32(parse(map, |m: models::Int32| m.parse())?),
+        "$numberLong" => Bson::Int64(parse(map, |m: models::Int64| m.parse())?),
+        "$numberUInt32" => Bson::UInt32(parse(map, |m: models::UInt32| m.parse())?),
+        "$numberUInt64" => Bson::UInt64(parse(map, |m: models::UInt64| m.parse())?),
+        "$numberDouble" => Bson::Double(parse(map, |m: models::Double| m.parse())?),
+        "$numberDecimal" => Bson::Decimal128(parse(map, |m: models::Decimal128| m.parse())?),
+        "$binary" => Bson::Binary(parse(map, |m: models::Binary| m.parse())?),
+        "$uuid" => Bson::Binary(parse(map, |m: models::Uuid| m.parse())?),
+        "$regularExpression" => {
+            Bson::RegularExpression(parse(map, |m: models::Regex| Ok(m.parse()))?)
+        }
+        "$code" | "$scope" => parse(map, |m: models::JavaScriptCodeWithScope| m.parse())?,
+        "$timestamp" => Bson::Timestamp(parse(map, |m: models::Timestamp| Ok(m.parse()))?),
+        "$date" => Bson::DateTime(parse(map, |m: models::DateTime| m.parse())?),
+        "$symbol" => Bson::Symbol(parse(map, |m: models::Symbol| Ok(m.value))?),
+        "$dbPointer" => Bson::DbPointer(parse(map, |m: models::DbPointer| m.parse())?),
+        "$minKey" => parse(map, |m: models::MinKey| m.parse())?,
+        "$maxKey" => parse(map, |m: models::MaxKey| m.parse())?,
+        "$undefined" => parse(map, |m: models::Undefined| m.parse())?,
+        "$unknown" => parse(map, |m: models::Unknown| m.parse())?,
+        _ => return Ok(Err(map)),
+    };
+    Ok(Ok(bson))
+}
+
+/// A map key read ahead of the rest of the map, borrowed from the input when possible.
+enum Key<'de> {
+    Borrowed(&'de str),
+    Owned(String),
+}
+
+impl<'de> Key<'de> {
+    fn as_str(&self) -> &str {
+        match self {
+            Key::Borrowed(s) => s,
+            Key::Owned(s) => s,
+        }
+    }
+}
+
+struct KeySeed;
+
+impl<'de> DeserializeSeed<'de> for KeySeed {
+    type Value = Key<'de>;
+
+    fn deserialize<D>(self, deserializer: D) -> result::Result<Key<'de>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed {
+    type Value = Key<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string key")
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> result::Result<Key<'de>, E> {
+        Ok(Key::Borrowed(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> result::Result<Key<'de>, E> {
+        Ok(Key::Owned(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> result::Result<Key<'de>, E> {
+        Ok(Key::Owned(v))
+    }
+}
+
+struct WrapMapAccess<'de, A> {
+    first_key: Option<Key<'de>>,
+    inner: A,
+    wrap_values: bool,
+}
+
+impl<'de, A> MapAccess<'de> for WrapMapAccess<'de, A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> result::Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.first_key.take() {
+            Some(Key::Borrowed(key)) => seed
+                .deserialize(BorrowedStrDeserializer::new(key))
+                .map(Some),
+            Some(Key::Owned(key)) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => self.inner.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<S>(&mut self, seed: S) -> result::Result<S::Value, A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        if self.wrap_values {
+            self.inner.next_value_seed(WrapSeed(seed))
+        } else {
+            self.inner.next_value_seed(seed)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+struct WrapSeqAccess<A>(A);
+
+impl<'de, A> SeqAccess<'de> for WrapSeqAccess<A>
+where
+    A: SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> result::Result<Option<S::Value>, A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        self.0.next_element_seed(WrapSeed(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+struct WrapEnumAccess<A>(A);
+
+impl<'de, A> EnumAccess<'de> for WrapEnumAccess<A>
+where
+    A: EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = WrapVariantAccess<A::Variant>;
+
+    fn variant_seed<S>(self, seed: S) -> result::Result<(S::Value, Self::Variant), A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let (value, variant) = self.0.variant_seed(seed)?;
+        Ok((value, WrapVariantAccess(variant)))
+    }
+}
+
+struct WrapVariantAccess<A>(A);
+
+impl<'de, A> VariantAccess<'de> for WrapVariantAccess<A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> result::Result<(), A::Error> {
+        self.0.unit_variant()
+    }
+
+    fn newtype_variant_seed<S>(self, seed: S) -> result::Result<S::Value, A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        self.0.newtype_variant_seed(WrapSeed(seed))
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> result::Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0
+            .tuple_variant(len, WrapVisitor::new(visitor, DeserializerHint::None))
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> result::Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0
+            .struct_variant(fields, WrapVisitor::new(visitor, DeserializerHint::None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, convert::TryFrom};
+
+    use serde::{Deserialize, Serialize};
+    use serde_bytes::ByteBuf;
+    use serde_json::json;
+
+    use super::{from_reader, from_str};
+    use crate::{
+        oid::ObjectId,
+        spec::BinarySubtype,
+        Binary,
+        Bson,
+        DateTime,
+        DbPointer,
+        Decimal128,
+        Document,
+        JavaScriptCodeWithScope,
+        RawBson,
+        RawDbPointer,
+        RawJavaScriptCodeWithScope,
+        Regex,
+        RegexError,
+        Timestamp,
+        Uuid,
+    };
+
+    #[test]
+    fn unsigned_integers() {
+        let canonical = json!({ "$numberUInt32": "4294967295" });
+        assert_eq!(
+            Bson::try_from(canonical.clone()).unwrap(),
+            Bson::UInt32(u32::MAX)
+        );
+        assert_eq!(Bson::UInt32(u32::MAX).into_canonical_extjson(), canonical);
+        assert_eq!(Bson::UInt32(7).into_relaxed_extjson(), json!(7));
+
+        let canonical = json!({ "$numberUInt64": "18446744073709551615" });
+        assert_eq!(
+            Bson::try_from(canonical.clone()).unwrap(),
+            Bson::UInt64(u64::MAX)
+        );
+        assert_eq!(Bson::UInt64(u64::MAX).into_canonical_extjson(), canonical);
+        assert_eq!(Bson::UInt64(u64::MAX).into_relaxed_extjson(), canonical);
+        assert_eq!(
+            Bson::UInt64(i64::MAX as u64).into_relaxed_extjson(),
+            json!(i64::MAX)
+        );
+        assert_eq!(
+            Bson::try_from(json!(u64::MAX)).unwrap(),
+            Bson::UInt64(u64::MAX)
+        );
+
+        Bson::try_from(json!({ "$numberUInt32": "-1" })).expect_err("negative u32");
+        Bson::try_from(json!({ "$numberUInt32": "4294967296" })).expect_err("u32 overflow");
+        Bson::try_from(json!({ "$numberUInt64": 5 })).expect_err("5 should be a string");
+    }
+
+    #[test]
+    fn number_double_through_serde() {
+        // `Bson`'s own `Deserialize` impl once parsed `$numberDouble` strings as 64-bit integers
+        let parse = |s: &str| serde_json::from_str::<Bson>(s).unwrap();
+        assert_eq!(parse(r#"{ "$numberDouble": "1.5" }"#), Bson::Double(1.5));
+        assert_eq!(parse(r#"{ "$numberDouble": "2" }"#), Bson::Double(2.0));
+        assert_eq!(
+            parse(r#"{ "$numberDouble": "-Infinity" }"#),
+            Bson::Double(f64::NEG_INFINITY)
+        );
+        assert!(serde_json::from_str::<Bson>(r#"{ "$numberDouble": "x" }"#).is_err());
+    }
+
+    #[test]
+    fn unsigned_32_bit_values_keep_their_type() {
+        // serde hands these to `Bson` through `visit_u32`, which must not widen them to UInt64
+        let doc: Document = from_str(r#"{ "n": { "$numberUInt32": "7" } }"#).unwrap();
+        assert_eq!(doc.get("n"), Some(&Bson::UInt32(7)));
+        assert_eq!(
+            crate::from_bson::<Bson>(Bson::UInt32(7)).unwrap(),
+            Bson::UInt32(7)
+        );
+        assert_eq!(crate::from_document::<Document>(doc.clone()).unwrap(), doc);
+    }
+
+    #[test]
+    fn canonical_round_trip() {
+        let doc = doc! {
+            "double": 1.5,
+            "string": "hello",
+            "array": [1, "two"],
+            "document": { "a": Bson::Null },
+            "bool": true,
+            "null": Bson::Null,
+            "i32": 1i32,
+            "i64": 1i64,
+            "u32": 1u32,
+            "u64": u64::MAX,
+            "timestamp": Timestamp { time: 1, increment: 2 },
+            "regex": Regex::new("^a.*z$", "mi"),
+            "code": Bson::JavaScriptCode("x + 1".to_string()),
+            "code_w_scope": JavaScriptCodeWithScope { code: "x + y".to_string(), scope: doc! { "x": 1i64 } },
+            "binary": Binary { subtype: BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+            "oid": ObjectId::parse_str("000000000000000000000001").unwrap(),
+            "datetime": DateTime::from_millis(-1),
+            "decimal": Bson::Decimal128("1.50".parse::<Decimal128>().unwrap()),
+            "symbol": Bson::Symbol("sym".to_string()),
+            "undefined": Bson::Undefined,
+            "min": Bson::MinKey,
+            "max": Bson::MaxKey,
+            "pointer": DbPointer {
+                namespace: "db.c".to_string(),
+                id: ObjectId::parse_str("000000000000000000000001").unwrap(),
+            },
+        };
+
+        let bson = Bson::Document(doc);
+        let canonical = bson.clone().into_canonical_extjson();
+        assert_eq!(Bson::try_from(canonical.clone()).unwrap(), bson);
+
+        let through_serde: Bson = serde_json::from_value(canonical).unwrap();
+        assert_eq!(through_serde, bson);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Unit,
+        Newtype(i64),
+        Tuple(i32, String),
+        Struct { side: f64 },
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Record {
+        oid: ObjectId,
+        date: DateTime,
+        relaxed_date: DateTime,
+        int: i32,
+        long: i64,
+        long_as_u8: u8,
+        unsigned: u64,
+        double: f64,
+        nan: f64,
+        decimal: Decimal128,
+        timestamp: Timestamp,
+        regex: Regex,
+        code: JavaScriptCodeWithScope,
+        pointer: DbPointer,
+        legacy: Vec<Bson>,
+        bytes: ByteBuf,
+        binary: Binary,
+        uuid: Uuid,
+        optional: Option<Box<Record>>,
+        missing: Option<i32>,
+        shapes: Vec<Shape>,
+        map: HashMap<String, DateTime>,
+        bson: Bson,
+        document: Document,
+    }
+
+    const RECORD: &str = r#"{
+        "oid": { "$oid": "5ed2a84eb8bf0a6ef1ad4c5e" },
+        "date": { "$date": { "$numberLong": "-1" } },
+        "relaxed_date": { "$date": "2020-05-30T18:47:10.123Z" },
+        "int": { "$numberInt": "-5" },
+        "long": { "$numberLong": "1099511627776" },
+        "long_as_u8": { "$numberLong": "200" },
+        "unsigned": { "$numberUInt64": "18446744073709551615" },
+        "double": { "$numberDouble": "1.5" },
+        "nan": { "$numberDouble": "NaN" },
+        "decimal": { "$numberDecimal": "1.50" },
+        "timestamp": { "$timestamp": { "t": 1, "i": 2 } },
+        "regex": { "$regularExpression": { "pattern": "^a", "options": "xi" } },
+        "code": { "$code": "x + y", "$scope": { "x": { "$numberLong": "1" } } },
+        "pointer": { "$dbPointer": { "$ref": "db.c", "$id": { "$oid": "5ed2a84eb8bf0a6ef1ad4c5e" } } },
+        "legacy": [{ "$minKey": 1 }, { "$maxKey": 1 }, { "$undefined": true }, { "$symbol": "s" }],
+        "bytes": { "$binary": { "base64": "AQID", "subType": "00" } },
+        "binary": { "$binary": { "base64": "AQID", "subType": "80" } },
+        "uuid": { "$uuid": "00112233-4455-6677-8899-aabbccddeeff" },
+        "optional": null,
+        "missing": null,
+        "shapes": ["Unit", { "Newtype": 1 }, { "Tuple": [2, "x"] }, { "Struct": { "side": 3 } }],
+        "map": { "a": { "$date": { "$numberLong": "0" } } },
+        "bson": { "x": 1, "y": 5000000000, "z": { "$numberLong": "1" } },
+        "document": { "n": { "$numberUInt32": "7" }, "nested": [{ "$oid": "000000000000000000000001" }] }
+    }"#;
+
+    #[test]
+    fn from_str_matches_from_slice() {
+        let record: Record = from_str(RECORD).unwrap();
+        assert_eq!(record.long, 1 << 40);
+        assert_eq!(record.long_as_u8, 200);
+        assert!(record.nan.is_nan());
+        assert_eq!(record.shapes[3], Shape::Struct { side: 3.0 });
+        assert_eq!(record.regex, Regex::new("^a", "ix"));
+        assert_eq!(record.code.scope, doc! { "x": 1i64 });
+        assert_eq!(record.pointer.namespace, "db.c");
+        assert_eq!(
+            record.legacy,
+            vec![
+                Bson::MinKey,
+                Bson::MaxKey,
+                Bson::Undefined,
+                Bson::Symbol("s".to_string())
+            ]
+        );
+        assert_eq!(
+            record.bson,
+            Bson::Document(doc! { "x": 1, "y": 5_000_000_000i64, "z": 1i64 })
+        );
+
+        // going through the Value path and then from_slice gives the same result
+        let value: serde_json::Value = serde_json::from_str(RECORD).unwrap();
+        let doc = match Bson::try_from(value).unwrap() {
+            Bson::Document(doc) => doc,
+            other => panic!("expected document, got {:?}", other),
+        };
+        let mut bytes = Vec::new();
+        doc.to_writer(&mut bytes).unwrap();
+        let expected: Record = crate::from_slice(&bytes).unwrap();
+        // compare the Debug output, as NaN is not equal to itself
+        assert_eq!(format!("{:?}", record), format!("{:?}", expected));
+
+        let from_reader: Record = from_reader(RECORD.as_bytes()).unwrap();
+        assert_eq!(format!("{:?}", from_reader), format!("{:?}", record));
+
+        let doc: Document = from_str(RECORD).unwrap();
+        let expected: Document = crate::from_slice(&bytes).unwrap();
+        assert_eq!(format!("{:?}", doc), format!("{:?}", expected));
+        assert_eq!(
+            format!("{:?}", doc),
+            format!("{:?}", Document::from_reader(bytes.as_slice()).unwrap())
+        );
+    }
+
+    #[test]
+    fn from_str_errors() {
+        from_str::<ObjectId>(r#"{ "$oid": "5ed2a84eb8bf0a6ef1ad4c5e", "x": 1 }"#)
+            .expect_err("extra key");
+        from_str::<i64>(r#"{ "$numberLong": 5 }"#).expect_err("5 should be a string");
+        from_str::<i32>(r#"{ "$numberLong": "5000000000" }"#).expect_err("out of range");
+        from_str::<DateTime>(r#"{ "$date": "yesterday" }"#).expect_err("invalid date");
+        from_str::<Uuid>(r#"{ "$binary": { "base64": "AQID", "subType": "00" } }"#)
+            .expect_err("wrong binary subtype");
+        from_str::<Document>(r#"{ "a": 1 } x"#).expect_err("trailing characters");
+        from_str::<Regex>(r#"{ "$regularExpression": { "pattern": "a" } }"#)
+            .expect_err("missing options");
+        from_str::<Bson>(r#"{ "$code": "f()", "$scope": 1 }"#).expect_err("scope not a document");
+        from_str::<Bson>(r#"{ "$minKey": 2 }"#).expect_err("$minKey must be 1");
+        from_str::<Bson>(r#"{ "$undefined": false }"#).expect_err("$undefined must be true");
+        from_str::<Bson>(r#"{ "$dbPointer": { "$ref": "db.c" } }"#).expect_err("missing $id");
+    }
+
+    #[test]
+    fn regular_expressions() {
+        let regex = Regex::new(r"end\s*$", "xmix");
+        assert_eq!(regex.options, "imx");
+        assert_eq!(regex.to_string(), r"/end\s*$/imx");
+        assert_eq!(Regex::try_new("a", "is"), Ok(Regex::new("a", "si")));
+        assert_eq!(
+            Regex::try_new("a", "ig"),
+            Err(RegexError::UnknownOption { option: 'g' })
+        );
+        assert_eq!(Regex::try_new("a\0", "i"), Err(RegexError::NullByte));
+
+        let canonical = json!({ "$regularExpression": { "pattern": "^a", "options": "im" } });
+        assert_eq!(
+            Bson::try_from(json!({ "$regularExpression": { "pattern": "^a", "options": "mi" } }))
+                .unwrap(),
+            Bson::RegularExpression(Regex::new("^a", "im"))
+        );
+        assert_eq!(
+            Bson::RegularExpression(Regex::new("^a", "mi")).into_relaxed_extjson(),
+            canonical
+        );
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Filter {
+            name: Regex,
+        }
+
+        let filter = Filter {
+            name: Regex::new("^a", "mi"),
+        };
+        let bytes = crate::to_vec(&filter).unwrap();
+        let raw = crate::RawDocument::new(&bytes).unwrap();
+        let raw_regex = raw.get_regex("name").unwrap();
+        assert_eq!((raw_regex.pattern, raw_regex.options), ("^a", "im"));
+        assert_eq!(crate::from_slice::<Filter>(&bytes).unwrap(), filter);
+
+        let doc = doc! { "name": Regex::new("^a", "mi") };
+        let mut doc_bytes = Vec::new();
+        doc.to_writer(&mut doc_bytes).unwrap();
+        assert_eq!(doc_bytes, bytes);
+        assert_eq!(Document::from_reader(bytes.as_slice()).unwrap(), doc);
+        assert_eq!(crate::to_document(&filter).unwrap(), doc);
+        assert_eq!(crate::from_document::<Filter>(doc).unwrap(), filter);
+    }
+
+    #[test]
+    fn javascript_code() {
+        let code_w_scope = JavaScriptCodeWithScope {
+            code: "x + y".to_string(),
+            scope: doc! { "x": 1, "y": { "z": "a" } },
+        };
+        assert_eq!(
+            Bson::JavaScriptCodeWithScope(code_w_scope.clone()).into_relaxed_extjson(),
+            json!({ "$code": "x + y", "$scope": { "x": 1, "y": { "z": "a" } } })
+        );
+        assert_eq!(
+            Bson::try_from(json!({ "$code": "f()" })).unwrap(),
+            Bson::JavaScriptCode("f()".to_string())
+        );
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Stored {
+            code: Bson,
+            code_w_scope: JavaScriptCodeWithScope,
+        }
+
+        let stored = Stored {
+            code: Bson::JavaScriptCode("f()".to_string()),
+            code_w_scope: code_w_scope.clone(),
+        };
+        let bytes = crate::to_vec(&stored).unwrap();
+        let doc = doc! {
+            "code": Bson::JavaScriptCode("f()".to_string()),
+            "code_w_scope": code_w_scope.clone(),
+        };
+        let mut doc_bytes = Vec::new();
+        doc.to_writer(&mut doc_bytes).unwrap();
+        assert_eq!(doc_bytes, bytes);
+        assert_eq!(Document::from_reader(bytes.as_slice()).unwrap(), doc);
+        assert_eq!(crate::from_slice::<Stored>(&bytes).unwrap(), stored);
+        assert_eq!(crate::to_document(&stored).unwrap(), doc);
+        assert_eq!(crate::from_document::<Stored>(doc).unwrap(), stored);
+
+        let raw = crate::RawDocument::new(&bytes).unwrap();
+        assert_eq!(
+            raw.get("code").unwrap().unwrap().as_javascript(),
+            Some("f()")
+        );
+        let raw_code = raw
+            .get("code_w_scope")
+            .unwrap()
+            .unwrap()
+            .as_javascript_with_scope()
+            .unwrap();
+        assert_eq!(raw_code.code(), "x + y");
+        assert_eq!(raw_code.scope().get_i32("x").unwrap(), 1);
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Borrowed<'a> {
+            #[serde(borrow)]
+            code_w_scope: RawJavaScriptCodeWithScope<'a>,
+        }
+
+        let borrowed: Borrowed = crate::from_slice(&bytes).unwrap();
+        assert_eq!(borrowed.code_w_scope, raw_code);
+        let borrowed_bytes = crate::to_vec(&borrowed).unwrap();
+        let expected = doc! { "code_w_scope": code_w_scope };
+        assert_eq!(
+            Document::from_reader(borrowed_bytes.as_slice()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn javascript_code_with_scope_lengths() {
+        let doc = doc! {
+            "c": JavaScriptCodeWithScope { code: "f()".to_string(), scope: doc! {} },
+        };
+        let mut bytes = Vec::new();
+        doc.to_writer(&mut bytes).unwrap();
+
+        // The value starts after the document length, the element type and the key "c".
+        let length_at = 4 + 1 + 2;
+        let with_length = |length: i32| {
+            let mut bytes = bytes.clone();
+            bytes[length_at..length_at + 4].copy_from_slice(&length.to_le_bytes());
+            bytes
+        };
+
+        for length in &[9, 100] {
+            let bytes = with_length(*length);
+            Document::from_reader(bytes.as_slice()).expect_err("invalid length");
+            crate::from_slice::<Document>(&bytes).expect_err("invalid length");
+            let raw = crate::RawDocument::new(&bytes).unwrap();
+            raw.get("c").expect_err("invalid length");
+        }
+    }
+
+    #[test]
+    fn legacy_types() {
+        let id = ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap();
+        let mut body = Vec::new();
+        body.extend_from_slice(b"\x06u\0");
+        body.extend_from_slice(b"\x0Cp\0\x05\0\0\0db.c\0");
+        body.extend_from_slice(&id.bytes());
+        body.extend_from_slice(b"\x0Es\0\x04\0\0\0sym\0");
+        body.extend_from_slice(b"\x7Fmax\0");
+        body.extend_from_slice(b"\xFFmin\0");
+        body.push(0);
+        let mut bytes = ((body.len() + 4) as i32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&body);
+
+        let doc = doc! {
+            "u": Bson::Undefined,
+            "p": DbPointer { namespace: "db.c".to_string(), id },
+            "s": Bson::Symbol("sym".to_string()),
+            "max": Bson::MaxKey,
+            "min": Bson::MinKey,
+        };
+        assert_eq!(Document::from_reader(bytes.as_slice()).unwrap(), doc);
+        let mut doc_bytes = Vec::new();
+        doc.to_writer(&mut doc_bytes).unwrap();
+        assert_eq!(doc_bytes, bytes);
+        assert_eq!(crate::from_slice::<Document>(&bytes).unwrap(), doc);
+        assert_eq!(crate::to_vec(&doc).unwrap(), bytes);
+        assert_eq!(crate::from_document::<Document>(doc.clone()).unwrap(), doc);
+
+        let raw = crate::RawDocument::new(&bytes).unwrap();
+        let values = raw
+            .into_iter()
+            .map(|r| r.map(|(_, v)| v))
+            .collect::<crate::raw::Result<Vec<_>>>()
+            .unwrap();
+        let pointer = values[1].as_db_pointer().unwrap();
+        assert_eq!((pointer.namespace(), pointer.id()), ("db.c", id));
+        assert_eq!(
+            values,
+            vec![
+                RawBson::Undefined,
+                RawBson::DbPointer(pointer),
+                RawBson::Symbol("sym"),
+                RawBson::MaxKey,
+                RawBson::MinKey,
+            ]
+        );
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Borrowed<'a> {
+            u: RawBson<'a>,
+            #[serde(borrow)]
+            p: RawDbPointer<'a>,
+            s: RawBson<'a>,
+            max: RawBson<'a>,
+            min: RawBson<'a>,
+        }
+
+        let borrowed: Borrowed = crate::from_slice(&bytes).unwrap();
+        assert_eq!(borrowed.p, pointer);
+        assert_eq!(borrowed.s, RawBson::Symbol("sym"));
+        assert_eq!(crate::to_vec(&borrowed).unwrap(), bytes);
+
+        let canonical = json!({
+            "u": { "$undefined": true },
+            "p": { "$dbPointer": { "$ref": "db.c", "$id": { "$oid": "5ed2a84eb8bf0a6ef1ad4c5e" } } },
+            "s": { "$symbol": "sym" },
+            "max": { "$maxKey": 1 },
+            "min": { "$minKey": 1 },
+        });
+        let bson = Bson::Document(doc.clone());
+        assert_eq!(bson.clone().into_canonical_extjson(), canonical);
+        assert_eq!(bson.clone().into_relaxed_extjson(), canonical);
+        assert_eq!(Bson::try_from(canonical.clone()).unwrap(), bson);
+        assert_eq!(from_str::<Document>(&canonical.to_string()).unwrap(), doc);
+        let mut json = Vec::new();
+        crate::extjson::ser::raw_document_to_writer(&mut json, raw, Default::default()).unwrap();
+        assert_eq!(json, serde_json::to_vec(&canonical).unwrap());
+    }
+
+    #[test]
+    fn unknown_elements() {
+        use crate::{spec::ElementSize, DeserializerOptions};
+
+        // { "f": <0x80, 2 bytes>, "l": <0x81, length prefixed>, "i": 1 }
+        let mut body = Vec::new();
+        body.extend_from_slice(b"\x80f\0\xAB\xCD");
+        body.extend_from_slice(b"\x81l\0\x06\0\0\0xy");
+        body.extend_from_slice(b"\x10i\0\x01\0\0\0");
+        body.push(0);
+        let mut bytes = ((body.len() + 4) as i32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&body);
+
+        let options = DeserializerOptions::builder()
+            .unknown_elements(true)
+            .unknown_element_size(0x80, ElementSize::Fixed(2))
+            .unknown_element_size(0x81, ElementSize::LengthPrefixed)
+            .build();
+        let doc = doc! {
+            "f": Bson::Unknown { tag: 0x80, bytes: vec![0xAB, 0xCD] },
+            "l": Bson::Unknown { tag: 0x81, bytes: b"\x06\0\0\0xy".to_vec() },
+            "i": 1,
+        };
+
+        assert!(Document::from_reader(bytes.as_slice()).is_err());
+        assert!(crate::from_slice::<Document>(&bytes).is_err());
+        let sizes_only = DeserializerOptions::builder()
+            .unknown_element_size(0x80, ElementSize::Fixed(2))
+            .unknown_element_size(0x81, ElementSize::LengthPrefixed)
+            .build();
+        assert!(Document::from_reader_with_options(bytes.as_slice(), sizes_only).is_err());
+        let missing_size = DeserializerOptions::builder()
+            .unknown_elements(true)
+            .unknown_element_size(0x80, ElementSize::Fixed(2))
+            .build();
+        assert!(crate::from_slice_with_options::<Document>(&bytes, missing_size).is_err());
+
+        // an unlisted size is not inferred from the document length, even for the last element
+        let last = b"\x10\0\0\0\x10i\0\x01\0\0\0\x82z\0\xAB\0";
+        assert!(Document::from_reader_with_options(&last[..], options.clone()).is_err());
+        assert!(crate::from_slice_with_options::<Document>(last, options.clone()).is_err());
+        let mut iter = crate::RawDocument::new(last).unwrap().iter_with_options(&options);
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+
+        assert_eq!(
+            Document::from_reader_with_options(bytes.as_slice(), options.clone()).unwrap(),
+            doc
+        );
+        let mut doc_bytes = Vec::new();
+        doc.to_writer(&mut doc_bytes).unwrap();
+        assert_eq!(doc_bytes, bytes);
+        assert_eq!(
+            crate::from_slice_with_options::<Document>(&bytes, options.clone()).unwrap(),
+            doc
+        );
+        assert_eq!(crate::to_vec(&doc).unwrap(), bytes);
+        assert_eq!(crate::from_document::<Document>(doc.clone()).unwrap(), doc);
+
+        // nested documents are decoded with the same options
+        let nested = doc! { "d": doc.clone(), "a": [doc.get("f").unwrap().clone()] };
+        let nested_bytes = crate::to_vec(&nested).unwrap();
+        assert_eq!(
+            Document::from_reader_with_options(nested_bytes.as_slice(), options.clone()).unwrap(),
+            nested
+        );
+
+        let raw = crate::RawDocument::new(&bytes).unwrap();
+        assert!(raw.into_iter().next().unwrap().is_err());
+        let values = raw
+            .iter_with_options(&options)
+            .map(|r| r.map(|(_, v)| v))
+            .collect::<crate::raw::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                RawBson::Unknown {
+                    tag: 0x80,
+                    bytes: b"\xAB\xCD"
+                },
+                RawBson::Unknown {
+                    tag: 0x81,
+                    bytes: b"\x06\0\0\0xy"
+                },
+                RawBson::Int32(1),
+            ]
+        );
+        assert_eq!(
+            Bson::try_from(values[1]).unwrap(),
+            doc.get("l").unwrap().clone()
+        );
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Borrowed<'a> {
+            #[serde(borrow)]
+            f: RawBson<'a>,
+            l: RawBson<'a>,
+            i: i32,
+        }
+
+        let borrowed: Borrowed = crate::from_slice_with_options(&bytes, options).unwrap();
+        assert_eq!(borrowed.f.as_unknown(), Some((0x80, &b"\xAB\xCD"[..])));
+        assert_eq!(crate::to_vec(&borrowed).unwrap(), bytes);
+
+        let canonical = json!({
+            "f": { "$unknown": { "tag": "80", "base64": "q80=" } },
+            "l": { "$unknown": { "tag": "81", "base64": "BgAAAHh5" } },
+            "i": { "$numberInt": "1" },
+        });
+        let bson = Bson::Document(doc.clone());
+        assert_eq!(bson.clone().into_canonical_extjson(), canonical);
+        assert_eq!(Bson::try_from(canonical.clone()).unwrap(), bson);
+        assert_eq!(from_str::<Document>(&canonical.to_string()).unwrap(), doc);
+        assert!(crate::extjson::ser::raw_document_to_writer(
+            &mut Vec::new(),
+            raw,
+            Default::default()
+        )
+        .is_err());
+
+        // only tags that are unassigned in this crate may be written as unknown elements
+        for &tag in &[0x00, 0x02, 0x14] {
+            let doc = doc! { "x": Bson::Unknown { tag, bytes: vec![] } };
+            assert!(crate::to_vec(&doc).is_err());
+            assert!(doc.to_writer(&mut Vec::new()).is_err());
+        }
+    }
+}
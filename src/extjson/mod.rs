@@ -31,6 +31,10 @@
 //!   - In relaxed mode, the string in the datetime object notation is RFC 3339 (ISO-8601) formatted
 //!     (if the date is after 1970).
 //!
+//! Unsigned 64-bit integers larger than `i64::MAX` are the exception to the first rule: many JSON
+//! parsers read such numbers back as doubles, so they keep the `$numberUInt64` notation in both
+//! modes.
+//!
 //! e.g.
 //! ```rust
 //! # use bson::bson;
@@ -88,6 +92,59 @@
 //! let canonical_extjson = doc.into_canonical_extjson();
 //! println!("{}", canonical_extjson); // { "x": { "$numberInt": "5" }, "_id": { "$oid": <hexstring> } }
 //! ```
+//!
+//! The conversions above panic if a value has no extended JSON representation.
+//! [`Bson::try_into_relaxed_extjson`](../enum.Bson.html#method.try_into_relaxed_extjson) and
+//! [`Bson::try_into_canonical_extjson`](../enum.Bson.html#method.try_into_canonical_extjson)
+//! return a [`ser::Error`] naming the key path of the offending value instead.
+//!
+//! ## Serde Integration
+//!
+//! [`from_str`] and [`from_reader`] deserialize extended JSON text directly into any type
+//! implementing [`serde::Deserialize`], and [`to_string`] and [`to_writer`] serialize any type
+//! implementing [`serde::Serialize`] directly into extended JSON text. Neither builds a
+//! [`serde_json::Value`] or [`Bson`](../enum.Bson.html) along the way, and types are mapped the
+//! same way as by [`crate::from_slice`] and [`crate::to_bson`].
+//!
+//! e.g.
+//! ```rust
+//! # use bson::{extjson, oid::ObjectId, DateTime};
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Event {
+//!     _id: ObjectId,
+//!     at: DateTime,
+//!     count: i64,
+//! }
+//!
+//! let json = r#"{
+//!     "_id": { "$oid": "5ed2a84eb8bf0a6ef1ad4c5e" },
+//!     "at": { "$date": "2020-05-30T18:47:10.123Z" },
+//!     "count": { "$numberLong": "3" }
+//! }"#;
+//! let event: Event = extjson::from_str(json)?;
+//! assert_eq!(event.count, 3);
+//!
+//! let relaxed = extjson::to_string(&event)?;
+//! assert_eq!(
+//!     relaxed,
+//!     r#"{"_id":{"$oid":"5ed2a84eb8bf0a6ef1ad4c5e"},"at":{"$date":"2020-05-30T18:47:10.123Z"},"count":3}"#
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Mongo Shell Syntax
+//!
+//! The [`shell`] module reads and writes the syntax of the legacy mongo shell, e.g.
+//! `{ "_id" : ObjectId("..."), "n" : NumberLong(5) }`, along with the Extended JSON v1 forms such
+//! as `{ "$date": 1590972160292 }`.
 
 pub mod de;
 pub(crate) mod models;
+pub mod ser;
+pub mod shell;
+
+pub use self::{
+    de::{from_reader, from_str},
+    ser::{to_string, to_writer, to_writer_with_options},
+};
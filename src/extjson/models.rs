@@ -47,6 +47,44 @@ impl Int64 {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct UInt32 {
+    #[serde(rename = "$numberUInt32")]
+    value: String,
+}
+
+impl UInt32 {
+    pub(crate) fn parse(self) -> extjson::de::Result<u32> {
+        let i: u32 = self.value.parse().map_err(|_| {
+            extjson::de::Error::invalid_value(
+                Unexpected::Str(self.value.as_str()),
+                &"expected u32 as a string",
+            )
+        })?;
+        Ok(i)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct UInt64 {
+    #[serde(rename = "$numberUInt64")]
+    value: String,
+}
+
+impl UInt64 {
+    pub(crate) fn parse(self) -> extjson::de::Result<u64> {
+        let i: u64 = self.value.parse().map_err(|_| {
+            extjson::de::Error::invalid_value(
+                Unexpected::Str(self.value.as_str()),
+                &"expected u64 as a string",
+            )
+        })?;
+        Ok(i)
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct Double {
@@ -73,6 +111,24 @@ impl Double {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Decimal128 {
+    #[serde(rename = "$numberDecimal")]
+    value: String,
+}
+
+impl Decimal128 {
+    pub(crate) fn parse(self) -> extjson::de::Result<crate::Decimal128> {
+        self.value.parse().map_err(|_| {
+            extjson::de::Error::invalid_value(
+                Unexpected::Str(self.value.as_str()),
+                &"decimal128 as a string",
+            )
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct ObjectId {
@@ -93,6 +149,25 @@ impl From<crate::oid::ObjectId> for ObjectId {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Regex {
+    #[serde(rename = "$regularExpression")]
+    body: RegexBody,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RegexBody {
+    pub(crate) pattern: String,
+    pub(crate) options: String,
+}
+
+impl Regex {
+    pub(crate) fn parse(self) -> crate::Regex {
+        crate::Regex::new(self.body.pattern, self.body.options)
+    }
+}
 
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -174,6 +249,22 @@ pub(crate) struct JavaScriptCodeWithScope {
     pub(crate) scope: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
+impl JavaScriptCodeWithScope {
+    pub(crate) fn parse(self) -> extjson::de::Result<Bson> {
+        use std::convert::TryInto;
+
+        match self.scope {
+            Some(scope) => Ok(Bson::JavaScriptCodeWithScope(
+                crate::JavaScriptCodeWithScope {
+                    code: self.code,
+                    scope: scope.try_into()?,
+                },
+            )),
+            None => Ok(Bson::JavaScriptCode(self.code)),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct Timestamp {
@@ -245,4 +336,136 @@ impl DateTime {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MinKey {
+    #[serde(rename = "$minKey")]
+    pub(crate) value: u8,
+}
+
+impl MinKey {
+    pub(crate) fn parse(self) -> extjson::de::Result<Bson> {
+        if self.value == 1 {
+            Ok(Bson::MinKey)
+        } else {
+            Err(extjson::de::Error::invalid_value(
+                Unexpected::Unsigned(self.value as u64),
+                &"value of $minKey should always be 1",
+            ))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MaxKey {
+    #[serde(rename = "$maxKey")]
+    pub(crate) value: u8,
+}
+
+impl MaxKey {
+    pub(crate) fn parse(self) -> extjson::de::Result<Bson> {
+        if self.value == 1 {
+            Ok(Bson::MaxKey)
+        } else {
+            Err(extjson::de::Error::invalid_value(
+                Unexpected::Unsigned(self.value as u64),
+                &"value of $maxKey should always be 1",
+            ))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Undefined {
+    #[serde(rename = "$undefined")]
+    pub(crate) value: bool,
+}
+
+impl Undefined {
+    pub(crate) fn parse(self) -> extjson::de::Result<Bson> {
+        if self.value {
+            Ok(Bson::Undefined)
+        } else {
+            Err(extjson::de::Error::invalid_value(
+                Unexpected::Bool(false),
+                &"$undefined should always be true",
+            ))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Symbol {
+    #[serde(rename = "$symbol")]
+    pub(crate) value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DbPointer {
+    #[serde(rename = "$dbPointer")]
+    body: DbPointerBody,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DbPointerBody {
+    #[serde(rename = "$ref")]
+    pub(crate) ref_ns: String,
 
+    #[serde(rename = "$id")]
+    pub(crate) id: ObjectId,
+}
+
+impl DbPointer {
+    pub(crate) fn parse(self) -> extjson::de::Result<crate::DbPointer> {
+        Ok(crate::DbPointer {
+            namespace: self.body.ref_ns,
+            id: self.body.id.parse()?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Unknown {
+    #[serde(rename = "$unknown")]
+    pub(crate) body: UnknownBody,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct UnknownBody {
+    pub(crate) tag: String,
+
+    pub(crate) base64: String,
+}
+
+impl Unknown {
+    pub(crate) fn parse(self) -> extjson::de::Result<Bson> {
+        let tag = hex::decode(self.body.tag.as_str()).map_err(|_| {
+            extjson::de::Error::invalid_value(
+                Unexpected::Str(self.body.tag.as_str()),
+                &"hexadecimal number as a string",
+            )
+        })?;
+        if tag.len() != 1 {
+            return Err(extjson::de::Error::invalid_value(
+                Unexpected::Bytes(tag.as_slice()),
+                &"one byte tag",
+            ));
+        }
+
+        let bytes = base64::decode(self.body.base64.as_str()).map_err(|_| {
+            extjson::de::Error::invalid_value(
+                Unexpected::Str(self.body.base64.as_str()),
+                &"base64 encoded bytes",
+            )
+        })?;
+
+        Ok(Bson::Unknown { tag: tag[0], bytes })
+    }
+}
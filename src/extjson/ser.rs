@@ -0,0 +1,1671 @@
+//! Serializing [MongoDB Extended JSON v2](https://docs.mongodb.com/manual/reference/mongodb-extended-json/)
+//!
+//! ## Usage
+//!
+//! Extended JSON can be created from [`Bson`](../../enum.Bson.html) with
+//! [`Bson::try_into_relaxed_extjson`](../../enum.Bson.html#method.try_into_relaxed_extjson) and
+//! [`Bson::try_into_canonical_extjson`](../../enum.Bson.html#method.try_into_canonical_extjson).
+//! If a value cannot be represented, the returned error names the key path leading to it.
+//!
+//! e.g.
+//! ```rust
+//! # use bson::{bson, Bson};
+//! let doc = bson!({ "x": 5i32, "y": [{ "z": 1.5 }] });
+//! let json = doc.try_into_canonical_extjson()?;
+//! # Ok::<(), bson::extjson::ser::Error>(())
+//! ```
+//!
+//! ## Streaming
+//!
+//! Building a [`serde_json::Value`] requires an owned [`Bson`](../../enum.Bson.html) and holds the
+//! whole JSON tree in memory. [`bson_to_writer`], [`document_to_writer`] and
+//! [`raw_document_to_writer`] instead write extended JSON directly to an [`io::Write`] from a
+//! borrowed value. The output is byte-for-byte the same as serializing the corresponding
+//! `serde_json::Value` with [`serde_json::to_writer`] or [`serde_json::to_writer_pretty`].
+//!
+//! e.g.
+//! ```rust
+//! # use bson::{doc, extjson::ser::{document_to_writer, WriterOptions}};
+//! let doc = doc! { "x": 5i32, "y": [1.5] };
+//!
+//! let mut relaxed = Vec::new();
+//! document_to_writer(&mut relaxed, &doc, WriterOptions::default())?;
+//! assert_eq!(relaxed, br#"{"x":5,"y":[1.5]}"#);
+//!
+//! let mut canonical = Vec::new();
+//! let options = WriterOptions::builder().canonical(true).pretty(true).build();
+//! document_to_writer(&mut canonical, &doc, options)?;
+//! # Ok::<(), bson::extjson::ser::Error>(())
+//! ```
+
+use std::{error, fmt, io, result, sync::Arc};
+
+use chrono::Datelike;
+use serde::{
+    ser::{
+        Error as _,
+        Impossible,
+        SerializeMap,
+        SerializeSeq,
+        SerializeStruct,
+        SerializeStructVariant,
+        SerializeTuple,
+        SerializeTupleStruct,
+        SerializeTupleVariant,
+    },
+    Serialize,
+};
+use serde_json::{
+    json,
+    ser::{CompactFormatter, Formatter, PrettyFormatter},
+    Value,
+};
+
+use crate::{
+    codec::ElementCodecRegistry,
+    raw::{
+        RawArray,
+        RawBinary,
+        RawBson,
+        RawDbPointer,
+        RawDocument,
+        RawJavaScriptCodeWithScope,
+        RawRegex,
+    },
+    spec::{BinarySubtype, ElementType},
+    uuid::UUID_NEWTYPE_NAME,
+    Binary,
+    Bson,
+    DbPointer,
+    DeserializerOptions,
+    Document,
+    JavaScriptCodeWithScope,
+    Regex,
+    Timestamp,
+};
+
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+/// Error cases that can occur during serialization to [extended JSON](https://docs.mongodb.com/manual/reference/mongodb-extended-json/).
+pub enum Error {
+    /// A [`std::io::Error`] encountered while writing the output.
+    Io(Arc<io::Error>),
+
+    /// A value of the given type has no extended JSON representation.
+    #[non_exhaustive]
+    UnsupportedType {
+        /// The dotted path of the key holding the value.
+        key_path: String,
+
+        /// The type of the value.
+        element_type: ElementType,
+    },
+
+    /// A general error that occurred during serialization.
+    /// See: <https://docs.rs/serde/1.0.110/serde/ser/trait.Error.html#tymethod.custom>
+    #[non_exhaustive]
+    SerializationError {
+        /// A message describing the error.
+        message: String,
+    },
+
+    /// A raw document being written contained malformed BSON.
+    #[non_exhaustive]
+    InvalidBson {
+        /// The dotted path of the document or array containing the malformed value.
+        key_path: String,
+
+        /// The error encountered while reading the raw BSON.
+        error: crate::raw::Error,
+    },
+}
+
+impl Error {
+    /// The dotted path of the key whose value failed to convert, with array elements named by
+    /// their index. Empty if the failing value was the top-level value itself or if the error
+    /// is not tied to a value.
+    pub fn key_path(&self) -> &str {
+        match self {
+            Error::Io(_) | Error::SerializationError { .. } => "",
+            Error::UnsupportedType { key_path, .. } | Error::InvalidBson { key_path, .. } => {
+                key_path
+            }
+        }
+    }
+
+    pub(crate) fn unsupported_type(element_type: ElementType) -> Self {
+        Error::UnsupportedType {
+            key_path: String::new(),
+            element_type,
+        }
+    }
+
+    /// Prepends the key of the containing document or array to the key path of this error.
+    pub(crate) fn with_parent_key(mut self, key: &str) -> Self {
+        match self {
+            Error::Io(_) | Error::SerializationError { .. } => {}
+            Error::UnsupportedType {
+                ref mut key_path, ..
+            }
+            | Error::InvalidBson {
+                ref mut key_path, ..
+            } => {
+                if key_path.is_empty() {
+                    *key_path = key.to_string();
+                } else {
+                    key_path.insert(0, '.');
+                    key_path.insert_str(0, key);
+                }
+            }
+        }
+        self
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(Arc::new(err))
+    }
+}
+
+impl From<crate::raw::Error> for Error {
+    fn from(error: crate::raw::Error) -> Error {
+        Error::InvalidBson {
+            key_path: String::new(),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(inner) => inner.fmt(fmt),
+            Error::SerializationError { message } => message.fmt(fmt),
+            Error::UnsupportedType {
+                key_path,
+                element_type,
+            } => {
+                write!(
+                    fmt,
+                    "value of type {:?} has no extended JSON representation",
+                    element_type
+                )?;
+                if !key_path.is_empty() {
+                    write!(fmt, " (at key \"{}\")", key_path)?;
+                }
+                Ok(())
+            }
+            Error::InvalidBson { key_path, error } if key_path.is_empty() => {
+                write!(fmt, "invalid BSON: {}", error)
+            }
+            Error::InvalidBson { key_path, error } => {
+                write!(fmt, "invalid BSON in \"{}\": {}", key_path, error)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(inner) => Some(inner.as_ref()),
+            Error::InvalidBson { error, .. } => Some(error),
+            Error::UnsupportedType { .. } | Error::SerializationError { .. } => None,
+        }
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::SerializationError {
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Alias for `Result<T, extjson::ser::Error>`.
+pub type Result<T> = result::Result<T, Error>;
+
+/// Options used to configure how extended JSON is written.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct WriterOptions {
+    /// Whether to write canonical rather than relaxed extended JSON.
+    /// The default value is false.
+    pub canonical: bool,
+
+    /// Whether to indent the output in the same way as [`serde_json::to_writer_pretty`].
+    /// The default value is false.
+    pub pretty: bool,
+
+    /// Codecs whose extended JSON form is used for elements of custom types instead of
+    /// `$unknown`. Raw documents may also contain elements of these types.
+    pub codecs: ElementCodecRegistry,
+}
+
+impl WriterOptions {
+    /// The options for reading raw documents that are being written.
+    fn read_options(&mut self) -> Arc<DeserializerOptions> {
+        Arc::new(
+            DeserializerOptions::builder()
+                .codecs(std::mem::take(&mut self.codecs))
+                .build(),
+        )
+    }
+}
+
+impl WriterOptions {
+    /// Create a builder used to construct a new [`WriterOptions`].
+    pub fn builder() -> WriterOptionsBuilder {
+        WriterOptionsBuilder {
+            options: Default::default(),
+        }
+    }
+}
+
+/// A builder used to construct new [`WriterOptions`] structs.
+pub struct WriterOptionsBuilder {
+    options: WriterOptions,
+}
+
+impl WriterOptionsBuilder {
+    /// Set the value for [`WriterOptions::canonical`].
+    pub fn canonical(mut self, value: bool) -> Self {
+        self.options.canonical = value;
+        self
+    }
+
+    /// Set the value for [`WriterOptions::pretty`].
+    pub fn pretty(mut self, value: bool) -> Self {
+        self.options.pretty = value;
+        self
+    }
+
+    /// Set the value for [`WriterOptions::codecs`].
+    pub fn codecs(mut self, value: ElementCodecRegistry) -> Self {
+        self.options.codecs = value;
+        self
+    }
+
+    /// Consume this builder and produce a [`WriterOptions`].
+    pub fn build(self) -> WriterOptions {
+        self.options
+    }
+}
+
+/// Write the extended JSON representation of `bson` to `writer`.
+pub fn bson_to_writer<W: io::Write>(writer: W, bson: &Bson, options: WriterOptions) -> Result<()> {
+    with_writer(writer, options, |w| w.write_bson(bson))
+}
+
+/// Write the extended JSON representation of `doc` to `writer`.
+pub fn document_to_writer<W: io::Write>(
+    writer: W,
+    doc: &Document,
+    options: WriterOptions,
+) -> Result<()> {
+    with_writer(writer, options, |w| w.write_document(doc))
+}
+
+/// Write the extended JSON representation of `doc` to `writer` without decoding it into a
+/// [`Document`] first.
+///
+/// Should `doc` contain the same key more than once, every occurrence is written, whereas
+/// converting it to a [`Document`] would only keep the last one.
+pub fn raw_document_to_writer<W: io::Write>(
+    writer: W,
+    doc: &RawDocument,
+    options: WriterOptions,
+) -> Result<()> {
+    with_writer(writer, options, |w| w.write_raw_document(doc))
+}
+
+/// Serialize the given `T` as a string of relaxed extended JSON.
+///
+/// Types are mapped the same way as by [`crate::to_bson`], without building an intermediate
+/// [`Bson`].
+pub fn to_string<T>(value: &T) -> Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, value)?;
+    String::from_utf8(bytes).map_err(Error::custom)
+}
+
+/// Serialize the given `T` as relaxed extended JSON into `writer`.
+///
+/// See [`to_string`] for how `T` is mapped onto extended JSON.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize + ?Sized,
+{
+    to_writer_with_options(writer, value, WriterOptions::default())
+}
+
+/// Serialize the given `T` as extended JSON into `writer`, configured with the provided
+/// [`WriterOptions`].
+pub fn to_writer_with_options<W, T>(writer: W, value: &T, mut options: WriterOptions) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize + ?Sized,
+{
+    if options.pretty {
+        value.serialize(&mut Writer {
+            writer,
+            formatter: PrettyFormatter::new(),
+            canonical: options.canonical,
+            read_options: options.read_options(),
+        })
+    } else {
+        value.serialize(&mut Writer {
+            writer,
+            formatter: CompactFormatter,
+            canonical: options.canonical,
+            read_options: options.read_options(),
+        })
+    }
+}
+
+fn with_writer<W, F>(writer: W, mut options: WriterOptions, f: F) -> Result<()>
+where
+    W: io::Write,
+    F: FnOnce(&mut dyn ValueWriter) -> Result<()>,
+{
+    if options.pretty {
+        f(&mut Writer {
+            writer,
+            formatter: PrettyFormatter::new(),
+            canonical: options.canonical,
+            read_options: options.read_options(),
+        })
+    } else {
+        f(&mut Writer {
+            writer,
+            formatter: CompactFormatter,
+            canonical: options.canonical,
+            read_options: options.read_options(),
+        })
+    }
+}
+
+/// Object-safe view of a [`Writer`], so that the entry points only need to be instantiated once
+/// per formatter.
+trait ValueWriter {
+    fn write_bson(&mut self, bson: &Bson) -> Result<()>;
+    fn write_document(&mut self, doc: &Document) -> Result<()>;
+    fn write_raw_document(&mut self, doc: &RawDocument) -> Result<()>;
+}
+
+impl<W: io::Write, F: Formatter> ValueWriter for Writer<W, F> {
+    fn write_bson(&mut self, bson: &Bson) -> Result<()> {
+        Writer::write_bson(self, bson)
+    }
+
+    fn write_document(&mut self, doc: &Document) -> Result<()> {
+        Writer::write_document(self, doc)
+    }
+
+    fn write_raw_document(&mut self, doc: &RawDocument) -> Result<()> {
+        Writer::write_raw_document(self, doc)
+    }
+}
+
+/// Writes extended JSON through a [`serde_json`] formatter, mirroring the shapes produced by
+/// `Bson::try_into_extjson`.
+struct Writer<W, F> {
+    writer: W,
+    formatter: F,
+    canonical: bool,
+
+    /// Options for iterating raw documents, holding the codecs of custom element types.
+    read_options: Arc<DeserializerOptions>,
+}
+
+impl<W: io::Write, F: Formatter> Writer<W, F> {
+    fn write_bson(&mut self, bson: &Bson) -> Result<()> {
+        match bson {
+            Bson::Array(v) => {
+                self.formatter.begin_array(&mut self.writer)?;
+                for (i, v) in v.iter().enumerate() {
+                    self.formatter.begin_array_value(&mut self.writer, i == 0)?;
+                    self.write_bson(v)
+                        .map_err(|e| e.with_parent_key(&i.to_string()))?;
+                    self.formatter.end_array_value(&mut self.writer)?;
+                }
+                self.formatter.end_array(&mut self.writer)?;
+                Ok(())
+            }
+            Bson::Document(doc) => self.write_document(doc),
+            Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope { code, scope }) => {
+                self.write_code_with_scope(code, |w| w.write_document(scope))
+            }
+            other => self.write_scalar(
+                raw_scalar(other).expect("documents, arrays and code with scope are handled above"),
+            ),
+        }
+    }
+
+    fn write_document(&mut self, doc: &Document) -> Result<()> {
+        self.formatter.begin_object(&mut self.writer)?;
+        for (i, (k, v)) in doc.iter().enumerate() {
+            self.begin_entry(k, i == 0)?;
+            self.write_bson(v).map_err(|e| e.with_parent_key(k))?;
+            self.formatter.end_object_value(&mut self.writer)?;
+        }
+        self.formatter.end_object(&mut self.writer)?;
+        Ok(())
+    }
+
+    fn write_raw_bson(&mut self, bson: RawBson) -> Result<()> {
+        match bson {
+            RawBson::Document(doc) => self.write_raw_document(doc),
+            RawBson::Array(arr) => self.write_raw_array(arr),
+            RawBson::JavaScriptCodeWithScope(RawJavaScriptCodeWithScope { code, scope }) => {
+                self.write_code_with_scope(code, |w| w.write_raw_document(scope))
+            }
+            other => self.write_scalar(other),
+        }
+    }
+
+    fn write_raw_document(&mut self, doc: &RawDocument) -> Result<()> {
+        let read_options = Arc::clone(&self.read_options);
+        self.formatter.begin_object(&mut self.writer)?;
+        for (i, result) in doc.iter_with_options(&read_options).enumerate() {
+            let (k, v) = result?;
+            self.begin_entry(k, i == 0)?;
+            self.write_raw_bson(v).map_err(|e| e.with_parent_key(k))?;
+            self.formatter.end_object_value(&mut self.writer)?;
+        }
+        self.formatter.end_object(&mut self.writer)?;
+        Ok(())
+    }
+
+    fn write_raw_array(&mut self, arr: &RawArray) -> Result<()> {
+        let read_options = Arc::clone(&self.read_options);
+        self.formatter.begin_array(&mut self.writer)?;
+        for (i, result) in arr.iter_with_options(&read_options).enumerate() {
+            let v = result?;
+            self.formatter.begin_array_value(&mut self.writer, i == 0)?;
+            self.write_raw_bson(v)
+                .map_err(|e| e.with_parent_key(&i.to_string()))?;
+            self.formatter.end_array_value(&mut self.writer)?;
+        }
+        self.formatter.end_array(&mut self.writer)?;
+        Ok(())
+    }
+
+    /// Writes `{ "$code": code, "$scope": scope }`, with the scope written by `write_scope`.
+    fn write_code_with_scope(
+        &mut self,
+        code: &str,
+        write_scope: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        self.formatter.begin_object(&mut self.writer)?;
+        self.begin_entry("$code", true)?;
+        self.write_str(code)?;
+        self.formatter.end_object_value(&mut self.writer)?;
+        self.begin_entry("$scope", false)?;
+        write_scope(self).map_err(|e| e.with_parent_key("$scope"))?;
+        self.formatter.end_object_value(&mut self.writer)?;
+        self.formatter.end_object(&mut self.writer)?;
+        Ok(())
+    }
+
+    /// Writes a value that is neither a document nor an array.
+    fn write_scalar(&mut self, value: RawBson) -> Result<()> {
+        match value {
+            RawBson::Unknown { tag, bytes } if self.read_options.codecs.contains(tag) => {
+                let read_options = Arc::clone(&self.read_options);
+                let (key, body) = read_options
+                    .codecs
+                    .to_extjson(tag, bytes)
+                    .unwrap_or_else(|| unreachable!("codec registered for {:#04x}", tag))
+                    .map_err(Error::custom)?;
+                self.begin_single_entry_object(key)?;
+                self.write_json(&body)?;
+                self.end_single_entry_object()
+            }
+            _ => match scalar_to_extjson(value, self.canonical) {
+                Some(json) => self.write_json(&json),
+                None => self.write_raw_bson(value),
+            },
+        }
+    }
+
+    /// Writes a plain JSON value.
+    fn write_json(&mut self, value: &serde_json::Value) -> Result<()> {
+        match value {
+            serde_json::Value::Null => self.formatter.write_null(&mut self.writer)?,
+            serde_json::Value::Bool(b) => self.formatter.write_bool(&mut self.writer, *b)?,
+            serde_json::Value::Number(n) => self
+                .formatter
+                .write_number_str(&mut self.writer, &n.to_string())?,
+            serde_json::Value::String(s) => self.write_str(s)?,
+            serde_json::Value::Array(values) => {
+                self.formatter.begin_array(&mut self.writer)?;
+                for (i, v) in values.iter().enumerate() {
+                    self.formatter.begin_array_value(&mut self.writer, i == 0)?;
+                    self.write_json(v)?;
+                    self.formatter.end_array_value(&mut self.writer)?;
+                }
+                self.formatter.end_array(&mut self.writer)?;
+            }
+            serde_json::Value::Object(map) => {
+                self.formatter.begin_object(&mut self.writer)?;
+                for (i, (k, v)) in map.iter().enumerate() {
+                    self.begin_entry(k, i == 0)?;
+                    self.write_json(v)?;
+                    self.formatter.end_object_value(&mut self.writer)?;
+                }
+                self.formatter.end_object(&mut self.writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn begin_single_entry_object(&mut self, key: &str) -> Result<()> {
+        self.formatter.begin_object(&mut self.writer)?;
+        self.begin_entry(key, true)
+    }
+
+    fn end_single_entry_object(&mut self) -> Result<()> {
+        self.formatter.end_object_value(&mut self.writer)?;
+        self.formatter.end_object(&mut self.writer)?;
+        Ok(())
+    }
+
+    /// Writes an object key and the separator following it.
+    fn begin_entry(&mut self, key: &str, first: bool) -> Result<()> {
+        self.formatter.begin_object_key(&mut self.writer, first)?;
+        self.write_str(key)?;
+        self.formatter.end_object_key(&mut self.writer)?;
+        self.formatter.begin_object_value(&mut self.writer)?;
+        Ok(())
+    }
+
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        // Strings are written the same way by every formatter, so borrowing serde_json's escaping
+        // through a compact serializer produces the same bytes.
+        serde_json::to_writer(&mut self.writer, s).map_err(io::Error::from)?;
+        Ok(())
+    }
+}
+
+/// Borrows a value that is neither a document, an array nor code with scope as a [`RawBson`], or
+/// returns `None` if it is one of those.
+pub(crate) fn raw_scalar(bson: &Bson) -> Option<RawBson<'_>> {
+    let value = match bson {
+        Bson::Double(v) => RawBson::Double(*v),
+        Bson::String(v) => RawBson::String(v),
+        Bson::Boolean(v) => RawBson::Boolean(*v),
+        Bson::Null => RawBson::Null,
+        Bson::RegularExpression(Regex { pattern, options }) => {
+            RawBson::RegularExpression(RawRegex { pattern, options })
+        }
+        Bson::JavaScriptCode(v) => RawBson::JavaScriptCode(v),
+        Bson::Int32(v) => RawBson::Int32(*v),
+        Bson::Int64(v) => RawBson::Int64(*v),
+        Bson::UInt32(v) => RawBson::UInt32(*v),
+        Bson::UInt64(v) => RawBson::UInt64(*v),
+        Bson::Timestamp(v) => RawBson::Timestamp(*v),
+        Bson::Binary(Binary { subtype, bytes }) => RawBson::Binary(RawBinary {
+            subtype: *subtype,
+            bytes,
+        }),
+        Bson::ObjectId(v) => RawBson::ObjectId(*v),
+        Bson::DateTime(v) => RawBson::DateTime(*v),
+        Bson::Symbol(v) => RawBson::Symbol(v),
+        Bson::Decimal128(v) => RawBson::Decimal128(*v),
+        Bson::Undefined => RawBson::Undefined,
+        Bson::MaxKey => RawBson::MaxKey,
+        Bson::MinKey => RawBson::MinKey,
+        Bson::DbPointer(DbPointer { namespace, id }) => {
+            RawBson::DbPointer(RawDbPointer { namespace, id: *id })
+        }
+        Bson::Unknown { tag, bytes } => RawBson::Unknown { tag: *tag, bytes },
+        Bson::Document(_) | Bson::Array(_) | Bson::JavaScriptCodeWithScope(_) => return None,
+    };
+    Some(value)
+}
+
+/// The extended JSON form of a value that is neither a document, an array nor code with scope,
+/// or `None` if it is one of those. Both `Bson::try_into_extjson` and [`Writer`] go through this,
+/// so that they produce the same shapes. Elements of unknown type get the `$unknown` form; the
+/// writer handles the ones with a registered codec itself.
+pub(crate) fn scalar_to_extjson(value: RawBson, canonical: bool) -> Option<Value> {
+    let json = match value {
+        RawBson::Int32(i) if canonical => json!({ "$numberInt": i.to_string() }),
+        RawBson::Int64(i) if canonical => json!({ "$numberLong": i.to_string() }),
+        RawBson::UInt32(i) if canonical => json!({ "$numberUInt32": i.to_string() }),
+        RawBson::UInt64(i) if canonical => json!({ "$numberUInt64": i.to_string() }),
+        RawBson::Double(f) if canonical || !f.is_finite() => {
+            json!({ "$numberDouble": format_double(f) })
+        }
+        RawBson::DateTime(date) if canonical => {
+            json!({ "$date": { "$numberLong": date.timestamp_millis().to_string() } })
+        }
+        RawBson::Double(v) => json!(v),
+        RawBson::String(v) => json!(v),
+        RawBson::Boolean(v) => json!(v),
+        RawBson::Null => Value::Null,
+        RawBson::RegularExpression(RawRegex { pattern, options }) => json!({
+            "$regularExpression": {
+                "pattern": pattern,
+                "options": options,
+            }
+        }),
+        RawBson::JavaScriptCode(code) => json!({ "$code": code }),
+        RawBson::Int32(v) => v.into(),
+        RawBson::Int64(v) => v.into(),
+        RawBson::UInt32(v) => v.into(),
+        // JSON numbers above i64::MAX are commonly read back as doubles, so keep those exact by
+        // falling back to the canonical form.
+        RawBson::UInt64(v) if v > i64::MAX as u64 => json!({ "$numberUInt64": v.to_string() }),
+        RawBson::UInt64(v) => v.into(),
+        RawBson::Timestamp(Timestamp { time, increment }) => json!({
+            "$timestamp": {
+                "t": time,
+                "i": increment,
+            }
+        }),
+        RawBson::Binary(RawBinary { subtype, bytes }) => {
+            let tval: u8 = From::from(subtype);
+            json!({
+                "$binary": {
+                    "base64": base64::encode(bytes),
+                    "subType": hex::encode([tval]),
+                }
+            })
+        }
+        RawBson::ObjectId(v) => json!({ "$oid": v.to_hex() }),
+        RawBson::DateTime(v) if v.timestamp_millis() >= 0 && v.to_chrono().year() <= 99999 => {
+            json!({ "$date": v.to_rfc3339_string() })
+        }
+        RawBson::DateTime(v) => json!({
+            "$date": { "$numberLong": v.timestamp_millis().to_string() },
+        }),
+        RawBson::Symbol(v) => json!({ "$symbol": v }),
+        RawBson::Decimal128(v) => json!({ "$numberDecimal": v.to_string() }),
+        RawBson::Undefined => json!({ "$undefined": true }),
+        RawBson::MinKey => json!({ "$minKey": 1 }),
+        RawBson::MaxKey => json!({ "$maxKey": 1 }),
+        RawBson::DbPointer(RawDbPointer { namespace, id }) => json!({
+            "$dbPointer": {
+                "$ref": namespace,
+                "$id": { "$oid": id.to_hex() },
+            }
+        }),
+        RawBson::Unknown { tag, bytes } => json!({
+            "$unknown": {
+                "tag": hex::encode([tag]),
+                "base64": base64::encode(bytes),
+            }
+        }),
+        RawBson::Document(_) | RawBson::Array(_) | RawBson::JavaScriptCodeWithScope(_) => {
+            return None
+        }
+    };
+    Some(json)
+}
+
+/// Formats a double as the string held by `$numberDouble`.
+///
+/// Finite values are written with the shortest digits that round-trip, always including a
+/// fractional part. Magnitudes from 1.0E-3 up to but excluding 1.0E+7 are written in decimal
+/// notation, e.g. `"1.5"` or `"0.001"`, and all others in exponent notation with an explicit sign,
+/// e.g. `"1.0E+300"` or `"-1.0E-10"`. This matches the output of the other MongoDB drivers and the
+/// BSON corpus. Non-finite values are written as `"NaN"`, `"Infinity"` and `"-Infinity"`, since
+/// extended JSON has no notation for the sign or payload of a NaN.
+pub(crate) fn format_double(f: f64) -> String {
+    if f.is_nan() {
+        return "NaN".to_string();
+    } else if f.is_infinite() {
+        let s = if f > 0.0 { "Infinity" } else { "-Infinity" };
+        return s.to_string();
+    } else if f == 0.0 {
+        let s = if f.is_sign_negative() { "-0.0" } else { "0.0" };
+        return s.to_string();
+    }
+
+    // `{:e}` produces the shortest digits that round-trip, e.g. "1.2345e18" or "5e-324".
+    let scientific = format!("{:e}", f.abs());
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap_or(0));
+    let exponent: i32 = exponent[1..].parse().unwrap_or(0);
+    let digits = mantissa.replace('.', "");
+
+    let mut s = String::with_capacity(digits.len() + 8);
+    if f < 0.0 {
+        s.push('-');
+    }
+    if (-3..7).contains(&exponent) {
+        if exponent < 0 {
+            s.push_str("0.");
+            s.push_str(&"0".repeat((-exponent - 1) as usize));
+            s.push_str(&digits);
+        } else {
+            let integer_len = exponent as usize + 1;
+            if digits.len() > integer_len {
+                s.push_str(&digits[..integer_len]);
+                s.push('.');
+                s.push_str(&digits[integer_len..]);
+            } else {
+                s.push_str(&digits);
+                s.push_str(&"0".repeat(integer_len - digits.len()));
+                s.push_str(".0");
+            }
+        }
+    } else {
+        s.push_str(&digits[..1]);
+        s.push('.');
+        s.push_str(if digits.len() > 1 { &digits[1..] } else { "0" });
+        s.push('E');
+        s.push(if exponent < 0 { '-' } else { '+' });
+        s.push_str(&exponent.abs().to_string());
+    }
+    s
+}
+
+impl<'a, W: io::Write, F: Formatter> serde::Serializer for &'a mut Writer<W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a, W, F>;
+    type SerializeTuple = Compound<'a, W, F>;
+    type SerializeTupleStruct = Compound<'a, W, F>;
+    type SerializeTupleVariant = Compound<'a, W, F>;
+    type SerializeMap = Compound<'a, W, F>;
+    type SerializeStruct = StructSerializer<'a, W, F>;
+    type SerializeStructVariant = Compound<'a, W, F>;
+
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.write_scalar(RawBson::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.write_scalar(RawBson::Int32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.write_scalar(RawBson::Int64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_i32(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.write_scalar(RawBson::UInt64(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.write_scalar(RawBson::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.write_scalar(RawBson::String(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.write_scalar(RawBson::Binary(RawBinary {
+            subtype: BinarySubtype::Generic,
+            bytes: v,
+        }))
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        self.write_scalar(RawBson::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        match name {
+            UUID_NEWTYPE_NAME => {
+                let bson = crate::Serializer::new()
+                    .serialize_newtype_struct(name, value)
+                    .map_err(Error::custom)?;
+                self.write_bson(&bson)
+            }
+            _ => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.begin_single_entry_object(variant)?;
+        value.serialize(&mut *self)?;
+        self.end_single_entry_object()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.formatter.begin_array(&mut self.writer)?;
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.begin_single_entry_object(variant)?;
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.formatter.begin_object(&mut self.writer)?;
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        match name {
+            // BSON types serialize themselves as structs named after their extended JSON key.
+            "$oid" | "$date" | "$binary" | "$regularExpression" | "$timestamp" | "$code"
+            | "$codeWithScope" | "$numberDecimal" | "$symbol" | "$dbPointer" | "$minKey"
+            | "$maxKey" | "$undefined" | "$unknown" => Ok(StructSerializer::Value {
+                writer: self,
+                inner: crate::Serializer::new()
+                    .serialize_struct(name, len)
+                    .map_err(Error::custom)?,
+            }),
+            _ => self
+                .serialize_map(Some(len))
+                .map(StructSerializer::Document),
+        }
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.begin_single_entry_object(variant)?;
+        self.serialize_map(Some(len))
+    }
+}
+
+/// Serializes the elements of arrays and the entries of objects.
+struct Compound<'a, W, F> {
+    writer: &'a mut Writer<W, F>,
+    first: bool,
+}
+
+impl<'a, W: io::Write, F: Formatter> Compound<'a, W, F> {
+    fn new(writer: &'a mut Writer<W, F>) -> Self {
+        Self {
+            writer,
+            first: true,
+        }
+    }
+
+    fn element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let first = std::mem::replace(&mut self.first, false);
+        self.writer
+            .formatter
+            .begin_array_value(&mut self.writer.writer, first)?;
+        value.serialize(&mut *self.writer)?;
+        self.writer
+            .formatter
+            .end_array_value(&mut self.writer.writer)?;
+        Ok(())
+    }
+
+    fn entry<T>(&mut self, key: &str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let first = std::mem::replace(&mut self.first, false);
+        self.writer.begin_entry(key, first)?;
+        value.serialize(&mut *self.writer)?;
+        self.writer
+            .formatter
+            .end_object_value(&mut self.writer.writer)?;
+        Ok(())
+    }
+
+    fn end_array(self) -> Result<()> {
+        self.writer.formatter.end_array(&mut self.writer.writer)?;
+        Ok(())
+    }
+
+    fn end_object(self) -> Result<()> {
+        self.writer.formatter.end_object(&mut self.writer.writer)?;
+        Ok(())
+    }
+}
+
+impl<'a, W: io::Write, F: Formatter> SerializeSeq for Compound<'a, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.end_array()
+    }
+}
+
+impl<'a, W: io::Write, F: Formatter> SerializeTuple for Compound<'a, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.end_array()
+    }
+}
+
+impl<'a, W: io::Write, F: Formatter> SerializeTupleStruct for Compound<'a, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.end_array()
+    }
+}
+
+impl<'a, W: io::Write, F: Formatter> SerializeTupleVariant for Compound<'a, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        let writer = &mut *self.writer;
+        writer.formatter.end_array(&mut writer.writer)?;
+        writer.end_single_entry_object()
+    }
+}
+
+impl<'a, W: io::Write, F: Formatter> SerializeMap for Compound<'a, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let first = std::mem::replace(&mut self.first, false);
+        key.serialize(KeySerializer {
+            writer: &mut *self.writer,
+            first,
+        })
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(&mut *self.writer)?;
+        self.writer
+            .formatter
+            .end_object_value(&mut self.writer.writer)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        self.end_object()
+    }
+}
+
+impl<'a, W: io::Write, F: Formatter> SerializeStructVariant for Compound<'a, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        let writer = &mut *self.writer;
+        writer.formatter.end_object(&mut writer.writer)?;
+        writer.end_single_entry_object()
+    }
+}
+
+/// Serializes structs, collecting the fields of the serde form of BSON types so that they can be
+/// written in their extended JSON form.
+enum StructSerializer<'a, W, F> {
+    Value {
+        writer: &'a mut Writer<W, F>,
+        inner: <crate::Serializer as serde::Serializer>::SerializeStruct,
+    },
+    Document(Compound<'a, W, F>),
+}
+
+impl<'a, W: io::Write, F: Formatter> SerializeStruct for StructSerializer<'a, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self {
+            StructSerializer::Value { inner, .. } => {
+                inner.serialize_field(key, value).map_err(Error::custom)
+            }
+            StructSerializer::Document(compound) => compound.entry(key, value),
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        match self {
+            StructSerializer::Value { writer, inner } => {
+                let bson = inner.end().map_err(Error::custom)?;
+                writer.write_bson(&bson)
+            }
+            StructSerializer::Document(compound) => compound.end_object(),
+        }
+    }
+}
+
+/// Serializes map keys, which must be strings.
+struct KeySerializer<'a, W, F> {
+    writer: &'a mut Writer<W, F>,
+    first: bool,
+}
+
+impl<'a, W, F> KeySerializer<'a, W, F> {
+    fn invalid_key() -> Error {
+        Error::custom("map keys must be strings")
+    }
+}
+
+impl<'a, W: io::Write, F: Formatter> serde::Serializer for KeySerializer<'a, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.writer.begin_entry(v, self.first)
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(Self::invalid_key())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Self::invalid_key())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::{
+        bson_to_writer,
+        document_to_writer,
+        format_double,
+        raw_document_to_writer,
+        to_string,
+        to_writer_with_options,
+        Error,
+        WriterOptions,
+    };
+    use crate::{
+        oid::ObjectId,
+        spec::{BinarySubtype, ElementType},
+        Binary,
+        Bson,
+        DateTime,
+        Decimal128,
+        Document,
+        RawDocument,
+        Timestamp,
+        Uuid,
+    };
+
+    #[test]
+    fn key_path() {
+        let err = Error::unsupported_type(ElementType::String);
+        assert_eq!(err.key_path(), "");
+        assert_eq!(
+            err.to_string(),
+            "value of type String has no extended JSON representation"
+        );
+
+        let err = err
+            .with_parent_key("b")
+            .with_parent_key("0")
+            .with_parent_key("a");
+        assert_eq!(err.key_path(), "a.0.b");
+        assert_eq!(
+            err.to_string(),
+            "value of type String has no extended JSON representation (at key \"a.0.b\")"
+        );
+
+        assert!(matches!(
+            Bson::String("x".to_string()).into_extended_document(),
+            Err(Error::UnsupportedType {
+                element_type: ElementType::String,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn nested_values() {
+        let d: Decimal128 = "1.5".parse().unwrap();
+        let bson = bson!({ "a": [1, { "b": Bson::Decimal128(d) }] });
+
+        assert_eq!(
+            bson.clone().try_into_relaxed_extjson().unwrap(),
+            json!({ "a": [1, { "b": { "$numberDecimal": "1.5" } }] })
+        );
+        assert_eq!(
+            bson.try_into_canonical_extjson().unwrap(),
+            json!({ "a": [{ "$numberInt": "1" }, { "b": { "$numberDecimal": "1.5" } }] })
+        );
+    }
+
+    fn all_types() -> Document {
+        doc! {
+            "double": 1.5,
+            "whole double": 3.0,
+            "big double": 1.0e300,
+            "subnormal": 5e-324,
+            "negative zero": -0.0,
+            "nan": f64::NAN,
+            "infinity": f64::NEG_INFINITY,
+            "string": "line\nbreak \"quoted\" \u{1} ünïcödé",
+            "array": [1, "two", [], {}, [3.5, { "deep": true }]],
+            "empty array": [],
+            "document": { "a": Bson::Null, "b": { "c": false } },
+            "empty document": {},
+            "i32": -7i32,
+            "i64": i64::MIN,
+            "u32": u32::MAX,
+            "u64 small": 5u64,
+            "u64 large": u64::MAX,
+            "timestamp": Timestamp { time: 1, increment: 2 },
+            "binary": Binary { subtype: BinarySubtype::Md5, bytes: vec![0, 255, 7] },
+            "generic binary": Binary { subtype: BinarySubtype::Generic, bytes: Vec::new() },
+            "oid": ObjectId::parse_str("0102030405060708090a0b0c").unwrap(),
+            "datetime": DateTime::from_millis(1_590_972_160_292),
+            "old datetime": DateTime::from_millis(-1),
+            "far datetime": DateTime::from_millis(i64::MAX),
+            "decimal": Bson::Decimal128("-1.00E-8".parse::<Decimal128>().unwrap()),
+        }
+    }
+
+    #[test]
+    fn writer_matches_value() {
+        let doc = all_types();
+        let mut raw = Vec::new();
+        doc.to_writer(&mut raw).unwrap();
+        let raw = RawDocument::new(&raw).unwrap();
+
+        for &canonical in &[false, true] {
+            let value = if canonical {
+                Bson::Document(doc.clone()).into_canonical_extjson()
+            } else {
+                Bson::Document(doc.clone()).into_relaxed_extjson()
+            };
+
+            for &pretty in &[false, true] {
+                let expected = if pretty {
+                    serde_json::to_vec_pretty(&value).unwrap()
+                } else {
+                    serde_json::to_vec(&value).unwrap()
+                };
+                let options = || {
+                    WriterOptions::builder()
+                        .canonical(canonical)
+                        .pretty(pretty)
+                        .build()
+                };
+
+                let mut out = Vec::new();
+                document_to_writer(&mut out, &doc, options()).unwrap();
+                assert_eq!(
+                    String::from_utf8(out).unwrap(),
+                    String::from_utf8(expected.clone()).unwrap(),
+                    "canonical: {}, pretty: {}",
+                    canonical,
+                    pretty
+                );
+
+                let mut out = Vec::new();
+                raw_document_to_writer(&mut out, raw, options()).unwrap();
+                assert_eq!(out, expected);
+
+                let mut out = Vec::new();
+                bson_to_writer(&mut out, &Bson::Array(vec![doc.clone().into()]), options())
+                    .unwrap();
+                let array_value = serde_json::Value::Array(vec![value.clone()]);
+                let expected = if pretty {
+                    serde_json::to_vec_pretty(&array_value).unwrap()
+                } else {
+                    serde_json::to_vec(&array_value).unwrap()
+                };
+                assert_eq!(out, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn writer_errors() {
+        let mut bytes = Vec::new();
+        doc! { "a": { "b": [1, "x"] } }
+            .to_writer(&mut bytes)
+            .unwrap();
+        // corrupt the length of the string "x" inside the array
+        let len = bytes.len();
+        bytes[len - 9] = 0xff;
+        let raw = RawDocument::new(&bytes).unwrap();
+
+        let err = raw_document_to_writer(Vec::new(), raw, WriterOptions::default()).unwrap_err();
+        assert!(matches!(err, Error::InvalidBson { .. }), "{:?}", err);
+        assert_eq!(err.key_path(), "a.b");
+    }
+
+    #[derive(Serialize)]
+    enum Shape {
+        Unit,
+        Newtype(i64),
+        Tuple(i32, String),
+        Struct { side: f64 },
+    }
+
+    #[derive(Serialize)]
+    struct Record {
+        small: u8,
+        int: i32,
+        unsigned: u32,
+        long: i64,
+        huge: u64,
+        float: f32,
+        nan: f64,
+        letter: char,
+        #[serde(with = "serde_bytes")]
+        bytes: Vec<u8>,
+        none: Option<i32>,
+        some: Option<&'static str>,
+        unit: (),
+        tuple: (i32, bool),
+        shapes: Vec<Shape>,
+        map: BTreeMap<char, DateTime>,
+        uuid: Uuid,
+        document: Document,
+    }
+
+    #[test]
+    fn to_string_matches_to_bson() {
+        let record = Record {
+            small: 1,
+            int: -2,
+            unsigned: u32::MAX,
+            long: i64::MIN,
+            huge: u64::MAX,
+            float: 0.5,
+            nan: f64::NAN,
+            letter: 'é',
+            bytes: vec![1, 2, 3],
+            none: None,
+            some: Some("x"),
+            unit: (),
+            tuple: (1, true),
+            shapes: vec![
+                Shape::Unit,
+                Shape::Newtype(1),
+                Shape::Tuple(2, "x".to_string()),
+                Shape::Struct { side: 3.0 },
+            ],
+            map: vec![('a', DateTime::from_millis(0))].into_iter().collect(),
+            uuid: Uuid::parse_str("00112233-4455-6677-8899-aabbccddeeff").unwrap(),
+            document: all_types(),
+        };
+        let bson = crate::to_bson(&record).unwrap();
+
+        for &canonical in &[false, true] {
+            for &pretty in &[false, true] {
+                let options = || {
+                    WriterOptions::builder()
+                        .canonical(canonical)
+                        .pretty(pretty)
+                        .build()
+                };
+
+                let mut expected = Vec::new();
+                bson_to_writer(&mut expected, &bson, options()).unwrap();
+                let mut out = Vec::new();
+                to_writer_with_options(&mut out, &record, options()).unwrap();
+                assert_eq!(
+                    String::from_utf8(out).unwrap(),
+                    String::from_utf8(expected).unwrap(),
+                    "canonical: {}, pretty: {}",
+                    canonical,
+                    pretty
+                );
+            }
+        }
+
+        assert_eq!(to_string(&5u32).unwrap(), "5");
+        assert_eq!(
+            to_string(&ObjectId::parse_str("0102030405060708090a0b0c").unwrap()).unwrap(),
+            r#"{"$oid":"0102030405060708090a0b0c"}"#
+        );
+    }
+
+    #[test]
+    fn canonical_doubles() {
+        let cases = [
+            (1.0, "1.0"),
+            (-1.0, "-1.0"),
+            (0.0, "0.0"),
+            (-0.0, "-0.0"),
+            (1.5, "1.5"),
+            (1.0001220703125, "1.0001220703125"),
+            (-1.0001220703125, "-1.0001220703125"),
+            (0.001, "0.001"),
+            (0.0001, "1.0E-4"),
+            (1234567.0, "1234567.0"),
+            (12345678.0, "1.2345678E+7"),
+            (1e300, "1.0E+300"),
+            (-1e-10, "-1.0E-10"),
+            (1.2345678921232e18, "1.2345678921232E+18"),
+            (-1.2345678921232e18, "-1.2345678921232E+18"),
+            (f64::MAX, "1.7976931348623157E+308"),
+            (f64::MIN_POSITIVE, "2.2250738585072014E-308"),
+            (5e-324, "5.0E-324"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+            (f64::NAN, "NaN"),
+            (-f64::NAN, "NaN"),
+        ];
+        let options = WriterOptions::builder().canonical(true).build();
+        for (value, expected) in cases.iter() {
+            assert_eq!(format_double(*value), *expected, "{:?}", value);
+
+            let bson = Bson::Double(*value);
+            let expected = json!({ "$numberDouble": expected });
+            assert_eq!(bson.clone().into_canonical_extjson(), expected);
+
+            let mut written = Vec::new();
+            bson_to_writer(&mut written, &bson, options.clone()).unwrap();
+            assert_eq!(written, expected.to_string().into_bytes());
+        }
+
+        // exhaustively exercise the formatter against a spread of bit patterns, including
+        // subnormals, checking that every finite value round-trips exactly.
+        let mut bits = 0x9E37_79B9_7F4A_7C15u64;
+        for _ in 0..20_000 {
+            bits ^= bits << 13;
+            bits ^= bits >> 7;
+            bits ^= bits << 17;
+            for candidate in [bits, bits >> 12, bits & 0x800F_FFFF_FFFF_FFFF].iter() {
+                let value = f64::from_bits(*candidate);
+                if !value.is_finite() {
+                    continue;
+                }
+                let formatted = format_double(value);
+                let parsed: f64 = formatted.parse().unwrap();
+                assert_eq!(parsed.to_bits(), value.to_bits(), "{}", formatted);
+            }
+        }
+    }
+
+    #[test]
+    fn to_string_errors() {
+        let map: BTreeMap<i32, i32> = vec![(1, 2)].into_iter().collect();
+        let err = to_string(&map).unwrap_err();
+        assert!(matches!(err, Error::SerializationError { .. }), "{:?}", err);
+    }
+}
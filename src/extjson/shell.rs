@@ -0,0 +1,1158 @@
+//! Reading and writing the mongo shell syntax used by legacy tooling.
+//!
+//! Before Extended JSON v2, the mongo shell and tools such as `mongoexport` produced text with
+//! constructor calls like `ObjectId("...")`, `ISODate("...")` and `NumberLong(5)` as well as the
+//! Extended JSON v1 forms such as `{ "$date": 1590972160292 }`. This module can parse that text
+//! into [`Bson`] values and print values back in the same syntax.
+//!
+//! ## Parsing
+//!
+//! [`from_str`] and [`document_from_str`] accept:
+//!   - JSON, with single-quoted strings, unquoted keys, trailing commas and `//` or `/* */`
+//!     comments also allowed.
+//!   - The constructors `ObjectId`, `ISODate`, `Date`, `NumberInt`, `NumberLong`,
+//!     `NumberDecimal`, `BinData`, `UUID` and `Timestamp`, optionally preceded by `new`.
+//!   - The literals `NaN`, `Infinity` and `-Infinity`, and regular expression literals such as
+//!     `/^a/i`.
+//!   - The Extended JSON v1 and v2 forms `$oid`, `$date`, `$numberInt`, `$numberLong`,
+//!     `$numberUInt32`, `$numberUInt64`, `$numberDouble`, `$numberDecimal`, `$binary` (with or
+//!     without `$type`), `$regularExpression`, `$regex` with `$options`, `$code` with or without
+//!     `$scope`, `$timestamp`, `$symbol`, `$minKey`, `$maxKey`, `$undefined` and `$dbPointer`,
+//!     as well as the `$unknown` form this crate uses for elements of unknown type.
+//!   - The deprecated and special types as the shell writes them: `MinKey`, `MaxKey`, `undefined`
+//!     and `DBPointer("<namespace>", ObjectId("..."))`.
+//!
+//! Numbers without a fraction or exponent become [`Bson::Int32`], or [`Bson::Int64`] if they do not
+//! fit, as with Extended JSON. All other numbers become [`Bson::Double`].
+//!
+//! ```rust
+//! # use bson::{doc, extjson::shell, oid::ObjectId, DateTime};
+//! let doc = shell::document_from_str(
+//!     r#"{ _id: ObjectId("5ed2a84eb8bf0a6ef1ad4c5e"), n: NumberLong(5), "t": { "$date": 0 } }"#,
+//! )?;
+//! assert_eq!(
+//!     doc,
+//!     doc! {
+//!         "_id": ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+//!         "n": 5i64,
+//!         "t": DateTime::from_millis(0),
+//!     }
+//! );
+//! # Ok::<(), bson::extjson::shell::Error>(())
+//! ```
+//!
+//! ## Printing
+//!
+//! [`to_string`] and [`document_to_string`] write values on a single line in the style of the
+//! legacy shell. Every type is written in a form that [`from_str`] and the shell read back as the
+//! same type: integers use `NumberInt` and `NumberLong`, and doubles always contain a decimal point
+//! or an exponent. The fork's unsigned integer types have no shell equivalent and are written as
+//! `$numberUInt32` and `$numberUInt64` documents. Regular expressions are written as literals,
+//! unless the pattern cannot be written that way, e.g. because it contains an unescaped `/`, in
+//! which case they are written as `$regex` documents. JavaScript code is written as `$code`
+//! documents, with a `$scope` key when it has one, symbols as `$symbol` documents and elements of
+//! unknown type as `$unknown` documents.
+//!
+//! ```rust
+//! # use bson::{doc, extjson::shell};
+//! let doc = doc! { "a": 1, "b": [2.0, "x"] };
+//! assert_eq!(shell::document_to_string(&doc), r#"{ "a" : NumberInt(1), "b" : [ 2.0, "x" ] }"#);
+//! ```
+
+use std::{convert::TryFrom, error, fmt, fmt::Write, result};
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::{
+    oid::ObjectId,
+    path::TypeName,
+    spec::BinarySubtype,
+    Binary,
+    Bson,
+    DateTime,
+    DbPointer,
+    Decimal128,
+    Document,
+    JavaScriptCodeWithScope,
+    Regex,
+    Timestamp,
+};
+
+/// An error encountered while parsing shell syntax.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Error {
+    /// A description of the problem.
+    pub message: String,
+
+    /// The 1-based line at which the problem was found.
+    pub line: usize,
+
+    /// The 1-based column, in characters, at which the problem was found.
+    pub column: usize,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl error::Error for Error {}
+
+/// Alias for `Result<T, extjson::shell::Error>`.
+pub type Result<T> = result::Result<T, Error>;
+
+/// Parse a single value written in shell syntax.
+pub fn from_str(s: &str) -> Result<Bson> {
+    let mut parser = Parser { input: s, pos: 0 };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos < s.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Parse a single document written in shell syntax.
+pub fn document_from_str(s: &str) -> Result<Document> {
+    match from_str(s)? {
+        Bson::Document(doc) => Ok(doc),
+        other => Err(Parser { input: s, pos: 0 }.error(format!(
+            "expected a document, found {}",
+            TypeName(other.tag())
+        ))),
+    }
+}
+
+/// Print a value in shell syntax.
+pub fn to_string(bson: &Bson) -> String {
+    let mut out = String::new();
+    let _ = write_bson(&mut out, bson);
+    out
+}
+
+/// Print a document in shell syntax.
+pub fn document_to_string(doc: &Document) -> String {
+    let mut out = String::new();
+    let _ = write_document(&mut out, doc);
+    out
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl Into<String>) -> Error {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, pos: usize, message: impl Into<String>) -> Error {
+        let before = &self.input[..pos];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Error {
+            message: message.into(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.skip_whitespace();
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", c)))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        match self.peek() {
+            Some(c) => self.error(format!("expected {}, found '{}'", expected, c)),
+            None => self.error(format!("expected {}, found end of input", expected)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.input[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                self.pos += comment.find("*/").map_or(trimmed.len(), |i| i + 4);
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Bson> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some('{') => self.parse_document(),
+            Some('[') => self.parse_array(),
+            Some(quote @ '"') | Some(quote @ '\'') => self.parse_string(quote).map(Bson::String),
+            // Comments were skipped above, so a slash can only start a regular expression.
+            Some('/') => self.parse_regex(),
+            Some(c) if c == '-' || c == '+' || c == '.' || c.is_ascii_digit() => {
+                self.parse_number()
+            }
+            Some(c) if is_identifier_start(c) => {
+                let mut name = self.parse_identifier();
+                if name == "new" {
+                    self.skip_whitespace();
+                    name = self.parse_identifier();
+                }
+                match name {
+                    "true" => Ok(Bson::Boolean(true)),
+                    "false" => Ok(Bson::Boolean(false)),
+                    "null" => Ok(Bson::Null),
+                    "NaN" => Ok(Bson::Double(f64::NAN)),
+                    "Infinity" => Ok(Bson::Double(f64::INFINITY)),
+                    "undefined" => Ok(Bson::Undefined),
+                    // The shell's MinKey and MaxKey may be written bare or called.
+                    "MinKey" | "MaxKey" => {
+                        self.skip_whitespace();
+                        if self.peek() == Some('(') {
+                            self.parse_constructor(name, start)
+                        } else if name == "MinKey" {
+                            Ok(Bson::MinKey)
+                        } else {
+                            Ok(Bson::MaxKey)
+                        }
+                    }
+                    "" => Err(self.unexpected("a constructor name")),
+                    name => self.parse_constructor(name, start),
+                }
+            }
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> &'a str {
+        let rest = &self.input[self.pos..];
+        let len = rest
+            .find(|c: char| !is_identifier_char(c))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn parse_document(&mut self) -> Result<Bson> {
+        let start = self.pos;
+        self.expect('{')?;
+        let mut doc = Document::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat('}') {
+                break;
+            }
+
+            let key = match self.peek() {
+                Some(quote @ '"') | Some(quote @ '\'') => self.parse_string(quote)?,
+                Some(c) if is_identifier_start(c) => self.parse_identifier().to_string(),
+                _ => return Err(self.unexpected("a key or '}'")),
+            };
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            doc.insert(key, value);
+
+            self.skip_whitespace();
+            if !self.eat(',') {
+                self.expect('}')?;
+                break;
+            }
+        }
+        self.extended_value(doc, start)
+    }
+
+    fn parse_array(&mut self) -> Result<Bson> {
+        self.expect('[')?;
+        let mut array = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat(']') {
+                break;
+            }
+
+            array.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            if !self.eat(',') {
+                self.expect(']')?;
+                break;
+            }
+        }
+        Ok(Bson::Array(array))
+    }
+
+    fn parse_string(&mut self, quote: char) -> Result<String> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some(c) if c == quote => return Ok(s),
+                Some('\\') => {
+                    let escape_start = self.pos - 1;
+                    let c = match self.bump() {
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape(escape_start)?,
+                        Some(c @ '"') | Some(c @ '\'') | Some(c @ '\\') | Some(c @ '/') => c,
+                        _ => return Err(self.error_at(escape_start, "invalid escape sequence")),
+                    };
+                    s.push(c);
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn parse_regex(&mut self) -> Result<Bson> {
+        let start = self.pos;
+        self.bump();
+        let mut in_class = false;
+        loop {
+            match self.bump() {
+                None | Some('\n') | Some('\r') => {
+                    return Err(self.error_at(start, "unterminated regular expression"))
+                }
+                Some('\\') => {
+                    if let None | Some('\n') | Some('\r') = self.bump() {
+                        return Err(self.error_at(start, "unterminated regular expression"));
+                    }
+                }
+                Some('[') => in_class = true,
+                Some(']') => in_class = false,
+                Some('/') if !in_class => break,
+                Some(_) => {}
+            }
+        }
+        let pattern = &self.input[start + 1..self.pos - 1];
+        let options = self.parse_identifier();
+        Ok(Bson::RegularExpression(Regex::new(pattern, options)))
+    }
+
+    fn parse_unicode_escape(&mut self, escape_start: usize) -> Result<char> {
+        let invalid = |p: &Self| p.error_at(escape_start, "invalid unicode escape");
+
+        let read_unit = |p: &mut Self| -> Result<u32> {
+            let hex = p.input.get(p.pos..p.pos + 4).ok_or_else(|| invalid(p))?;
+            let unit = u32::from_str_radix(hex, 16).map_err(|_| invalid(p))?;
+            p.pos += 4;
+            Ok(unit)
+        };
+
+        let first = read_unit(self)?;
+        let code = if (0xd800..0xdc00).contains(&first) {
+            if !self.input[self.pos..].starts_with("\\u") {
+                return Err(invalid(self));
+            }
+            self.pos += 2;
+            let second = read_unit(self)?;
+            if !(0xdc00..0xe000).contains(&second) {
+                return Err(invalid(self));
+            }
+            0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)
+        } else {
+            first
+        };
+        std::char::from_u32(code).ok_or_else(|| invalid(self))
+    }
+
+    fn parse_number(&mut self) -> Result<Bson> {
+        let start = self.pos;
+        let negative = self.eat('-');
+        if !negative {
+            self.eat('+');
+        }
+        if self.input[self.pos..].starts_with("Infinity") {
+            self.pos += "Infinity".len();
+            return Ok(Bson::Double(if negative {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            }));
+        }
+
+        let mut is_integer = true;
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' => {}
+                '.' | 'e' | 'E' => is_integer = false,
+                '+' | '-'
+                    if matches!(self.input[..self.pos].chars().last(), Some('e') | Some('E')) => {}
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        let text = &self.input[start..self.pos];
+        if is_integer {
+            if let Ok(i) = text.parse::<i64>() {
+                return Ok(match i32::try_from(i) {
+                    Ok(i) => Bson::Int32(i),
+                    Err(_) => Bson::Int64(i),
+                });
+            }
+        }
+        text.parse::<f64>()
+            .map(Bson::Double)
+            .map_err(|_| self.error_at(start, format!("invalid number \"{}\"", text)))
+    }
+
+    fn parse_constructor(&mut self, name: &str, start: usize) -> Result<Bson> {
+        self.expect('(')?;
+        let mut args = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat(')') {
+                break;
+            }
+            args.push(self.parse_value()?);
+            self.skip_whitespace();
+            if !self.eat(',') {
+                self.expect(')')?;
+                break;
+            }
+        }
+
+        let invalid = || self.error_at(start, format!("invalid arguments to {}", name));
+        let value = match (name, args.as_slice()) {
+            ("ObjectId", [Bson::String(s)]) => {
+                Bson::ObjectId(ObjectId::parse_str(s).map_err(|_| invalid())?)
+            }
+            ("ISODate", [Bson::String(s)]) | ("Date", [Bson::String(s)]) => {
+                Bson::DateTime(parse_date(s).ok_or_else(invalid)?)
+            }
+            ("ISODate", [millis]) | ("Date", [millis]) => {
+                Bson::DateTime(DateTime::from_millis(as_i64(millis).ok_or_else(invalid)?))
+            }
+            ("NumberInt", [Bson::String(s)]) => Bson::Int32(s.parse().map_err(|_| invalid())?),
+            ("NumberInt", [n]) => Bson::Int32(
+                as_i64(n)
+                    .and_then(|i| i32::try_from(i).ok())
+                    .ok_or_else(invalid)?,
+            ),
+            ("NumberLong", [Bson::String(s)]) => Bson::Int64(s.parse().map_err(|_| invalid())?),
+            ("NumberLong", [n]) => Bson::Int64(as_i64(n).ok_or_else(invalid)?),
+            ("NumberDecimal", [Bson::String(s)]) => {
+                Bson::Decimal128(s.parse().map_err(|_| invalid())?)
+            }
+            ("BinData", [subtype, Bson::String(data)]) => {
+                let subtype = as_i64(subtype)
+                    .and_then(|i| u8::try_from(i).ok())
+                    .ok_or_else(invalid)?;
+                Bson::Binary(Binary {
+                    subtype: subtype.into(),
+                    bytes: base64::decode(data).map_err(|_| invalid())?,
+                })
+            }
+            ("UUID", [Bson::String(s)]) => {
+                let bytes = hex::decode(s.replace('-', "")).map_err(|_| invalid())?;
+                if bytes.len() != 16 {
+                    return Err(invalid());
+                }
+                Bson::Binary(Binary {
+                    subtype: BinarySubtype::Uuid,
+                    bytes,
+                })
+            }
+            ("Timestamp", [time, increment]) => Bson::Timestamp(Timestamp {
+                time: as_u32(time).ok_or_else(invalid)?,
+                increment: as_u32(increment).ok_or_else(invalid)?,
+            }),
+            ("MinKey", []) => Bson::MinKey,
+            ("MaxKey", []) => Bson::MaxKey,
+            ("DBPointer", [Bson::String(namespace), Bson::ObjectId(id)]) => {
+                Bson::DbPointer(DbPointer {
+                    namespace: namespace.clone(),
+                    id: *id,
+                })
+            }
+            (
+                "ObjectId" | "ISODate" | "Date" | "NumberInt" | "NumberLong" | "NumberDecimal"
+                | "BinData" | "UUID" | "Timestamp" | "MinKey" | "MaxKey" | "DBPointer",
+                _,
+            ) => return Err(invalid()),
+            _ => return Err(self.error_at(start, format!("unknown constructor {}", name))),
+        };
+        Ok(value)
+    }
+
+    /// Converts documents using the Extended JSON v1 or v2 notation for a type into that type.
+    /// Documents with other keys, e.g. query operators, are returned unchanged.
+    fn extended_value(&self, doc: Document, start: usize) -> Result<Bson> {
+        let mut keys: Vec<_> = doc.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        let invalid = |what: &str| self.error_at(start, format!("invalid {} value", what));
+        let get_str = |key: &str| match doc.get(key) {
+            Some(Bson::String(s)) => Ok(s.as_str()),
+            _ => Err(invalid(key)),
+        };
+
+        let value = match keys.as_slice() {
+            ["$oid"] => {
+                Bson::ObjectId(ObjectId::parse_str(get_str("$oid")?).map_err(|_| invalid("$oid"))?)
+            }
+            ["$date"] => {
+                let date = match doc.get("$date") {
+                    Some(Bson::String(s)) => parse_date(s),
+                    Some(other) => as_i64(other).map(DateTime::from_millis),
+                    None => None,
+                };
+                Bson::DateTime(date.ok_or_else(|| invalid("$date"))?)
+            }
+            ["$numberInt"] => Bson::Int32(
+                get_str("$numberInt")?
+                    .parse()
+                    .map_err(|_| invalid("$numberInt"))?,
+            ),
+            ["$numberLong"] => Bson::Int64(
+                get_str("$numberLong")?
+                    .parse()
+                    .map_err(|_| invalid("$numberLong"))?,
+            ),
+            ["$numberUInt32"] => Bson::UInt32(
+                get_str("$numberUInt32")?
+                    .parse()
+                    .map_err(|_| invalid("$numberUInt32"))?,
+            ),
+            ["$numberUInt64"] => Bson::UInt64(
+                get_str("$numberUInt64")?
+                    .parse()
+                    .map_err(|_| invalid("$numberUInt64"))?,
+            ),
+            ["$numberDouble"] => Bson::Double(match get_str("$numberDouble")? {
+                "Infinity" => f64::INFINITY,
+                "-Infinity" => f64::NEG_INFINITY,
+                "NaN" => f64::NAN,
+                other => other.parse().map_err(|_| invalid("$numberDouble"))?,
+            }),
+            ["$numberDecimal"] => Bson::Decimal128(
+                get_str("$numberDecimal")?
+                    .parse::<Decimal128>()
+                    .map_err(|_| invalid("$numberDecimal"))?,
+            ),
+            ["$binary", "$type"] => {
+                let subtype = hex::decode(get_str("$type")?).map_err(|_| invalid("$type"))?;
+                if subtype.len() != 1 {
+                    return Err(invalid("$type"));
+                }
+                Bson::Binary(Binary {
+                    subtype: subtype[0].into(),
+                    bytes: base64::decode(get_str("$binary")?).map_err(|_| invalid("$binary"))?,
+                })
+            }
+            ["$binary"] => {
+                Bson::Binary(Binary::from_extended_doc(&doc).ok_or_else(|| invalid("$binary"))?)
+            }
+            ["$regularExpression"] => {
+                let regex = match doc.get("$regularExpression") {
+                    Some(Bson::Document(regex)) if regex.len() == 2 => regex
+                        .get_str("pattern")
+                        .ok()
+                        .zip(regex.get_str("options").ok()),
+                    _ => None,
+                };
+                let (pattern, options) = regex.ok_or_else(|| invalid("$regularExpression"))?;
+                Bson::RegularExpression(Regex::new(pattern, options))
+            }
+            // Only the Extended JSON v1 form with both keys holding strings is converted, leaving
+            // query operators such as `{ "$regex": /^a/ }` untouched.
+            ["$options", "$regex"] => match (doc.get("$regex"), doc.get("$options")) {
+                (Some(Bson::String(pattern)), Some(Bson::String(options))) => {
+                    Bson::RegularExpression(Regex::new(pattern, options))
+                }
+                _ => return Ok(Bson::Document(doc)),
+            },
+            ["$code"] => Bson::JavaScriptCode(get_str("$code")?.to_string()),
+            ["$code", "$scope"] => {
+                let scope = match doc.get("$scope") {
+                    Some(Bson::Document(scope)) => scope.clone(),
+                    _ => return Err(invalid("$scope")),
+                };
+                Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope {
+                    code: get_str("$code")?.to_string(),
+                    scope,
+                })
+            }
+            ["$timestamp"] => {
+                let timestamp = match doc.get("$timestamp") {
+                    Some(Bson::Document(ts)) if ts.len() == 2 => ts
+                        .get("t")
+                        .and_then(as_u32)
+                        .zip(ts.get("i").and_then(as_u32)),
+                    _ => None,
+                };
+                let (time, increment) = timestamp.ok_or_else(|| invalid("$timestamp"))?;
+                Bson::Timestamp(Timestamp { time, increment })
+            }
+            ["$symbol"] => Bson::Symbol(get_str("$symbol")?.to_string()),
+            ["$undefined"] => match doc.get("$undefined") {
+                Some(Bson::Boolean(true)) => Bson::Undefined,
+                _ => return Err(invalid("$undefined")),
+            },
+            ["$minKey"] => match doc.get("$minKey").and_then(as_i64) {
+                Some(1) => Bson::MinKey,
+                _ => return Err(invalid("$minKey")),
+            },
+            ["$maxKey"] => match doc.get("$maxKey").and_then(as_i64) {
+                Some(1) => Bson::MaxKey,
+                _ => return Err(invalid("$maxKey")),
+            },
+            ["$dbPointer"] => {
+                let pointer = match doc.get("$dbPointer") {
+                    Some(Bson::Document(pointer)) => DbPointer::from_extended_doc(pointer),
+                    _ => None,
+                };
+                Bson::DbPointer(pointer.ok_or_else(|| invalid("$dbPointer"))?)
+            }
+            ["$unknown"] => match Bson::from_extended_document(doc) {
+                unknown @ Bson::Unknown { .. } => unknown,
+                _ => return Err(invalid("$unknown")),
+            },
+            _ => return Ok(Bson::Document(doc)),
+        };
+        Ok(value)
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Returns the value of an integer, or of a double without a fractional part.
+fn as_i64(bson: &Bson) -> Option<i64> {
+    match *bson {
+        Bson::Int32(i) => Some(i.into()),
+        Bson::Int64(i) => Some(i),
+        Bson::Double(f) if f.fract() == 0.0 && f.abs() < 9.2e18 => Some(f as i64),
+        _ => None,
+    }
+}
+
+fn as_u32(bson: &Bson) -> Option<u32> {
+    as_i64(bson).and_then(|i| u32::try_from(i).ok())
+}
+
+/// Parses the dates accepted by `ISODate`: RFC 3339 timestamps, with or without a time zone, and
+/// plain dates.
+fn parse_date(s: &str) -> Option<DateTime> {
+    if let Ok(date) = DateTime::parse_rfc3339_str(s) {
+        return Some(date);
+    }
+    if let Ok(date) = chrono::DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        return Some(DateTime::from_millis(date.timestamp_millis()));
+    }
+    let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })?;
+    Some(DateTime::from_millis(
+        Utc.from_utc_datetime(&naive).timestamp_millis(),
+    ))
+}
+
+pub(crate) fn write_bson<W: Write>(out: &mut W, bson: &Bson) -> fmt::Result {
+    match bson {
+        Bson::Double(f) => write_double(out, *f),
+        Bson::String(s) => write_string(out, s),
+        Bson::Array(array) => {
+            if array.is_empty() {
+                return out.write_str("[ ]");
+            }
+            out.write_str("[ ")?;
+            for (i, value) in array.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(", ")?;
+                }
+                write_bson(out, value)?;
+            }
+            out.write_str(" ]")
+        }
+        Bson::Document(doc) => write_document(out, doc),
+        Bson::Boolean(b) => out.write_str(if *b { "true" } else { "false" }),
+        Bson::Null => out.write_str("null"),
+        Bson::Int32(i) => write!(out, "NumberInt({})", i),
+        // The shell reads numbers as doubles, so only integers that a double holds exactly are
+        // written without quotes.
+        Bson::Int64(i) if (-(1_i64 << 53)..=1 << 53).contains(i) => {
+            write!(out, "NumberLong({})", i)
+        }
+        Bson::Int64(i) => write!(out, "NumberLong(\"{}\")", i),
+        Bson::UInt32(i) => write!(out, "{{ \"$numberUInt32\" : \"{}\" }}", i),
+        Bson::UInt64(i) => write!(out, "{{ \"$numberUInt64\" : \"{}\" }}", i),
+        Bson::Timestamp(Timestamp { time, increment }) => {
+            write!(out, "Timestamp({}, {})", time, increment)
+        }
+        Bson::RegularExpression(regex) => write_regex(out, regex),
+        Bson::JavaScriptCode(code) => {
+            out.write_str("{ \"$code\" : ")?;
+            write_string(out, code)?;
+            out.write_str(" }")
+        }
+        Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope { code, scope }) => {
+            out.write_str("{ \"$code\" : ")?;
+            write_string(out, code)?;
+            out.write_str(", \"$scope\" : ")?;
+            write_document(out, scope)?;
+            out.write_str(" }")
+        }
+        Bson::Binary(binary) => write_binary(out, binary),
+        Bson::ObjectId(oid) => write!(out, "ObjectId(\"{}\")", oid.to_hex()),
+        Bson::DateTime(date) => match Utc.timestamp_millis_opt(date.timestamp_millis()) {
+            chrono::LocalResult::Single(d) if (0..=9999).contains(&d.year()) => {
+                write!(out, "ISODate(\"{}\")", d.format("%Y-%m-%dT%H:%M:%S%.3fZ"))
+            }
+            _ => write!(out, "new Date({})", date.timestamp_millis()),
+        },
+        Bson::Decimal128(d) => write!(out, "NumberDecimal(\"{}\")", d),
+        Bson::Symbol(s) => {
+            out.write_str("{ \"$symbol\" : ")?;
+            write_string(out, s)?;
+            out.write_str(" }")
+        }
+        Bson::Undefined => out.write_str("undefined"),
+        Bson::MinKey => out.write_str("MinKey"),
+        Bson::MaxKey => out.write_str("MaxKey"),
+        Bson::DbPointer(DbPointer { namespace, id }) => {
+            out.write_str("DBPointer(")?;
+            write_string(out, namespace)?;
+            write!(out, ", ObjectId(\"{}\"))", id.to_hex())
+        }
+        Bson::Unknown { tag, bytes } => write!(
+            out,
+            "{{ \"$unknown\" : {{ \"tag\" : \"{}\", \"base64\" : \"{}\" }} }}",
+            hex::encode([*tag]),
+            base64::encode(bytes)
+        ),
+    }
+}
+
+fn write_document<W: Write>(out: &mut W, doc: &Document) -> fmt::Result {
+    if doc.is_empty() {
+        return out.write_str("{ }");
+    }
+    out.write_str("{ ")?;
+    for (i, (key, value)) in doc.iter().enumerate() {
+        if i > 0 {
+            out.write_str(", ")?;
+        }
+        write_string(out, key)?;
+        out.write_str(" : ")?;
+        write_bson(out, value)?;
+    }
+    out.write_str(" }")
+}
+
+pub(crate) fn write_binary<W: Write>(out: &mut W, binary: &Binary) -> fmt::Result {
+    match binary {
+        Binary {
+            subtype: BinarySubtype::Uuid,
+            bytes,
+        } if bytes.len() == 16 => {
+            let hex = hex::encode(bytes);
+            write!(
+                out,
+                "UUID(\"{}-{}-{}-{}-{}\")",
+                &hex[..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..]
+            )
+        }
+        Binary { subtype, bytes } => write!(
+            out,
+            "BinData({}, \"{}\")",
+            u8::from(*subtype),
+            base64::encode(bytes)
+        ),
+    }
+}
+
+fn write_regex<W: Write>(out: &mut W, regex: &Regex) -> fmt::Result {
+    if is_literal_pattern(&regex.pattern) && regex.options.chars().all(is_identifier_char) {
+        write!(out, "/{}/{}", regex.pattern, regex.options)
+    } else {
+        out.write_str("{ \"$regex\" : ")?;
+        write_string(out, &regex.pattern)?;
+        out.write_str(", \"$options\" : ")?;
+        write_string(out, &regex.options)?;
+        out.write_str(" }")
+    }
+}
+
+/// Whether `pattern` can be written between slashes and read back unchanged. Patterns starting
+/// with `*` would be read as comments, and ones containing an unescaped `/`, a line break or an
+/// unclosed `[` would not end where they should.
+fn is_literal_pattern(pattern: &str) -> bool {
+    if pattern.is_empty() || pattern.starts_with('*') {
+        return false;
+    }
+    let mut in_class = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' | '\n' | '\r' => return false,
+            '\\' => {
+                if let None | Some('\n') | Some('\r') = chars.next() {
+                    return false;
+                }
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            _ => {}
+        }
+    }
+    !in_class
+}
+
+pub(crate) fn write_string<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    // serde_json's escaping is valid in the shell as well.
+    out.write_str(&serde_json::Value::from(s).to_string())
+}
+
+fn write_double<W: Write>(out: &mut W, f: f64) -> fmt::Result {
+    if f.is_nan() {
+        out.write_str("NaN")
+    } else if f.is_infinite() {
+        out.write_str(if f > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        // The Debug representation always contains a decimal point or an exponent, which keeps
+        // whole numbers from being read back as integers.
+        write!(out, "{:?}", f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{document_from_str, document_to_string, from_str, to_string};
+    use crate::{
+        oid::ObjectId,
+        spec::BinarySubtype,
+        Binary,
+        Bson,
+        DateTime,
+        DbPointer,
+        Decimal128,
+        JavaScriptCodeWithScope,
+        Regex,
+        Timestamp,
+    };
+
+    #[test]
+    fn parse_constructors() {
+        let doc = document_from_str(
+            r#"{
+                // comment
+                _id: ObjectId("5ed2a84eb8bf0a6ef1ad4c5e"),
+                'created': ISODate("2020-05-30T18:47:10.123Z"),
+                "day": ISODate("2020-05-30"),
+                "offset": ISODate("2020-05-30T18:47:10.000+0000"),
+                "epoch": new Date(0),
+                "long": NumberLong(5), "long str": NumberLong("-9223372036854775808"),
+                "int": NumberInt(1), "int str": NumberInt("2"),
+                "dec": NumberDecimal("1.50"),
+                "bin": BinData(0, "AQID"),
+                "uuid": UUID("00112233-4455-6677-8899-aabbccddeeff"),
+                "ts": Timestamp(1, 2),
+                "min": MinKey, "max": MaxKey(), "undefined": undefined,
+                "ptr": DBPointer("db.c", ObjectId("5ed2a84eb8bf0a6ef1ad4c5e")),
+                /* trailing comma */
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc,
+            doc! {
+                "_id": ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+                "created": DateTime::from_millis(1_590_864_430_123),
+                "day": DateTime::from_millis(1_590_796_800_000),
+                "offset": DateTime::from_millis(1_590_864_430_000),
+                "epoch": DateTime::from_millis(0),
+                "long": 5i64,
+                "long str": i64::MIN,
+                "int": 1,
+                "int str": 2,
+                "dec": Bson::Decimal128("1.50".parse::<Decimal128>().unwrap()),
+                "bin": Binary { subtype: BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+                "uuid": Binary {
+                    subtype: BinarySubtype::Uuid,
+                    bytes: hex::decode("00112233445566778899aabbccddeeff").unwrap(),
+                },
+                "ts": Timestamp { time: 1, increment: 2 },
+                "min": Bson::MinKey,
+                "max": Bson::MaxKey,
+                "undefined": Bson::Undefined,
+                "ptr": DbPointer {
+                    namespace: "db.c".to_string(),
+                    id: ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn parse_extended_json_v1() {
+        let doc = document_from_str(
+            r#"{
+                "oid": { "$oid": "5ed2a84eb8bf0a6ef1ad4c5e" },
+                "date": { "$date": 1590864430123 },
+                "date str": { "$date": "2020-05-30T18:47:10.123Z" },
+                "date long": { "$date": { "$numberLong": "-1" } },
+                "bin": { "$binary": "AQID", "$type": "80" },
+                "bin v2": { "$binary": { "base64": "AQID", "subType": "00" } },
+                "ts": { "$timestamp": { "t": 1, "i": 2 } },
+                "u64": { "$numberUInt64": "18446744073709551615" },
+                "code": { "$code": "f()" },
+                "code scope": { "$scope": { "x": NumberLong(1) }, "$code": "x" },
+                "symbol": { "$symbol": "s" },
+                "min": { "$minKey": 1 },
+                "max": { "$maxKey": 1 },
+                "undefined": { "$undefined": true },
+                "ptr": {
+                    "$dbPointer": { "$ref": "db.c", "$id": { "$oid": "5ed2a84eb8bf0a6ef1ad4c5e" } }
+                },
+                "query": { "$gt": 5, "$type": "string" },
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc,
+            doc! {
+                "oid": ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+                "date": DateTime::from_millis(1_590_864_430_123),
+                "date str": DateTime::from_millis(1_590_864_430_123),
+                "date long": DateTime::from_millis(-1),
+                "bin": Binary { subtype: BinarySubtype::UserDefined(0x80), bytes: vec![1, 2, 3] },
+                "bin v2": Binary { subtype: BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+                "ts": Timestamp { time: 1, increment: 2 },
+                "u64": u64::MAX,
+                "code": Bson::JavaScriptCode("f()".to_string()),
+                "code scope": JavaScriptCodeWithScope { code: "x".to_string(), scope: doc! { "x": 1i64 } },
+                "symbol": Bson::Symbol("s".to_string()),
+                "min": Bson::MinKey,
+                "max": Bson::MaxKey,
+                "undefined": Bson::Undefined,
+                "ptr": DbPointer {
+                    namespace: "db.c".to_string(),
+                    id: ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+                },
+                "query": { "$gt": 5, "$type": "string" },
+            }
+        );
+    }
+
+    #[test]
+    fn parse_scalars() {
+        assert_eq!(from_str("5").unwrap(), Bson::Int32(5));
+        assert_eq!(
+            from_str("-2147483649").unwrap(),
+            Bson::Int64(-2_147_483_649)
+        );
+        assert_eq!(from_str("1.5e3").unwrap(), Bson::Double(1500.0));
+        assert_eq!(from_str("3.0").unwrap(), Bson::Double(3.0));
+        assert_eq!(
+            from_str("-Infinity").unwrap(),
+            Bson::Double(f64::NEG_INFINITY)
+        );
+        assert!(matches!(from_str("NaN").unwrap(), Bson::Double(f) if f.is_nan()));
+        assert_eq!(
+            from_str(r#"'it\'s "quoted" é😀'"#).unwrap(),
+            Bson::String("it's \"quoted\" é😀".to_string())
+        );
+        assert_eq!(
+            from_str("[ true, false, null, [ ] ]").unwrap(),
+            bson!([true, false, Bson::Null, []])
+        );
+    }
+
+    #[test]
+    fn int64_extremes() {
+        for i in vec![i64::MIN, i64::MAX, -(1 << 53), 1 << 53] {
+            let printed = to_string(&Bson::Int64(i));
+            assert_eq!(from_str(&printed).unwrap(), Bson::Int64(i), "{}", printed);
+        }
+        assert_eq!(
+            to_string(&Bson::Int64(i64::MIN)),
+            "NumberLong(\"-9223372036854775808\")"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = from_str("{\n  a: ObjectId(\"xyz\")\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 6));
+        assert_eq!(err.message, "invalid arguments to ObjectId");
+
+        let err = from_str("{ a: Foo(1) }").unwrap_err();
+        assert_eq!(err.message, "unknown constructor Foo");
+
+        assert!(from_str("{ a: 1 ").is_err());
+        assert!(from_str("{ a: 1 } x").is_err());
+        assert!(from_str("NumberInt(2147483648)").is_err());
+        assert!(from_str(r#"{ "$date": "yesterday" }"#).is_err());
+        assert_eq!(
+            document_from_str("[1]").unwrap_err().message,
+            "expected a document, found Array"
+        );
+        let err =
+            document_from_str(r#"{ "$unknown": { "tag": "80", "base64": "AQ==" } }"#).unwrap_err();
+        assert_eq!(err.message, "expected a document, found 0x80");
+        assert!(from_str("/abc").is_err());
+        assert!(from_str("/a[/]").is_err());
+        assert!(from_str(r#"{ "$code": "f()", "$scope": 1 }"#).is_err());
+        assert!(from_str(r#"{ "$minKey": 0 }"#).is_err());
+        assert!(from_str(r#"{ "$undefined": false }"#).is_err());
+        assert!(from_str(r#"DBPointer("db.c")"#).is_err());
+        assert!(from_str("MinKey(1)").is_err());
+    }
+
+    #[test]
+    fn regular_expressions() {
+        let doc = document_from_str(
+            r#"{
+                // comment
+                "literal": /^a\/b[/]/mi, /* comment */
+                "v1": { "$regex": "^a", "$options": "xi" },
+                "v2": { "$regularExpression": { "pattern": "^a", "options": "" } },
+                "query": { "$regex": /^a/, "$options": "i" },
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc,
+            doc! {
+                "literal": Regex::new(r"^a\/b[/]", "im"),
+                "v1": Regex::new("^a", "ix"),
+                "v2": Regex::new("^a", ""),
+                "query": { "$regex": Regex::new("^a", ""), "$options": "i" },
+            }
+        );
+
+        let doc = doc! {
+            "literal": Regex::new(r"^a\/b", "mi"),
+            "slash": Regex::new("a/b", "i"),
+            "empty": Regex::new("", ""),
+            "star": Regex::new("*", ""),
+            "class": Regex::new("[a", ""),
+        };
+        let printed = document_to_string(&doc);
+        assert_eq!(
+            printed,
+            concat!(
+                r#"{ "literal" : /^a\/b/im, "slash" : { "$regex" : "a/b", "$options" : "i" }, "#,
+                r#""empty" : { "$regex" : "", "$options" : "" }, "#,
+                r#""star" : { "$regex" : "*", "$options" : "" }, "#,
+                r#""class" : { "$regex" : "[a", "$options" : "" } }"#,
+            )
+        );
+        assert_eq!(document_from_str(&printed).unwrap(), doc);
+    }
+
+    #[test]
+    fn print_round_trip() {
+        let doc = doc! {
+            "_id": ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+            "double": 3.0,
+            "small": 1e-7,
+            "nan": f64::NAN,
+            "inf": f64::NEG_INFINITY,
+            "string": "line\nbreak \"quoted\"",
+            "array": [1, [], {}],
+            "int": 1,
+            "long": 5i64,
+            "big long": i64::MAX,
+            "u32": 7u32,
+            "u64": u64::MAX,
+            "ts": Timestamp { time: 1, increment: 2 },
+            "bin": Binary { subtype: BinarySubtype::Md5, bytes: vec![1, 2, 3] },
+            "uuid": Binary { subtype: BinarySubtype::Uuid, bytes: vec![0xab; 16] },
+            "date": DateTime::from_millis(1_590_864_430_120),
+            "far date": DateTime::from_millis(i64::MAX),
+            "dec": Bson::Decimal128("-1.00E-8".parse::<Decimal128>().unwrap()),
+            "code": Bson::JavaScriptCode("f()".to_string()),
+            "code scope": JavaScriptCodeWithScope { code: "x".to_string(), scope: doc! { "x": 1 } },
+            "null": Bson::Null,
+            "bool": true,
+            "symbol": Bson::Symbol("s".to_string()),
+            "min": Bson::MinKey,
+            "max": Bson::MaxKey,
+            "undefined": Bson::Undefined,
+            "ptr": DbPointer {
+                namespace: "db.c".to_string(),
+                id: ObjectId::parse_str("5ed2a84eb8bf0a6ef1ad4c5e").unwrap(),
+            },
+        };
+
+        let printed = document_to_string(&doc);
+        assert_eq!(
+            printed,
+            concat!(
+                r#"{ "_id" : ObjectId("5ed2a84eb8bf0a6ef1ad4c5e"), "double" : 3.0, "small" : 1e-7, "#,
+                r#""nan" : NaN, "inf" : -Infinity, "string" : "line\nbreak \"quoted\"", "#,
+                r#""array" : [ NumberInt(1), [ ], { } ], "int" : NumberInt(1), "#,
+                r#""long" : NumberLong(5), "big long" : NumberLong("9223372036854775807"), "#,
+                r#""u32" : { "$numberUInt32" : "7" }, "#,
+                r#""u64" : { "$numberUInt64" : "18446744073709551615" }, "#,
+                r#""ts" : Timestamp(1, 2), "bin" : BinData(5, "AQID"), "#,
+                r#""uuid" : UUID("abababab-abab-abab-abab-abababababab"), "#,
+                r#""date" : ISODate("2020-05-30T18:47:10.120Z"), "#,
+                r#""far date" : new Date(9223372036854775807), "#,
+                r#""dec" : NumberDecimal("-1.00E-8"), "code" : { "$code" : "f()" }, "#,
+                r#""code scope" : { "$code" : "x", "$scope" : { "x" : NumberInt(1) } }, "#,
+                r#""null" : null, "bool" : true, "symbol" : { "$symbol" : "s" }, "#,
+                r#""min" : MinKey, "max" : MaxKey, "undefined" : undefined, "#,
+                r#""ptr" : DBPointer("db.c", ObjectId("5ed2a84eb8bf0a6ef1ad4c5e")) }"#,
+            )
+        );
+
+        let parsed = document_from_str(&printed).unwrap();
+        assert_eq!(document_to_string(&parsed), printed);
+        assert_eq!(to_string(&Bson::Document(parsed)), printed);
+    }
+}
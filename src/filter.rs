@@ -0,0 +1,1083 @@
+//! MongoDB query filters evaluated against documents in memory.
+//!
+//! A [`Filter`] is compiled once from a filter document such as `{ "a.b": { "$gt": 1 } }` and can
+//! then be matched against any number of [`Document`]s or [`RawDocument`]s. The following
+//! operators are supported:
+//!
+//! | Operator                     | Matches values that                        |
+//! |------------------------------|--------------------------------------------|
+//! | `$eq`, `$ne`                 | equal or do not equal a value              |
+//! | `$gt`, `$gte`, `$lt`, `$lte` | compare to a value of the same type        |
+//! | `$in`, `$nin`                | equal one or none of a list of values      |
+//! | `$exists`                    | are present or missing                     |
+//! | `$type`                      | have one of the given types                |
+//! | `$all`                       | are arrays containing all the given values |
+//! | `$size`                      | are arrays of the given length             |
+//! | `$elemMatch`                 | are arrays with an element that matches    |
+//! | `$mod`                       | leave the given remainder when divided     |
+//! | `$not`                       | do not match the given operators           |
+//!
+//! Pattern matching with regular expressions, including `$regex`, is not supported. A regular
+//! expression operand of `$eq` or `$ne` only equals an identical stored regular expression, as on
+//! the server. The server matches a regular expression given as the value of a field or in `$in`,
+//! `$nin` or `$all` against strings instead, so [`Filter::compile`] rejects those.
+//!
+//! The top-level operators `$and`, `$or` and `$nor` combine a list of filters, and a field given
+//! a value that is not a document of operators must equal that value.
+//!
+//! The semantics follow those of the server:
+//!
+//! * Paths are dotted, with numeric segments naming the elements of arrays. A path that reaches
+//!   an array also continues into each embedded document stored in that array.
+//! * A value that is an array matches if the array itself or any of its elements does, except
+//!   for `$size` and `$elemMatch`, which only look at the array itself.
+//! * Comparisons use the server's cross-type order, but only match values in the same bracket of
+//!   types, so `{ "$gt": 1 }` matches any number greater than one but never a string. Numbers of
+//!   all types compare by value.
+//! * A missing field matches `null` in `$eq`, `$in`, `$gte` and `$lte`.
+//! * `$type` accepts the server's type aliases, including `"number"`, or the numeric type codes.
+//!
+//! Matching a [`RawDocument`] only decodes the values that are looked up by the filter.
+//!
+//! ```rust
+//! use bson::{doc, filter::Filter, RawDocumentBuf};
+//!
+//! let filter = Filter::compile(&doc! {
+//!     "status": { "$in": ["A", "D"] },
+//!     "qty": { "$gte": 10, "$lt": 50.5 },
+//!     "tags": "red",
+//!     "$or": [{ "size.h": { "$exists": false } }, { "size.uom": "cm" }],
+//! })?;
+//!
+//! let doc = doc! {
+//!     "status": "A",
+//!     "qty": 25_i64,
+//!     "tags": ["blue", "red"],
+//!     "size": { "h": 14, "uom": "cm" },
+//! };
+//! assert!(filter.matches(&doc));
+//! assert!(filter.matches_raw(&RawDocumentBuf::from_document(&doc)?)?);
+//!
+//! assert!(!filter.matches(&doc! { "status": "A", "qty": "25", "tags": "red" }));
+//!
+//! let err = Filter::compile(&doc! { "qty": { "$size": -1 } }).unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "invalid filter in $size at \"qty\": expected a non-negative integer"
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{borrow::Cow, cmp::Ordering, convert::TryFrom, fmt};
+
+use crate::{
+    decimal128::RoundingMode,
+    ordering::{self, Number},
+    path::parse_index,
+    raw::{self, RawBson, RawDocument, RawElements},
+    spec::ElementType,
+    Bson,
+    Decimal128,
+    Document,
+};
+
+/// An error that occurs when compiling a [`Filter`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// The type of error that was encountered.
+    pub kind: ErrorKind,
+
+    /// The operator in which the error was encountered, if any.
+    operator: Option<String>,
+
+    /// The path of the field whose condition was invalid, if any.
+    path: Option<String>,
+}
+
+impl Error {
+    fn new(kind: ErrorKind, operator: Option<&str>, path: Option<&str>) -> Self {
+        Self {
+            kind,
+            operator: operator.map(String::from),
+            path: path.map(String::from),
+        }
+    }
+
+    /// The operator in which the error was encountered, if any.
+    pub fn operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+
+    /// The path of the field whose condition was invalid, if any. Paths inside `$elemMatch` are
+    /// relative to the elements of the array.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+}
+
+/// The different categories of errors that can occur when compiling a [`Filter`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The filter uses an operator that is not supported, or one that is not valid where it
+    /// appears.
+    UnknownOperator,
+
+    /// An operator was given an argument it cannot use.
+    #[non_exhaustive]
+    InvalidArgument { message: String },
+
+    /// A field path is empty or contains an empty segment.
+    InvalidPath,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid filter")?;
+        if let Some(operator) = &self.operator {
+            write!(f, " in {}", operator)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, " at {:?}", path)?;
+        }
+        f.write_str(": ")?;
+
+        match &self.kind {
+            ErrorKind::UnknownOperator => f.write_str("unknown operator"),
+            ErrorKind::InvalidArgument { message } => f.write_str(message),
+            ErrorKind::InvalidPath => f.write_str("paths must not contain empty segments"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Alias for `Result<T, filter::Error>`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A compiled filter document, which can be matched against documents with [`Filter::matches`]
+/// and [`Filter::matches_raw`]. See the [`filter`](crate::filter) module for the supported
+/// operators.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    expression: Expression,
+}
+
+impl Filter {
+    /// Compile a filter document.
+    pub fn compile(filter: &Document) -> Result<Filter> {
+        Ok(Filter {
+            expression: compile_document(filter)?,
+        })
+    }
+
+    /// Whether `doc` matches this filter.
+    pub fn matches(&self, doc: &Document) -> bool {
+        self.expression
+            .matches(Container::Document(doc))
+            .expect("decoded documents cannot be malformed")
+    }
+
+    /// Whether `doc` matches this filter. Only the values looked up by the filter are decoded,
+    /// and an error is returned if one of them, or the structure of the document leading to it,
+    /// is malformed.
+    pub fn matches_raw(&self, doc: &RawDocument) -> raw::Result<bool> {
+        self.expression.matches(Container::Raw(doc))
+    }
+
+    /// The index of the first element of the array at `path` that satisfies the conditions this
+    /// filter places on that array, as used by the positional `$` projection. Returns `None` if
+    /// the path does not lead to an array through embedded documents, the filter has no
+    /// conditions on it, or no element satisfies them.
+    pub(crate) fn matching_element(&self, doc: &Document, path: &[String]) -> Option<usize> {
+        self.matching_element_in(Container::Document(doc), path)
+            .expect("decoded documents cannot be malformed")
+    }
+
+    /// Like [`Filter::matching_element`], for a raw document.
+    pub(crate) fn matching_element_raw(
+        &self,
+        doc: &RawDocument,
+        path: &[String],
+    ) -> raw::Result<Option<usize>> {
+        self.matching_element_in(Container::Raw(doc), path)
+    }
+
+    fn matching_element_in(&self, doc: Container, path: &[String]) -> raw::Result<Option<usize>> {
+        let mut clauses = Vec::new();
+        self.expression.conditions_on(path, &mut clauses);
+        if clauses.is_empty() {
+            return Ok(None);
+        }
+
+        let mut container = doc;
+        let mut value = None;
+        for (i, segment) in path.iter().enumerate() {
+            value = container.get(segment)?;
+            match value.and_then(|value| value.as_document()) {
+                Some(doc) if i + 1 < path.len() => container = doc,
+                _ if i + 1 < path.len() => return Ok(None),
+                _ => {}
+            }
+        }
+        let elements = match value {
+            Some(value) => match value.elements()? {
+                Some(elements) => elements,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+
+        'elements: for (index, element) in elements.into_iter().enumerate() {
+            for (rest, predicate) in &clauses {
+                let mut candidates = Vec::new();
+                resolve_value(element, rest, &mut candidates)?;
+                if !predicate.matches_candidates(&candidates)? {
+                    continue 'elements;
+                }
+            }
+            return Ok(Some(index));
+        }
+        Ok(None)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expression {
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+    Nor(Vec<Expression>),
+    Field(Vec<String>, Predicate),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Predicate {
+    Eq(Bson),
+    Compare(Comparison, Bson),
+    In(Vec<Bson>),
+    Exists(bool),
+    Type(Vec<u8>),
+    All(Vec<Predicate>),
+    Size(usize),
+    ElemMatch(Box<ElementCondition>),
+    Mod(i64, i64),
+    Not(Box<Predicate>),
+    And(Vec<Predicate>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+/// A condition on the elements of an array, as given to `$elemMatch` or `$pull`.
+#[derive(Clone, Debug, PartialEq)]
+enum ElementCondition {
+    /// The element must be a document matching the filter.
+    Document(Expression),
+
+    /// The element itself must match the operators, without looking into it if it is an array.
+    Value(Predicate),
+}
+
+impl ElementCondition {
+    fn compile(path: Option<&str>, condition: &Document) -> Result<Self> {
+        match condition.keys().next() {
+            Some(key) if key.starts_with('$') && !is_logical(key) => {
+                Ok(ElementCondition::Value(compile_operators(path, condition)?))
+            }
+            _ => Ok(ElementCondition::Document(compile_document(condition)?)),
+        }
+    }
+
+    fn matches(&self, element: Value) -> raw::Result<bool> {
+        match self {
+            ElementCondition::Document(expression) => match element.as_document() {
+                Some(doc) => expression.matches(doc),
+                None => Ok(false),
+            },
+            ElementCondition::Value(predicate) => predicate.matches_single(element),
+        }
+    }
+}
+
+/// A condition on the elements of an array, for the operators of other modules that select
+/// elements.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ElementMatcher(ElementCondition);
+
+impl ElementMatcher {
+    /// Compile the condition of a `$pull`, which is a value that elements must equal, a document
+    /// of operators or a filter on elements that are documents.
+    pub(crate) fn for_pull(condition: &Bson) -> Result<Self> {
+        match condition {
+            Bson::Document(condition) => ElementCondition::compile(None, condition).map(Self),
+            value => {
+                reject_regex(value, None, None)?;
+                Ok(Self(ElementCondition::Value(Predicate::Eq(value.clone()))))
+            }
+        }
+    }
+
+    /// Compile the condition of an `$elemMatch` projection of the field at `path`.
+    pub(crate) fn for_elem_match(path: &str, condition: &Document) -> Result<Self> {
+        ElementCondition::compile(Some(path), condition).map(Self)
+    }
+
+    /// Whether the array element `element` matches.
+    pub(crate) fn matches(&self, element: &Bson) -> bool {
+        self.0
+            .matches(Value::Bson(element))
+            .expect("decoded documents cannot be malformed")
+    }
+
+    /// Whether the raw array element `element` matches.
+    pub(crate) fn matches_raw(&self, element: RawBson) -> raw::Result<bool> {
+        self.0.matches(Value::Raw(element))
+    }
+}
+
+fn is_logical(key: &str) -> bool {
+    matches!(key, "$and" | "$or" | "$nor")
+}
+
+fn is_operator_document(doc: &Document) -> bool {
+    matches!(doc.keys().next(), Some(key) if key.starts_with('$'))
+}
+
+fn compile_document(filter: &Document) -> Result<Expression> {
+    let mut clauses = Vec::new();
+    for (key, value) in filter {
+        let clause = match key.as_str() {
+            "$and" | "$or" | "$nor" => {
+                let list = compile_list(key, value)?;
+                match key.as_str() {
+                    "$and" => Expression::And(list),
+                    "$or" => Expression::Or(list),
+                    _ => Expression::Nor(list),
+                }
+            }
+            "$comment" => continue,
+            operator if operator.starts_with('$') => {
+                return Err(Error::new(ErrorKind::UnknownOperator, Some(operator), None))
+            }
+            path => {
+                let segments: Vec<String> = path.split('.').map(String::from).collect();
+                if segments.iter().any(String::is_empty) {
+                    return Err(Error::new(ErrorKind::InvalidPath, None, Some(path)));
+                }
+                let predicate = match value {
+                    Bson::Document(doc) if is_operator_document(doc) => {
+                        compile_operators(Some(path), doc)?
+                    }
+                    value => {
+                        reject_regex(value, None, Some(path))?;
+                        Predicate::Eq(value.clone())
+                    }
+                };
+                Expression::Field(segments, predicate)
+            }
+        };
+        clauses.push(clause);
+    }
+    Ok(Expression::And(clauses))
+}
+
+fn compile_list(operator: &str, value: &Bson) -> Result<Vec<Expression>> {
+    let invalid = || {
+        Error::new(
+            invalid_argument("expected a non-empty array of documents"),
+            Some(operator),
+            None,
+        )
+    };
+    match value {
+        Bson::Array(filters) if !filters.is_empty() => filters
+            .iter()
+            .map(|filter| match filter {
+                Bson::Document(filter) => compile_document(filter),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
+
+/// Fail if `value` is a regular expression used where the server would match strings against it,
+/// which is not supported.
+fn reject_regex(value: &Bson, operator: Option<&str>, path: Option<&str>) -> Result<()> {
+    match value {
+        Bson::RegularExpression(_) => Err(Error::new(
+            invalid_argument("regular expression matching is not supported"),
+            operator,
+            path,
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn invalid_argument(message: impl Into<String>) -> ErrorKind {
+    ErrorKind::InvalidArgument {
+        message: message.into(),
+    }
+}
+
+fn compile_operators(path: Option<&str>, operators: &Document) -> Result<Predicate> {
+    operators
+        .iter()
+        .map(|(operator, argument)| compile_operator(path, operator, argument))
+        .collect::<Result<_>>()
+        .map(Predicate::And)
+}
+
+fn compile_operator(path: Option<&str>, operator: &str, argument: &Bson) -> Result<Predicate> {
+    let invalid = |message: &str| Error::new(invalid_argument(message), Some(operator), path);
+
+    Ok(match operator {
+        "$eq" => Predicate::Eq(argument.clone()),
+        "$ne" => Predicate::Not(Box::new(Predicate::Eq(argument.clone()))),
+        "$gt" => Predicate::Compare(Comparison::Gt, argument.clone()),
+        "$gte" => Predicate::Compare(Comparison::Gte, argument.clone()),
+        "$lt" => Predicate::Compare(Comparison::Lt, argument.clone()),
+        "$lte" => Predicate::Compare(Comparison::Lte, argument.clone()),
+        "$in" | "$nin" => {
+            let values = match argument {
+                Bson::Array(values) => values,
+                _ => return Err(invalid("expected an array")),
+            };
+            if values
+                .iter()
+                .any(|value| matches!(value, Bson::Document(doc) if is_operator_document(doc)))
+            {
+                return Err(invalid("the values must not contain operators"));
+            }
+            for value in values {
+                reject_regex(value, Some(operator), path)?;
+            }
+            let predicate = Predicate::In(values.clone());
+            if operator == "$in" {
+                predicate
+            } else {
+                Predicate::Not(Box::new(predicate))
+            }
+        }
+        "$exists" => Predicate::Exists(is_truthy(argument)),
+        "$type" => {
+            let aliases = match argument {
+                Bson::Array(aliases) => aliases.iter().collect(),
+                alias => vec![alias],
+            };
+            let mut tags = Vec::new();
+            for alias in aliases {
+                tags.extend(type_tags(alias).ok_or_else(|| invalid("unknown type"))?);
+            }
+            Predicate::Type(tags)
+        }
+        "$all" => {
+            let values = match argument {
+                Bson::Array(values) => values,
+                _ => return Err(invalid("expected an array")),
+            };
+            let predicates = values
+                .iter()
+                .map(|value| match value {
+                    Bson::Document(doc) if is_operator_document(doc) => {
+                        if doc.keys().any(|key| key != "$elemMatch") {
+                            return Err(invalid("only $elemMatch may be used in the values"));
+                        }
+                        compile_operators(path, doc)
+                    }
+                    value => {
+                        reject_regex(value, Some(operator), path)?;
+                        Ok(Predicate::Eq(value.clone()))
+                    }
+                })
+                .collect::<Result<_>>()?;
+            Predicate::All(predicates)
+        }
+        "$size" => match exact_integer(argument) {
+            Some(size) if size >= 0 => Predicate::Size(size as usize),
+            _ => return Err(invalid("expected a non-negative integer")),
+        },
+        "$elemMatch" => match argument {
+            Bson::Document(condition) => {
+                Predicate::ElemMatch(Box::new(ElementCondition::compile(path, condition)?))
+            }
+            _ => return Err(invalid("expected a document")),
+        },
+        "$mod" => {
+            let operands = match argument {
+                Bson::Array(operands) if operands.len() == 2 => operands
+                    .iter()
+                    .map(truncated_integer)
+                    .collect::<Option<Vec<_>>>(),
+                _ => None,
+            };
+            match operands.as_deref() {
+                Some([0, _]) => return Err(invalid("the divisor must not be zero")),
+                Some(&[divisor, remainder]) => Predicate::Mod(divisor, remainder),
+                _ => return Err(invalid("expected an array of a divisor and a remainder")),
+            }
+        }
+        "$not" => match argument {
+            Bson::Document(operators) if is_operator_document(operators) => {
+                Predicate::Not(Box::new(compile_operators(path, operators)?))
+            }
+            _ => return Err(invalid("expected a document of operators")),
+        },
+        _ => return Err(Error::new(ErrorKind::UnknownOperator, Some(operator), path)),
+    })
+}
+
+/// Whether `value` counts as true where the server expects a boolean.
+fn is_truthy(value: &Bson) -> bool {
+    match ordering::number(value) {
+        Some(Number::Integer(i)) => i != 0,
+        Some(Number::Double(d)) => d != 0.0,
+        Some(Number::Decimal(d)) => !d.is_zero(),
+        None => !matches!(value, Bson::Boolean(false) | Bson::Null | Bson::Undefined),
+    }
+}
+
+/// The number `value` holds, if it is an integer of any numeric type that fits in an `i64`.
+fn exact_integer(value: &Bson) -> Option<i64> {
+    match ordering::number(value)? {
+        Number::Integer(i) => i64::try_from(i).ok(),
+        Number::Double(d) if d.fract() == 0.0 => truncated_integer(value),
+        Number::Decimal(d) => {
+            let i = d.to_i64_rounded(RoundingMode::Down).ok()?;
+            let exact = Decimal128::from_i128_rounded(i.into(), Default::default());
+            if d.value_cmp(&exact) == Some(Ordering::Equal) {
+                Some(i)
+            } else {
+                None
+            }
+        }
+        Number::Double(_) => None,
+    }
+}
+
+/// The number `value` holds, truncated towards zero, if it is a finite number that fits in an
+/// `i64`.
+fn truncated_integer(value: &Bson) -> Option<i64> {
+    match ordering::number(value)? {
+        Number::Integer(i) => i64::try_from(i).ok(),
+        Number::Double(d) if d >= i64::MIN as f64 && d < i64::MAX as f64 => Some(d as i64),
+        Number::Double(_) => None,
+        Number::Decimal(d) => d.to_i64_rounded(RoundingMode::Down).ok(),
+    }
+}
+
+const NUMBER_TAGS: [ElementType; 6] = [
+    ElementType::Double,
+    ElementType::Int32,
+    ElementType::Int64,
+    ElementType::Decimal128,
+    ElementType::UInt32,
+    ElementType::UInt64,
+];
+
+/// The type tags selected by a `$type` alias or type code.
+fn type_tags(alias: &Bson) -> Option<Vec<u8>> {
+    let element_type = match alias {
+        Bson::String(alias) => match alias.as_str() {
+            "double" => ElementType::Double,
+            "string" => ElementType::String,
+            "object" => ElementType::EmbeddedDocument,
+            "array" => ElementType::Array,
+            "binData" => ElementType::Binary,
+            "undefined" => ElementType::Undefined,
+            "objectId" => ElementType::ObjectId,
+            "bool" => ElementType::Boolean,
+            "date" => ElementType::DateTime,
+            "null" => ElementType::Null,
+            "regex" => ElementType::RegularExpression,
+            "dbPointer" => ElementType::DbPointer,
+            "javascript" => ElementType::JavaScriptCode,
+            "symbol" => ElementType::Symbol,
+            "javascriptWithScope" => ElementType::JavaScriptCodeWithScope,
+            "int" => ElementType::Int32,
+            "timestamp" => ElementType::Timestamp,
+            "long" => ElementType::Int64,
+            "decimal" => ElementType::Decimal128,
+            "minKey" => ElementType::MinKey,
+            "maxKey" => ElementType::MaxKey,
+            "number" => return Some(NUMBER_TAGS.iter().map(|t| *t as u8).collect()),
+            _ => return None,
+        },
+        code => match exact_integer(code)? {
+            -1 => ElementType::MinKey,
+            code => ElementType::from(u8::try_from(code).ok()?)?,
+        },
+    };
+    Some(vec![element_type as u8])
+}
+
+/// A document being matched.
+#[derive(Clone, Copy)]
+enum Container<'a> {
+    Document(&'a Document),
+    Raw(&'a RawDocument),
+}
+
+impl<'a> Container<'a> {
+    fn get(self, key: &str) -> raw::Result<Option<Value<'a>>> {
+        match self {
+            Container::Document(doc) => Ok(doc.get(key).map(Value::Bson)),
+            Container::Raw(doc) => {
+                for element in RawElements::new(doc) {
+                    let element = element?;
+                    if element.key_bytes() == key.as_bytes() {
+                        return element.value().map(|value| Some(Value::Raw(value)));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// A value being matched.
+#[derive(Clone, Copy)]
+enum Value<'a> {
+    Bson(&'a Bson),
+    Raw(RawBson<'a>),
+}
+
+impl<'a> Value<'a> {
+    fn tag(&self) -> u8 {
+        match self {
+            Value::Bson(value) => value.tag(),
+            Value::Raw(value) => value.tag(),
+        }
+    }
+
+    fn as_document(&self) -> Option<Container<'a>> {
+        match *self {
+            Value::Bson(Bson::Document(doc)) => Some(Container::Document(doc)),
+            Value::Raw(RawBson::Document(doc)) => Some(Container::Raw(doc)),
+            _ => None,
+        }
+    }
+
+    /// The elements of this value, if it is an array.
+    fn elements(&self) -> raw::Result<Option<Vec<Value<'a>>>> {
+        match *self {
+            Value::Bson(Bson::Array(array)) => Ok(Some(array.iter().map(Value::Bson).collect())),
+            Value::Raw(RawBson::Array(array)) => RawElements::new(&array.doc)
+                .map(|element| element?.value().map(Value::Raw))
+                .collect::<raw::Result<_>>()
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn to_bson(self) -> raw::Result<Cow<'a, Bson>> {
+        match self {
+            Value::Bson(value) => Ok(Cow::Borrowed(value)),
+            Value::Raw(value) => Bson::try_from(value).map(Cow::Owned),
+        }
+    }
+
+    /// Compare this value to `other` if they are in the same bracket of types.
+    fn compare(self, other: &Bson) -> raw::Result<Option<Ordering>> {
+        if ordering::canonical_type(self.tag()) != ordering::canonical_type(other.tag()) {
+            return Ok(None);
+        }
+        Ok(Some(ordering::compare(&*self.to_bson()?, other)))
+    }
+}
+
+impl Expression {
+    /// Collect the conditions that all documents matching this expression satisfy on the values
+    /// at or below `path`, with the remainder of their paths.
+    fn conditions_on<'a>(
+        &'a self,
+        path: &[String],
+        clauses: &mut Vec<(&'a [String], &'a Predicate)>,
+    ) {
+        match self {
+            Expression::And(expressions) => {
+                for expression in expressions {
+                    expression.conditions_on(path, clauses);
+                }
+            }
+            Expression::Field(field, predicate) if field.starts_with(path) => {
+                clauses.push((&field[path.len()..], predicate))
+            }
+            _ => {}
+        }
+    }
+
+    fn matches(&self, doc: Container) -> raw::Result<bool> {
+        match self {
+            Expression::And(clauses) => {
+                for clause in clauses {
+                    if !clause.matches(doc)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Expression::Or(clauses) => any(clauses, |clause| clause.matches(doc)),
+            Expression::Nor(clauses) => any(clauses, |clause| clause.matches(doc)).map(|b| !b),
+            Expression::Field(path, predicate) => {
+                let mut candidates = Vec::new();
+                resolve(doc, path, &mut candidates)?;
+                predicate.matches_candidates(&candidates)
+            }
+        }
+    }
+}
+
+fn any<T>(items: &[T], mut f: impl FnMut(&T) -> raw::Result<bool>) -> raw::Result<bool> {
+    for item in items {
+        if f(item)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Collect the values found at `path` in `doc`, with `None` for each place the path ends at a
+/// missing field.
+fn resolve<'a>(
+    doc: Container<'a>,
+    path: &[String],
+    candidates: &mut Vec<Option<Value<'a>>>,
+) -> raw::Result<()> {
+    match doc.get(&path[0])? {
+        Some(value) => resolve_value(value, &path[1..], candidates),
+        None => {
+            candidates.push(None);
+            Ok(())
+        }
+    }
+}
+
+fn resolve_value<'a>(
+    value: Value<'a>,
+    rest: &[String],
+    candidates: &mut Vec<Option<Value<'a>>>,
+) -> raw::Result<()> {
+    if rest.is_empty() {
+        candidates.push(Some(value));
+        return Ok(());
+    }
+
+    if let Some(doc) = value.as_document() {
+        return resolve(doc, rest, candidates);
+    }
+
+    let elements = match value.elements()? {
+        Some(elements) => elements,
+        None => {
+            candidates.push(None);
+            return Ok(());
+        }
+    };
+    let found = candidates.len();
+    if let Some(element) = parse_index(&rest[0]).and_then(|i| elements.get(i)) {
+        resolve_value(*element, &rest[1..], candidates)?;
+    }
+    for element in elements {
+        if let Some(doc) = element.as_document() {
+            resolve(doc, rest, candidates)?;
+        }
+    }
+    if candidates.len() == found {
+        candidates.push(None);
+    }
+    Ok(())
+}
+
+impl Predicate {
+    /// Whether the values found at a path match, looking into those that are arrays.
+    fn matches_candidates(&self, candidates: &[Option<Value>]) -> raw::Result<bool> {
+        match self {
+            Predicate::Not(predicate) => predicate.matches_candidates(candidates).map(|b| !b),
+            Predicate::All(predicates) if predicates.is_empty() => Ok(false),
+            Predicate::All(predicates) | Predicate::And(predicates) => {
+                for predicate in predicates {
+                    if !predicate.matches_candidates(candidates)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Predicate::Exists(exists) => Ok(candidates.iter().any(Option::is_some) == *exists),
+            _ => any(candidates, |candidate| match candidate {
+                None => Ok(self.matches_missing()),
+                Some(value) => {
+                    if self.matches_single(*value)? {
+                        return Ok(true);
+                    }
+                    match value.elements()? {
+                        Some(elements) if self.looks_into_arrays() => {
+                            any(&elements, |element| self.matches_single(*element))
+                        }
+                        _ => Ok(false),
+                    }
+                }
+            }),
+        }
+    }
+
+    fn looks_into_arrays(&self) -> bool {
+        !matches!(self, Predicate::Size(_) | Predicate::ElemMatch(_))
+    }
+
+    fn matches_missing(&self) -> bool {
+        match self {
+            Predicate::Eq(Bson::Null)
+            | Predicate::Compare(Comparison::Gte, Bson::Null)
+            | Predicate::Compare(Comparison::Lte, Bson::Null) => true,
+            Predicate::In(values) => values.contains(&Bson::Null),
+            _ => false,
+        }
+    }
+
+    /// Whether a single value matches, without looking into it if it is an array.
+    fn matches_single(&self, value: Value) -> raw::Result<bool> {
+        match self {
+            Predicate::Eq(expected) => equals(value, expected),
+            Predicate::Compare(comparison, operand) => {
+                let ordering = match value.compare(operand)? {
+                    Some(ordering) => ordering,
+                    None => return Ok(false),
+                };
+                // NaN only compares equal to itself, rather than less than every number
+                if is_nan(operand) || is_nan(&*value.to_bson()?) {
+                    return Ok(ordering == Ordering::Equal
+                        && matches!(comparison, Comparison::Gte | Comparison::Lte));
+                }
+                Ok(match comparison {
+                    Comparison::Gt => ordering == Ordering::Greater,
+                    Comparison::Gte => ordering != Ordering::Less,
+                    Comparison::Lt => ordering == Ordering::Less,
+                    Comparison::Lte => ordering != Ordering::Greater,
+                })
+            }
+            Predicate::In(values) => any(values, |expected| equals(value, expected)),
+            Predicate::Exists(exists) => Ok(*exists),
+            Predicate::Type(tags) => Ok(tags.contains(&value.tag())),
+            Predicate::All(predicates) if predicates.is_empty() => Ok(false),
+            Predicate::All(predicates) | Predicate::And(predicates) => {
+                for predicate in predicates {
+                    if !predicate.matches_single(value)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Predicate::Size(size) => Ok(matches!(value.elements()?, Some(e) if e.len() == *size)),
+            Predicate::ElemMatch(condition) => match value.elements()? {
+                Some(elements) => any(&elements, |element| condition.matches(*element)),
+                None => Ok(false),
+            },
+            Predicate::Mod(divisor, remainder) => {
+                let dividend = match ordering::number(&*value.to_bson()?) {
+                    Some(Number::Integer(i)) => i,
+                    Some(Number::Double(d)) if d.is_finite() => d.trunc() as i128,
+                    Some(Number::Decimal(d)) => match d.to_i128_rounded(RoundingMode::Down) {
+                        Ok(i) => i,
+                        Err(_) => return Ok(false),
+                    },
+                    _ => return Ok(false),
+                };
+                Ok(dividend % i128::from(*divisor) == i128::from(*remainder))
+            }
+            Predicate::Not(predicate) => predicate.matches_single(value).map(|b| !b),
+        }
+    }
+}
+
+fn equals(value: Value, expected: &Bson) -> raw::Result<bool> {
+    Ok(value.compare(expected)? == Some(Ordering::Equal))
+}
+
+fn is_nan(value: &Bson) -> bool {
+    match ordering::number(value) {
+        Some(Number::Double(d)) => d.is_nan(),
+        Some(Number::Decimal(d)) => d.is_nan(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ElementMatcher, ErrorKind, Filter};
+    use crate::{Bson, Document, RawDocumentBuf, Regex};
+
+    fn matches(filter: Document, doc: &Document) -> bool {
+        let filter = Filter::compile(&filter).unwrap();
+        let raw = RawDocumentBuf::from_document(doc).unwrap();
+        let matched = filter.matches(doc);
+        assert_eq!(filter.matches_raw(&raw).unwrap(), matched, "{:?}", filter);
+        matched
+    }
+
+    #[test]
+    fn comparisons() {
+        let doc = doc! { "n": 5_i64, "d": 2.5, "s": "abc", "z": Bson::Null, "nan": f64::NAN };
+
+        assert!(matches(doc! { "n": 5.0 }, &doc));
+        assert!(matches(doc! { "n": { "$gt": 4, "$lte": 5.0 } }, &doc));
+        assert!(!matches(doc! { "n": { "$gt": "4" } }, &doc));
+        assert!(matches(doc! { "s": { "$gt": "ab", "$lt": "b" } }, &doc));
+        assert!(matches(
+            doc! { "d": { "$in": [1, 2.5] }, "n": { "$nin": [1, 2] } },
+            &doc
+        ));
+        assert!(matches(doc! { "d": { "$ne": 2 } }, &doc));
+        assert!(matches(
+            doc! { "missing": Bson::Null, "z": Bson::Null },
+            &doc
+        ));
+        assert!(!matches(doc! { "missing": { "$gt": Bson::Null } }, &doc));
+        assert!(matches(doc! { "nan": { "$gte": f64::NAN } }, &doc));
+        assert!(!matches(doc! { "nan": { "$lt": 0 } }, &doc));
+
+        assert!(matches(
+            doc! { "z": { "$exists": true }, "x": { "$exists": 0 } },
+            &doc
+        ));
+        assert!(matches(
+            doc! { "n": { "$type": "number" }, "s": { "$type": [2] } },
+            &doc
+        ));
+        assert!(!matches(doc! { "n": { "$type": "int" } }, &doc));
+        assert!(matches(
+            doc! { "n": { "$mod": [3, 2] }, "d": { "$mod": [2.9, 0] } },
+            &doc
+        ));
+        assert!(matches(
+            doc! { "n": { "$not": { "$gt": 5 } }, "x": { "$not": { "$lt": 1 } } },
+            &doc
+        ));
+    }
+
+    #[test]
+    fn arrays_and_paths() {
+        let doc = doc! {
+            "tags": ["a", "b", ["c"]],
+            "items": [{ "k": "x", "n": 1 }, { "k": "y", "n": 5 }],
+            "grid": [[1, 2], [3]],
+            "meta": { "owner": { "name": "ann" } },
+        };
+
+        assert!(matches(doc! { "tags": "a", "tags.1": "b" }, &doc));
+        assert!(matches(doc! { "tags": ["c"] }, &doc));
+        assert!(!matches(doc! { "tags": "c" }, &doc));
+        assert!(matches(
+            doc! { "tags": { "$all": ["b", "a"], "$size": 3 } },
+            &doc
+        ));
+        assert!(!matches(doc! { "tags": { "$all": [] } }, &doc));
+        assert!(matches(
+            doc! { "items.k": "y", "items.n": { "$gt": 4 } },
+            &doc
+        ));
+        assert!(matches(
+            doc! { "items.0.n": 1, "meta.owner.name": "ann" },
+            &doc
+        ));
+        assert!(!matches(doc! { "items.2.n": { "$exists": true } }, &doc));
+        assert!(!matches(
+            doc! { "items": { "$elemMatch": { "k": "x", "n": 5 } } },
+            &doc
+        ));
+        assert!(matches(
+            doc! { "items": { "$elemMatch": { "k": "y", "n": { "$gte": 5 } } } },
+            &doc
+        ));
+        assert!(matches(
+            doc! { "grid": { "$elemMatch": { "$elemMatch": { "$gt": 2 } } } },
+            &doc
+        ));
+        assert!(matches(
+            doc! {
+                "items": { "$all": [{ "$elemMatch": { "n": 1 } }, { "$elemMatch": { "n": 5 } }] },
+            },
+            &doc
+        ));
+        assert!(!matches(doc! { "grid": { "$size": 3 } }, &doc));
+        assert!(matches(doc! { "grid": { "$type": "array" } }, &doc));
+
+        assert!(matches(
+            doc! { "$or": [{ "tags": "z" }, { "items.k": "x" }], "$nor": [{ "grid": 3 }] },
+            &doc
+        ));
+        assert!(matches(
+            doc! {
+                "$and": [
+                    { "tags": "a" },
+                    { "$or": [{ "meta.owner": { "$exists": false } }, { "grid.0": [1, 2] }] },
+                ],
+            },
+            &doc
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |filter: Document| Filter::compile(&filter).unwrap_err();
+
+        assert_eq!(
+            error(doc! { "$where": "1" }).kind,
+            ErrorKind::UnknownOperator
+        );
+        assert_eq!(
+            error(doc! { "a": { "$regex": "x" } }).operator(),
+            Some("$regex")
+        );
+        assert_eq!(error(doc! { "a..b": 1 }).kind, ErrorKind::InvalidPath);
+        assert_eq!(
+            error(doc! { "$or": [] }).to_string(),
+            "invalid filter in $or: expected a non-empty array of documents"
+        );
+        assert_eq!(
+            error(doc! { "a": { "$mod": [0, 1] } }).to_string(),
+            "invalid filter in $mod at \"a\": the divisor must not be zero"
+        );
+        assert!(Filter::compile(&doc! { "a": { "$size": 1.5 } }).is_err());
+        assert!(Filter::compile(&doc! { "a": { "$type": "text" } }).is_err());
+        assert!(Filter::compile(&doc! { "a": { "$not": 1 } }).is_err());
+        assert!(Filter::compile(&doc! { "a": { "$in": [{ "$gt": 1 }] } }).is_err());
+    }
+
+    #[test]
+    fn regular_expressions() {
+        let regex = Regex::new("^a", "i");
+        let doc = doc! { "r": regex.clone(), "s": "abc" };
+
+        // explicit equality compares stored regular expressions
+        assert!(matches(doc! { "r": { "$eq": regex.clone() } }, &doc));
+        assert!(!matches(doc! { "s": { "$eq": regex.clone() } }, &doc));
+        assert!(matches(doc! { "s": { "$ne": regex.clone() } }, &doc));
+
+        // the server matches strings against these, which is not supported
+        let error = |filter: Document| Filter::compile(&filter).unwrap_err();
+        assert_eq!(
+            error(doc! { "s": regex.clone() }).to_string(),
+            "invalid filter at \"s\": regular expression matching is not supported"
+        );
+        assert_eq!(
+            error(doc! { "s": { "$in": ["x", regex.clone()] } }).to_string(),
+            "invalid filter in $in at \"s\": regular expression matching is not supported"
+        );
+        assert_eq!(
+            error(doc! { "s": { "$nin": [regex.clone()] } }).operator(),
+            Some("$nin")
+        );
+        assert_eq!(
+            error(doc! { "s": { "$all": [regex.clone()] } }).operator(),
+            Some("$all")
+        );
+        assert!(Filter::compile(&doc! { "a": { "$elemMatch": { "s": regex.clone() } } }).is_err());
+        assert!(ElementMatcher::for_pull(&Bson::RegularExpression(regex)).is_err());
+    }
+}
@@ -269,19 +269,24 @@
 
 #[doc(inline)]
 pub use self::{
-    bson::{Array, Binary, Bson, Document, Timestamp},
+    bson::{
+        Array, Binary, Bson, DbPointer, Document, JavaScriptCodeWithScope, Regex, RegexError,
+        Timestamp,
+    },
     datetime::DateTime,
     de::{
         from_bson, from_bson_with_options, from_document, from_document_with_options, from_reader, from_reader_utf8_lossy,
-        from_slice, from_slice_utf8_lossy, Deserializer, DeserializerOptions,
+        from_slice, from_slice_utf8_lossy, from_slice_with_options, Deserializer,
+        DeserializerOptions,
     },
     decimal128::Decimal128,
     raw::{
-        RawArray, RawBinary, RawBson, RawDocument, RawDocumentBuf
+        RawArray, RawBinary, RawBson, RawDbPointer, RawDocument, RawDocumentBuf,
+        RawJavaScriptCodeWithScope, RawRegex,
     },
     ser::{
-        to_bson, to_bson_with_options, to_document, to_document_with_options, to_vec, Serializer,
-        SerializerOptions,
+        to_bson, to_bson_with_options, to_document, to_document_with_options, to_vec,
+        to_vec_with_options, Serializer, SerializerOptions,
     },
     uuid::{Uuid, UuidRepresentation},
 };
@@ -289,16 +294,26 @@ pub use self::{
 #[macro_use]
 mod macros;
 mod bson;
+pub mod codec;
 pub mod datetime;
 pub mod de;
 pub mod decimal128;
+pub mod diff;
+pub mod display;
 pub mod document;
 pub mod extjson;
+pub mod filter;
+pub mod merge;
 pub mod oid;
+mod ordering;
+pub mod patch;
+pub mod path;
+pub mod projection;
 pub mod raw;
 pub mod ser;
 pub mod serde_helpers;
 pub mod spec;
+pub mod update;
 pub mod uuid;
 
 #[cfg(test)]
@@ -0,0 +1,243 @@
+//! Deep merging of documents.
+//!
+//! [`Document::merge`] overlays one document onto another: fields of the overlay replace the
+//! fields of the same name, except that embedded documents present on both sides are merged
+//! recursively and arrays present on both sides are combined according to
+//! [`MergeOptions::arrays`]. Fields that already exist keep their position, and new fields are
+//! appended in the order they appear in the overlay.
+//!
+//! ```rust
+//! use bson::{doc, merge::{ArrayMergeStrategy, MergeOptions}, Bson};
+//!
+//! let mut config = doc! {
+//!     "server": { "host": "localhost", "port": 27017 },
+//!     "tags": ["a", "b"],
+//!     "debug": true,
+//! };
+//! let patch = doc! {
+//!     "server": { "port": 27018, "tls": true },
+//!     "tags": ["b", "c"],
+//!     "debug": Bson::Null,
+//! };
+//!
+//! let options = MergeOptions::builder()
+//!     .arrays(ArrayMergeStrategy::Union)
+//!     .null_removes(true)
+//!     .build();
+//! config.merge(patch, options);
+//!
+//! assert_eq!(
+//!     config,
+//!     doc! {
+//!         "server": { "host": "localhost", "port": 27018, "tls": true },
+//!         "tags": ["a", "b", "c"],
+//!     }
+//! );
+//! ```
+
+use crate::{Array, Bson, Document};
+
+/// How [`Document::merge`] combines two arrays stored under the same key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArrayMergeStrategy {
+    /// The overlay's array replaces the original array.
+    Replace,
+
+    /// The overlay's elements are appended to the original array.
+    Append,
+
+    /// The overlay's elements are appended to the original array, except for those equal to an
+    /// element already in the result.
+    Union,
+
+    /// The elements at the same index are merged: embedded documents and arrays are merged
+    /// recursively and other elements are replaced. Elements past the end of the original array
+    /// are appended.
+    MergeByIndex,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for ArrayMergeStrategy {
+    fn default() -> Self {
+        ArrayMergeStrategy::Replace
+    }
+}
+
+/// Options used to configure how [`Document::merge`] combines documents.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct MergeOptions {
+    /// How arrays present in both documents are combined. The default value is
+    /// [`ArrayMergeStrategy::Replace`].
+    pub arrays: ArrayMergeStrategy,
+
+    /// Whether a [`Bson::Null`] field in the overlay removes the field of the same name instead
+    /// of being stored, as in a JSON merge patch. This applies at every level of the overlay,
+    /// including documents that are added or replace another value rather than being merged,
+    /// but not to the elements of arrays. The default value is false.
+    pub null_removes: bool,
+}
+
+impl MergeOptions {
+    /// Create a builder used to construct a new [`MergeOptions`].
+    pub fn builder() -> MergeOptionsBuilder {
+        MergeOptionsBuilder {
+            options: Default::default(),
+        }
+    }
+}
+
+/// A builder used to construct new [`MergeOptions`] structs.
+pub struct MergeOptionsBuilder {
+    options: MergeOptions,
+}
+
+impl MergeOptionsBuilder {
+    /// Set the value for [`MergeOptions::arrays`].
+    pub fn arrays(mut self, value: ArrayMergeStrategy) -> Self {
+        self.options.arrays = value;
+        self
+    }
+
+    /// Set the value for [`MergeOptions::null_removes`].
+    pub fn null_removes(mut self, value: bool) -> Self {
+        self.options.null_removes = value;
+        self
+    }
+
+    /// Consume this builder and produce a [`MergeOptions`].
+    pub fn build(self) -> MergeOptions {
+        self.options
+    }
+}
+
+pub(crate) fn merge_documents(target: &mut Document, overlay: Document, options: &MergeOptions) {
+    for (key, value) in overlay {
+        if options.null_removes && value == Bson::Null {
+            target.remove(&key);
+            continue;
+        }
+
+        match target.get_mut(&key) {
+            Some(existing) => merge_values(existing, value, options),
+            None => {
+                let value = match value {
+                    Bson::Document(doc) if options.null_removes => {
+                        let mut added = Document::new();
+                        merge_documents(&mut added, doc, options);
+                        Bson::Document(added)
+                    }
+                    value => value,
+                };
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+fn merge_values(target: &mut Bson, overlay: Bson, options: &MergeOptions) {
+    match (target, overlay) {
+        (Bson::Document(target), Bson::Document(overlay)) => {
+            merge_documents(target, overlay, options)
+        }
+        (Bson::Array(target), Bson::Array(overlay)) => merge_arrays(target, overlay, options),
+        (target, Bson::Document(overlay)) if options.null_removes => {
+            let mut replacement = Document::new();
+            merge_documents(&mut replacement, overlay, options);
+            *target = Bson::Document(replacement);
+        }
+        (target, overlay) => *target = overlay,
+    }
+}
+
+fn merge_arrays(target: &mut Array, overlay: Array, options: &MergeOptions) {
+    match options.arrays {
+        ArrayMergeStrategy::Replace => *target = overlay,
+        ArrayMergeStrategy::Append => target.extend(overlay),
+        ArrayMergeStrategy::Union => {
+            for value in overlay {
+                if !target.contains(&value) {
+                    target.push(value);
+                }
+            }
+        }
+        ArrayMergeStrategy::MergeByIndex => {
+            let mut overlay = overlay.into_iter();
+            for (existing, value) in target.iter_mut().zip(overlay.by_ref()) {
+                merge_values(existing, value, options);
+            }
+            target.extend(overlay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArrayMergeStrategy, MergeOptions};
+    use crate::Bson;
+
+    #[test]
+    fn array_strategies() {
+        let base = doc! { "a": [1, { "x": 1, "y": 1 }, [1, 2]], "b": 1 };
+        let overlay = doc! { "a": [1, { "y": 2 }, [3], 4], "c": 2 };
+        let merged = |arrays| {
+            base.merged(
+                overlay.clone(),
+                MergeOptions::builder().arrays(arrays).build(),
+            )
+        };
+
+        assert_eq!(
+            merged(ArrayMergeStrategy::Replace),
+            doc! { "a": [1, { "y": 2 }, [3], 4], "b": 1, "c": 2 }
+        );
+        assert_eq!(
+            merged(ArrayMergeStrategy::Append),
+            doc! {
+                "a": [1, { "x": 1, "y": 1 }, [1, 2], 1, { "y": 2 }, [3], 4],
+                "b": 1,
+                "c": 2,
+            }
+        );
+        assert_eq!(
+            merged(ArrayMergeStrategy::Union),
+            doc! { "a": [1, { "x": 1, "y": 1 }, [1, 2], { "y": 2 }, [3], 4], "b": 1, "c": 2 }
+        );
+        assert_eq!(
+            merged(ArrayMergeStrategy::MergeByIndex),
+            doc! { "a": [1, { "x": 1, "y": 2 }, [3, 2], 4], "b": 1, "c": 2 }
+        );
+    }
+
+    #[test]
+    fn nested_documents_and_nulls() {
+        let mut doc = doc! { "a": { "b": 1, "c": { "d": 1 } }, "e": 1, "f": "x" };
+        let overlay = doc! {
+            "f": { "g": 1 },
+            "a": { "c": { "d": Bson::Null, "h": 2 }, "b": 3 },
+            "e": Bson::Null,
+            "new": { "i": Bson::Null, "j": 1 },
+        };
+
+        assert_eq!(
+            doc.merged(overlay.clone(), MergeOptions::default()),
+            doc! {
+                "a": { "b": 3, "c": { "d": Bson::Null, "h": 2 } },
+                "e": Bson::Null,
+                "f": { "g": 1 },
+                "new": { "i": Bson::Null, "j": 1 },
+            }
+        );
+
+        doc.merge(overlay, MergeOptions::builder().null_removes(true).build());
+        assert_eq!(
+            doc,
+            doc! {
+                "a": { "b": 3, "c": { "h": 2 } },
+                "f": { "g": 1 },
+                "new": { "j": 1 },
+            }
+        );
+    }
+}
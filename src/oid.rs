@@ -283,7 +283,14 @@ fn test_counter_overflow_usize_max() {
 
 #[cfg(test)]
 mod test {
+    use std::convert::TryFrom;
+
     use chrono::{offset::TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::ObjectId;
+    use crate::{doc, Bson, Document, RawDocument};
 
     #[test]
     fn test_display() {
@@ -336,4 +343,41 @@ mod test {
             id.timestamp().to_chrono()
         );
     }
+
+    #[test]
+    fn binary_round_trip() {
+        let id = ObjectId::parse_str("53e37d08776f724e42000000").unwrap();
+        let doc = doc! { "_id": id };
+
+        let mut bytes = Vec::new();
+        doc.to_writer(&mut bytes).unwrap();
+        // the 0x07 type tag and key, followed by the 12 bytes of the id
+        assert_eq!(&bytes[4..9], b"\x07_id\x00");
+        assert_eq!(&bytes[9..21], &id.bytes());
+        assert_eq!(Document::from_reader(bytes.as_slice()).unwrap(), doc);
+
+        let raw = RawDocument::new(&bytes).unwrap();
+        assert_eq!(raw.get_object_id("_id").unwrap(), id);
+        assert!(raw.get_object_id("missing").is_err());
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Foo {
+            _id: ObjectId,
+        }
+
+        let foo = Foo { _id: id };
+        assert_eq!(crate::to_vec(&foo).unwrap(), bytes);
+        assert_eq!(crate::from_slice::<Foo>(&bytes).unwrap(), foo);
+    }
+
+    #[test]
+    fn extended_json() {
+        let id = ObjectId::parse_str("53e37d08776f724e42000000").unwrap();
+        let extjson = json!({ "$oid": "53e37d08776f724e42000000" });
+
+        assert_eq!(Bson::ObjectId(id).into_relaxed_extjson(), extjson);
+        assert_eq!(Bson::ObjectId(id).into_canonical_extjson(), extjson);
+        assert_eq!(Bson::try_from(extjson).unwrap(), Bson::ObjectId(id));
+        assert!(Bson::try_from(json!({ "$oid": "xyz" })).is_err());
+    }
 }
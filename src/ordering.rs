@@ -0,0 +1,285 @@
+//! The order in which the server compares and sorts values of any type.
+
+use std::cmp::Ordering;
+
+use crate::{spec::ElementType, Bson, Decimal128, Document};
+
+/// The rank of a type in the cross-type order, given its tag, which puts all numbers together
+/// and strings with symbols.
+pub(crate) fn canonical_type(tag: u8) -> u8 {
+    match ElementType::from(tag) {
+        Some(ElementType::MinKey) => 0,
+        Some(ElementType::Undefined) => 1,
+        Some(ElementType::Null) => 5,
+        Some(ElementType::Double)
+        | Some(ElementType::Int32)
+        | Some(ElementType::Int64)
+        | Some(ElementType::UInt32)
+        | Some(ElementType::UInt64)
+        | Some(ElementType::Decimal128) => 10,
+        Some(ElementType::String) | Some(ElementType::Symbol) => 15,
+        Some(ElementType::EmbeddedDocument) => 20,
+        Some(ElementType::Array) => 25,
+        Some(ElementType::Binary) => 30,
+        Some(ElementType::ObjectId) => 35,
+        Some(ElementType::Boolean) => 40,
+        Some(ElementType::DateTime) => 45,
+        Some(ElementType::Timestamp) => 47,
+        Some(ElementType::RegularExpression) => 50,
+        Some(ElementType::DbPointer) => 55,
+        Some(ElementType::JavaScriptCode) => 60,
+        Some(ElementType::JavaScriptCodeWithScope) => 65,
+        None => 100,
+        Some(ElementType::MaxKey) => 127,
+    }
+}
+
+/// Compare two values as the server does: first by the rank of their types, then by value.
+///
+/// Numbers of all types are compared by their numeric values, with NaN equal to itself and less
+/// than every other number. Strings are compared by their bytes, and documents and arrays element
+/// by element, comparing the type, then the key, then the value of each pair of elements.
+pub(crate) fn compare(a: &Bson, b: &Bson) -> Ordering {
+    let rank = canonical_type(a.tag()).cmp(&canonical_type(b.tag()));
+    if rank != Ordering::Equal {
+        return rank;
+    }
+
+    match (a, b) {
+        (Bson::String(a), Bson::String(b))
+        | (Bson::String(a), Bson::Symbol(b))
+        | (Bson::Symbol(a), Bson::String(b))
+        | (Bson::Symbol(a), Bson::Symbol(b))
+        | (Bson::JavaScriptCode(a), Bson::JavaScriptCode(b)) => a.as_bytes().cmp(b.as_bytes()),
+        (Bson::Document(a), Bson::Document(b)) => compare_documents(a, b),
+        (Bson::Array(a), Bson::Array(b)) => compare_arrays(a, b),
+        (Bson::Binary(a), Bson::Binary(b)) => a
+            .bytes
+            .len()
+            .cmp(&b.bytes.len())
+            .then_with(|| u8::from(a.subtype).cmp(&u8::from(b.subtype)))
+            .then_with(|| a.bytes.cmp(&b.bytes)),
+        (Bson::ObjectId(a), Bson::ObjectId(b)) => a.bytes().cmp(&b.bytes()),
+        (Bson::Boolean(a), Bson::Boolean(b)) => a.cmp(b),
+        (Bson::DateTime(a), Bson::DateTime(b)) => a.timestamp_millis().cmp(&b.timestamp_millis()),
+        (Bson::Timestamp(a), Bson::Timestamp(b)) => a.cmp(b),
+        (Bson::RegularExpression(a), Bson::RegularExpression(b)) => a
+            .pattern
+            .cmp(&b.pattern)
+            .then_with(|| a.options.cmp(&b.options)),
+        (Bson::DbPointer(a), Bson::DbPointer(b)) => a
+            .namespace
+            .cmp(&b.namespace)
+            .then_with(|| a.id.bytes().cmp(&b.id.bytes())),
+        (Bson::JavaScriptCodeWithScope(a), Bson::JavaScriptCodeWithScope(b)) => a
+            .code
+            .cmp(&b.code)
+            .then_with(|| compare_documents(&a.scope, &b.scope)),
+        (Bson::Unknown { tag: a, bytes: x }, Bson::Unknown { tag: b, bytes: y }) => {
+            a.cmp(b).then_with(|| x.cmp(y))
+        }
+        _ => match (number(a), number(b)) {
+            (Some(a), Some(b)) => compare_numbers(a, b),
+            // the remaining types of the same rank have a single value each
+            _ => Ordering::Equal,
+        },
+    }
+}
+
+fn compare_documents(a: &Document, b: &Document) -> Ordering {
+    for ((a_key, a), (b_key, b)) in a.iter().zip(b.iter()) {
+        let ordering = canonical_type(a.tag())
+            .cmp(&canonical_type(b.tag()))
+            .then_with(|| a_key.as_bytes().cmp(b_key.as_bytes()))
+            .then_with(|| compare(a, b));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn compare_arrays(a: &[Bson], b: &[Bson]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        let ordering = compare(a, b);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// The forms in which numbers of the different types are compared.
+#[derive(Clone, Copy)]
+pub(crate) enum Number {
+    Integer(i128),
+    Double(f64),
+    Decimal(Decimal128),
+}
+
+impl Number {
+    fn is_nan(&self) -> bool {
+        match self {
+            Number::Integer(_) => false,
+            Number::Double(d) => d.is_nan(),
+            Number::Decimal(d) => d.is_nan(),
+        }
+    }
+}
+
+/// The numeric value of `value`, if it is a number.
+pub(crate) fn number(value: &Bson) -> Option<Number> {
+    match *value {
+        Bson::Int32(i) => Some(Number::Integer(i.into())),
+        Bson::Int64(i) => Some(Number::Integer(i.into())),
+        Bson::UInt32(i) => Some(Number::Integer(i.into())),
+        Bson::UInt64(i) => Some(Number::Integer(i.into())),
+        Bson::Double(d) => Some(Number::Double(d)),
+        Bson::Decimal128(d) => Some(Number::Decimal(d)),
+        _ => None,
+    }
+}
+
+pub(crate) fn compare_numbers(a: Number, b: Number) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        (false, false) => {}
+    }
+
+    match (a, b) {
+        (Number::Integer(a), Number::Integer(b)) => a.cmp(&b),
+        (Number::Double(a), Number::Double(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (Number::Integer(i), Number::Double(d)) => compare_integer_to_double(i, d),
+        (Number::Double(d), Number::Integer(i)) => compare_integer_to_double(i, d).reverse(),
+        (Number::Decimal(a), b) => compare_decimal(a, b),
+        (a, Number::Decimal(b)) => compare_decimal(b, a).reverse(),
+    }
+}
+
+/// Compare an integer to a double without losing the precision of either.
+fn compare_integer_to_double(i: i128, d: f64) -> Ordering {
+    // 2^127, past which no i128 reaches
+    const LIMIT: f64 = 170_141_183_460_469_231_731_687_303_715_884_105_728.0;
+    if d >= LIMIT {
+        return Ordering::Less;
+    } else if d < -LIMIT {
+        return Ordering::Greater;
+    }
+
+    let truncated = d.trunc();
+    i.cmp(&(truncated as i128)).then_with(|| {
+        if d > truncated {
+            Ordering::Less
+        } else if d < truncated {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    })
+}
+
+fn compare_decimal(a: Decimal128, b: Number) -> Ordering {
+    let b = match b {
+        Number::Integer(i) => Decimal128::from_i128_rounded(i, Default::default()),
+        Number::Double(d) if d.is_infinite() => {
+            return if a.is_infinite() && a.is_sign_negative() == (d < 0.0) {
+                Ordering::Equal
+            } else if d > 0.0 {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+        }
+        Number::Double(d) => Decimal128::from(d),
+        Number::Decimal(d) => d,
+    };
+    a.value_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use super::compare;
+    use crate::{oid::ObjectId, Bson, DateTime, Decimal128};
+
+    #[test]
+    fn cross_type_order() {
+        let ascending = vec![
+            Bson::MinKey,
+            Bson::Null,
+            Bson::Double(f64::NAN),
+            Bson::Double(f64::NEG_INFINITY),
+            Bson::Int64(i64::MIN),
+            Bson::Decimal128("-1.5".parse::<Decimal128>().unwrap()),
+            Bson::Int32(-1),
+            Bson::Double(0.5),
+            Bson::UInt64(u64::MAX),
+            Bson::Double(1e30),
+            Bson::String("".to_string()),
+            Bson::Symbol("a".to_string()),
+            Bson::String("b".to_string()),
+            Bson::Document(doc! {}),
+            Bson::Document(doc! { "a": 1 }),
+            Bson::Document(doc! { "b": 1 }),
+            Bson::Document(doc! { "a": "x" }),
+            Bson::Array(vec![]),
+            Bson::Array(vec![Bson::Int32(1)]),
+            Bson::Array(vec![Bson::Int32(1), Bson::Null]),
+            Bson::ObjectId(ObjectId::from_bytes([0; 12])),
+            Bson::Boolean(false),
+            Bson::Boolean(true),
+            Bson::DateTime(DateTime::from_millis(-1)),
+            Bson::DateTime(DateTime::from_millis(0)),
+            Bson::MaxKey,
+        ];
+
+        for (i, a) in ascending.iter().enumerate() {
+            for (j, b) in ascending.iter().enumerate() {
+                assert_eq!(compare(a, b), i.cmp(&j), "{:?} <=> {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn numbers() {
+        let equal = vec![
+            Bson::Int32(1),
+            Bson::Int64(1),
+            Bson::UInt32(1),
+            Bson::Double(1.0),
+            Bson::Decimal128("1.00".parse().unwrap()),
+        ];
+        for a in &equal {
+            for b in &equal {
+                assert_eq!(compare(a, b), Ordering::Equal);
+            }
+        }
+
+        // 2^63 is exactly representable as a double but not as an i64
+        assert_eq!(
+            compare(&Bson::Int64(i64::MAX), &Bson::Double(i64::MAX as f64)),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare(&Bson::Int32(2), &Bson::Double(1.5)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare(
+                &Bson::Decimal128("Infinity".parse().unwrap()),
+                &Bson::Double(f64::INFINITY)
+            ),
+            Ordering::Equal
+        );
+        assert_eq!(
+            compare(
+                &Bson::Decimal128("NaN".parse().unwrap()),
+                &Bson::Double(f64::NAN)
+            ),
+            Ordering::Equal
+        );
+    }
+}
@@ -0,0 +1,758 @@
+//! JSON Patch ([RFC 6902](https://tools.ietf.org/html/rfc6902)) and JSON Merge Patch
+//! ([RFC 7386](https://tools.ietf.org/html/rfc7386)) for [`Document`]s.
+//!
+//! Both kinds of patch are applied to [`Bson`] values directly rather than through JSON, so
+//! values that JSON cannot represent, such as dates, binaries and unsigned integers, are
+//! preserved.
+//!
+//! A merge patch is a document that is overlaid onto the target, in which `null` removes a field;
+//! it is applied with [`Document::apply_merge_patch`].
+//!
+//! A [`JsonPatch`] is a list of [`PatchOperation`]s addressing values with JSON Pointers
+//! ([RFC 6901](https://tools.ietf.org/html/rfc6901)) such as `/a/b/0`, in which `~1` stands for a
+//! `/` and `~0` for a `~` in a key, and `-` for the end of an array. A patch is applied
+//! atomically: if one of its operations fails, the document is left unchanged and the error
+//! reports which operation failed. [`JsonPatch::diff`] generates the patch that turns one
+//! document into another.
+//!
+//! ```rust
+//! use bson::{doc, patch::{JsonPatch, PatchOperation}, Bson, DateTime};
+//!
+//! let mut doc = doc! { "name": "a", "tags": ["x"], "seen": DateTime::from_millis(0) };
+//! let patch: JsonPatch = bson::from_bson(Bson::Array(vec![
+//!     doc! { "op": "test", "path": "/name", "value": "a" }.into(),
+//!     doc! { "op": "add", "path": "/tags/-", "value": "y" }.into(),
+//!     doc! { "op": "replace", "path": "/seen", "value": DateTime::from_millis(1) }.into(),
+//! ]))?;
+//! patch.apply(&mut doc)?;
+//! assert_eq!(doc, doc! { "name": "a", "tags": ["x", "y"], "seen": DateTime::from_millis(1) });
+//!
+//! let failing = JsonPatch::new(vec![
+//!     PatchOperation::Remove { path: "/name".to_string() },
+//!     PatchOperation::Remove { path: "/missing".to_string() },
+//! ]);
+//! let err = failing.apply(&mut doc).unwrap_err();
+//! assert_eq!(err.operation(), 1);
+//! assert_eq!(err.to_string(), r#"error in operation 1 at "/missing": value is not present"#);
+//! assert_eq!(doc.get_str("name")?, "a");
+//!
+//! let target = doc! { "name": "b", "tags": ["x"] };
+//! let diff = JsonPatch::diff(&doc, &target);
+//! diff.apply(&mut doc)?;
+//! assert_eq!(doc, target);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{borrow::Cow, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Array, Bson, Document};
+
+/// An error that occurs when applying a [`JsonPatch`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// The type of error that was encountered.
+    pub kind: ErrorKind,
+
+    /// The index of the operation that failed.
+    operation: usize,
+
+    /// The JSON Pointer at which the error was encountered.
+    pointer: String,
+}
+
+impl Error {
+    /// The index of the operation that failed within the patch.
+    pub fn operation(&self) -> usize {
+        self.operation
+    }
+
+    /// The JSON Pointer at which the error was encountered, which is either the `path` or the
+    /// `from` of the failed operation.
+    pub fn pointer(&self) -> &str {
+        self.pointer.as_str()
+    }
+}
+
+/// The different categories of errors that can occur when applying a [`JsonPatch`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The JSON Pointer is malformed.
+    #[non_exhaustive]
+    InvalidPointer { message: String },
+
+    /// There is no value at the JSON Pointer.
+    NotPresent,
+
+    /// The JSON Pointer refers to an index past the end of an array.
+    #[non_exhaustive]
+    IndexOutOfBounds {
+        /// The length of the array.
+        length: usize,
+    },
+
+    /// The JSON Pointer applies a token that is not an index to an array.
+    InvalidIndex,
+
+    /// The JSON Pointer passes through a value that is neither a document nor an array.
+    #[non_exhaustive]
+    NotTraversable {
+        /// The type tag of the value.
+        actual: u8,
+    },
+
+    /// The operation cannot be performed, e.g. removing the whole document or moving a value
+    /// into one of its own children.
+    #[non_exhaustive]
+    InvalidOperation { message: String },
+
+    /// A `test` operation found a different value, or none.
+    #[non_exhaustive]
+    TestFailed {
+        /// The value that was found.
+        actual: Option<Box<Bson>>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "error in operation {} at {:?}: ",
+            self.operation, self.pointer
+        )?;
+        match &self.kind {
+            ErrorKind::InvalidPointer { message } => write!(f, "invalid JSON Pointer: {}", message),
+            ErrorKind::NotPresent => write!(f, "value is not present"),
+            ErrorKind::IndexOutOfBounds { length } => {
+                write!(f, "index out of bounds for array of length {}", length)
+            }
+            ErrorKind::InvalidIndex => write!(f, "invalid array index"),
+            ErrorKind::NotTraversable { actual } => {
+                write!(f, "cannot descend into a value of type {:#04x}", actual)
+            }
+            ErrorKind::InvalidOperation { message } => write!(f, "{}", message),
+            ErrorKind::TestFailed {
+                actual: Some(actual),
+            } => {
+                write!(f, "test failed, found {}", actual)
+            }
+            ErrorKind::TestFailed { actual: None } => {
+                write!(f, "test failed, value is not present")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Alias for `Result<T, patch::Error>`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The result of applying an operation, with the pointer at which it failed.
+type OperationResult<T> = std::result::Result<T, (String, ErrorKind)>;
+
+/// A single operation of a [`JsonPatch`].
+///
+/// This serializes to and deserializes from the object form of RFC 6902, e.g.
+/// `{ "op": "move", "from": "/a", "path": "/b" }`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum PatchOperation {
+    /// Add `value` at `path`, replacing the field of the same name or inserting it into an array
+    /// before the element at that index.
+    Add { path: String, value: Bson },
+
+    /// Remove the value at `path`.
+    Remove { path: String },
+
+    /// Replace the value at `path`, which must exist, with `value`.
+    Replace { path: String, value: Bson },
+
+    /// Remove the value at `from` and add it at `path`.
+    Move { from: String, path: String },
+
+    /// Add a copy of the value at `from` at `path`.
+    Copy { from: String, path: String },
+
+    /// Check that the value at `path` is equal to `value`.
+    Test { path: String, value: Bson },
+}
+
+/// A JSON Patch: a list of operations applied in order. See the [`patch`](crate::patch) module
+/// for an example.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JsonPatch {
+    operations: Vec<PatchOperation>,
+}
+
+impl JsonPatch {
+    /// Create a patch from its operations.
+    pub fn new(operations: Vec<PatchOperation>) -> Self {
+        Self { operations }
+    }
+
+    /// The operations of this patch.
+    pub fn operations(&self) -> &[PatchOperation] {
+        &self.operations
+    }
+
+    /// Append an operation to this patch.
+    pub fn push(&mut self, operation: PatchOperation) {
+        self.operations.push(operation);
+    }
+
+    /// Apply this patch to `doc`. If an operation fails, `doc` is left unchanged.
+    pub fn apply(&self, doc: &mut Document) -> Result<()> {
+        let mut patched = doc.clone();
+        for (index, operation) in self.operations.iter().enumerate() {
+            apply_operation(&mut patched, operation).map_err(|(pointer, kind)| Error {
+                kind,
+                operation: index,
+                pointer,
+            })?;
+        }
+        *doc = patched;
+        Ok(())
+    }
+
+    /// Generate a patch that turns `from` into `to`.
+    ///
+    /// Embedded documents and arrays are compared recursively, so the patch only touches the
+    /// values that differ. Arrays are compared element by element, with elements added to or
+    /// removed from their end. Applying the patch to `from` yields a document equal to `to`,
+    /// although fields added to an existing document are placed after the fields it already
+    /// has.
+    pub fn diff(from: &Document, to: &Document) -> Self {
+        let mut patch = Self::default();
+        patch.diff_documents(from, to, "");
+        patch
+    }
+
+    fn diff_documents(&mut self, from: &Document, to: &Document, pointer: &str) {
+        for key in from.keys() {
+            if !to.contains_key(key) {
+                self.push(PatchOperation::Remove {
+                    path: child_pointer(pointer, key),
+                });
+            }
+        }
+        for (key, value) in to {
+            let path = child_pointer(pointer, key);
+            match from.get(key) {
+                Some(old) => self.diff_values(old, value, path),
+                None => self.push(PatchOperation::Add {
+                    path,
+                    value: value.clone(),
+                }),
+            }
+        }
+    }
+
+    fn diff_values(&mut self, from: &Bson, to: &Bson, pointer: String) {
+        if from == to {
+            return;
+        }
+        match (from, to) {
+            (Bson::Document(from), Bson::Document(to)) => self.diff_documents(from, to, &pointer),
+            (Bson::Array(from), Bson::Array(to)) => {
+                let common = from.len().min(to.len());
+                for (i, (old, new)) in from.iter().zip(to).enumerate() {
+                    self.diff_values(old, new, child_pointer(&pointer, &i.to_string()));
+                }
+                for i in (common..from.len()).rev() {
+                    self.push(PatchOperation::Remove {
+                        path: child_pointer(&pointer, &i.to_string()),
+                    });
+                }
+                for value in &to[common..] {
+                    self.push(PatchOperation::Add {
+                        path: child_pointer(&pointer, "-"),
+                        value: value.clone(),
+                    });
+                }
+            }
+            _ => self.push(PatchOperation::Replace {
+                path: pointer,
+                value: to.clone(),
+            }),
+        }
+    }
+}
+
+impl From<Vec<PatchOperation>> for JsonPatch {
+    fn from(operations: Vec<PatchOperation>) -> Self {
+        Self::new(operations)
+    }
+}
+
+/// Append `key` to `pointer` as a reference token.
+fn child_pointer(pointer: &str, key: &str) -> String {
+    format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Split a JSON Pointer into its unescaped reference tokens.
+fn parse_pointer(pointer: &str) -> OperationResult<Vec<String>> {
+    let invalid = |message: &str| {
+        (
+            pointer.to_string(),
+            ErrorKind::InvalidPointer {
+                message: message.to_string(),
+            },
+        )
+    };
+
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    } else if !pointer.starts_with('/') {
+        return Err(invalid("a non-empty pointer must start with '/'"));
+    }
+
+    pointer[1..]
+        .split('/')
+        .map(|token| {
+            let mut unescaped = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '~' => match chars.next() {
+                        Some('0') => unescaped.push('~'),
+                        Some('1') => unescaped.push('/'),
+                        _ => return Err(invalid("'~' must be followed by '0' or '1'")),
+                    },
+                    c => unescaped.push(c),
+                }
+            }
+            Ok(unescaped)
+        })
+        .collect()
+}
+
+/// A JSON Pointer together with its unescaped reference tokens.
+struct Pointer<'a> {
+    text: &'a str,
+    tokens: Vec<String>,
+}
+
+impl<'a> Pointer<'a> {
+    fn parse(text: &'a str) -> OperationResult<Self> {
+        Ok(Self {
+            text,
+            tokens: parse_pointer(text)?,
+        })
+    }
+
+    fn error(&self, kind: ErrorKind) -> (String, ErrorKind) {
+        (self.text.to_string(), kind)
+    }
+
+    /// Parse an array index, which must not have leading zeros.
+    fn index(&self, token: &str) -> OperationResult<usize> {
+        let valid = token == "0" || (!token.starts_with('0') && !token.is_empty());
+        match crate::path::parse_index(token) {
+            Some(index) if valid => Ok(index),
+            _ => Err(self.error(ErrorKind::InvalidIndex)),
+        }
+    }
+
+    fn child<'b>(&self, value: &'b Bson, token: &str) -> OperationResult<&'b Bson> {
+        match value {
+            Bson::Document(doc) => doc
+                .get(token)
+                .ok_or_else(|| self.error(ErrorKind::NotPresent)),
+            Bson::Array(array) => {
+                let index = self.index(token)?;
+                array.get(index).ok_or_else(|| {
+                    self.error(ErrorKind::IndexOutOfBounds {
+                        length: array.len(),
+                    })
+                })
+            }
+            other => Err(self.error(ErrorKind::NotTraversable {
+                actual: other.tag(),
+            })),
+        }
+    }
+
+    fn child_mut<'b>(&self, value: &'b mut Bson, token: &str) -> OperationResult<&'b mut Bson> {
+        match value {
+            Bson::Document(doc) => match doc.get_mut(token) {
+                Some(value) => Ok(value),
+                None => Err(self.error(ErrorKind::NotPresent)),
+            },
+            Bson::Array(array) => {
+                let index = self.index(token)?;
+                let length = array.len();
+                array
+                    .get_mut(index)
+                    .ok_or_else(|| self.error(ErrorKind::IndexOutOfBounds { length }))
+            }
+            other => Err(self.error(ErrorKind::NotTraversable {
+                actual: other.tag(),
+            })),
+        }
+    }
+
+    /// The value at this pointer, which is the whole document if it has no tokens.
+    fn get<'b>(&self, doc: &'b Document) -> OperationResult<Cow<'b, Bson>> {
+        let (first, rest) = match self.tokens.split_first() {
+            Some(split) => split,
+            None => return Ok(Cow::Owned(Bson::Document(doc.clone()))),
+        };
+        let mut value = doc
+            .get(first)
+            .ok_or_else(|| self.error(ErrorKind::NotPresent))?;
+        for token in rest {
+            value = self.child(value, token)?;
+        }
+        Ok(Cow::Borrowed(value))
+    }
+
+    /// The container holding the value at this pointer, and the token naming the value in it.
+    fn parent_mut<'b>(&self, doc: &'b mut Document) -> OperationResult<(Container<'b>, &str)> {
+        let (last, init) = match self.tokens.split_last() {
+            Some(split) => split,
+            None => {
+                return Err(self.error(ErrorKind::InvalidOperation {
+                    message: "the whole document has no parent".to_string(),
+                }))
+            }
+        };
+        let (first, rest) = match init.split_first() {
+            Some(split) => split,
+            None => return Ok((Container::Document(doc), last)),
+        };
+
+        let mut value = match doc.get_mut(first) {
+            Some(value) => value,
+            None => return Err(self.error(ErrorKind::NotPresent)),
+        };
+        for token in rest {
+            value = self.child_mut(value, token)?;
+        }
+        match value {
+            Bson::Document(doc) => Ok((Container::Document(doc), last)),
+            Bson::Array(array) => Ok((Container::Array(array), last)),
+            other => Err(self.error(ErrorKind::NotTraversable {
+                actual: other.tag(),
+            })),
+        }
+    }
+
+    fn add(&self, doc: &mut Document, value: Bson) -> OperationResult<()> {
+        if self.tokens.is_empty() {
+            return self.replace_root(doc, value);
+        }
+        match self.parent_mut(doc)? {
+            (Container::Document(parent), key) => {
+                parent.insert(key, value);
+            }
+            (Container::Array(array), "-") => array.push(value),
+            (Container::Array(array), token) => {
+                let index = self.index(token)?;
+                if index > array.len() {
+                    return Err(self.error(ErrorKind::IndexOutOfBounds {
+                        length: array.len(),
+                    }));
+                }
+                array.insert(index, value);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, doc: &mut Document) -> OperationResult<Bson> {
+        if self.tokens.is_empty() {
+            return Err(self.error(ErrorKind::InvalidOperation {
+                message: "the whole document cannot be removed".to_string(),
+            }));
+        }
+        match self.parent_mut(doc)? {
+            (Container::Document(parent), key) => parent
+                .remove(key)
+                .ok_or_else(|| self.error(ErrorKind::NotPresent)),
+            (Container::Array(array), token) => {
+                let index = self.index(token)?;
+                if index >= array.len() {
+                    return Err(self.error(ErrorKind::IndexOutOfBounds {
+                        length: array.len(),
+                    }));
+                }
+                Ok(array.remove(index))
+            }
+        }
+    }
+
+    fn replace(&self, doc: &mut Document, value: Bson) -> OperationResult<()> {
+        let (first, rest) = match self.tokens.split_first() {
+            Some(split) => split,
+            None => return self.replace_root(doc, value),
+        };
+        let mut target = match doc.get_mut(first) {
+            Some(target) => target,
+            None => return Err(self.error(ErrorKind::NotPresent)),
+        };
+        for token in rest {
+            target = self.child_mut(target, token)?;
+        }
+        *target = value;
+        Ok(())
+    }
+
+    fn replace_root(&self, doc: &mut Document, value: Bson) -> OperationResult<()> {
+        match value {
+            Bson::Document(value) => {
+                *doc = value;
+                Ok(())
+            }
+            _ => Err(self.error(ErrorKind::InvalidOperation {
+                message: "the whole document can only be replaced by a document".to_string(),
+            })),
+        }
+    }
+}
+
+enum Container<'a> {
+    Document(&'a mut Document),
+    Array(&'a mut Array),
+}
+
+fn apply_operation(doc: &mut Document, operation: &PatchOperation) -> OperationResult<()> {
+    match operation {
+        PatchOperation::Add { path, value } => Pointer::parse(path)?.add(doc, value.clone()),
+        PatchOperation::Remove { path } => Pointer::parse(path)?.remove(doc).map(drop),
+        PatchOperation::Replace { path, value } => {
+            Pointer::parse(path)?.replace(doc, value.clone())
+        }
+        PatchOperation::Move { from, path } => {
+            let from = Pointer::parse(from)?;
+            let path = Pointer::parse(path)?;
+            if from.tokens == path.tokens {
+                // still fails if there is no value to move
+                return from.get(doc).map(drop);
+            } else if path.tokens.starts_with(&from.tokens) {
+                return Err(from.error(ErrorKind::InvalidOperation {
+                    message: "a value cannot be moved into one of its children".to_string(),
+                }));
+            }
+            let value = from.remove(doc)?;
+            path.add(doc, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = Pointer::parse(from)?.get(doc)?.into_owned();
+            Pointer::parse(path)?.add(doc, value)
+        }
+        PatchOperation::Test { path, value } => {
+            let pointer = Pointer::parse(path)?;
+            match pointer.get(doc) {
+                Ok(actual) if actual.as_ref() == value => Ok(()),
+                Ok(actual) => Err(pointer.error(ErrorKind::TestFailed {
+                    actual: Some(Box::new(actual.into_owned())),
+                })),
+                Err(_) => Err(pointer.error(ErrorKind::TestFailed { actual: None })),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ErrorKind, JsonPatch, PatchOperation};
+    use crate::{Binary, Bson, DateTime, Document};
+
+    fn patch(operations: Vec<Document>) -> JsonPatch {
+        crate::from_bson(Bson::Array(
+            operations.into_iter().map(Bson::from).collect(),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn operations() {
+        let mut doc = doc! {
+            "a": { "b": [1, 2, 3], "c~/d": true },
+            "bin": Binary { subtype: crate::spec::BinarySubtype::Generic, bytes: vec![1] },
+            "u": Bson::UInt64(u64::MAX),
+        };
+        patch(vec![
+            doc! { "op": "add", "path": "/a/b/1", "value": 10 },
+            doc! { "op": "add", "path": "/a/b/-", "value": 4 },
+            doc! { "op": "remove", "path": "/a/b/0" },
+            doc! { "op": "replace", "path": "/a/c~0~1d", "value": false },
+            doc! { "op": "copy", "from": "/a/b", "path": "/copy" },
+            doc! { "op": "move", "from": "/u", "path": "/a/u" },
+            doc! { "op": "move", "from": "/a/u", "path": "/a/u" },
+            doc! { "op": "test", "path": "/copy/0", "value": 10 },
+        ])
+        .apply(&mut doc)
+        .unwrap();
+
+        assert_eq!(
+            doc,
+            doc! {
+                "a": { "b": [10, 2, 3, 4], "c~/d": false, "u": Bson::UInt64(u64::MAX) },
+                "bin": Binary { subtype: crate::spec::BinarySubtype::Generic, bytes: vec![1] },
+                "copy": [10, 2, 3, 4],
+            }
+        );
+
+        let replaced = doc! { "new": DateTime::from_millis(5) };
+        JsonPatch::new(vec![PatchOperation::Replace {
+            path: String::new(),
+            value: replaced.clone().into(),
+        }])
+        .apply(&mut doc)
+        .unwrap();
+        assert_eq!(doc, replaced);
+    }
+
+    #[test]
+    fn errors_leave_document_unchanged() {
+        let original = doc! { "a": [1, 2], "s": "x", "n": Bson::Null };
+        let error = |operations: Vec<Document>| {
+            let mut doc = original.clone();
+            let mut operations = operations;
+            operations.insert(0, doc! { "op": "remove", "path": "/s" });
+            let err = patch(operations).apply(&mut doc).unwrap_err();
+            assert_eq!(doc, original);
+            assert_eq!(err.operation(), 1);
+            (err.pointer().to_string(), err.kind)
+        };
+
+        assert_eq!(
+            error(vec![doc! { "op": "remove", "path": "/missing" }]),
+            ("/missing".to_string(), ErrorKind::NotPresent)
+        );
+        assert_eq!(
+            error(vec![doc! { "op": "add", "path": "/a/3", "value": 1 }]).1,
+            ErrorKind::IndexOutOfBounds { length: 2 }
+        );
+        assert_eq!(
+            error(vec![doc! { "op": "replace", "path": "/a/01", "value": 1 }]).1,
+            ErrorKind::InvalidIndex
+        );
+        assert!(matches!(
+            error(vec![doc! { "op": "add", "path": "a", "value": 1 }]).1,
+            ErrorKind::InvalidPointer { .. }
+        ));
+        assert!(matches!(
+            error(vec![doc! { "op": "move", "from": "/a", "path": "/a/0" }]),
+            (pointer, ErrorKind::InvalidOperation { .. }) if pointer == "/a"
+        ));
+        assert!(matches!(
+            error(vec![doc! { "op": "remove", "path": "" }]).1,
+            ErrorKind::InvalidOperation { .. }
+        ));
+        assert_eq!(
+            error(vec![doc! { "op": "test", "path": "/a/0", "value": 1_i64 }]).1,
+            ErrorKind::TestFailed {
+                actual: Some(Box::new(Bson::Int32(1)))
+            }
+        );
+        assert_eq!(
+            error(vec![doc! { "op": "test", "path": "/n/x", "value": 1 }]).1,
+            ErrorKind::TestFailed { actual: None }
+        );
+    }
+
+    #[test]
+    fn diff_and_serialize() {
+        let from = doc! {
+            "a": { "b": 1, "c": [1, { "d": 2 }, 3], "x/y": 1 },
+            "removed": true,
+            "same": [1],
+        };
+        let to = doc! {
+            "a": { "c": [1, { "d": 3 }], "b": 1, "x/y": 2 },
+            "same": [1],
+            "added": { "e": DateTime::from_millis(1) },
+        };
+
+        let diff = JsonPatch::diff(&from, &to);
+        assert_eq!(
+            diff,
+            JsonPatch::new(vec![
+                PatchOperation::Remove {
+                    path: "/removed".to_string()
+                },
+                PatchOperation::Replace {
+                    path: "/a/c/1/d".to_string(),
+                    value: Bson::Int32(3)
+                },
+                PatchOperation::Remove {
+                    path: "/a/c/2".to_string()
+                },
+                PatchOperation::Replace {
+                    path: "/a/x~1y".to_string(),
+                    value: Bson::Int32(2)
+                },
+                PatchOperation::Add {
+                    path: "/added".to_string(),
+                    value: doc! { "e": DateTime::from_millis(1) }.into()
+                },
+            ])
+        );
+
+        let mut patched = from.clone();
+        diff.apply(&mut patched).unwrap();
+        assert_eq!(patched, to);
+        assert!(JsonPatch::diff(&to, &patched).operations().is_empty());
+
+        let serialized = crate::to_bson(&diff).unwrap();
+        assert_eq!(
+            serialized.as_array().unwrap()[0],
+            Bson::Document(doc! { "op": "remove", "path": "/removed" })
+        );
+        assert_eq!(crate::from_bson::<JsonPatch>(serialized).unwrap(), diff);
+    }
+
+    #[test]
+    fn merge_patch() {
+        // the examples from RFC 7386, appendix A
+        let cases = vec![
+            (doc! { "a": "b" }, doc! { "a": "c" }, doc! { "a": "c" }),
+            (
+                doc! { "a": "b" },
+                doc! { "b": "c" },
+                doc! { "a": "b", "b": "c" },
+            ),
+            (doc! { "a": "b" }, doc! { "a": Bson::Null }, doc! {}),
+            (
+                doc! { "a": "b", "b": "c" },
+                doc! { "a": Bson::Null },
+                doc! { "b": "c" },
+            ),
+            (doc! { "a": ["b"] }, doc! { "a": "c" }, doc! { "a": "c" }),
+            (doc! { "a": "c" }, doc! { "a": ["b"] }, doc! { "a": ["b"] }),
+            (
+                doc! { "a": { "b": "c" } },
+                doc! { "a": { "b": "d", "c": Bson::Null } },
+                doc! { "a": { "b": "d" } },
+            ),
+            (
+                doc! { "a": [{ "b": "c" }] },
+                doc! { "a": [1] },
+                doc! { "a": [1] },
+            ),
+            (
+                doc! { "e": Bson::Null },
+                doc! { "a": 1 },
+                doc! { "e": Bson::Null, "a": 1 },
+            ),
+            (
+                doc! { "a": "foo" },
+                doc! { "a": { "bb": { "ccc": Bson::Null } } },
+                doc! { "a": { "bb": {} } },
+            ),
+        ];
+
+        for (mut target, patch, expected) in cases {
+            target.apply_merge_patch(&patch);
+            assert_eq!(target, expected);
+        }
+    }
+}
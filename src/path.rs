@@ -0,0 +1,1067 @@
+//! Dotted paths into nested documents and arrays.
+//!
+//! A path such as `"a.b.0.c"` names the value found by looking up `a` in a document, `b` in the
+//! document stored there, the element at index 0 of the array stored there, and finally `c` in
+//! that element. Segments are separated by `.`; a key that itself contains a `.` or a `\` is
+//! written with that character escaped as `\.` or `\\`. [`KeyPath`] holds a path that has
+//! already been split into its segments.
+//!
+//! Paths are accepted by the path methods of [`Document`](crate::Document), such as
+//! [`Document::get_path`](crate::Document::get_path) and
+//! [`Document::insert_path`](crate::Document::insert_path), either as a `&str` or as a
+//! [`KeyPath`].
+//!
+//! ```rust
+//! use bson::{doc, path::KeyPath, Bson};
+//!
+//! let mut doc = doc! { "a": { "b": [{ "c": 1 }, { "c": 2 }] }, "x.y": true };
+//! assert_eq!(doc.get_path("a.b.1.c")?, &Bson::Int32(2));
+//! assert_eq!(doc.get_path_bool(r"x\.y")?, true);
+//! assert_eq!(doc.get_path_bool(&KeyPath::from_segments(vec!["x.y"]))?, true);
+//!
+//! doc.insert_path("a.d.e", "new")?;
+//! assert_eq!(doc.get_path_str("a.d.e")?, "new");
+//!
+//! let err = doc.get_path("a.b.5.c").unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     r#"error at segment 2 ("5") of path "a.b.5.c": index out of bounds for array of length 2"#
+//! );
+//! # Ok::<(), bson::path::Error>(())
+//! ```
+
+use std::{borrow::Cow, fmt, slice, str::FromStr};
+
+use crate::{
+    raw::{RawBson, RawDocument, RawElement, RawElements},
+    spec::ElementType,
+    Array,
+    Bson,
+    Document,
+};
+
+/// An error that occurs when resolving a path.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Error {
+    /// The type of error that was encountered.
+    pub kind: ErrorKind,
+
+    /// The path, in dotted notation.
+    path: String,
+
+    /// The index and the unescaped text of the segment at which the error was encountered.
+    segment: Option<(usize, String)>,
+}
+
+impl Error {
+    pub(crate) fn new(
+        path: impl Into<String>,
+        segment: Option<(usize, &str)>,
+        kind: ErrorKind,
+    ) -> Self {
+        Self {
+            kind,
+            path: path.into(),
+            segment: segment.map(|(index, key)| (index, key.to_string())),
+        }
+    }
+
+    /// The path being resolved, in dotted notation.
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    /// The index of the segment at which the error was encountered, if any.
+    pub fn segment_index(&self) -> Option<usize> {
+        self.segment.as_ref().map(|(index, _)| *index)
+    }
+
+    /// The unescaped segment at which the error was encountered, if any.
+    pub fn segment(&self) -> Option<&str> {
+        self.segment.as_ref().map(|(_, key)| key.as_str())
+    }
+}
+
+/// The different categories of errors that can occur when resolving a path.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The path is not well-formed, e.g. it is empty or contains an empty segment.
+    #[non_exhaustive]
+    InvalidPath { message: String },
+
+    /// The document has no field with the segment as its key.
+    NotPresent,
+
+    /// The segment is an index past the end of an array.
+    #[non_exhaustive]
+    IndexOutOfBounds {
+        /// The length of the array.
+        length: usize,
+    },
+
+    /// The segment is applied to an array but is not an index.
+    InvalidIndex,
+
+    /// Inserting at the segment would pad an array with more than 1,500,000 nulls.
+    #[non_exhaustive]
+    IndexTooLarge {
+        /// The length of the array.
+        length: usize,
+    },
+
+    /// The segment is applied to a value that is neither a document nor an array.
+    #[non_exhaustive]
+    NotTraversable {
+        /// The type tag of the value.
+        actual: u8,
+    },
+
+    /// The value at the end of the path does not have the expected type.
+    #[non_exhaustive]
+    UnexpectedType {
+        /// The type that was expected.
+        expected: ElementType,
+
+        /// The type tag of the value that was found.
+        actual: u8,
+    },
+
+    /// The raw document being traversed is malformed.
+    InvalidBson(crate::raw::Error),
+}
+
+/// Formats a type tag as its [`ElementType`], or in hexadecimal if it is not one.
+pub(crate) struct TypeName(pub(crate) u8);
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match ElementType::from(self.0) {
+            Some(element_type) => write!(f, "{:?}", element_type),
+            None => write!(f, "{:#04x}", self.0),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.segment {
+            Some((index, ref key)) => write!(
+                f,
+                "error at segment {} ({:?}) of path {:?}: ",
+                index, key, self.path
+            )?,
+            None => write!(f, "error in path {:?}: ", self.path)?,
+        }
+
+        match &self.kind {
+            ErrorKind::InvalidPath { message } => write!(f, "invalid path: {}", message),
+            ErrorKind::NotPresent => write!(f, "field is not present"),
+            ErrorKind::IndexOutOfBounds { length } => {
+                write!(f, "index out of bounds for array of length {}", length)
+            }
+            ErrorKind::InvalidIndex => write!(f, "array index is not a non-negative integer"),
+            ErrorKind::IndexTooLarge { length } => f.write_str(&padding_message(*length)),
+            ErrorKind::NotTraversable { actual } => {
+                write!(
+                    f,
+                    "cannot descend into a value of type {}",
+                    TypeName(*actual)
+                )
+            }
+            ErrorKind::UnexpectedType { expected, actual } => write!(
+                f,
+                "unexpected element type: {}, expected: {:?}",
+                TypeName(*actual),
+                expected
+            ),
+            ErrorKind::InvalidBson(error) => write!(f, "invalid BSON: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Alias for `Result<T, path::Error>`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A path that has been split into its segments.
+///
+/// A `KeyPath` is parsed from dotted notation with [`KeyPath::parse`] or [`str::parse`], or built
+/// from unescaped segments with [`KeyPath::from_segments`], which allows keys containing `.` to
+/// be used without escaping them. Its [`Display`](fmt::Display) implementation writes the path
+/// in dotted notation, escaping where necessary.
+///
+/// ```rust
+/// use bson::path::KeyPath;
+///
+/// let path = KeyPath::parse(r"a.b\.c.0")?;
+/// assert_eq!(path.segments(), ["a", "b.c", "0"]);
+/// assert_eq!(path, KeyPath::from_segments(vec!["a", "b.c", "0"]));
+/// assert_eq!(path.to_string(), r"a.b\.c.0");
+/// # Ok::<(), bson::path::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyPath {
+    segments: Vec<String>,
+}
+
+impl KeyPath {
+    /// Parse a path in dotted notation.
+    pub fn parse(path: &str) -> Result<Self> {
+        let segments = Segments::dotted(path)
+            .map(|segment| segment.map(Cow::into_owned))
+            .collect::<Result<_>>()?;
+        Ok(Self { segments })
+    }
+
+    /// Create a path from its unescaped segments.
+    ///
+    /// Segments are not validated; a path with no segments or with an empty segment fails to
+    /// resolve with [`ErrorKind::InvalidPath`].
+    pub fn from_segments<I, S>(segments: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            segments: segments.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// The unescaped segments of this path.
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    /// Append a segment to the end of this path.
+    pub fn push(&mut self, segment: impl Into<String>) {
+        self.segments.push(segment.into());
+    }
+
+    /// Remove the last segment of this path and return it, if any.
+    pub fn pop(&mut self) -> Option<String> {
+        self.segments.pop()
+    }
+
+    /// The number of segments in this path.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Whether this path has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+impl FromStr for KeyPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for KeyPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            for c in segment.chars() {
+                if c == '.' || c == '\\' {
+                    f.write_str("\\")?;
+                }
+                fmt::Write::write_char(f, c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: Into<String>> std::iter::FromIterator<S> for KeyPath {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        Self::from_segments(iter)
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl Sealed for super::KeyPath {}
+    impl<T: Sealed + ?Sized> Sealed for &T {}
+}
+
+/// A value that can be used as a path: a string in dotted notation or a [`KeyPath`].
+///
+/// This trait is sealed and cannot be implemented outside of this crate.
+pub trait AsKeyPath: private::Sealed {
+    /// The path in dotted notation, as reported in errors.
+    fn dotted(&self) -> Cow<'_, str>;
+
+    /// An iterator over the unescaped segments of the path, which fails at the first malformed
+    /// segment.
+    fn iter_segments(&self) -> Segments<'_>;
+}
+
+impl AsKeyPath for str {
+    fn dotted(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+
+    fn iter_segments(&self) -> Segments<'_> {
+        Segments::dotted(self)
+    }
+}
+
+impl AsKeyPath for String {
+    fn dotted(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.as_str())
+    }
+
+    fn iter_segments(&self) -> Segments<'_> {
+        Segments::dotted(self.as_str())
+    }
+}
+
+impl AsKeyPath for KeyPath {
+    fn dotted(&self) -> Cow<'_, str> {
+        Cow::Owned(self.to_string())
+    }
+
+    fn iter_segments(&self) -> Segments<'_> {
+        Segments {
+            inner: SegmentsInner::Split {
+                path: self,
+                index: 0,
+                iter: self.segments.iter(),
+            },
+        }
+    }
+}
+
+impl<T: AsKeyPath + ?Sized> AsKeyPath for &T {
+    fn dotted(&self) -> Cow<'_, str> {
+        (**self).dotted()
+    }
+
+    fn iter_segments(&self) -> Segments<'_> {
+        (**self).iter_segments()
+    }
+}
+
+/// An iterator over the unescaped segments of a path, created by
+/// [`AsKeyPath::iter_segments`].
+///
+/// Segments without escapes are borrowed from the path, so iterating over a string path only
+/// allocates for segments containing `\.` or `\\`.
+pub struct Segments<'a> {
+    inner: SegmentsInner<'a>,
+}
+
+enum SegmentsInner<'a> {
+    Dotted {
+        path: &'a str,
+        rest: Option<&'a str>,
+        index: usize,
+    },
+    Split {
+        path: &'a KeyPath,
+        index: usize,
+        iter: slice::Iter<'a, String>,
+    },
+}
+
+impl<'a> Segments<'a> {
+    fn dotted(path: &'a str) -> Self {
+        Self {
+            inner: SegmentsInner::Dotted {
+                path,
+                rest: Some(path),
+                index: 0,
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Result<Cow<'a, str>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.inner {
+            SegmentsInner::Dotted {
+                path,
+                ref mut rest,
+                ref mut index,
+            } => {
+                let s = rest.take()?;
+                let invalid = |message: &str| {
+                    Error::new(
+                        path,
+                        None,
+                        ErrorKind::InvalidPath {
+                            message: message.to_string(),
+                        },
+                    )
+                };
+
+                let mut unescaped: Option<String> = None;
+                let mut end = s.len();
+                let mut chars = s.char_indices();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '.' => {
+                            end = i;
+                            *rest = Some(&s[i + 1..]);
+                            break;
+                        }
+                        '\\' => {
+                            let escaped = match chars.next() {
+                                Some((_, c @ '.')) | Some((_, c @ '\\')) => c,
+                                Some(_) => return Some(Err(invalid("invalid escape sequence"))),
+                                None => return Some(Err(invalid("dangling escape character"))),
+                            };
+                            unescaped
+                                .get_or_insert_with(|| s[..i].to_string())
+                                .push(escaped);
+                        }
+                        c => {
+                            if let Some(ref mut unescaped) = unescaped {
+                                unescaped.push(c);
+                            }
+                        }
+                    }
+                }
+
+                if end == 0 {
+                    *rest = None;
+                    return Some(Err(invalid(if path.is_empty() {
+                        "path is empty"
+                    } else {
+                        "path contains an empty segment"
+                    })));
+                }
+                *index += 1;
+                Some(Ok(match unescaped {
+                    Some(unescaped) => Cow::Owned(unescaped),
+                    None => Cow::Borrowed(&s[..end]),
+                }))
+            }
+            SegmentsInner::Split {
+                path,
+                ref mut index,
+                ref mut iter,
+            } => {
+                if *index == 0 && path.is_empty() {
+                    *index += 1;
+                    return Some(Err(Error::new(
+                        path.to_string(),
+                        None,
+                        ErrorKind::InvalidPath {
+                            message: "path is empty".to_string(),
+                        },
+                    )));
+                }
+                let segment = iter.next()?;
+                *index += 1;
+                if segment.is_empty() {
+                    iter.by_ref().for_each(drop);
+                    return Some(Err(Error::new(
+                        path.to_string(),
+                        None,
+                        ErrorKind::InvalidPath {
+                            message: "path contains an empty segment".to_string(),
+                        },
+                    )));
+                }
+                Some(Ok(Cow::Borrowed(segment.as_str())))
+            }
+        }
+    }
+}
+
+/// The most nulls [`Document::insert_path`] and updates pad an array with, as the server does.
+pub(crate) const MAX_PADDING: usize = 1_500_000;
+
+/// Whether setting element `index` of an array of `length` elements would pad it with more than
+/// [`MAX_PADDING`] nulls.
+pub(crate) fn exceeds_padding(length: usize, index: usize) -> bool {
+    index.saturating_sub(length) > MAX_PADDING
+}
+
+/// Describe the failure to set an element that [`exceeds_padding`] an array of `length` elements.
+pub(crate) fn padding_message(length: usize) -> String {
+    format!(
+        "index would pad array of length {} with more than {} nulls",
+        length, MAX_PADDING
+    )
+}
+
+/// The element at `index` of `array`, first padding the array with nulls and appending `fill()`
+/// if `index` is past its end. The padding must have been checked with [`exceeds_padding`].
+pub(crate) fn element_or_pad(
+    array: &mut Array,
+    index: usize,
+    fill: impl FnOnce() -> Bson,
+) -> &mut Bson {
+    if index >= array.len() {
+        array.resize(index, Bson::Null);
+        array.push(fill());
+    }
+    &mut array[index]
+}
+
+/// Parse a segment applied to an array as an index.
+pub(crate) fn parse_index(segment: &str) -> Option<usize> {
+    if segment.bytes().all(|b| b.is_ascii_digit()) {
+        segment.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// A path whose segments have all been checked to be well-formed.
+struct Resolver<'a> {
+    dotted: Cow<'a, str>,
+    segments: Vec<Cow<'a, str>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new<P: AsKeyPath + ?Sized>(path: &'a P) -> Result<Self> {
+        Ok(Self {
+            segments: path.iter_segments().collect::<Result<_>>()?,
+            dotted: path.dotted(),
+        })
+    }
+
+    fn error(&self, index: usize, kind: ErrorKind) -> Error {
+        Error::new(
+            self.dotted.as_ref(),
+            Some((index, self.segments[index].as_ref())),
+            kind,
+        )
+    }
+
+    fn last(&self) -> usize {
+        self.segments.len() - 1
+    }
+
+    /// Resolve the child of `value` named by segment `index`.
+    fn child<'b>(&self, value: &'b Bson, index: usize) -> Result<&'b Bson> {
+        match value {
+            Bson::Document(doc) => self.field(doc, index),
+            Bson::Array(array) => {
+                let i = self.index(index)?;
+                array.get(i).ok_or_else(|| {
+                    self.error(
+                        index,
+                        ErrorKind::IndexOutOfBounds {
+                            length: array.len(),
+                        },
+                    )
+                })
+            }
+            other => Err(self.error(
+                index,
+                ErrorKind::NotTraversable {
+                    actual: other.tag(),
+                },
+            )),
+        }
+    }
+
+    fn child_mut<'b>(&self, value: &'b mut Bson, index: usize) -> Result<&'b mut Bson> {
+        match value {
+            Bson::Document(doc) => self.field_mut(doc, index),
+            Bson::Array(array) => {
+                let i = self.index(index)?;
+                let length = array.len();
+                array
+                    .get_mut(i)
+                    .ok_or_else(|| self.error(index, ErrorKind::IndexOutOfBounds { length }))
+            }
+            other => Err(self.error(
+                index,
+                ErrorKind::NotTraversable {
+                    actual: other.tag(),
+                },
+            )),
+        }
+    }
+
+    fn field<'b>(&self, doc: &'b Document, index: usize) -> Result<&'b Bson> {
+        doc.get(&self.segments[index])
+            .ok_or_else(|| self.error(index, ErrorKind::NotPresent))
+    }
+
+    fn field_mut<'b>(&self, doc: &'b mut Document, index: usize) -> Result<&'b mut Bson> {
+        match doc.get_mut(&self.segments[index]) {
+            Some(value) => Ok(value),
+            None => Err(self.error(index, ErrorKind::NotPresent)),
+        }
+    }
+
+    fn index(&self, index: usize) -> Result<usize> {
+        parse_index(&self.segments[index]).ok_or_else(|| self.error(index, ErrorKind::InvalidIndex))
+    }
+
+    fn get<'b>(&self, doc: &'b Document) -> Result<&'b Bson> {
+        let mut value = self.field(doc, 0)?;
+        for index in 1..self.segments.len() {
+            value = self.child(value, index)?;
+        }
+        Ok(value)
+    }
+
+    fn get_mut<'b>(&self, doc: &'b mut Document) -> Result<&'b mut Bson> {
+        let mut value = self.field_mut(doc, 0)?;
+        for index in 1..self.segments.len() {
+            value = self.child_mut(value, index)?;
+        }
+        Ok(value)
+    }
+
+    /// Resolve the parent of the value at the end of a path with at least two segments.
+    fn parent_mut<'b>(&self, doc: &'b mut Document) -> Result<&'b mut Bson> {
+        let mut value = self.field_mut(doc, 0)?;
+        for index in 1..self.last() {
+            value = self.child_mut(value, index)?;
+        }
+        Ok(value)
+    }
+
+    /// An empty container to insert for segment `index` when it is missing: an array if the
+    /// following segment is an index, a document otherwise.
+    fn container_for(&self, index: usize) -> Bson {
+        if parse_index(&self.segments[index + 1]).is_some() {
+            Bson::Array(Array::new())
+        } else {
+            Bson::Document(Document::new())
+        }
+    }
+
+    /// Check that inserting along the path pads no array with more than [`MAX_PADDING`] nulls,
+    /// before anything is inserted.
+    fn check_padding(&self, doc: &Document) -> Result<()> {
+        let mut current = doc.get(self.segments[0].as_ref());
+        for index in 1..self.segments.len() {
+            let length = match current {
+                Some(Bson::Document(doc)) => {
+                    current = doc.get(self.segments[index].as_ref());
+                    continue;
+                }
+                Some(Bson::Array(array)) => array.len(),
+                // the array for this segment will be created empty
+                None => 0,
+                // the insertion fails when it reaches this value
+                Some(_) => return Ok(()),
+            };
+            let i = match parse_index(&self.segments[index]) {
+                Some(i) => i,
+                // a document will be created, or the insertion fails on the array
+                None => continue,
+            };
+            if exceeds_padding(length, i) {
+                return Err(self.error(index, ErrorKind::IndexTooLarge { length }));
+            }
+            current = current.and_then(|array| match array {
+                Bson::Array(array) => array.get(i),
+                _ => None,
+            });
+        }
+        Ok(())
+    }
+
+    fn insert(&self, doc: &mut Document, value: Bson) -> Result<Option<Bson>> {
+        let last = self.last();
+        if last == 0 {
+            return Ok(doc.insert(self.segments[0].to_string(), value));
+        }
+        self.check_padding(doc)?;
+        let mut current = doc
+            .entry(self.segments[0].to_string())
+            .or_insert_with(|| self.container_for(0));
+
+        for index in 1..=last {
+            let fill = || {
+                if index == last {
+                    Bson::Null
+                } else {
+                    self.container_for(index)
+                }
+            };
+            current = match current {
+                Bson::Document(doc) if index == last => {
+                    return Ok(doc.insert(self.segments[index].to_string(), value));
+                }
+                Bson::Document(doc) => doc
+                    .entry(self.segments[index].to_string())
+                    .or_insert_with(fill),
+                Bson::Array(array) => {
+                    let i = self.index(index)?;
+                    if i < array.len() && index == last {
+                        return Ok(Some(std::mem::replace(&mut array[i], value)));
+                    }
+                    element_or_pad(array, i, fill)
+                }
+                other => {
+                    return Err(self.error(
+                        index,
+                        ErrorKind::NotTraversable {
+                            actual: other.tag(),
+                        },
+                    ))
+                }
+            };
+        }
+
+        // the last segment was a new index, which has been filled with a placeholder
+        *current = value;
+        Ok(None)
+    }
+
+    fn remove(&self, doc: &mut Document) -> Result<Option<Bson>> {
+        let last = self.last();
+        if last == 0 {
+            return Ok(doc.remove(&self.segments[0]));
+        }
+        let parent = match self.parent_mut(doc) {
+            Ok(parent) => parent,
+            Err(Error {
+                kind: ErrorKind::NotPresent,
+                ..
+            })
+            | Err(Error {
+                kind: ErrorKind::IndexOutOfBounds { .. },
+                ..
+            }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        match parent {
+            Bson::Document(doc) => Ok(doc.remove(&self.segments[last])),
+            Bson::Array(array) => {
+                let i = self.index(last)?;
+                Ok(if i < array.len() {
+                    Some(array.remove(i))
+                } else {
+                    None
+                })
+            }
+            other => Err(self.error(
+                last,
+                ErrorKind::NotTraversable {
+                    actual: other.tag(),
+                },
+            )),
+        }
+    }
+}
+
+pub(crate) fn get<'a, P: AsKeyPath + ?Sized>(doc: &'a Document, path: &P) -> Result<&'a Bson> {
+    Resolver::new(path)?.get(doc)
+}
+
+pub(crate) fn get_mut<'a, P: AsKeyPath + ?Sized>(
+    doc: &'a mut Document,
+    path: &P,
+) -> Result<&'a mut Bson> {
+    Resolver::new(path)?.get_mut(doc)
+}
+
+/// Resolve `path` and convert the value found with `convert`, reporting a value that it rejects
+/// as being of an unexpected type.
+pub(crate) fn get_typed<'a, P, T, F>(
+    doc: &'a Document,
+    path: &P,
+    expected: ElementType,
+    convert: F,
+) -> Result<T>
+where
+    P: AsKeyPath + ?Sized,
+    F: FnOnce(&'a Bson) -> Option<T>,
+{
+    let resolver = Resolver::new(path)?;
+    let value = resolver.get(doc)?;
+    convert(value).ok_or_else(|| {
+        resolver.error(
+            resolver.last(),
+            ErrorKind::UnexpectedType {
+                expected,
+                actual: value.tag(),
+            },
+        )
+    })
+}
+
+pub(crate) fn insert<P: AsKeyPath + ?Sized>(
+    doc: &mut Document,
+    path: &P,
+    value: Bson,
+) -> Result<Option<Bson>> {
+    Resolver::new(path)?.insert(doc, value)
+}
+
+pub(crate) fn remove<P: AsKeyPath + ?Sized>(doc: &mut Document, path: &P) -> Result<Option<Bson>> {
+    Resolver::new(path)?.remove(doc)
+}
+
+/// Resolve `path` in a raw document, returning the value found along with the index and text of
+/// the last segment of the path.
+///
+/// Elements that are not on the path are skipped over by their length without being decoded, and
+/// nothing is allocated unless the path contains escapes or cannot be resolved.
+fn resolve_raw<'a, 'p, P>(
+    doc: &'a RawDocument,
+    path: &'p P,
+) -> Result<(RawBson<'a>, usize, Cow<'p, str>)>
+where
+    P: AsKeyPath + ?Sized,
+{
+    let error =
+        |index: usize, segment: &str, kind| Error::new(path.dotted(), Some((index, segment)), kind);
+    let invalid_bson =
+        |index: usize, segment: &str, e| error(index, segment, ErrorKind::InvalidBson(e));
+
+    let mut found: Option<(RawElement<'a>, usize, Cow<'p, str>)> = None;
+    for (index, segment) in path.iter_segments().enumerate() {
+        let segment = segment?;
+        let (container, is_array) = match found {
+            None => (doc, false),
+            Some((element, parent_index, ref parent)) => match element.container() {
+                Ok(Some(container)) => (container, element.tag() == ElementType::Array as u8),
+                Ok(None) => {
+                    return Err(error(
+                        index,
+                        &segment,
+                        ErrorKind::NotTraversable {
+                            actual: element.tag(),
+                        },
+                    ))
+                }
+                Err(e) => return Err(invalid_bson(parent_index, parent, e)),
+            },
+        };
+
+        let mut elements = RawElements::new(container);
+        let element = if is_array {
+            let i = parse_index(&segment)
+                .ok_or_else(|| error(index, &segment, ErrorKind::InvalidIndex))?;
+            match elements.by_ref().nth(i) {
+                Some(element) => element.map_err(|e| invalid_bson(index, &segment, e))?,
+                None => {
+                    let length = container_length(container)
+                        .map_err(|e| invalid_bson(index, &segment, e))?;
+                    return Err(error(
+                        index,
+                        &segment,
+                        ErrorKind::IndexOutOfBounds { length },
+                    ));
+                }
+            }
+        } else {
+            let key = segment.as_bytes();
+            loop {
+                match elements.next() {
+                    Some(Ok(element)) if element.key_bytes() == key => break element,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(invalid_bson(index, &segment, e)),
+                    None => return Err(error(index, &segment, ErrorKind::NotPresent)),
+                }
+            }
+        };
+        found = Some((element, index, segment));
+    }
+
+    match found {
+        Some((element, index, segment)) => match element.value() {
+            Ok(value) => Ok((value, index, segment)),
+            Err(e) => Err(invalid_bson(index, &segment, e)),
+        },
+        None => Err(Error::new(
+            path.dotted(),
+            None,
+            ErrorKind::InvalidPath {
+                message: "path is empty".to_string(),
+            },
+        )),
+    }
+}
+
+/// The number of elements in a raw document or array.
+fn container_length(doc: &RawDocument) -> crate::raw::Result<usize> {
+    RawElements::new(doc).try_fold(0, |length, element| element.map(|_| length + 1))
+}
+
+pub(crate) fn get_raw<'a, P: AsKeyPath + ?Sized>(
+    doc: &'a RawDocument,
+    path: &P,
+) -> Result<RawBson<'a>> {
+    resolve_raw(doc, path).map(|(value, _, _)| value)
+}
+
+/// Resolve `path` in a raw document and convert the value found with `convert`, reporting a
+/// value that it rejects as being of an unexpected type.
+pub(crate) fn get_raw_typed<'a, P, T, F>(
+    doc: &'a RawDocument,
+    path: &P,
+    expected: ElementType,
+    convert: F,
+) -> Result<T>
+where
+    P: AsKeyPath + ?Sized,
+    F: FnOnce(RawBson<'a>) -> Option<T>,
+{
+    let (value, index, segment) = resolve_raw(doc, path)?;
+    convert(value).ok_or_else(|| {
+        Error::new(
+            path.dotted(),
+            Some((index, &segment)),
+            ErrorKind::UnexpectedType {
+                expected,
+                actual: value.tag(),
+            },
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ErrorKind, KeyPath};
+    use crate::{spec::ElementType, Bson};
+
+    #[test]
+    fn parse_and_display() {
+        let path = KeyPath::parse(r"a\\b.c\.d.0").unwrap();
+        assert_eq!(path.segments(), [r"a\b", "c.d", "0"]);
+        assert_eq!(path.to_string(), r"a\\b.c\.d.0");
+        assert_eq!(path.to_string().parse::<KeyPath>().unwrap(), path);
+
+        for invalid in &["", "a..b", "a.", ".a", r"a\", r"a\b"] {
+            let err = KeyPath::parse(invalid).unwrap_err();
+            assert!(
+                matches!(err.kind, ErrorKind::InvalidPath { .. }),
+                "{}: {:?}",
+                invalid,
+                err
+            );
+            assert_eq!(err.path(), *invalid);
+        }
+
+        let doc = doc! { "a": 1 };
+        assert!(matches!(
+            doc.get_path(KeyPath::default()).unwrap_err().kind,
+            ErrorKind::InvalidPath { .. }
+        ));
+        assert!(matches!(
+            doc.get_path(KeyPath::from_segments(vec!["a", ""]))
+                .unwrap_err()
+                .kind,
+            ErrorKind::InvalidPath { .. }
+        ));
+    }
+
+    #[test]
+    fn get_path() {
+        let mut doc = doc! {
+            "a": { "b": [{ "c": 1 }, [true, false]], "d.e": "x" },
+            "n": 5_i64,
+        };
+
+        assert_eq!(doc.get_path("a.b.0.c").unwrap(), &Bson::Int32(1));
+        assert_eq!(doc.get_path("a.b.1.1").unwrap(), &Bson::Boolean(false));
+        assert_eq!(doc.get_path_str(r"a.d\.e").unwrap(), "x");
+        assert_eq!(doc.get_path_i64("n").unwrap(), 5);
+        assert_eq!(doc.get_path_array("a.b").unwrap().len(), 2);
+        assert!(doc.contains_path("a.b.1"));
+        assert!(!doc.contains_path("a.b.2"));
+        assert!(!doc.contains_path("a..b"));
+
+        let err = doc.get_path("a.x.c").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotPresent);
+        assert_eq!(err.segment_index(), Some(1));
+        assert_eq!(err.segment(), Some("x"));
+        assert_eq!(
+            doc.get_path("a.b.c").unwrap_err().kind,
+            ErrorKind::InvalidIndex
+        );
+        assert_eq!(
+            doc.get_path("a.b.2").unwrap_err().kind,
+            ErrorKind::IndexOutOfBounds { length: 2 }
+        );
+        assert_eq!(
+            doc.get_path("n.x").unwrap_err().kind,
+            ErrorKind::NotTraversable {
+                actual: ElementType::Int64 as u8
+            }
+        );
+        let err = doc.get_path_i32("n").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ErrorKind::UnexpectedType {
+                expected: ElementType::Int32,
+                actual: ElementType::Int64 as u8
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"error at segment 0 ("n") of path "n": unexpected element type: Int64, expected: Int32"#
+        );
+
+        *doc.get_path_mut("a.b.1.0").unwrap() = Bson::Null;
+        assert_eq!(doc.get_path("a.b.1.0").unwrap(), &Bson::Null);
+    }
+
+    #[test]
+    fn insert_and_remove_path() {
+        let mut doc = doc! { "a": { "b": [1, 2] }, "s": "x" };
+
+        assert_eq!(doc.insert_path("a.c", 3).unwrap(), None);
+        assert_eq!(doc.insert_path("a.b.0", 10).unwrap(), Some(Bson::Int32(1)));
+        assert_eq!(doc.insert_path("a.b.4", 5).unwrap(), None);
+        assert_eq!(doc.insert_path("x.y.1.z", true).unwrap(), None);
+        assert_eq!(doc.insert_path("t", 1).unwrap(), None);
+        assert_eq!(
+            doc,
+            doc! {
+                "a": { "b": [10, 2, Bson::Null, Bson::Null, 5], "c": 3 },
+                "s": "x",
+                "x": { "y": [Bson::Null, { "z": true }] },
+                "t": 1,
+            }
+        );
+
+        let err = doc.insert_path("s.t", 1).unwrap_err();
+        assert_eq!(err.segment_index(), Some(1));
+        assert_eq!(
+            doc.insert_path("a.b.z", 1).unwrap_err().kind,
+            ErrorKind::InvalidIndex
+        );
+        let err = doc.insert_path("q.r.4000000000", 1).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::IndexTooLarge { length: 0 });
+        assert_eq!(err.segment_index(), Some(2));
+        assert!(!doc.contains_key("q"));
+        assert_eq!(
+            doc.insert_path("a.b.1500006", 1).unwrap_err().kind,
+            ErrorKind::IndexTooLarge { length: 5 }
+        );
+
+        assert_eq!(doc.remove_path("a.b.1").unwrap(), Some(Bson::Int32(2)));
+        assert_eq!(doc.remove_path("x.y").unwrap().map(|v| v.tag()), Some(0x04));
+        assert_eq!(doc.remove_path("x.y").unwrap(), None);
+        assert_eq!(doc.remove_path("q.r.s").unwrap(), None);
+        assert_eq!(doc.remove_path("t").unwrap(), Some(Bson::Int32(1)));
+        assert!(doc.remove_path("s.t").is_err());
+        assert_eq!(
+            doc,
+            doc! {
+                "a": { "b": [10, Bson::Null, Bson::Null, 5], "c": 3 },
+                "s": "x",
+                "x": {},
+            }
+        );
+    }
+}
@@ -0,0 +1,844 @@
+//! Projections that select the fields of documents.
+//!
+//! A [`Projection`] is compiled from a projection document such as
+//! `{ "a": 1, "b.c": 1, "_id": 0 }` and can then be applied to any number of [`Document`]s or
+//! [`RawDocument`]s, producing a new document. Each field of the projection document names a
+//! dotted path, or holds an embedded projection document for the paths below it, and gives one
+//! of the following:
+//!
+//! | Value                          | Effect                                                     |
+//! |--------------------------------|------------------------------------------------------------|
+//! | `1` or `true`                  | Include the field                                          |
+//! | `0` or `false`                 | Exclude the field                                          |
+//! | `{ "$slice": n }`              | Include the first `n` elements of an array, or the last    |
+//! | `{ "$slice": [skip, n] }`      | Include `n` elements of an array after skipping some       |
+//! | `{ "$elemMatch": filter }`     | Include the first element of an array that matches         |
+//! | `1` for a path ending in `.$`  | Include the first element of an array matched by the filter|
+//!
+//! The semantics follow those of the server:
+//!
+//! * A projection either includes the listed fields and drops the rest, or excludes the listed
+//!   fields and keeps the rest. Mixing the two is an error, except that `_id`, which is included
+//!   by default, may be excluded from an inclusion projection. `$slice` is allowed in either,
+//!   while `$elemMatch` and positional projections are inclusions.
+//! * Paths that pass through an array apply to each embedded document in it. Inclusions drop the
+//!   other elements of such arrays, while exclusions keep them.
+//! * Fields are returned in the order of the document, except that fields projected with
+//!   `$elemMatch` are returned after the others.
+//! * `$elemMatch` can only be applied to top-level fields, and a projection can only contain one
+//!   positional path. The positional `$` uses the conditions the filter given to
+//!   [`Projection::compile`] places on the array, and it is an error if no element meets them.
+//!
+//! Applying a projection to a [`RawDocument`] copies the bytes of the selected elements into a
+//! new [`RawDocumentBuf`] without decoding them.
+//!
+//! ```rust
+//! use bson::{doc, filter::Filter, projection::Projection, RawDocumentBuf};
+//!
+//! let doc = doc! {
+//!     "_id": 1,
+//!     "name": "ann",
+//!     "address": { "city": "Oslo", "zip": "0150" },
+//!     "scores": [70, 92, 85],
+//! };
+//!
+//! let projection = Projection::compile(
+//!     &doc! { "address.city": 1, "scores": { "$slice": -2 }, "_id": 0 },
+//!     None,
+//! )?;
+//! let expected = doc! { "address": { "city": "Oslo" }, "scores": [92, 85] };
+//! assert_eq!(projection.apply(&doc)?, expected);
+//!
+//! let raw = RawDocumentBuf::from_document(&doc)?;
+//! assert_eq!(projection.apply_raw(&raw)?, RawDocumentBuf::from_document(&expected)?);
+//!
+//! let filter = Filter::compile(&doc! { "scores": { "$gt": 80 } })?;
+//! let positional = Projection::compile(&doc! { "scores.$": 1 }, Some(&filter))?;
+//! assert_eq!(positional.apply(&doc)?, doc! { "_id": 1, "scores": [92] });
+//!
+//! let err = Projection::compile(&doc! { "name": 1, "address": 0 }, None).unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "invalid projection at \"address\": cannot do exclusion on field address in inclusion \
+//!      projection"
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{convert::TryFrom, fmt, ops::Range};
+
+use crate::{
+    decimal128::RoundingMode,
+    filter::{ElementMatcher, Filter},
+    ordering::{self, Number},
+    raw::{self, RawDocument, RawDocumentBuf, RawElement, RawElements},
+    spec::ElementType,
+    Array,
+    Bson,
+    Document,
+};
+
+/// An error that occurs when compiling or applying a [`Projection`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// The type of error that was encountered.
+    pub kind: ErrorKind,
+
+    /// The path at which the error was encountered, if any.
+    path: Option<String>,
+}
+
+impl Error {
+    fn new(kind: ErrorKind, path: Option<&str>) -> Self {
+        Self {
+            kind,
+            path: path.map(String::from),
+        }
+    }
+
+    fn invalid(message: impl Into<String>, path: &str) -> Self {
+        Self::new(
+            ErrorKind::InvalidProjection {
+                message: message.into(),
+            },
+            Some(path),
+        )
+    }
+
+    /// The path at which the error was encountered, if any.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+}
+
+/// The different categories of errors that can occur when compiling or applying a
+/// [`Projection`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The projection document is malformed, e.g. it mixes inclusion and exclusion, gives an
+    /// operator an invalid argument or names colliding paths.
+    #[non_exhaustive]
+    InvalidProjection { message: String },
+
+    /// The filter does not select an element of the array projected with the positional `$`.
+    NoPositionalMatch,
+
+    /// The raw document being projected is malformed.
+    InvalidBson(raw::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ErrorKind::InvalidProjection { .. } => f.write_str("invalid projection")?,
+            _ => f.write_str("projection failed")?,
+        }
+        if let Some(path) = &self.path {
+            write!(f, " at {:?}", path)?;
+        }
+        f.write_str(": ")?;
+
+        match &self.kind {
+            ErrorKind::InvalidProjection { message } => f.write_str(message),
+            ErrorKind::NoPositionalMatch => {
+                f.write_str("the filter does not match an element of the array")
+            }
+            ErrorKind::InvalidBson(error) => write!(f, "invalid BSON: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<raw::Error> for Error {
+    fn from(error: raw::Error) -> Self {
+        Self::new(ErrorKind::InvalidBson(error), None)
+    }
+}
+
+/// Alias for `Result<T, projection::Error>`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A compiled projection document, which can be applied to documents with
+/// [`Projection::apply`] and [`Projection::apply_raw`]. See the
+/// [`projection`](crate::projection) module for the supported forms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    /// Whether the fields that are not listed are dropped rather than kept.
+    inclusion: bool,
+
+    fields: Fields,
+
+    /// The path of the array projected with the positional `$` and the filter selecting its
+    /// element, if any.
+    positional: Option<(Vec<String>, Filter)>,
+}
+
+type Fields = Vec<(String, Node)>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Include,
+    Exclude,
+    Slice { skip: Option<i64>, limit: i64 },
+    ElemMatch(ElementMatcher),
+    Positional,
+    Children(Fields),
+}
+
+impl Projection {
+    /// Compile a projection document. The `filter` is only used by positional projections, and
+    /// must be given if the projection contains one.
+    pub fn compile(projection: &Document, filter: Option<&Filter>) -> Result<Projection> {
+        let mut specs = Vec::new();
+        flatten(projection, "", &mut specs)?;
+
+        let mut positional = None;
+        for (path, node) in specs.iter_mut() {
+            let position = match path.iter().position(|segment| segment == "$") {
+                Some(position) => position,
+                None => continue,
+            };
+            let dotted = path.join(".");
+            if position + 1 != path.len() {
+                return Err(Error::invalid(
+                    "the positional operator must be the last segment of the path",
+                    &dotted,
+                ));
+            } else if positional.is_some() {
+                return Err(Error::invalid(
+                    "a projection may only contain one positional path",
+                    &dotted,
+                ));
+            } else if position == 0 {
+                return Err(Error::invalid(
+                    "the positional operator must follow the path of an array",
+                    &dotted,
+                ));
+            }
+            match (&node, filter) {
+                (Node::Include, Some(filter)) => {
+                    path.pop();
+                    positional = Some((path.clone(), filter.clone()));
+                    *node = Node::Positional;
+                }
+                (Node::Include, None) => {
+                    return Err(Error::invalid(
+                        "a positional projection requires a filter",
+                        &dotted,
+                    ))
+                }
+                (Node::Exclude, _) => {
+                    return Err(Error::invalid(
+                        "array elements cannot be excluded with the positional operator",
+                        &dotted,
+                    ))
+                }
+                _ => {
+                    return Err(Error::invalid(
+                        "the positional operator cannot be combined with other operators",
+                        &dotted,
+                    ))
+                }
+            }
+        }
+
+        let is_id = |path: &[String]| path.len() == 1 && path[0] == "_id";
+        let mut inclusion = None;
+        for (path, node) in &specs {
+            let includes = match node {
+                Node::Include | Node::ElemMatch(_) | Node::Positional => true,
+                Node::Exclude => false,
+                _ => continue,
+            };
+            if is_id(path) {
+                continue;
+            }
+            match inclusion {
+                None => inclusion = Some(includes),
+                Some(inclusion) if inclusion != includes => {
+                    let dotted = path.join(".");
+                    let message = if inclusion {
+                        format!(
+                            "cannot do exclusion on field {} in inclusion projection",
+                            dotted
+                        )
+                    } else {
+                        format!(
+                            "cannot do inclusion on field {} in exclusion projection",
+                            dotted
+                        )
+                    };
+                    return Err(Error::invalid(message, &dotted));
+                }
+                Some(_) => {}
+            }
+        }
+        let id = specs
+            .iter()
+            .find(|(path, _)| is_id(path))
+            .map(|(_, node)| node);
+        let inclusion = inclusion.unwrap_or(matches!(id, Some(Node::Include)));
+
+        let mut fields = Fields::new();
+        if inclusion && !specs.iter().any(|(path, _)| path[0] == "_id") {
+            fields.push(("_id".to_string(), Node::Include));
+        }
+        for (path, node) in specs {
+            match node {
+                Node::Exclude if inclusion => continue,
+                Node::Include if !inclusion => continue,
+                node => insert(&mut fields, &path, node, &path.join("."))?,
+            }
+        }
+
+        Ok(Projection {
+            inclusion,
+            fields,
+            positional,
+        })
+    }
+
+    /// Apply this projection to `doc`.
+    pub fn apply(&self, doc: &Document) -> Result<Document> {
+        let index = match &self.positional {
+            Some((path, filter)) => filter.matching_element(doc, path),
+            None => None,
+        };
+        self.project_document(doc, &self.fields, index)
+    }
+
+    /// Apply this projection to `doc`, copying the bytes of the selected elements into a new
+    /// document. An error is returned if a part of `doc` that the projection looks into is
+    /// malformed.
+    pub fn apply_raw(&self, doc: &RawDocument) -> Result<RawDocumentBuf> {
+        let index = match &self.positional {
+            Some((path, filter)) => filter.matching_element_raw(doc, path)?,
+            None => None,
+        };
+        let mut bytes = Vec::new();
+        self.project_raw_document(doc, &self.fields, index, &mut bytes)?;
+        Ok(RawDocumentBuf::new(bytes)?)
+    }
+
+    fn no_positional_match(&self) -> Error {
+        let path = self.positional.as_ref().map(|(path, _)| path.join("."));
+        Error::new(ErrorKind::NoPositionalMatch, path.as_deref())
+    }
+
+    fn project_document(
+        &self,
+        doc: &Document,
+        fields: &[(String, Node)],
+        index: Option<usize>,
+    ) -> Result<Document> {
+        let mut projected = Document::new();
+        let mut matched = Vec::new();
+        for (key, value) in doc {
+            let node = match find(fields, key.as_bytes()) {
+                Some(node) => node,
+                None if self.inclusion => continue,
+                None => {
+                    projected.insert(key.clone(), value.clone());
+                    continue;
+                }
+            };
+
+            let value = match (node, value) {
+                (Node::Include, value) => value.clone(),
+                (Node::Exclude, _) => continue,
+                (Node::Children(children), Bson::Document(doc)) => {
+                    Bson::Document(self.project_document(doc, children, index)?)
+                }
+                (Node::Children(children), Bson::Array(array)) => {
+                    Bson::Array(self.project_array(array, children, index)?)
+                }
+                (Node::Children(_), _) if self.inclusion => continue,
+                (Node::Slice { skip, limit }, Bson::Array(array)) => {
+                    Bson::Array(array[slice(array.len(), *skip, *limit)].to_vec())
+                }
+                (Node::ElemMatch(condition), Bson::Array(array)) => {
+                    if let Some(element) = array.iter().find(|e| condition.matches(e)) {
+                        matched.push((key.clone(), Bson::Array(vec![element.clone()])));
+                    }
+                    continue;
+                }
+                (Node::ElemMatch(_), _) => continue,
+                (Node::Positional, Bson::Array(array)) => {
+                    match index.and_then(|index| array.get(index)) {
+                        Some(element) => Bson::Array(vec![element.clone()]),
+                        None => return Err(self.no_positional_match()),
+                    }
+                }
+                (_, value) => value.clone(),
+            };
+            projected.insert(key.clone(), value);
+        }
+
+        for (key, value) in matched {
+            projected.insert(key, value);
+        }
+        Ok(projected)
+    }
+
+    fn project_array(
+        &self,
+        array: &Array,
+        fields: &[(String, Node)],
+        index: Option<usize>,
+    ) -> Result<Array> {
+        let mut projected = Array::new();
+        for element in array {
+            match element {
+                Bson::Document(doc) => {
+                    projected.push(Bson::Document(self.project_document(doc, fields, index)?))
+                }
+                Bson::Array(array) => {
+                    projected.push(Bson::Array(self.project_array(array, fields, index)?))
+                }
+                _ if self.inclusion => {}
+                element => projected.push(element.clone()),
+            }
+        }
+        Ok(projected)
+    }
+
+    fn project_raw_document(
+        &self,
+        doc: &RawDocument,
+        fields: &[(String, Node)],
+        index: Option<usize>,
+        out: &mut Vec<u8>,
+    ) -> Result<()> {
+        let start = begin_document(out);
+        let mut matched = Vec::new();
+        for element in RawElements::new(doc) {
+            let element = element?;
+            let key = element.key_bytes();
+            let node = match find(fields, key) {
+                Some(node) => node,
+                None if self.inclusion => continue,
+                None => {
+                    write_element(out, element.tag(), key, element.value_bytes());
+                    continue;
+                }
+            };
+
+            let is_array = element.tag() == ElementType::Array as u8;
+            match (node, element.container()?) {
+                (Node::Include, _) => write_element(out, element.tag(), key, element.value_bytes()),
+                (Node::Exclude, _) => {}
+                (Node::Children(children), Some(container)) => {
+                    write_key(out, element.tag(), key);
+                    if is_array {
+                        self.project_raw_array(container, children, index, out)?;
+                    } else {
+                        self.project_raw_document(container, children, index, out)?;
+                    }
+                }
+                (Node::Children(_), _) if self.inclusion => {}
+                (Node::Slice { skip, limit }, Some(array)) if is_array => {
+                    let elements = RawElements::new(array).collect::<raw::Result<Vec<_>>>()?;
+                    let range = slice(elements.len(), *skip, *limit);
+                    write_key(out, element.tag(), key);
+                    write_array(out, &elements[range]);
+                }
+                (Node::ElemMatch(condition), Some(array)) if is_array => {
+                    for candidate in RawElements::new(array) {
+                        let candidate = candidate?;
+                        if condition.matches_raw(candidate.value()?)? {
+                            matched.push((key, candidate));
+                            break;
+                        }
+                    }
+                }
+                (Node::ElemMatch(_), _) => {}
+                (Node::Positional, Some(array)) if is_array => {
+                    let selected = match index {
+                        Some(index) => RawElements::new(array).nth(index).transpose()?,
+                        None => None,
+                    };
+                    match selected {
+                        Some(selected) => {
+                            write_key(out, element.tag(), key);
+                            write_array(out, &[selected]);
+                        }
+                        None => return Err(self.no_positional_match()),
+                    }
+                }
+                _ => write_element(out, element.tag(), key, element.value_bytes()),
+            }
+        }
+
+        for (key, element) in matched {
+            write_key(out, ElementType::Array as u8, key);
+            write_array(out, &[element]);
+        }
+        end_document(out, start);
+        Ok(())
+    }
+
+    fn project_raw_array(
+        &self,
+        array: &RawDocument,
+        fields: &[(String, Node)],
+        index: Option<usize>,
+        out: &mut Vec<u8>,
+    ) -> Result<()> {
+        let start = begin_document(out);
+        let mut length = 0;
+        for element in RawElements::new(array) {
+            let element = element?;
+            let key = length.to_string();
+            match element.container()? {
+                Some(container) => {
+                    write_key(out, element.tag(), key.as_bytes());
+                    if element.tag() == ElementType::Array as u8 {
+                        self.project_raw_array(container, fields, index, out)?;
+                    } else {
+                        self.project_raw_document(container, fields, index, out)?;
+                    }
+                }
+                None if self.inclusion => continue,
+                None => write_element(out, element.tag(), key.as_bytes(), element.value_bytes()),
+            }
+            length += 1;
+        }
+        end_document(out, start);
+        Ok(())
+    }
+}
+
+/// Flatten a projection document into the paths it names, expanding embedded projection
+/// documents.
+fn flatten(
+    projection: &Document,
+    prefix: &str,
+    specs: &mut Vec<(Vec<String>, Node)>,
+) -> Result<()> {
+    for (key, value) in projection {
+        let dotted = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        let path: Vec<String> = dotted.split('.').map(String::from).collect();
+        if path.iter().any(String::is_empty) {
+            return Err(Error::invalid(
+                "paths must not contain empty segments",
+                &dotted,
+            ));
+        } else if path
+            .iter()
+            .any(|segment| segment.starts_with('$') && segment != "$")
+        {
+            return Err(Error::invalid(
+                "field names must not start with '$'",
+                &dotted,
+            ));
+        }
+
+        let node = match value {
+            Bson::Document(operators) if matches!(operators.keys().next(), Some(key) if key.starts_with('$')) =>
+            {
+                if operators.len() > 1 {
+                    return Err(Error::invalid(
+                        "a field may only be given one projection operator",
+                        &dotted,
+                    ));
+                }
+                let (operator, argument) = operators.iter().next().unwrap();
+                compile_operator(&dotted, operator, argument)?
+            }
+            Bson::Document(embedded) if embedded.is_empty() => {
+                return Err(Error::invalid(
+                    "an empty sub-projection is not a valid value",
+                    &dotted,
+                ));
+            }
+            Bson::Document(embedded) => {
+                flatten(embedded, &dotted, specs)?;
+                continue;
+            }
+            value => {
+                let include = match (value, ordering::number(value)) {
+                    (Bson::Boolean(b), _) => *b,
+                    (_, Some(Number::Integer(i))) => i != 0,
+                    (_, Some(Number::Double(d))) => d != 0.0,
+                    (_, Some(Number::Decimal(d))) => !d.is_zero(),
+                    (_, None) => {
+                        return Err(Error::invalid(
+                            "expected a number, a boolean, a projection document or an operator",
+                            &dotted,
+                        ))
+                    }
+                };
+                if include {
+                    Node::Include
+                } else {
+                    Node::Exclude
+                }
+            }
+        };
+        specs.push((path, node));
+    }
+    Ok(())
+}
+
+fn compile_operator(path: &str, operator: &str, argument: &Bson) -> Result<Node> {
+    match operator {
+        "$slice" => {
+            let invalid =
+                || Error::invalid("$slice expects a number or an array of two numbers", path);
+            let (skip, limit) = match argument {
+                Bson::Array(bounds) if bounds.len() == 2 => (
+                    Some(integer(&bounds[0]).ok_or_else(invalid)?),
+                    integer(&bounds[1]).ok_or_else(invalid)?,
+                ),
+                argument => (None, integer(argument).ok_or_else(invalid)?),
+            };
+            if skip.is_some() && limit <= 0 {
+                return Err(Error::invalid("the $slice limit must be positive", path));
+            }
+            Ok(Node::Slice { skip, limit })
+        }
+        "$elemMatch" => {
+            if path.contains('.') {
+                return Err(Error::invalid(
+                    "cannot use $elemMatch projection on a nested field",
+                    path,
+                ));
+            }
+            match argument {
+                Bson::Document(condition) => ElementMatcher::for_elem_match(path, condition)
+                    .map(Node::ElemMatch)
+                    .map_err(|e| Error::invalid(e.to_string(), path)),
+                _ => Err(Error::invalid("$elemMatch expects a document", path)),
+            }
+        }
+        _ => Err(Error::invalid(
+            format!("unknown projection operator {}", operator),
+            path,
+        )),
+    }
+}
+
+/// The number `value` holds, truncated towards zero and saturated to the range of `i64`.
+fn integer(value: &Bson) -> Option<i64> {
+    match ordering::number(value)? {
+        Number::Integer(i) => {
+            Some(i64::try_from(i).unwrap_or(if i < 0 { i64::MIN } else { i64::MAX }))
+        }
+        Number::Double(d) if d.is_nan() => None,
+        Number::Double(d) => Some(d as i64),
+        Number::Decimal(d) => d.to_i64_rounded(RoundingMode::Down).ok(),
+    }
+}
+
+fn insert(fields: &mut Fields, path: &[String], node: Node, dotted: &str) -> Result<()> {
+    let (first, rest) = path.split_first().expect("paths are not empty");
+    match fields.iter_mut().find(|(key, _)| key == first) {
+        None if rest.is_empty() => fields.push((first.clone(), node)),
+        None => {
+            let mut children = Fields::new();
+            insert(&mut children, rest, node, dotted)?;
+            fields.push((first.clone(), Node::Children(children)));
+        }
+        Some((_, Node::Children(children))) if !rest.is_empty() => {
+            insert(children, rest, node, dotted)?
+        }
+        Some(_) => {
+            return Err(Error::invalid(
+                format!("path collision at {}", dotted),
+                dotted,
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn find<'a>(fields: &'a [(String, Node)], key: &[u8]) -> Option<&'a Node> {
+    fields
+        .iter()
+        .find(|(field, _)| field.as_bytes() == key)
+        .map(|(_, node)| node)
+}
+
+/// The range of the elements of an array of length `length` selected by `$slice`.
+fn slice(length: usize, skip: Option<i64>, limit: i64) -> Range<usize> {
+    let length = length as i128;
+    let clamp = |n: i128| n.max(0).min(length) as usize;
+    let (skip, limit) = (skip.map(i128::from), i128::from(limit));
+    match skip {
+        None if limit >= 0 => 0..clamp(limit),
+        None => clamp(length + limit)..length as usize,
+        Some(skip) => {
+            let start = if skip >= 0 { skip } else { length + skip };
+            clamp(start)..clamp(clamp(start) as i128 + limit)
+        }
+    }
+}
+
+/// Start a document or array, returning the offset of its length, which is filled in by
+/// [`end_document`].
+fn begin_document(out: &mut Vec<u8>) -> usize {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    start
+}
+
+fn end_document(out: &mut Vec<u8>, start: usize) {
+    out.push(0);
+    let length = (out.len() - start) as i32;
+    out[start..start + 4].copy_from_slice(&length.to_le_bytes());
+}
+
+fn write_key(out: &mut Vec<u8>, tag: u8, key: &[u8]) {
+    out.push(tag);
+    out.extend_from_slice(key);
+    out.push(0);
+}
+
+fn write_element(out: &mut Vec<u8>, tag: u8, key: &[u8], value: &[u8]) {
+    write_key(out, tag, key);
+    out.extend_from_slice(value);
+}
+
+/// Write the given elements as an array, renumbering their keys.
+fn write_array(out: &mut Vec<u8>, elements: &[RawElement]) {
+    let start = begin_document(out);
+    for (index, element) in elements.iter().enumerate() {
+        write_element(
+            out,
+            element.tag(),
+            index.to_string().as_bytes(),
+            element.value_bytes(),
+        );
+    }
+    end_document(out, start);
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{ErrorKind, Projection};
+    use crate::{filter::Filter, Document, RawDocumentBuf};
+
+    fn project(projection: Document, filter: Option<Document>, doc: &Document) -> Document {
+        let filter = filter.map(|filter| Filter::compile(&filter).unwrap());
+        let projection = Projection::compile(&projection, filter.as_ref()).unwrap();
+        let projected = projection.apply(doc).unwrap();
+        let raw = projection
+            .apply_raw(&RawDocumentBuf::from_document(doc).unwrap())
+            .unwrap();
+        assert_eq!(Document::try_from(raw).unwrap(), projected);
+        projected
+    }
+
+    #[test]
+    fn inclusion_and_exclusion() {
+        let doc = doc! {
+            "_id": 1,
+            "a": { "b": 1, "c": 2 },
+            "d": [{ "b": 3, "c": 4 }, 5, [{ "b": 6 }], { "c": 7 }],
+            "e": "x",
+        };
+
+        assert_eq!(
+            project(doc! { "e": 1, "a.b": true, "d": { "b": 1 } }, None, &doc),
+            doc! {
+                "_id": 1,
+                "a": { "b": 1 },
+                "d": [{ "b": 3 }, [{ "b": 6 }], {}],
+                "e": "x",
+            }
+        );
+        assert_eq!(
+            project(doc! { "a.b": 0, "d.b": 0, "_id": 0 }, None, &doc),
+            doc! {
+                "a": { "c": 2 },
+                "d": [{ "c": 4 }, 5, [{}], { "c": 7 }],
+                "e": "x",
+            }
+        );
+        assert_eq!(project(doc! { "_id": 1 }, None, &doc), doc! { "_id": 1 });
+        assert_eq!(project(doc! { "_id": 0, "z": 1 }, None, &doc), doc! {});
+        assert_eq!(project(doc! {}, None, &doc), doc);
+    }
+
+    #[test]
+    fn operators() {
+        let doc = doc! {
+            "_id": 1,
+            "n": [1, 2, 3, 4, 5],
+            "items": [{ "k": "a", "v": 1 }, { "k": "b", "v": 2 }, { "k": "b", "v": 3 }],
+            "x": 1,
+        };
+
+        assert_eq!(
+            project(doc! { "n": { "$slice": [-3, 2] } }, None, &doc),
+            doc! { "_id": 1, "n": [3, 4], "items": doc.get("items").unwrap().clone(), "x": 1 }
+        );
+        assert_eq!(
+            project(
+                doc! { "items": { "$elemMatch": { "k": "b" } }, "n": { "$slice": -1 }, "x": 1 },
+                None,
+                &doc
+            ),
+            doc! { "_id": 1, "n": [5], "x": 1, "items": [{ "k": "b", "v": 2 }] }
+        );
+        assert_eq!(
+            project(
+                doc! { "items.$": 1, "_id": 0 },
+                Some(doc! { "x": 1, "items.v": { "$gt": 2 } }),
+                &doc
+            ),
+            doc! { "items": [{ "k": "b", "v": 3 }] }
+        );
+        assert_eq!(
+            project(doc! { "n.$": 1 }, Some(doc! { "n": { "$gte": 2 } }), &doc),
+            doc! { "_id": 1, "n": [2] }
+        );
+
+        let filter = Filter::compile(&doc! { "n": 9 }).unwrap();
+        let positional = Projection::compile(&doc! { "n.$": 1 }, Some(&filter)).unwrap();
+        assert_eq!(
+            positional.apply(&doc).unwrap_err().kind,
+            ErrorKind::NoPositionalMatch
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |projection: Document| {
+            Projection::compile(&projection, None)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error(doc! { "a": 0, "b": 1 }),
+            "invalid projection at \"b\": cannot do inclusion on field b in exclusion projection"
+        );
+        assert_eq!(
+            error(doc! { "a": 1, "a.b": 1 }),
+            "invalid projection at \"a.b\": path collision at a.b"
+        );
+        assert_eq!(
+            error(doc! { "a.$": 1 }),
+            "invalid projection at \"a.$\": a positional projection requires a filter"
+        );
+        assert_eq!(
+            error(doc! { "a.b": { "$elemMatch": { "c": 1 } } }),
+            "invalid projection at \"a.b\": cannot use $elemMatch projection on a nested field"
+        );
+        assert!(Projection::compile(&doc! { "a": { "$slice": [1, 0] } }, None).is_err());
+        assert!(Projection::compile(&doc! { "a": "b" }, None).is_err());
+        assert!(Projection::compile(&doc! { "a": {} }, None).is_err());
+        assert!(
+            Projection::compile(&doc! { "_id": 0, "a": 0, "b": { "$slice": 1 } }, None).is_ok()
+        );
+    }
+}
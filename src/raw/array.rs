@@ -9,6 +9,7 @@ use super::{
     RawBinary,
     RawBson,
     RawDocument,
+    RawRegex,
     Result,
 };
 use crate::{
@@ -17,6 +18,7 @@ use crate::{
     spec::{BinarySubtype, ElementType},
     Bson,
     DateTime,
+    DeserializerOptions,
     Timestamp,
 };
 
@@ -89,6 +91,17 @@ impl RawArray {
         unsafe { &*(doc as *const RawDocument as *const RawArray) }
     }
 
+    /// Iterate over the values, skipping over elements of unknown type as described by
+    /// `options`.
+    pub(crate) fn iter_with_options<'a>(
+        &'a self,
+        options: &'a DeserializerOptions,
+    ) -> RawArrayIter<'a> {
+        RawArrayIter {
+            inner: self.doc.iter_with_options(options),
+        }
+    }
+
     /// Gets a reference to the value at the given index.
     pub fn get(&self, index: usize) -> Result<Option<RawBson<'_>>> {
         self.into_iter().nth(index).transpose()
@@ -116,7 +129,9 @@ impl RawArray {
                 key: index.to_string(),
                 kind: ValueAccessErrorKind::UnexpectedType {
                     expected: expected_type,
-                    actual: bson.element_type(),
+                    actual: bson
+                        .element_type()
+                        .expect("elements are read without unknown element passthrough"),
                 },
             }),
         }
@@ -153,6 +168,12 @@ impl RawArray {
     }
 
 
+    /// Gets the ObjectId at the given index or returns an error if the value at that index isn't an
+    /// ObjectId.
+    pub fn get_object_id(&self, index: usize) -> ValueAccessResult<ObjectId> {
+        self.get_with(index, ElementType::ObjectId, RawBson::as_object_id)
+    }
+
     /// Gets the boolean at the given index or returns an error if the value at that index isn't a
     /// boolean.
     pub fn get_bool(&self, index: usize) -> ValueAccessResult<bool> {
@@ -165,6 +186,11 @@ impl RawArray {
         self.get_with(index, ElementType::DateTime, RawBson::as_datetime)
     }
 
+    /// Gets a reference to the BSON regex at the given index or returns an error if the
+    /// value at that index isn't a regex.
+    pub fn get_regex(&self, index: usize) -> ValueAccessResult<RawRegex<'_>> {
+        self.get_with(index, ElementType::RegularExpression, RawBson::as_regex)
+    }
 
     /// Gets a reference to the BSON timestamp at the given index or returns an error if the
     /// value at that index isn't a timestamp.
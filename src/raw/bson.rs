@@ -9,10 +9,14 @@ use crate::{
     extjson,
     oid::{self, ObjectId},
     raw::{RAW_ARRAY_NEWTYPE, RAW_BSON_NEWTYPE, RAW_DOCUMENT_NEWTYPE},
+    ser::serialize_unknown,
     spec::{BinarySubtype, ElementType},
     Bson,
     DateTime,
+    DbPointer,
     Decimal128,
+    JavaScriptCodeWithScope,
+    Regex,
     Timestamp,
 };
 
@@ -31,6 +35,12 @@ pub enum RawBson<'a> {
     Boolean(bool),
     /// Null value
     Null,
+    /// Regular expression
+    RegularExpression(RawRegex<'a>),
+    /// JavaScript code
+    JavaScriptCode(&'a str),
+    /// JavaScript code w/ scope
+    JavaScriptCodeWithScope(RawJavaScriptCodeWithScope<'a>),
     /// 32-bit signed integer
     Int32(i32),
     /// 64-bit signed integer
@@ -43,30 +53,71 @@ pub enum RawBson<'a> {
     Timestamp(Timestamp),
     /// Binary data
     Binary(RawBinary<'a>),
+    /// [ObjectId](http://dochub.mongodb.org/core/objectids)
+    ObjectId(oid::ObjectId),
     /// UTC datetime
     DateTime(crate::DateTime),
+    /// Symbol (Deprecated)
+    Symbol(&'a str),
     /// [128-bit decimal floating point](https://github.com/mongodb/specifications/blob/master/source/bson-decimal128/decimal128.rst)
     Decimal128(Decimal128),
+    /// Undefined value (Deprecated)
+    Undefined,
+    /// Max key
+    MaxKey,
+    /// Min key
+    MinKey,
+    /// DBPointer (Deprecated)
+    DbPointer(RawDbPointer<'a>),
+    /// An element whose type tag is not recognized by this crate. These are only produced when
+    /// iterating with [`DeserializerOptions::unknown_elements`](crate::DeserializerOptions)
+    /// enabled.
+    Unknown {
+        /// The element type tag.
+        tag: u8,
+        /// The raw bytes of the value, not including the tag or key.
+        bytes: &'a [u8],
+    },
 }
 
 impl<'a> RawBson<'a> {
-    /// Get the [`ElementType`] of this value.
-    pub fn element_type(&self) -> ElementType {
-        match *self {
+    /// Get the [`ElementType`] of this value, or `None` if this is a [`RawBson::Unknown`], which
+    /// by definition has no `ElementType`. Use [`RawBson::tag`] to get the type byte of any value.
+    pub fn element_type(&self) -> Option<ElementType> {
+        let element_type = match *self {
             RawBson::Double(..) => ElementType::Double,
             RawBson::String(..) => ElementType::String,
             RawBson::Array(..) => ElementType::Array,
             RawBson::Document(..) => ElementType::EmbeddedDocument,
             RawBson::Boolean(..) => ElementType::Boolean,
             RawBson::Null => ElementType::Null,
+            RawBson::RegularExpression(..) => ElementType::RegularExpression,
+            RawBson::JavaScriptCode(..) => ElementType::JavaScriptCode,
+            RawBson::JavaScriptCodeWithScope(..) => ElementType::JavaScriptCodeWithScope,
             RawBson::Int32(..) => ElementType::Int32,
             RawBson::Int64(..) => ElementType::Int64,
             RawBson::UInt32(..) => ElementType::UInt32,
             RawBson::UInt64(..) => ElementType::UInt64,
             RawBson::Timestamp(..) => ElementType::Timestamp,
             RawBson::Binary(..) => ElementType::Binary,
+            RawBson::ObjectId(..) => ElementType::ObjectId,
             RawBson::DateTime(..) => ElementType::DateTime,
+            RawBson::Symbol(..) => ElementType::Symbol,
             RawBson::Decimal128(..) => ElementType::Decimal128,
+            RawBson::Undefined => ElementType::Undefined,
+            RawBson::MaxKey => ElementType::MaxKey,
+            RawBson::MinKey => ElementType::MinKey,
+            RawBson::DbPointer(..) => ElementType::DbPointer,
+            RawBson::Unknown { .. } => return None,
+        };
+        Some(element_type)
+    }
+
+    /// Get the type tag this value is stored with.
+    pub fn tag(&self) -> u8 {
+        match *self {
+            RawBson::Unknown { tag, .. } => tag,
+            _ => self.element_type().expect("only unknown elements have no element type") as u8,
         }
     }
 
@@ -143,6 +194,23 @@ impl<'a> RawBson<'a> {
         }
     }
 
+    /// Gets the [`RawRegex`] that's referenced or returns `None` if the referenced value isn't a
+    /// BSON regular expression.
+    pub fn as_regex(self) -> Option<RawRegex<'a>> {
+        match self {
+            RawBson::RegularExpression(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Gets the [`ObjectId`] that's referenced or returns `None` if the referenced value isn't a
+    /// BSON ObjectID.
+    pub fn as_object_id(self) -> Option<oid::ObjectId> {
+        match self {
+            RawBson::ObjectId(v) => Some(v),
+            _ => None,
+        }
+    }
 
     /// Gets the [`crate::DateTime`] that's referenced or returns `None` if the referenced value
     /// isn't a BSON datetime.
@@ -172,6 +240,50 @@ impl<'a> RawBson<'a> {
         }
     }
 
+    /// Gets the JavaScript code that's referenced or returns `None` if the referenced value isn't a
+    /// BSON JavaScript code.
+    pub fn as_javascript(self) -> Option<&'a str> {
+        match self {
+            RawBson::JavaScriptCode(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Gets the [`RawJavaScriptCodeWithScope`] that's referenced or returns `None` if the
+    /// referenced value isn't a BSON JavaScript code with scope.
+    pub fn as_javascript_with_scope(self) -> Option<RawJavaScriptCodeWithScope<'a>> {
+        match self {
+            RawBson::JavaScriptCodeWithScope(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Gets the symbol that's referenced or returns `None` if the referenced value isn't a BSON
+    /// symbol.
+    pub fn as_symbol(self) -> Option<&'a str> {
+        match self {
+            RawBson::Symbol(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Gets the [`RawDbPointer`] that's referenced or returns `None` if the referenced value isn't
+    /// a BSON DBPointer.
+    pub fn as_db_pointer(self) -> Option<RawDbPointer<'a>> {
+        match self {
+            RawBson::DbPointer(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Gets the tag and value bytes of an element of unknown type, or returns `None` if the
+    /// referenced value is of a known type.
+    pub fn as_unknown(self) -> Option<(u8, &'a [u8])> {
+        match self {
+            RawBson::Unknown { tag, bytes } => Some((tag, bytes)),
+            _ => None,
+        }
+    }
 }
 
 /// A visitor used to deserialize types backed by raw BSON.
@@ -300,6 +412,10 @@ impl<'de> Visitor<'de> for RawBsonVisitor {
             .next_key::<&str>()?
             .ok_or_else(|| serde::de::Error::custom("expected a key when deserializing RawBson"))?;
         match k {
+            "$oid" => {
+                let oid: ObjectId = map.next_value()?;
+                Ok(RawBson::ObjectId(oid))
+            }
             "$numberDecimalBytes" => {
                 let bytes = map.next_value::<ByteBuf>()?;
                 return Ok(RawBson::Decimal128(Decimal128::deserialize_from_slice(
@@ -322,6 +438,90 @@ impl<'de> Visitor<'de> for RawBsonVisitor {
                     subtype: v.subtype.into(),
                 }))
             }
+            "$regularExpression" => {
+                let body = map.next_value::<BorrowedRegexBody>()?;
+                Ok(RawBson::RegularExpression(RawRegex {
+                    pattern: body.pattern,
+                    options: body.options,
+                }))
+            }
+            "$code" => {
+                let code = map.next_value::<&str>()?;
+                match map.next_key::<&str>()? {
+                    Some("$scope") => {
+                        let scope = map.next_value::<&RawDocument>()?;
+                        Ok(RawBson::JavaScriptCodeWithScope(
+                            RawJavaScriptCodeWithScope { code, scope },
+                        ))
+                    }
+                    Some(k) => Err(serde::de::Error::unknown_field(k, &["$scope"])),
+                    None => Ok(RawBson::JavaScriptCode(code)),
+                }
+            }
+            "$symbol" => {
+                let v = map.next_value::<&str>()?;
+                Ok(RawBson::Symbol(v))
+            }
+            "$dbPointer" => {
+                #[derive(Deserialize)]
+                struct BorrowedDbPointerBody<'a> {
+                    #[serde(rename = "$ref")]
+                    ns: &'a str,
+
+                    #[serde(rename = "$id")]
+                    id: ObjectId,
+                }
+
+                let body: BorrowedDbPointerBody = map.next_value()?;
+                Ok(RawBson::DbPointer(RawDbPointer {
+                    namespace: body.ns,
+                    id: body.id,
+                }))
+            }
+            "$minKey" => {
+                let v = map.next_value::<i32>()?;
+                if v != 1 {
+                    return Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Signed(v as i64),
+                        &"value of $minKey should always be 1",
+                    ));
+                }
+                Ok(RawBson::MinKey)
+            }
+            "$maxKey" => {
+                let v = map.next_value::<i32>()?;
+                if v != 1 {
+                    return Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Signed(v as i64),
+                        &"value of $maxKey should always be 1",
+                    ));
+                }
+                Ok(RawBson::MaxKey)
+            }
+            "$undefined" => {
+                let v = map.next_value::<bool>()?;
+                if !v {
+                    return Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Bool(v),
+                        &"$undefined should always be true",
+                    ));
+                }
+                Ok(RawBson::Undefined)
+            }
+            "$unknown" => {
+                #[derive(Deserialize)]
+                struct BorrowedUnknownBody<'a> {
+                    tag: u8,
+
+                    bytes: &'a [u8],
+                }
+
+                let body: BorrowedUnknownBody = map.next_value()?;
+                Ok(RawBson::Unknown {
+                    tag: body.tag,
+                    bytes: body.bytes,
+                })
+            }
             "$date" => {
                 let v = map.next_value::<i64>()?;
                 Ok(RawBson::DateTime(DateTime::from_millis(v)))
@@ -372,14 +572,44 @@ impl<'a> Serialize for RawBson<'a> {
             RawBson::Document(v) => v.serialize(serializer),
             RawBson::Boolean(v) => serializer.serialize_bool(*v),
             RawBson::Null => serializer.serialize_unit(),
+            RawBson::RegularExpression(re) => re.serialize(serializer),
+            RawBson::JavaScriptCode(code) => {
+                let mut state = serializer.serialize_struct("$code", 1)?;
+                state.serialize_field("$code", code)?;
+                state.end()
+            }
+            RawBson::JavaScriptCodeWithScope(code_w_scope) => code_w_scope.serialize(serializer),
             RawBson::Int32(v) => serializer.serialize_i32(*v),
             RawBson::Int64(v) => serializer.serialize_i64(*v),
             RawBson::UInt32(v) => serializer.serialize_u32(*v),
             RawBson::UInt64(v) => serializer.serialize_u64(*v),
             RawBson::DateTime(dt) => dt.serialize(serializer),
             RawBson::Binary(b) => b.serialize(serializer),
+            RawBson::ObjectId(oid) => oid.serialize(serializer),
             RawBson::Timestamp(t) => t.serialize(serializer),
             RawBson::Decimal128(d) => d.serialize(serializer),
+            RawBson::Symbol(s) => {
+                let mut state = serializer.serialize_struct("$symbol", 1)?;
+                state.serialize_field("$symbol", s)?;
+                state.end()
+            }
+            RawBson::Undefined => {
+                let mut state = serializer.serialize_struct("$undefined", 1)?;
+                state.serialize_field("$undefined", &true)?;
+                state.end()
+            }
+            RawBson::MaxKey => {
+                let mut state = serializer.serialize_struct("$maxKey", 1)?;
+                state.serialize_field("$maxKey", &1)?;
+                state.end()
+            }
+            RawBson::MinKey => {
+                let mut state = serializer.serialize_struct("$minKey", 1)?;
+                state.serialize_field("$minKey", &1)?;
+                state.end()
+            }
+            RawBson::DbPointer(dbp) => dbp.serialize(serializer),
+            RawBson::Unknown { tag, bytes } => serialize_unknown(serializer, *tag, bytes),
         }
     }
 }
@@ -413,15 +643,38 @@ impl<'a> TryFrom<RawBson<'a>> for Bson {
                     bytes: data.to_vec(),
                 })
             }
+            RawBson::ObjectId(rawbson) => Bson::ObjectId(rawbson),
             RawBson::Boolean(rawbson) => Bson::Boolean(rawbson),
             RawBson::DateTime(rawbson) => Bson::DateTime(rawbson),
             RawBson::Null => Bson::Null,
+            RawBson::RegularExpression(rawregex) => {
+                Bson::RegularExpression(Regex::new(rawregex.pattern, rawregex.options))
+            }
+            RawBson::JavaScriptCode(rawbson) => Bson::JavaScriptCode(rawbson.to_string()),
+            RawBson::JavaScriptCodeWithScope(rawbson) => {
+                Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope {
+                    code: rawbson.code.to_string(),
+                    scope: rawbson.scope.try_into()?,
+                })
+            }
             RawBson::Int32(rawbson) => Bson::Int32(rawbson),
             RawBson::Timestamp(rawbson) => Bson::Timestamp(rawbson),
             RawBson::Int64(rawbson) => Bson::Int64(rawbson),
             RawBson::UInt32(rawbson) => Bson::UInt32(rawbson),
             RawBson::UInt64(rawbson) => Bson::UInt64(rawbson),
             RawBson::Decimal128(rawbson) => Bson::Decimal128(rawbson),
+            RawBson::Symbol(rawbson) => Bson::Symbol(rawbson.to_string()),
+            RawBson::Undefined => Bson::Undefined,
+            RawBson::MaxKey => Bson::MaxKey,
+            RawBson::MinKey => Bson::MinKey,
+            RawBson::DbPointer(rawbson) => Bson::DbPointer(DbPointer {
+                namespace: rawbson.namespace.to_string(),
+                id: rawbson.id,
+            }),
+            RawBson::Unknown { tag, bytes } => Bson::Unknown {
+                tag,
+                bytes: bytes.to_vec(),
+            },
         })
     }
 }
@@ -485,3 +738,155 @@ impl<'a> Serialize for RawBinary<'a> {
         }
     }
 }
+
+/// A BSON regex referencing raw bytes stored elsewhere.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RawRegex<'a> {
+    /// The regex pattern to match.
+    pub pattern: &'a str,
+
+    /// The options for the regex, which the BSON spec requires to be stored in alphabetical
+    /// order.
+    pub options: &'a str,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct BorrowedRegexBody<'a> {
+    pattern: &'a str,
+    options: &'a str,
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for RawRegex<'a> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawBson::deserialize(deserializer)? {
+            RawBson::RegularExpression(b) => Ok(b),
+            c => Err(serde::de::Error::custom(format!(
+                "expected regex, but got {:?} instead",
+                c
+            ))),
+        }
+    }
+}
+
+impl<'a> Serialize for RawRegex<'a> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("$regularExpression", 1)?;
+        let body = BorrowedRegexBody {
+            pattern: self.pattern,
+            options: self.options,
+        };
+        state.serialize_field("$regularExpression", &body)?;
+        state.end()
+    }
+}
+
+/// A BSON "code with scope" value referencing raw bytes stored elsewhere.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RawJavaScriptCodeWithScope<'a> {
+    pub(crate) code: &'a str,
+
+    pub(crate) scope: &'a RawDocument,
+}
+
+impl<'a> RawJavaScriptCodeWithScope<'a> {
+    /// Gets the code in the value.
+    pub fn code(self) -> &'a str {
+        self.code
+    }
+
+    /// Gets the scope in the value.
+    pub fn scope(self) -> &'a RawDocument {
+        self.scope
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for RawJavaScriptCodeWithScope<'a> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawBson::deserialize(deserializer)? {
+            RawBson::JavaScriptCodeWithScope(b) => Ok(b),
+            c => Err(serde::de::Error::custom(format!(
+                "expected scoped javascript code, but got {:?} instead",
+                c
+            ))),
+        }
+    }
+}
+
+impl<'a> Serialize for RawJavaScriptCodeWithScope<'a> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("$codeWithScope", 2)?;
+        state.serialize_field("$code", &self.code)?;
+        state.serialize_field("$scope", &self.scope)?;
+        state.end()
+    }
+}
+
+/// A BSON DB pointer value referencing raw bytes stored elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawDbPointer<'a> {
+    pub(crate) namespace: &'a str,
+    pub(crate) id: ObjectId,
+}
+
+impl<'a> RawDbPointer<'a> {
+    /// Gets the namespace of the collection the pointed-to document lives in.
+    pub fn namespace(self) -> &'a str {
+        self.namespace
+    }
+
+    /// Gets the id of the pointed-to document.
+    pub fn id(self) -> ObjectId {
+        self.id
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for RawDbPointer<'a> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawBson::deserialize(deserializer)? {
+            RawBson::DbPointer(b) => Ok(b),
+            c => Err(serde::de::Error::custom(format!(
+                "expected DbPointer, but got {:?} instead",
+                c
+            ))),
+        }
+    }
+}
+
+impl<'a> Serialize for RawDbPointer<'a> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct BorrowedDbPointerBody<'a> {
+            #[serde(rename = "$ref")]
+            ref_ns: &'a str,
+
+            #[serde(rename = "$id")]
+            id: ObjectId,
+        }
+
+        let mut state = serializer.serialize_struct("$dbPointer", 1)?;
+        let body = BorrowedDbPointerBody {
+            ref_ns: self.namespace,
+            id: self.id,
+        };
+        state.serialize_field("$dbPointer", &body)?;
+        state.end()
+    }
+}
@@ -6,6 +6,7 @@ use std::{
 use serde::{ser::SerializeMap, Deserialize, Serialize};
 
 use crate::{
+    path::{self, AsKeyPath},
     raw::{error::ErrorKind, RawBsonVisitor, RAW_DOCUMENT_NEWTYPE},
     spec::BinarySubtype,
     DateTime,
@@ -21,9 +22,10 @@ use super::{
     RawBinary,
     RawBson,
     RawDocumentBuf,
+    RawRegex,
     Result,
 };
-use crate::{oid::ObjectId, spec::ElementType, Document};
+use crate::{oid::ObjectId, spec::ElementType, DeserializerOptions, Document};
 
 /// A slice of a BSON document (akin to [`std::str`]). This can be created from a
 /// [`RawDocumentBuf`] or any type that contains valid BSON data, including static binary literals,
@@ -151,6 +153,32 @@ impl RawDocument {
         RawDocumentBuf::new(self.data.to_owned()).unwrap()
     }
 
+    /// Gets an iterator over the elements of this document that decodes them according to
+    /// `options`. With [`DeserializerOptions::unknown_elements`] enabled, this yields elements
+    /// of types this crate does not know as [`RawBson::Unknown`] instead of an error.
+    ///
+    /// ```
+    /// # use bson::raw::Error;
+    /// use bson::{raw::{RawBson, RawDocument}, spec::ElementSize, DeserializerOptions};
+    ///
+    /// // { "a": <0x80 element holding the length-prefixed value 0x01> }
+    /// let bytes = b"\x0d\x00\x00\x00\x80a\x00\x05\x00\x00\x00\x01\x00";
+    /// let doc = RawDocument::new(bytes)?;
+    /// assert!(doc.get("a").is_err());
+    ///
+    /// let options = DeserializerOptions::builder()
+    ///     .unknown_elements(true)
+    ///     .unknown_element_size(0x80, ElementSize::LengthPrefixed)
+    ///     .build();
+    /// let (key, value) = doc.iter_with_options(&options).next().unwrap()?;
+    /// assert_eq!(key, "a");
+    /// assert_eq!(value.as_unknown(), Some((0x80, &b"\x05\x00\x00\x00\x01"[..])));
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn iter_with_options<'a>(&'a self, options: &'a DeserializerOptions) -> Iter<'a> {
+        Iter::with_options(self, options)
+    }
+
     /// Gets a reference to the value corresponding to the given key by iterating until the key is
     /// found.
     ///
@@ -202,7 +230,9 @@ impl RawDocument {
                 key: key.to_string(),
                 kind: ValueAccessErrorKind::UnexpectedType {
                     expected: expected_type,
-                    actual: bson.element_type(),
+                    actual: bson
+                        .element_type()
+                        .expect("elements are read without unknown element passthrough"),
                 },
             }),
         }
@@ -320,6 +350,27 @@ impl RawDocument {
         self.get_with(key, ElementType::Binary, RawBson::as_binary)
     }
 
+    /// Gets a reference to the BSON objectid value corresponding to a given key or returns an
+    /// error if the key corresponds to a value which isn't an ObjectId.
+    ///
+    /// ```
+    /// # use bson::raw::Error;
+    /// use bson::{doc, oid::ObjectId, raw::{ValueAccessErrorKind, RawDocumentBuf}};
+    ///
+    /// let doc = RawDocumentBuf::from_document(&doc! {
+    ///     "_id": ObjectId::new(),
+    ///     "bool": true,
+    /// })?;
+    ///
+    /// let oid = doc.get_object_id("_id")?;
+    /// assert!(matches!(doc.get_object_id("bool").unwrap_err().kind, ValueAccessErrorKind::UnexpectedType { .. }));
+    /// assert!(matches!(doc.get_object_id("unknown").unwrap_err().kind, ValueAccessErrorKind::NotPresent));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn get_object_id(&self, key: impl AsRef<str>) -> ValueAccessResult<ObjectId> {
+        self.get_with(key, ElementType::ObjectId, RawBson::as_object_id)
+    }
+
     /// Gets a reference to the boolean value corresponding to a given key or returns an error if
     /// the key corresponds to a value which isn't a boolean.
     ///
@@ -363,6 +414,26 @@ impl RawDocument {
         self.get_with(key, ElementType::DateTime, RawBson::as_datetime)
     }
 
+    /// Gets a reference to the BSON regex value corresponding to a given key or returns an error if
+    /// the key corresponds to a value which isn't a regex.
+    ///
+    /// ```
+    /// use bson::{doc, Regex, raw::{RawDocumentBuf, ValueAccessErrorKind}};
+    ///
+    /// let doc = RawDocumentBuf::from_document(&doc! {
+    ///     "regex": Regex::new(r"end\s*$", "i"),
+    ///     "bool": true,
+    /// })?;
+    ///
+    /// assert_eq!(doc.get_regex("regex")?.pattern, r"end\s*$");
+    /// assert_eq!(doc.get_regex("regex")?.options, "i");
+    /// assert!(matches!(doc.get_regex("bool").unwrap_err().kind, ValueAccessErrorKind::UnexpectedType { .. }));
+    /// assert!(matches!(doc.get_regex("unknown").unwrap_err().kind, ValueAccessErrorKind::NotPresent));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn get_regex(&self, key: impl AsRef<str>) -> ValueAccessResult<RawRegex<'_>> {
+        self.get_with(key, ElementType::RegularExpression, RawBson::as_regex)
+    }
 
     /// Gets a reference to the BSON timestamp value corresponding to a given key or returns an
     /// error if the key corresponds to a value which isn't a timestamp.
@@ -430,6 +501,103 @@ impl RawDocument {
         self.get_with(key, ElementType::Int64, RawBson::as_i64)
     }
 
+    /// Gets the value at the given path, which is either a string in dotted notation such as
+    /// `"a.b.3.c"` or a [`KeyPath`](crate::path::KeyPath). See the [`path`](crate::path) module for
+    /// the syntax.
+    ///
+    /// Unlike chained calls to [`RawDocument::get_document`], this skips over the elements that are
+    /// not on the path by their length prefixes without decoding them, descends into arrays by
+    /// index, and only decodes the value found. It does not allocate unless the path contains
+    /// escaped characters or cannot be resolved.
+    ///
+    /// ```
+    /// use bson::{doc, raw::RawDocumentBuf};
+    ///
+    /// let doc = RawDocumentBuf::from_document(&doc! {
+    ///     "reply": { "cursor": { "batch": [{ "n": 1 }, { "n": 2 }] } },
+    /// })?;
+    ///
+    /// assert_eq!(doc.get_path("reply.cursor.batch.1.n")?.as_i32(), Some(2));
+    /// assert_eq!(doc.get_path_i32("reply.cursor.batch.0.n")?, 1);
+    /// assert!(doc.get_path("reply.cursor.batch.2.n").is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn get_path(&self, path: impl AsKeyPath) -> path::Result<RawBson<'_>> {
+        path::get_raw(self, &path)
+    }
+
+    /// Gets the BSON double value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_f64(&self, path: impl AsKeyPath) -> path::Result<f64> {
+        path::get_raw_typed(self, &path, ElementType::Double, RawBson::as_f64)
+    }
+
+    /// Gets the string value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_str(&self, path: impl AsKeyPath) -> path::Result<&'_ str> {
+        path::get_raw_typed(self, &path, ElementType::String, RawBson::as_str)
+    }
+
+    /// Gets the document value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_document(&self, path: impl AsKeyPath) -> path::Result<&'_ RawDocument> {
+        path::get_raw_typed(self, &path, ElementType::EmbeddedDocument, RawBson::as_document)
+    }
+
+    /// Gets the array value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_array(&self, path: impl AsKeyPath) -> path::Result<&'_ RawArray> {
+        path::get_raw_typed(self, &path, ElementType::Array, RawBson::as_array)
+    }
+
+    /// Gets the BSON binary value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_binary(&self, path: impl AsKeyPath) -> path::Result<RawBinary<'_>> {
+        path::get_raw_typed(self, &path, ElementType::Binary, RawBson::as_binary)
+    }
+
+    /// Gets the BSON objectid value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_object_id(&self, path: impl AsKeyPath) -> path::Result<ObjectId> {
+        path::get_raw_typed(self, &path, ElementType::ObjectId, RawBson::as_object_id)
+    }
+
+    /// Gets the boolean value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_bool(&self, path: impl AsKeyPath) -> path::Result<bool> {
+        path::get_raw_typed(self, &path, ElementType::Boolean, RawBson::as_bool)
+    }
+
+    /// Gets the BSON DateTime value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_datetime(&self, path: impl AsKeyPath) -> path::Result<DateTime> {
+        path::get_raw_typed(self, &path, ElementType::DateTime, RawBson::as_datetime)
+    }
+
+    /// Gets the BSON regex value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_regex(&self, path: impl AsKeyPath) -> path::Result<RawRegex<'_>> {
+        path::get_raw_typed(self, &path, ElementType::RegularExpression, RawBson::as_regex)
+    }
+
+    /// Gets the BSON timestamp value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_timestamp(&self, path: impl AsKeyPath) -> path::Result<Timestamp> {
+        path::get_raw_typed(self, &path, ElementType::Timestamp, RawBson::as_timestamp)
+    }
+
+    /// Gets the BSON int32 value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_i32(&self, path: impl AsKeyPath) -> path::Result<i32> {
+        path::get_raw_typed(self, &path, ElementType::Int32, RawBson::as_i32)
+    }
+
+    /// Gets the BSON int64 value at the given path or returns an error if the path cannot
+    /// be resolved or leads to a value of another type.
+    pub fn get_path_i64(&self, path: impl AsKeyPath) -> path::Result<i64> {
+        path::get_raw_typed(self, &path, ElementType::Int64, RawBson::as_i64)
+    }
+
     /// Return a reference to the contained data as a `&[u8]`
     ///
     /// ```
@@ -46,6 +46,17 @@ pub enum ErrorKind {
 
     /// Improper UTF-8 bytes were found when proper UTF-8 was expected.
     Utf8EncodingError(Utf8Error),
+
+    /// An element of a type rejected by the [`DeserializerOptions`] the document is iterated
+    /// with was found, see [`DeserializerOptions::reject_unsigned_integers`].
+    ///
+    /// [`DeserializerOptions`]: crate::DeserializerOptions
+    /// [`DeserializerOptions::reject_unsigned_integers`]: crate::DeserializerOptions::reject_unsigned_integers
+    #[non_exhaustive]
+    ElementTypeNotAllowed {
+        /// The type tag of the rejected element.
+        element_type: u8,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -62,6 +73,9 @@ impl std::fmt::Display for Error {
                 write!(f, "{}malformed value: {:?}", prefix, message)
             }
             ErrorKind::Utf8EncodingError(e) => write!(f, "{}utf-8 encoding error: {}", prefix, e),
+            ErrorKind::ElementTypeNotAllowed { element_type } => {
+                write!(f, "{}element type not allowed: `{:#x}`", prefix, element_type)
+            }
         }
     }
 }
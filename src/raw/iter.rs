@@ -4,9 +4,10 @@ use crate::{
     de::{read_bool, MIN_BSON_DOCUMENT_SIZE, MIN_CODE_WITH_SCOPE_SIZE},
     oid::ObjectId,
     raw::{Error, ErrorKind, Result},
-    spec::{BinarySubtype, ElementType},
+    spec::{BinarySubtype, ElementSize, ElementType},
     DateTime,
     Decimal128,
+    DeserializerOptions,
     Timestamp,
 };
 use crate::raw::{u32_from_slice, u64_from_slice};
@@ -22,7 +23,10 @@ use super::{
     RawArray,
     RawBinary,
     RawBson,
+    RawDbPointer,
     RawDocument,
+    RawJavaScriptCodeWithScope,
+    RawRegex,
 };
 
 /// An iterator over the document's entries.
@@ -33,6 +37,9 @@ pub struct Iter<'a> {
     /// Whether the underlying doc is assumed to be valid or if an error has been encountered.
     /// After an error, all subsequent iterations will return None.
     valid: bool,
+
+    /// Options describing how to skip over elements of unknown type, if any.
+    options: Option<&'a DeserializerOptions>,
 }
 
 impl<'a> Iter<'a> {
@@ -41,6 +48,22 @@ impl<'a> Iter<'a> {
             doc,
             offset: 4,
             valid: true,
+            options: None,
+        }
+    }
+
+    /// An iterator that starts at the element whose type tag is at `offset`.
+    pub(crate) fn starting_at(doc: &'a RawDocument, offset: usize) -> Self {
+        Self {
+            offset,
+            ..Self::new(doc)
+        }
+    }
+
+    pub(crate) fn with_options(doc: &'a RawDocument, options: &'a DeserializerOptions) -> Self {
+        Self {
+            options: Some(options),
+            ..Self::new(doc)
         }
     }
 
@@ -91,6 +114,21 @@ impl<'a> Iter<'a> {
         }
         RawDocument::new(&self.doc.as_bytes()[starting_at..end])
     }
+
+    fn unknown_element_length(&self, size: ElementSize, starting_at: usize) -> Result<usize> {
+        match size {
+            ElementSize::Fixed(length) => Ok(length),
+            ElementSize::LengthPrefixed => {
+                let length = i32_from_slice(&self.doc.as_bytes()[starting_at..])?;
+                if length < 4 {
+                    return Err(Error::new_without_key(ErrorKind::MalformedValue {
+                        message: format!("element length too small: {} bytes", length),
+                    }));
+                }
+                Ok(length as usize)
+            }
+        }
+    }
 }
 
 impl<'a> Iterator for Iter<'a> {
@@ -130,16 +168,43 @@ impl<'a> Iterator for Iter<'a> {
         let kvp_result = try_with_key(key, || {
             let valueoffset = self.offset + 1 + key.len() + 1; // type specifier + key + \0
 
-            let element_type = match ElementType::from(self.doc.as_bytes()[self.offset]) {
-                Some(et) => et,
-                None => {
+            let tag = self.doc.as_bytes()[self.offset];
+            let element_type = match ElementType::from(tag) {
+                Some(_) if matches!(self.options, Some(o) if o.rejects(tag)) => {
                     return Err(Error::new_with_key(
                         key,
-                        ErrorKind::MalformedValue {
-                            message: format!("invalid tag: {}", self.doc.as_bytes()[self.offset]),
-                        },
+                        ErrorKind::ElementTypeNotAllowed { element_type: tag },
                     ))
                 }
+                Some(et) => et,
+                None => {
+                    let size = match self.options.and_then(|o| o.unknown_element_size(tag)) {
+                        Some(size) => self.unknown_element_length(size, valueoffset)?,
+                        None => {
+                            return Err(Error::new_with_key(
+                                key,
+                                ErrorKind::MalformedValue {
+                                    message: format!("invalid tag: {}", tag),
+                                },
+                            ))
+                        }
+                    };
+
+                    self.verify_enough_bytes(valueoffset, size)?;
+                    self.offset = valueoffset + size;
+                    let bytes = &self.doc.as_bytes()[valueoffset..self.offset];
+                    if let Some(options) = self.options {
+                        options.codecs.check(tag, bytes).map_err(|e| {
+                            Error::new_with_key(
+                                key,
+                                ErrorKind::MalformedValue {
+                                    message: e.to_string(),
+                                },
+                            )
+                        })?;
+                    }
+                    return Ok((key, RawBson::Unknown { tag, bytes }));
+                }
             };
 
             let (element, element_size) = match element_type {
@@ -211,6 +276,10 @@ impl<'a> Iterator for Iter<'a> {
                         4 + 1 + len,
                     )
                 }
+                ElementType::ObjectId => {
+                    let oid = self.next_oid(valueoffset)?;
+                    (RawBson::ObjectId(oid), 12)
+                }
                 ElementType::Boolean => {
                     let b = read_bool(&self.doc.as_bytes()[valueoffset..]).map_err(|e| {
                         Error::new_with_key(
@@ -227,6 +296,42 @@ impl<'a> Iterator for Iter<'a> {
                     (RawBson::DateTime(DateTime::from_millis(ms)), 8)
                 }
                 ElementType::Null => (RawBson::Null, 0),
+                ElementType::RegularExpression => {
+                    let pattern = read_nullterminated(&self.doc.as_bytes()[valueoffset..])?;
+                    let options = read_nullterminated(
+                        &self.doc.as_bytes()[valueoffset + pattern.len() + 1..],
+                    )?;
+                    (
+                        RawBson::RegularExpression(RawRegex { pattern, options }),
+                        pattern.len() + 1 + options.len() + 1,
+                    )
+                }
+                ElementType::JavaScriptCode => {
+                    let code = read_lenencoded(&self.doc.as_bytes()[valueoffset..])?;
+                    (RawBson::JavaScriptCode(code), 4 + code.len() + 1)
+                }
+                ElementType::JavaScriptCodeWithScope => {
+                    let length = i32_from_slice(&self.doc.as_bytes()[valueoffset..])? as usize;
+
+                    if length < MIN_CODE_WITH_SCOPE_SIZE as usize {
+                        return Err(Error::new_without_key(ErrorKind::MalformedValue {
+                            message: "code with scope length too small".to_string(),
+                        }));
+                    }
+
+                    self.verify_enough_bytes(valueoffset, length)?;
+                    let slice = &self.doc.as_bytes()[valueoffset..(valueoffset + length)];
+                    let code = read_lenencoded(&slice[4..])?;
+                    let scope_start = 4 + 4 + code.len() + 1;
+                    let scope = RawDocument::new(&slice[scope_start..])?;
+                    (
+                        RawBson::JavaScriptCodeWithScope(RawJavaScriptCodeWithScope {
+                            code,
+                            scope,
+                        }),
+                        length,
+                    )
+                }
                 ElementType::Timestamp => {
                     let ts = Timestamp::from_reader(&self.doc.as_bytes()[valueoffset..]).map_err(
                         |e| {
@@ -248,6 +353,21 @@ impl<'a> Iterator for Iter<'a> {
                         16,
                     )
                 }
+                ElementType::Symbol => {
+                    let s = read_lenencoded(&self.doc.as_bytes()[valueoffset..])?;
+                    (RawBson::Symbol(s), 4 + s.len() + 1)
+                }
+                ElementType::Undefined => (RawBson::Undefined, 0),
+                ElementType::MinKey => (RawBson::MinKey, 0),
+                ElementType::MaxKey => (RawBson::MaxKey, 0),
+                ElementType::DbPointer => {
+                    let namespace = read_lenencoded(&self.doc.as_bytes()[valueoffset..])?;
+                    let id = self.next_oid(valueoffset + 4 + namespace.len() + 1)?;
+                    (
+                        RawBson::DbPointer(RawDbPointer { namespace, id }),
+                        4 + namespace.len() + 1 + 12,
+                    )
+                }
             };
 
             self.offset = valueoffset + element_size;
@@ -118,6 +118,8 @@ mod document;
 mod document_buf;
 mod error;
 mod iter;
+mod scan;
+mod selector;
 #[cfg(test)]
 mod test;
 
@@ -127,14 +129,18 @@ use crate::de::MIN_BSON_STRING_SIZE;
 
 pub use self::{
     array::{RawArray, RawArrayIter},
-    bson::{RawBinary, RawBson},
+    bson::{RawBinary, RawBson, RawDbPointer, RawJavaScriptCodeWithScope, RawRegex},
     document::RawDocument,
     document_buf::RawDocumentBuf,
     error::{Error, ErrorKind, Result, ValueAccessError, ValueAccessErrorKind, ValueAccessResult},
     iter::Iter,
+    selector::FieldSelector,
 };
 
-pub(crate) use self::bson::RawBsonVisitor;
+pub(crate) use self::{
+    bson::RawBsonVisitor,
+    scan::{RawElement, RawElements},
+};
 
 /// Special newtype name indicating that the type being (de)serialized is a raw BSON document.
 pub(crate) const RAW_DOCUMENT_NEWTYPE: &str = "$__private__bson_RawDocument";
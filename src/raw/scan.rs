@@ -0,0 +1,195 @@
+use crate::{
+    de::{MIN_BSON_DOCUMENT_SIZE, MIN_BSON_STRING_SIZE, MIN_CODE_WITH_SCOPE_SIZE},
+    spec::ElementType,
+};
+
+use super::{i32_from_slice, Error, ErrorKind, Iter, RawBson, RawDocument, Result};
+
+/// An element of a raw document that has been located but whose value has not been decoded.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RawElement<'a> {
+    doc: &'a RawDocument,
+
+    /// The offset of the element's type tag.
+    start: usize,
+
+    /// The offset of the element's value.
+    value_start: usize,
+
+    /// The offset just past the element's value.
+    end: usize,
+}
+
+impl<'a> RawElement<'a> {
+    /// The type tag of this element.
+    pub(crate) fn tag(&self) -> u8 {
+        self.doc.as_bytes()[self.start]
+    }
+
+    /// The key of this element, which has not been checked to be valid UTF-8.
+    pub(crate) fn key_bytes(&self) -> &'a [u8] {
+        &self.doc.as_bytes()[self.start + 1..self.value_start - 1]
+    }
+
+    /// The bytes of this element's value.
+    pub(crate) fn value_bytes(&self) -> &'a [u8] {
+        &self.doc.as_bytes()[self.value_start..self.end]
+    }
+
+    /// Decode the value of this element.
+    pub(crate) fn value(&self) -> Result<RawBson<'a>> {
+        match Iter::starting_at(self.doc, self.start).next() {
+            Some(result) => result.map(|(_, value)| value),
+            None => Err(Error::new_without_key(ErrorKind::MalformedValue {
+                message: "element overflowed document".to_string(),
+            })),
+        }
+    }
+
+    /// The document this element holds if it is an embedded document or an array, without
+    /// decoding any of its elements.
+    pub(crate) fn container(&self) -> Result<Option<&'a RawDocument>> {
+        if self.tag() == ElementType::EmbeddedDocument as u8
+            || self.tag() == ElementType::Array as u8
+        {
+            RawDocument::new(self.value_bytes())
+                .map(Some)
+                .map_err(|e| e.with_key(String::from_utf8_lossy(self.key_bytes())))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// An iterator over the elements of a raw document that reads only the type and key of each
+/// element and skips over its value using its length, without decoding or validating it.
+pub(crate) struct RawElements<'a> {
+    doc: &'a RawDocument,
+    offset: usize,
+
+    /// Whether an error has been encountered, after which the iterator is exhausted.
+    failed: bool,
+}
+
+impl<'a> RawElements<'a> {
+    pub(crate) fn new(doc: &'a RawDocument) -> Self {
+        Self {
+            doc,
+            offset: 4,
+            failed: false,
+        }
+    }
+
+    fn malformed(&self, message: impl Into<String>) -> Error {
+        Error::new_without_key(ErrorKind::MalformedValue {
+            message: message.into(),
+        })
+    }
+
+    /// The length of a value that starts with an `i32` length of at least `min` bytes, which
+    /// `extra` bytes precede in the value.
+    fn length_prefixed(&self, value_start: usize, min: i32, extra: usize) -> Result<usize> {
+        let length = i32_from_slice(&self.doc.as_bytes()[value_start..])?;
+        if length < min {
+            return Err(self.malformed(format!("length too small: {} bytes", length)));
+        }
+        Ok(length as usize + extra)
+    }
+
+    /// The length of the null-terminated string starting at `at`, including its terminator.
+    fn cstring(&self, at: usize) -> Result<usize> {
+        self.doc.as_bytes()[at..]
+            .iter()
+            .position(|b| *b == 0)
+            .map(|i| i + 1)
+            .ok_or_else(|| self.malformed("expected null terminator"))
+    }
+
+    fn value_length(&self, tag: u8, value_start: usize) -> Result<usize> {
+        let length = match ElementType::from(tag) {
+            Some(ElementType::Undefined)
+            | Some(ElementType::Null)
+            | Some(ElementType::MinKey)
+            | Some(ElementType::MaxKey) => 0,
+            Some(ElementType::Boolean) => 1,
+            Some(ElementType::Int32) | Some(ElementType::UInt32) => 4,
+            Some(ElementType::Double)
+            | Some(ElementType::DateTime)
+            | Some(ElementType::Timestamp)
+            | Some(ElementType::Int64)
+            | Some(ElementType::UInt64) => 8,
+            Some(ElementType::ObjectId) => 12,
+            Some(ElementType::Decimal128) => 16,
+            Some(ElementType::String)
+            | Some(ElementType::JavaScriptCode)
+            | Some(ElementType::Symbol) => {
+                self.length_prefixed(value_start, MIN_BSON_STRING_SIZE - 4, 4)?
+            }
+            Some(ElementType::EmbeddedDocument) | Some(ElementType::Array) => {
+                self.length_prefixed(value_start, MIN_BSON_DOCUMENT_SIZE, 0)?
+            }
+            Some(ElementType::JavaScriptCodeWithScope) => {
+                self.length_prefixed(value_start, MIN_CODE_WITH_SCOPE_SIZE, 0)?
+            }
+            Some(ElementType::Binary) => self.length_prefixed(value_start, 0, 4 + 1)?,
+            Some(ElementType::DbPointer) => {
+                self.length_prefixed(value_start, MIN_BSON_STRING_SIZE - 4, 4 + 12)?
+            }
+            Some(ElementType::RegularExpression) => {
+                let pattern = self.cstring(value_start)?;
+                pattern + self.cstring(value_start + pattern)?
+            }
+            None => return Err(self.malformed(format!("invalid tag: {}", tag))),
+        };
+        Ok(length)
+    }
+
+    fn next_element(&self) -> Result<RawElement<'a>> {
+        let bytes = self.doc.as_bytes();
+        let start = self.offset;
+        let value_start = start + 1 + self.cstring(start + 1)?;
+        let end = value_start
+            .checked_add(self.value_length(bytes[start], value_start)?)
+            .filter(|end| *end < bytes.len())
+            .ok_or_else(|| {
+                self.malformed("length exceeds remaining length of buffer")
+                    .with_key(String::from_utf8_lossy(&bytes[start + 1..value_start - 1]))
+            })?;
+
+        Ok(RawElement {
+            doc: self.doc,
+            start,
+            value_start,
+            end,
+        })
+    }
+}
+
+impl<'a> Iterator for RawElements<'a> {
+    type Item = Result<RawElement<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let bytes = self.doc.as_bytes();
+        if self.offset == bytes.len() - 1 && bytes[self.offset] == 0 {
+            return None;
+        } else if self.offset >= bytes.len() - 1 {
+            self.failed = true;
+            return Some(Err(self.malformed("iteration overflowed document")));
+        }
+
+        match self.next_element() {
+            Ok(element) => {
+                self.offset = element.end;
+                Some(Ok(element))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    path::{self, parse_index, AsKeyPath, KeyPath},
+    spec::ElementType,
+};
+
+use super::{RawBson, RawDocument, RawElement, RawElements, Result};
+
+/// A compiled set of paths whose values are extracted from a [`RawDocument`] in a single pass.
+///
+/// Looking up N fields with [`RawDocument::get_path`] scans the document N times. A
+/// `FieldSelector` instead merges its paths into a tree of segments, then walks each document
+/// once: elements that are not on any of the paths are skipped over by their length prefixes
+/// without being decoded, embedded documents and arrays are only entered if a path continues
+/// into them, and the walk stops as soon as every path has been resolved.
+///
+/// [`FieldSelector::select`] returns one slot per path, in the order the paths were given, which
+/// is `None` if the path cannot be resolved in that document. A path cannot be resolved if a
+/// field is missing, an index is past the end of an array, or the path continues into a value
+/// that is neither a document nor an array. If a key occurs more than once in a document, the
+/// first occurrence that provides a value is used.
+///
+/// ```
+/// use bson::{doc, raw::{FieldSelector, RawDocumentBuf}};
+///
+/// let selector = FieldSelector::new(vec!["level", "ctx.host", "ctx.tags.0", "missing"])?;
+///
+/// let doc = RawDocumentBuf::from_document(&doc! {
+///     "ts": 1,
+///     "level": "warn",
+///     "ctx": { "tags": ["db", "slow"], "host": "a1" },
+/// })?;
+/// let values = selector.select(&doc)?;
+/// assert_eq!(values[0].and_then(|v| v.as_str()), Some("warn"));
+/// assert_eq!(values[1].and_then(|v| v.as_str()), Some("a1"));
+/// assert_eq!(values[2].and_then(|v| v.as_str()), Some("db"));
+/// assert!(values[3].is_none());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct FieldSelector {
+    paths: Vec<KeyPath>,
+    root: Node,
+}
+
+/// A segment shared by one or more of the selected paths.
+#[derive(Clone, Debug, Default)]
+struct Node {
+    /// The slots filled with the value found at this node, one for each path that ends here.
+    ends: Vec<usize>,
+
+    /// The number of slots in the subtree rooted at this node, including its own.
+    slots: usize,
+
+    children: Vec<Child>,
+}
+
+#[derive(Clone, Debug)]
+struct Child {
+    key: String,
+
+    /// The segment as an array index, if it is one.
+    index: Option<usize>,
+
+    node: Node,
+}
+
+impl Node {
+    fn insert(&mut self, segments: &[String], slot: usize) {
+        self.slots += 1;
+        let (first, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => {
+                self.ends.push(slot);
+                return;
+            }
+        };
+
+        let position = match self.children.iter().position(|c| &c.key == first) {
+            Some(position) => position,
+            None => {
+                self.children.push(Child {
+                    key: first.clone(),
+                    index: parse_index(first),
+                    node: Node::default(),
+                });
+                self.children.len() - 1
+            }
+        };
+        self.children[position].node.insert(rest, slot);
+    }
+
+    /// Fill the slots of the children of this node from `doc`, returning the number of slots
+    /// newly filled.
+    fn select<'a>(
+        &self,
+        doc: &'a RawDocument,
+        is_array: bool,
+        values: &mut [Option<RawBson<'a>>],
+    ) -> Result<usize> {
+        let mut filled = 0;
+        for (position, element) in RawElements::new(doc).enumerate() {
+            let element = element?;
+            let matches = |child: &&Child| {
+                if is_array {
+                    child.index == Some(position)
+                } else {
+                    child.key.as_bytes() == element.key_bytes()
+                }
+            };
+            for child in self.children.iter().filter(matches) {
+                filled += child.node.fill(element, values)?;
+            }
+
+            if filled == self.slots - self.ends.len() {
+                break;
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Fill the slots of this node and its descendants from `element`, returning the number of
+    /// slots newly filled.
+    fn fill<'a>(
+        &self,
+        element: RawElement<'a>,
+        values: &mut [Option<RawBson<'a>>],
+    ) -> Result<usize> {
+        let mut filled = 0;
+        if let Some(&slot) = self.ends.first() {
+            if values[slot].is_none() {
+                let value = element.value()?;
+                for &slot in &self.ends {
+                    values[slot] = Some(value);
+                }
+                filled += self.ends.len();
+            }
+        }
+
+        if !self.children.is_empty() {
+            if let Some(container) = element.container()? {
+                let is_array = element.tag() == ElementType::Array as u8;
+                filled += self.select(container, is_array, values)?;
+            }
+        }
+        Ok(filled)
+    }
+}
+
+impl FieldSelector {
+    /// Compile a selector for the given paths, each of which is either a string in dotted
+    /// notation or a [`KeyPath`]. See the [`path`](crate::path) module for the syntax.
+    ///
+    /// This fails if any of the paths is malformed.
+    pub fn new<I, P>(paths: I) -> path::Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsKeyPath,
+    {
+        let mut selector = Self {
+            paths: Vec::new(),
+            root: Node::default(),
+        };
+        for path in paths {
+            let path = path
+                .iter_segments()
+                .map(|segment| segment.map(|s| s.into_owned()))
+                .collect::<path::Result<KeyPath>>()?;
+            selector.root.insert(path.segments(), selector.paths.len());
+            selector.paths.push(path);
+        }
+        Ok(selector)
+    }
+
+    /// The paths this selector extracts, in the order of the slots returned by
+    /// [`FieldSelector::select`].
+    pub fn paths(&self) -> &[KeyPath] {
+        &self.paths
+    }
+
+    /// Extract the values at this selector's paths from `doc` in a single pass, returning one
+    /// slot per path that holds `None` if the path cannot be resolved in `doc`.
+    ///
+    /// This fails if `doc` is malformed in a part that is walked, or if one of the values found
+    /// is malformed.
+    pub fn select<'a>(&self, doc: &'a RawDocument) -> Result<Vec<Option<RawBson<'a>>>> {
+        let mut values = vec![None; self.paths.len()];
+        self.root.select(doc, false, &mut values)?;
+        Ok(values)
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     doc,
     oid::ObjectId,
     raw::error::ValueAccessErrorKind,
-    spec::BinarySubtype,
+    spec::{BinarySubtype, ElementType},
     Binary,
     Bson,
     DateTime,
@@ -479,6 +479,284 @@ fn into_bson_conversion() {
     );
 }
 
+#[test]
+fn unsigned_integer_policies() {
+    use crate::{ser::UnsignedIntegerPolicy, Decimal128, DeserializerOptions, SerializerOptions};
+
+    #[derive(serde::Serialize)]
+    struct Counters {
+        small: u32,
+        medium: u32,
+        large: u64,
+        huge: u64,
+    }
+
+    let counters = Counters {
+        small: 1,
+        medium: u32::MAX,
+        large: i64::MAX as u64,
+        huge: u64::MAX,
+    };
+    let doc = doc! {
+        "small": Bson::UInt32(1),
+        "medium": Bson::UInt32(u32::MAX),
+        "large": Bson::UInt64(i64::MAX as u64),
+        "huge": Bson::UInt64(u64::MAX),
+    };
+    let options = |policy| {
+        SerializerOptions::builder()
+            .unsigned_integers(policy)
+            .build()
+    };
+    let expected = |huge: Bson| {
+        doc! {
+            "small": 1,
+            "medium": u32::MAX as i64,
+            "large": i64::MAX,
+            "huge": huge,
+        }
+    };
+
+    for &(policy, ref huge) in &[
+        (
+            UnsignedIntegerPolicy::Int64OrDecimal128,
+            Bson::Decimal128(Decimal128::from(u64::MAX)),
+        ),
+        (
+            UnsignedIntegerPolicy::Int64OrDouble,
+            Bson::Double(u64::MAX as f64),
+        ),
+    ] {
+        let expected = expected(huge.clone());
+
+        let mut bytes = Vec::new();
+        doc.to_writer_with_options(&mut bytes, options(policy))
+            .unwrap();
+        assert_eq!(bytes, to_bytes(&expected));
+        assert_eq!(
+            crate::to_vec_with_options(&counters, options(policy)).unwrap(),
+            bytes
+        );
+        assert_eq!(
+            crate::to_vec_with_options(&doc, options(policy)).unwrap(),
+            bytes
+        );
+        assert_eq!(
+            crate::to_bson_with_options(&counters, options(policy)).unwrap(),
+            Bson::Document(expected)
+        );
+    }
+
+    let policy = UnsignedIntegerPolicy::Int64OrError;
+    assert!(matches!(
+        doc.to_writer_with_options(&mut Vec::new(), options(policy)),
+        Err(crate::ser::Error::UnsignedIntegerExceededRange(u64::MAX))
+    ));
+    assert!(crate::to_vec_with_options(&counters, options(policy)).is_err());
+    assert!(crate::to_bson_with_options(&counters, options(policy)).is_err());
+    let doc = doc! { "small": Bson::UInt32(1), "large": Bson::UInt64(i64::MAX as u64) };
+    let mut bytes = Vec::new();
+    doc.to_writer_with_options(&mut bytes, options(policy))
+        .unwrap();
+    assert_eq!(bytes, to_bytes(&doc! { "small": 1, "large": i64::MAX }));
+
+    // the native policy keeps the crate-specific element types
+    let bytes = to_bytes(&doc);
+    let mut native = Vec::new();
+    doc.to_writer_with_options(&mut native, Default::default())
+        .unwrap();
+    assert_eq!(native, bytes);
+
+    let strict = DeserializerOptions::builder()
+        .reject_unsigned_integers(true)
+        .build();
+    assert_eq!(
+        crate::Document::from_reader_with_options(bytes.as_slice(), Default::default()).unwrap(),
+        doc
+    );
+    // all three binary decoders report a rejected tag the same way
+    let rejected = |result: crate::de::Result<crate::Document>| match result {
+        Err(crate::de::Error::ElementTypeNotAllowed { key, element_type }) => {
+            (key, element_type)
+        }
+        other => panic!("expected a rejected element type, got {:?}", other),
+    };
+    assert_eq!(
+        rejected(crate::Document::from_reader_with_options(
+            bytes.as_slice(),
+            strict.clone()
+        )),
+        ("small".to_string(), 0x14)
+    );
+    assert_eq!(
+        rejected(crate::from_slice_with_options(&bytes, strict.clone())),
+        ("small".to_string(), 0x14)
+    );
+    let raw = RawDocument::new(&bytes).unwrap();
+    let err = raw.iter_with_options(&strict).next().unwrap().unwrap_err();
+    assert_eq!(err.key(), Some("small"));
+    assert_eq!(
+        err.kind,
+        crate::raw::ErrorKind::ElementTypeNotAllowed { element_type: 0x14 }
+    );
+    let large = to_bytes(&doc! { "large": Bson::UInt64(1) });
+    assert_eq!(
+        rejected(crate::from_slice_with_options(&large, strict.clone())),
+        ("large".to_string(), 0x15)
+    );
+
+    let signed = to_bytes(&doc! { "small": 1, "large": i64::MAX });
+    assert!(crate::from_slice_with_options::<crate::Document>(&signed, strict).is_ok());
+}
+
+#[test]
+fn get_path() {
+    use crate::path::{ErrorKind, KeyPath};
+
+    let doc = doc! {
+        "skipped": {
+            "binary": Binary { subtype: BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+            "regex": Regex::new("a.*", "i"),
+            "code": JavaScriptCodeWithScope { code: "f()".into(), scope: doc! { "x": 1 } },
+        },
+        "reply": {
+            "ok": 1.0,
+            "cursor": {
+                "batch": [
+                    { "n": 1 },
+                    [true, "x"],
+                    { "n": 3, "id.x": ObjectId::from_bytes([1; 12]) },
+                ],
+                "id": 0_i64,
+            },
+        },
+    };
+    let bytes = to_bytes(&doc);
+    let rawdoc = RawDocument::new(&bytes).unwrap();
+
+    for path in &[
+        "skipped.binary",
+        "skipped.regex",
+        "skipped.code",
+        "reply.ok",
+        "reply.cursor.batch",
+        "reply.cursor.batch.0.n",
+        "reply.cursor.batch.1.1",
+        r"reply.cursor.batch.2.id\.x",
+        "reply.cursor.id",
+    ] {
+        let expected = doc.get_path(*path).unwrap();
+        let value: Bson = rawdoc.get_path(*path).unwrap().try_into().unwrap();
+        assert_eq!(&value, expected, "{}", path);
+    }
+
+    assert_eq!(rawdoc.get_path_i64("reply.cursor.id").unwrap(), 0);
+    assert_eq!(rawdoc.get_path_str("reply.cursor.batch.1.1").unwrap(), "x");
+    assert_eq!(
+        rawdoc
+            .get_path_document("reply.cursor.batch.2")
+            .unwrap()
+            .get_i32("n")
+            .unwrap(),
+        3
+    );
+    assert_eq!(
+        rawdoc
+            .get_path_object_id(KeyPath::from_segments(vec![
+                "reply", "cursor", "batch", "2", "id.x"
+            ]))
+            .unwrap(),
+        ObjectId::from_bytes([1; 12])
+    );
+
+    let kind = |path: &str| rawdoc.get_path(path).unwrap_err().kind;
+    assert_eq!(kind("reply.missing"), ErrorKind::NotPresent);
+    assert_eq!(kind("reply.cursor.batch.x"), ErrorKind::InvalidIndex);
+    assert_eq!(
+        kind("reply.cursor.batch.3"),
+        ErrorKind::IndexOutOfBounds { length: 3 }
+    );
+    assert_eq!(
+        kind("reply.ok.x"),
+        ErrorKind::NotTraversable {
+            actual: ElementType::Double as u8
+        }
+    );
+    assert!(matches!(kind("reply..ok"), ErrorKind::InvalidPath { .. }));
+
+    let err = rawdoc.get_path_i32("reply.ok").unwrap_err();
+    assert_eq!(err.segment_index(), Some(1));
+    assert_eq!(err.segment(), Some("ok"));
+    assert_eq!(
+        err.kind,
+        ErrorKind::UnexpectedType {
+            expected: ElementType::Int32,
+            actual: ElementType::Double as u8
+        }
+    );
+
+    // a sibling whose length runs past the end of the document cannot be skipped
+    let mut bytes = to_bytes(&doc! { "a": "x", "b": 1 });
+    bytes[7] = 0x7f;
+    let rawdoc = RawDocument::new(&bytes).unwrap();
+    assert!(matches!(
+        rawdoc.get_path("b").unwrap_err().kind,
+        ErrorKind::InvalidBson(_)
+    ));
+}
+
+#[test]
+fn field_selector() {
+    let doc = doc! {
+        "ts": DateTime::from_millis(1),
+        "msg": "started",
+        "ctx": {
+            "host": "a1",
+            "tags": ["db", { "k": "v" }],
+            "pid": 10,
+        },
+        "level": "info",
+    };
+    let bytes = to_bytes(&doc);
+    let rawdoc = RawDocument::new(&bytes).unwrap();
+
+    let paths = vec![
+        "level",
+        "ctx",
+        "ctx.host",
+        "ctx.tags.1.k",
+        "ctx.tags.0",
+        "ctx.tags.2",
+        "ctx.pid.x",
+        "missing.x",
+        "level",
+    ];
+    let selector = FieldSelector::new(paths.clone()).unwrap();
+    assert_eq!(selector.paths().len(), paths.len());
+
+    let values = selector.select(rawdoc).unwrap();
+    assert_eq!(values.len(), paths.len());
+    for (path, value) in paths.iter().zip(values) {
+        assert_eq!(value, rawdoc.get_path(*path).ok(), "{}", path);
+    }
+
+    assert!(FieldSelector::new(vec!["a", "b..c"]).is_err());
+    assert!(FieldSelector::new(Vec::<&str>::new())
+        .unwrap()
+        .select(rawdoc)
+        .unwrap()
+        .is_empty());
+
+    // the walk stops once every path has been resolved, so later malformed bytes are not read
+    let mut bytes = to_bytes(&doc! { "a": 1, "b": "x" });
+    let len = bytes.len();
+    bytes[len - 7] = 0x7f;
+    let rawdoc = RawDocument::new(&bytes).unwrap();
+    let values = FieldSelector::new(vec!["a"]).unwrap().select(rawdoc).unwrap();
+    assert_eq!(values, vec![Some(RawBson::Int32(1))]);
+    assert!(FieldSelector::new(vec!["b"]).unwrap().select(rawdoc).is_err());
+}
+
 use props::arbitrary_bson;
 use proptest::prelude::*;
 use std::convert::TryInto;
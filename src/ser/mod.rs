@@ -27,16 +27,23 @@ mod serde;
 
 pub use self::{
     error::{Error, Result},
-    serde::{Serializer, SerializerOptions},
+    serde::{Serializer, SerializerOptions, UnsignedIntegerPolicy},
 };
 
+pub(crate) use self::serde::serialize_unknown;
+
 use std::{io::Write, iter::FromIterator, mem};
 
 use crate::{
     bson::{Bson, Document},
     de::MAX_BSON_SIZE,
-    spec::BinarySubtype,
+    path::TypeName,
+    spec::{BinarySubtype, ElementType},
     Binary,
+    DbPointer,
+    Decimal128,
+    JavaScriptCodeWithScope,
+    Regex,
 };
 use ::serde::{ser::Error as SerdeError, Serialize};
 
@@ -121,10 +128,14 @@ fn write_binary<W: Write>(mut writer: W, bytes: &[u8], subtype: BinarySubtype) -
     writer.write_all(bytes).map_err(From::from)
 }
 
-fn serialize_array<W: Write + ?Sized>(writer: &mut W, arr: &[Bson]) -> Result<()> {
+fn serialize_array<W: Write + ?Sized>(
+    writer: &mut W,
+    arr: &[Bson],
+    options: &SerializerOptions,
+) -> Result<()> {
     let mut buf = Vec::new();
     for (key, val) in arr.iter().enumerate() {
-        serialize_bson(&mut buf, &key.to_string(), val)?;
+        serialize_bson(&mut buf, &key.to_string(), val, options)?;
     }
 
     write_i32(
@@ -136,19 +147,65 @@ fn serialize_array<W: Write + ?Sized>(writer: &mut W, arr: &[Bson]) -> Result<()
     Ok(())
 }
 
+/// The value to write in place of an unsigned integer according to `policy`, or `None` if it is to
+/// be written as an unsigned integer.
+pub(crate) fn convert_unsigned(value: u64, policy: UnsignedIntegerPolicy) -> Result<Option<Bson>> {
+    let converted = match policy {
+        UnsignedIntegerPolicy::Native => return Ok(None),
+        _ if value <= i32::MAX as u64 => Bson::Int32(value as i32),
+        _ if value <= i64::MAX as u64 => Bson::Int64(value as i64),
+        UnsignedIntegerPolicy::Int64OrError => {
+            return Err(Error::UnsignedIntegerExceededRange(value))
+        }
+        UnsignedIntegerPolicy::Int64OrDecimal128 => Bson::Decimal128(Decimal128::from(value)),
+        UnsignedIntegerPolicy::Int64OrDouble => Bson::Double(value as f64),
+    };
+    Ok(Some(converted))
+}
+
+/// Ensure `tag` can be written as the type of an unknown element: it must neither end the
+/// document nor claim to be a type this crate would decode differently.
+pub(crate) fn check_unknown_tag(tag: u8) -> Result<()> {
+    if tag == 0 || ElementType::from(tag).is_some() {
+        return Err(Error::custom(format!(
+            "{:#04x} cannot be used as the tag of an unknown element",
+            tag
+        )));
+    }
+    Ok(())
+}
+
 pub(crate) fn serialize_bson<W: Write + ?Sized>(
     writer: &mut W,
     key: &str,
     val: &Bson,
+    options: &SerializerOptions,
 ) -> Result<()> {
-    writer.write_all(&[val.element_type() as u8])?;
+    match *val {
+        Bson::UInt32(v) => {
+            if let Some(signed) = convert_unsigned(v.into(), options.unsigned_integers)? {
+                return serialize_bson(writer, key, &signed, options);
+            }
+        }
+        Bson::UInt64(v) => {
+            if let Some(signed) = convert_unsigned(v, options.unsigned_integers)? {
+                return serialize_bson(writer, key, &signed, options);
+            }
+        }
+        Bson::Unknown { tag, ref bytes } => {
+            check_unknown_tag(tag)?;
+            options.codecs.check(tag, bytes).map_err(Error::custom)?;
+        }
+        _ => {}
+    }
+    writer.write_all(&[val.tag()])?;
     write_cstring(writer, key)?;
 
     match *val {
         Bson::Double(v) => write_f64(writer, v),
         Bson::String(ref v) => write_string(writer, v),
-        Bson::Array(ref v) => serialize_array(writer, v),
-        Bson::Document(ref v) => v.to_writer(writer),
+        Bson::Array(ref v) => serialize_array(writer, v, options),
+        Bson::Document(ref v) => v.encode(writer, options),
         Bson::Boolean(v) => writer
             .write_all(&[if v { 0x01 } else { 0x00 }])
             .map_err(From::from),
@@ -158,12 +215,45 @@ pub(crate) fn serialize_bson<W: Write + ?Sized>(
         Bson::UInt64(v) => write_u64(writer, v),
         Bson::Timestamp(ts) => write_i64(writer, ts.to_le_i64()),
         Bson::Binary(Binary { subtype, ref bytes }) => write_binary(writer, bytes, subtype),
+        Bson::ObjectId(ref id) => writer.write_all(&id.bytes()).map_err(From::from),
         Bson::DateTime(ref v) => write_i64(writer, v.timestamp_millis()),
         Bson::Null => Ok(()),
+        Bson::RegularExpression(Regex {
+            ref pattern,
+            ref options,
+        }) => {
+            write_cstring(writer, pattern)?;
+            write_cstring(writer, options)
+        }
+        Bson::JavaScriptCode(ref code) => write_string(writer, code),
+        Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope {
+            ref code,
+            ref scope,
+        }) => {
+            let mut buf = Vec::new();
+            write_string(&mut buf, code)?;
+            scope.encode(&mut buf, options)?;
+
+            write_i32(writer, (buf.len() + mem::size_of::<i32>()) as i32)?;
+            writer.write_all(&buf)?;
+            Ok(())
+        }
+        Bson::Symbol(ref v) => write_string(writer, v),
         Bson::Decimal128(ref v) => {
             writer.write_all(&v.bytes)?;
             Ok(())
         }
+        Bson::Undefined => Ok(()),
+        Bson::MinKey => Ok(()),
+        Bson::MaxKey => Ok(()),
+        Bson::DbPointer(DbPointer {
+            ref namespace,
+            ref id,
+        }) => {
+            write_string(writer, namespace)?;
+            writer.write_all(&id.bytes()).map_err(From::from)
+        }
+        Bson::Unknown { ref bytes, .. } => writer.write_all(bytes).map_err(From::from),
     }
 }
 
@@ -249,8 +339,8 @@ where
         Bson::Document(doc) => Ok(doc),
         bson => Err(Error::SerializationError {
             message: format!(
-                "Could not be serialized to Document, got {:?} instead",
-                bson.element_type()
+                "Could not be serialized to Document, got {} instead",
+                TypeName(bson.tag())
             ),
         }),
     }
@@ -266,3 +356,18 @@ where
     value.serialize(&mut serializer)?;
     Ok(serializer.into_vec())
 }
+
+/// Serialize the given `T` as a BSON byte vector, configured with the provided options.
+///
+/// Unsigned integers are written according to [`SerializerOptions::unsigned_integers`], and
+/// elements of custom types are checked against [`SerializerOptions::codecs`].
+/// [`SerializerOptions::human_readable`] does not apply to BSON bytes and is ignored.
+#[inline]
+pub fn to_vec_with_options<T>(value: &T, options: SerializerOptions) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut serializer = raw::Serializer::new_with_options(options);
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_vec())
+}
@@ -10,10 +10,19 @@ use serde::{
 
 use self::value_serializer::{ValueSerializer, ValueType};
 
-use super::{write_binary, write_cstring, write_f64, write_i32, write_i64, write_string};
+use super::{
+    check_unknown_tag,
+    convert_unsigned,
+    write_binary,
+    write_cstring,
+    write_f64,
+    write_i32,
+    write_i64,
+    write_string,
+};
 use crate::{
     raw::{RAW_ARRAY_NEWTYPE, RAW_DOCUMENT_NEWTYPE},
-    ser::{Error, Result},
+    ser::{Error, Result, SerializerOptions},
     spec::{BinarySubtype, ElementType},
     uuid::UUID_NEWTYPE_NAME,
 };
@@ -30,6 +39,8 @@ pub(crate) struct Serializer {
 
     /// Hint provided by the type being serialized.
     hint: SerializerHint,
+
+    options: SerializerOptions,
 }
 
 /// Various bits of information that the serialized type can provide to the serializer to
@@ -56,10 +67,15 @@ impl SerializerHint {
 
 impl Serializer {
     pub(crate) fn new() -> Self {
+        Self::new_with_options(Default::default())
+    }
+
+    pub(crate) fn new_with_options(options: SerializerOptions) -> Self {
         Self {
             bytes: Vec::new(),
             type_index: 0,
             hint: SerializerHint::None,
+            options,
         }
     }
 
@@ -94,6 +110,22 @@ impl Serializer {
         Ok(())
     }
 
+    /// Retroactively set the type tag of the most recently serialized element to that of an
+    /// unknown element.
+    #[inline]
+    fn update_unknown_element_tag(&mut self, tag: u8) -> Result<()> {
+        check_unknown_tag(tag)?;
+        if self.type_index == 0 {
+            return Err(Error::custom(format!(
+                "attempted to encode a non-document type at the top level: unknown element {:#04x}",
+                tag
+            )));
+        }
+
+        self.bytes[self.type_index] = tag;
+        Ok(())
+    }
+
     /// Replace an i32 value at the given index with the given value.
     #[inline]
     fn replace_i32(&mut self, at: usize, with: i32) {
@@ -161,13 +193,17 @@ impl<'a> serde::Serializer for &'a mut Serializer {
 
     #[inline]
     fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
-        self.serialize_i64(v.into())
+        self.serialize_u64(v.into())
     }
 
     #[inline]
     fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
         use std::convert::TryFrom;
 
+        if let Some(converted) = convert_unsigned(v, self.options.unsigned_integers)? {
+            return converted.serialize(self);
+        }
+
         match i64::try_from(v) {
             Ok(ivalue) => self.serialize_i64(ivalue),
             Err(_) => Err(Error::UnsignedIntegerExceededRange(v)),
@@ -335,18 +371,32 @@ impl<'a> serde::Serializer for &'a mut Serializer {
     #[inline]
     fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
         let value_type = match name {
+            "$oid" => Some(ValueType::ObjectId),
             "$date" => Some(ValueType::DateTime),
             "$binary" => Some(ValueType::Binary),
+            "$regularExpression" => Some(ValueType::RegularExpression),
             "$timestamp" => Some(ValueType::Timestamp),
+            "$code" => Some(ValueType::JavaScriptCode),
+            "$codeWithScope" => Some(ValueType::JavaScriptCodeWithScope),
             "$numberDecimal" => Some(ValueType::Decimal128),
+            "$symbol" => Some(ValueType::Symbol),
+            "$undefined" => Some(ValueType::Undefined),
+            "$maxKey" => Some(ValueType::MaxKey),
+            "$minKey" => Some(ValueType::MinKey),
+            "$dbPointer" => Some(ValueType::DbPointer),
+            "$unknown" => Some(ValueType::Unknown),
             _ => None,
         };
 
-        self.update_element_type(
-            value_type
-                .map(Into::into)
-                .unwrap_or(ElementType::EmbeddedDocument),
-        )?;
+        match value_type {
+            // the tag of an unknown element is set by the value serializer once it reaches it
+            Some(ValueType::Unknown) => {}
+            _ => self.update_element_type(
+                value_type
+                    .map(Into::into)
+                    .unwrap_or(ElementType::EmbeddedDocument),
+            )?,
+        }
         match value_type {
             Some(vt) => Ok(StructSerializer::Value(ValueSerializer::new(self, vt))),
             None => Ok(StructSerializer::Document(DocumentSerializer::start(self)?)),
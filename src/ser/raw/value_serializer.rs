@@ -10,6 +10,8 @@ use crate::{
     raw::RAW_DOCUMENT_NEWTYPE,
     ser::{write_binary, write_cstring, write_i32, write_i64, write_string, Error, Result},
     spec::{BinarySubtype, ElementType},
+    Decimal128,
+    Regex,
 };
 
 use super::{document_serializer::DocumentSerializer, Serializer};
@@ -25,6 +27,8 @@ pub(crate) struct ValueSerializer<'a> {
 /// currently on.
 #[derive(Debug)]
 enum SerializationStep {
+    Oid,
+
     DateTime,
     DateTimeNumberLong,
 
@@ -39,6 +43,10 @@ enum SerializationStep {
         bytes: Vec<u8>,
     },
 
+    RegEx,
+    RegExPattern,
+    RegExOptions,
+
     Timestamp,
     TimestampTime,
     TimestampIncrement {
@@ -52,25 +60,60 @@ enum SerializationStep {
     },
     Decimal128,
     Decimal128Value,
+
+    Symbol,
+
+    DbPointer,
+    DbPointerRef,
+    DbPointerId,
+
+    MinKey,
+    MaxKey,
+    Undefined,
+
+    Unknown,
+    UnknownTag,
+    UnknownBytes,
+
     Done,
 }
 
 /// Enum of BSON "value" types that this serializer can serialize.
 #[derive(Debug, Clone, Copy)]
 pub(super) enum ValueType {
+    ObjectId,
     DateTime,
     Binary,
+    RegularExpression,
     Timestamp,
+    JavaScriptCode,
+    JavaScriptCodeWithScope,
     Decimal128,
+    Symbol,
+    DbPointer,
+    MaxKey,
+    MinKey,
+    Undefined,
+    Unknown,
 }
 
 impl From<ValueType> for ElementType {
     fn from(vt: ValueType) -> Self {
         match vt {
             ValueType::Binary => ElementType::Binary,
+            ValueType::ObjectId => ElementType::ObjectId,
             ValueType::DateTime => ElementType::DateTime,
             ValueType::Decimal128 => ElementType::Decimal128,
             ValueType::Timestamp => ElementType::Timestamp,
+            ValueType::RegularExpression => ElementType::RegularExpression,
+            ValueType::JavaScriptCode => ElementType::JavaScriptCode,
+            ValueType::JavaScriptCodeWithScope => ElementType::JavaScriptCodeWithScope,
+            ValueType::Symbol => ElementType::Symbol,
+            ValueType::DbPointer => ElementType::DbPointer,
+            ValueType::MaxKey => ElementType::MaxKey,
+            ValueType::MinKey => ElementType::MinKey,
+            ValueType::Undefined => ElementType::Undefined,
+            ValueType::Unknown => unreachable!("unknown elements have no element type"),
         }
     }
 }
@@ -78,10 +121,20 @@ impl From<ValueType> for ElementType {
 impl<'a> ValueSerializer<'a> {
     pub(super) fn new(rs: &'a mut Serializer, value_type: ValueType) -> Self {
         let state = match value_type {
+            ValueType::ObjectId => SerializationStep::Oid,
             ValueType::DateTime => SerializationStep::DateTime,
             ValueType::Binary => SerializationStep::Binary,
+            ValueType::RegularExpression => SerializationStep::RegEx,
             ValueType::Timestamp => SerializationStep::Timestamp,
+            ValueType::JavaScriptCode => SerializationStep::Code,
+            ValueType::JavaScriptCodeWithScope => SerializationStep::CodeWithScopeCode,
             ValueType::Decimal128 => SerializationStep::Decimal128,
+            ValueType::Symbol => SerializationStep::Symbol,
+            ValueType::DbPointer => SerializationStep::DbPointer,
+            ValueType::MaxKey => SerializationStep::MaxKey,
+            ValueType::MinKey => SerializationStep::MinKey,
+            ValueType::Undefined => SerializationStep::Undefined,
+            ValueType::Unknown => SerializationStep::Unknown,
         };
         Self {
             root_serializer: rs,
@@ -110,8 +163,11 @@ impl<'a, 'b> serde::Serializer for &'b mut ValueSerializer<'a> {
     type SerializeStructVariant = Impossible<(), Error>;
 
     #[inline]
-    fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
-        Err(self.invalid_step("bool"))
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        match self.state {
+            SerializationStep::Undefined if v => Ok(()),
+            _ => Err(self.invalid_step("bool")),
+        }
     }
 
     #[inline]
@@ -125,8 +181,11 @@ impl<'a, 'b> serde::Serializer for &'b mut ValueSerializer<'a> {
     }
 
     #[inline]
-    fn serialize_i32(self, _v: i32) -> Result<Self::Ok> {
-        Err(self.invalid_step("i32"))
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        match self.state {
+            SerializationStep::MinKey | SerializationStep::MaxKey if v == 1 => Ok(()),
+            _ => Err(self.invalid_step("i32")),
+        }
     }
 
     #[inline]
@@ -156,6 +215,11 @@ impl<'a, 'b> serde::Serializer for &'b mut ValueSerializer<'a> {
                 self.state = SerializationStep::Done;
                 Ok(())
             }
+            SerializationStep::UnknownTag => {
+                self.root_serializer.update_unknown_element_tag(v)?;
+                self.state = SerializationStep::UnknownBytes;
+                Ok(())
+            }
             _ => Err(self.invalid_step("u8")),
         }
     }
@@ -192,6 +256,14 @@ impl<'a, 'b> serde::Serializer for &'b mut ValueSerializer<'a> {
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
         match &self.state {
+            SerializationStep::Oid => {
+                let oid = ObjectId::parse_str(v).map_err(Error::custom)?;
+                self.root_serializer.bytes.write_all(&oid.bytes())?;
+            }
+            SerializationStep::Decimal128Value => {
+                let d: Decimal128 = v.parse().map_err(Error::custom)?;
+                self.root_serializer.bytes.write_all(&d.bytes())?;
+            }
             SerializationStep::DateTimeNumberLong => {
                 let millis: i64 = v.parse().map_err(Error::custom)?;
                 write_i64(&mut self.root_serializer.bytes, millis)?;
@@ -209,6 +281,25 @@ impl<'a, 'b> serde::Serializer for &'b mut ValueSerializer<'a> {
 
                 write_binary(&mut self.root_serializer.bytes, bytes.as_slice(), subtype)?;
             }
+            SerializationStep::RegExPattern => {
+                write_cstring(&mut self.root_serializer.bytes, v)?;
+            }
+            SerializationStep::RegExOptions => {
+                let options = Regex::normalize_options(v);
+                write_cstring(&mut self.root_serializer.bytes, options.as_str())?;
+            }
+            SerializationStep::Code => {
+                write_string(&mut self.root_serializer.bytes, v)?;
+            }
+            SerializationStep::CodeWithScopeCode => {
+                self.state = SerializationStep::CodeWithScopeScope {
+                    code: v.to_string(),
+                    raw: false,
+                };
+            }
+            SerializationStep::Symbol | SerializationStep::DbPointerRef => {
+                write_string(&mut self.root_serializer.bytes, v)?;
+            }
             s => {
                 return Err(Error::custom(format!(
                     "can't serialize string for step {:?}",
@@ -230,6 +321,25 @@ impl<'a, 'b> serde::Serializer for &'b mut ValueSerializer<'a> {
                 self.state = SerializationStep::RawBinarySubType { bytes: v.to_vec() };
                 Ok(())
             }
+            SerializationStep::CodeWithScopeScope { ref code, raw } if raw => {
+                let len = 4 + 4 + code.len() as i32 + 1 + v.len() as i32;
+                write_i32(&mut self.root_serializer.bytes, len)?;
+                write_string(&mut self.root_serializer.bytes, code)?;
+                self.root_serializer.bytes.write_all(v)?;
+                self.state = SerializationStep::Done;
+                Ok(())
+            }
+            SerializationStep::UnknownBytes => {
+                let tag = self.root_serializer.bytes[self.root_serializer.type_index];
+                self.root_serializer
+                    .options
+                    .codecs
+                    .check(tag, v)
+                    .map_err(Error::custom)?;
+                self.root_serializer.bytes.write_all(v)?;
+                self.state = SerializationStep::Done;
+                Ok(())
+            }
             _ => Err(self.invalid_step("&[u8]")),
         }
     }
@@ -371,6 +481,10 @@ impl<'a, 'b> SerializeStruct for &'b mut ValueSerializer<'a> {
         T: Serialize,
     {
         match (&self.state, key) {
+            (SerializationStep::Oid, "$oid") => {
+                value.serialize(&mut **self)?;
+                self.state = SerializationStep::Done;
+            }
             (SerializationStep::DateTime, "$date") => {
                 self.state = SerializationStep::DateTimeNumberLong;
                 value.serialize(&mut **self)?;
@@ -395,6 +509,18 @@ impl<'a, 'b> SerializeStruct for &'b mut ValueSerializer<'a> {
                 value.serialize(&mut **self)?;
                 self.state = SerializationStep::Done;
             }
+            (SerializationStep::RegEx, "$regularExpression") => {
+                self.state = SerializationStep::RegExPattern;
+                value.serialize(&mut **self)?;
+            }
+            (SerializationStep::RegExPattern, "pattern") => {
+                value.serialize(&mut **self)?;
+                self.state = SerializationStep::RegExOptions;
+            }
+            (SerializationStep::RegExOptions, "options") => {
+                value.serialize(&mut **self)?;
+                self.state = SerializationStep::Done;
+            }
             (SerializationStep::Timestamp, "$timestamp") => {
                 self.state = SerializationStep::TimestampTime;
                 value.serialize(&mut **self)?;
@@ -428,6 +554,39 @@ impl<'a, 'b> SerializeStruct for &'b mut ValueSerializer<'a> {
                 value.serialize(&mut **self)?;
                 self.state = SerializationStep::Done;
             }
+            (SerializationStep::Symbol, "$symbol") => {
+                value.serialize(&mut **self)?;
+                self.state = SerializationStep::Done;
+            }
+            (SerializationStep::DbPointer, "$dbPointer") => {
+                self.state = SerializationStep::DbPointerRef;
+                value.serialize(&mut **self)?;
+            }
+            (SerializationStep::DbPointerRef, "$ref") => {
+                value.serialize(&mut **self)?;
+                self.state = SerializationStep::DbPointerId;
+            }
+            (SerializationStep::DbPointerId, "$id") => {
+                // the id is written by the $oid step
+                self.state = SerializationStep::Oid;
+                value.serialize(&mut **self)?;
+            }
+            (SerializationStep::MinKey, "$minKey") | (SerializationStep::MaxKey, "$maxKey") => {
+                value.serialize(&mut **self)?;
+                self.state = SerializationStep::Done;
+            }
+            (SerializationStep::Undefined, "$undefined") => {
+                value.serialize(&mut **self)?;
+                self.state = SerializationStep::Done;
+            }
+            (SerializationStep::Unknown, "$unknown") => {
+                self.state = SerializationStep::UnknownTag;
+                value.serialize(&mut **self)?;
+            }
+            (SerializationStep::UnknownTag, "tag") | (SerializationStep::UnknownBytes, "bytes") => {
+                // state is updated in serialize
+                value.serialize(&mut **self)?;
+            }
             (SerializationStep::Done, k) => {
                 return Err(Error::custom(format!(
                     "expected to end serialization of type, got extra key \"{}\"",
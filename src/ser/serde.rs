@@ -13,7 +13,8 @@ use serde::ser::{
 use serde_bytes::Bytes;
 
 use crate::{
-    bson::{Array, Bson, Document, Timestamp},
+    bson::{Array, Bson, DbPointer, Document, JavaScriptCodeWithScope, Regex, Timestamp},
+    codec::ElementCodecRegistry,
     datetime::DateTime,
     extjson,
     oid::ObjectId,
@@ -24,7 +25,7 @@ use crate::{
     Decimal128,
 };
 
-use super::{to_bson_with_options, Error};
+use super::{convert_unsigned, to_bson_with_options, Error};
 
 impl Serialize for ObjectId {
     #[inline]
@@ -71,16 +72,78 @@ impl Serialize for Bson {
             Bson::UInt64(v) => serializer.serialize_u64(*v),
             Bson::DateTime(dt) => dt.serialize(serializer),
             Bson::Binary(b) => b.serialize(serializer),
+            Bson::ObjectId(oid) => oid.serialize(serializer),
             Bson::Timestamp(t) => t.serialize(serializer),
+            Bson::RegularExpression(re) => re.serialize(serializer),
+            Bson::JavaScriptCode(code) => {
+                let mut state = serializer.serialize_struct("$code", 1)?;
+                state.serialize_field("$code", code)?;
+                state.end()
+            }
+            Bson::JavaScriptCodeWithScope(code_w_scope) => code_w_scope.serialize(serializer),
+            Bson::Symbol(v) => {
+                let mut state = serializer.serialize_struct("$symbol", 1)?;
+                state.serialize_field("$symbol", v)?;
+                state.end()
+            }
             Bson::Decimal128(d) => {
                 let mut state = serializer.serialize_struct("$numberDecimal", 1)?;
                 state.serialize_field("$numberDecimalBytes", Bytes::new(&d.bytes))?;
                 state.end()
             }
+            Bson::Undefined => {
+                let mut state = serializer.serialize_struct("$undefined", 1)?;
+                state.serialize_field("$undefined", &true)?;
+                state.end()
+            }
+            Bson::MaxKey => {
+                let mut state = serializer.serialize_struct("$maxKey", 1)?;
+                state.serialize_field("$maxKey", &1)?;
+                state.end()
+            }
+            Bson::MinKey => {
+                let mut state = serializer.serialize_struct("$minKey", 1)?;
+                state.serialize_field("$minKey", &1)?;
+                state.end()
+            }
+            Bson::DbPointer(p) => p.serialize(serializer),
+            Bson::Unknown { tag, bytes } => serialize_unknown(serializer, *tag, bytes),
         }
     }
 }
 
+/// Serializes an element of unknown type as
+/// `{ "$unknown": { "tag": <hex string>, "base64": <base64 encoded data> } }`, or as
+/// `{ "$unknown": { "tag": <u8>, "bytes": <bytes> } }` if the serializer is not human readable.
+pub(crate) fn serialize_unknown<S>(serializer: S, tag: u8, bytes: &[u8]) -> Result<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    if serializer.is_human_readable() {
+        let mut state = serializer.serialize_struct("$unknown", 1)?;
+        let body = extjson::models::UnknownBody {
+            tag: hex::encode([tag]),
+            base64: base64::encode(bytes),
+        };
+        state.serialize_field("$unknown", &body)?;
+        state.end()
+    } else {
+        #[derive(serde::Serialize)]
+        struct BorrowedUnknownBody<'a> {
+            tag: u8,
+            bytes: &'a Bytes,
+        }
+
+        let mut state = serializer.serialize_struct("$unknown", 1)?;
+        let body = BorrowedUnknownBody {
+            tag,
+            bytes: Bytes::new(bytes),
+        };
+        state.serialize_field("$unknown", &body)?;
+        state.end()
+    }
+}
+
 /// Serde Serializer
 #[non_exhaustive]
 pub struct Serializer {
@@ -94,6 +157,50 @@ pub struct SerializerOptions {
     /// Whether the [`Serializer`] should present itself as human readable or not.
     /// The default value is true.
     pub human_readable: Option<bool>,
+
+    /// Codecs that elements of custom types are checked against by the binary encoders
+    /// [`crate::to_vec_with_options`] and [`Document::to_writer_with_options`]. Elements whose
+    /// tag has no codec are written as they are.
+    pub codecs: ElementCodecRegistry,
+
+    /// How unsigned integers are written. The default value is
+    /// [`UnsignedIntegerPolicy::Native`].
+    pub unsigned_integers: UnsignedIntegerPolicy,
+}
+
+/// How the serializers write unsigned integers.
+///
+/// The UInt32 (0x14) and UInt64 (0x15) element types are specific to this crate; MongoDB and
+/// other BSON implementations reject documents containing them. Every policy other than
+/// [`UnsignedIntegerPolicy::Native`] writes unsigned integers with the standard element types
+/// instead: as an Int32 if the value fits in one, otherwise as an Int64, and the policy decides
+/// what happens to values above `i64::MAX`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnsignedIntegerPolicy {
+    /// [`Bson::UInt32`] and [`Bson::UInt64`] values are written as UInt32 and UInt64 elements.
+    /// [`crate::to_bson`] serializes `u64` values as [`Bson::UInt64`] and `u32` values as
+    /// [`Bson::Int64`], and [`crate::to_vec`] writes both as Int64, failing for values above
+    /// `i64::MAX`.
+    Native,
+
+    /// Values above `i64::MAX` fail to serialize with
+    /// [`Error::UnsignedIntegerExceededRange`](crate::ser::Error::UnsignedIntegerExceededRange).
+    Int64OrError,
+
+    /// Values above `i64::MAX` are written as Decimal128, which represents them exactly.
+    Int64OrDecimal128,
+
+    /// Values above `i64::MAX` are written as a Double, rounded to the nearest representable
+    /// value.
+    Int64OrDouble,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for UnsignedIntegerPolicy {
+    fn default() -> Self {
+        UnsignedIntegerPolicy::Native
+    }
 }
 
 impl SerializerOptions {
@@ -117,6 +224,18 @@ impl SerializerOptionsBuilder {
         self
     }
 
+    /// Set the value for [`SerializerOptions::codecs`].
+    pub fn codecs(mut self, value: ElementCodecRegistry) -> Self {
+        self.options.codecs = value;
+        self
+    }
+
+    /// Set the value for [`SerializerOptions::unsigned_integers`].
+    pub fn unsigned_integers(mut self, value: UnsignedIntegerPolicy) -> Self {
+        self.options.unsigned_integers = value;
+        self
+    }
+
     /// Consume this builder and produce a [`SerializerOptions`].
     pub fn build(self) -> SerializerOptions {
         self.options
@@ -182,7 +301,10 @@ impl ser::Serializer for Serializer {
 
     #[inline]
     fn serialize_u32(self, value: u32) -> crate::ser::Result<Bson> {
-        Ok(Bson::Int64(value as i64))
+        Ok(
+            convert_unsigned(value.into(), self.options.unsigned_integers)?
+                .unwrap_or(Bson::Int64(value as i64)),
+        )
     }
 
     #[inline]
@@ -192,7 +314,7 @@ impl ser::Serializer for Serializer {
 
     #[inline]
     fn serialize_u64(self, value: u64) -> crate::ser::Result<Bson> {
-        Ok(Bson::UInt64(value))
+        Ok(convert_unsigned(value, self.options.unsigned_integers)?.unwrap_or(Bson::UInt64(value)))
     }
 
     #[inline]
@@ -610,6 +732,50 @@ impl Serialize for Timestamp {
     }
 }
 
+impl Serialize for Regex {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let mut state = serializer.serialize_struct("$regularExpression", 1)?;
+        let body = extjson::models::RegexBody {
+            pattern: self.pattern.clone(),
+            options: self.options.clone(),
+        };
+        state.serialize_field("$regularExpression", &body)?;
+        state.end()
+    }
+}
+
+impl Serialize for JavaScriptCodeWithScope {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let mut state = serializer.serialize_struct("$codeWithScope", 2)?;
+        state.serialize_field("$code", &self.code)?;
+        state.serialize_field("$scope", &self.scope)?;
+        state.end()
+    }
+}
+
+impl Serialize for DbPointer {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let mut state = serializer.serialize_struct("$dbPointer", 1)?;
+        let body = extjson::models::DbPointerBody {
+            ref_ns: self.namespace.clone(),
+            id: self.id.into(),
+        };
+        state.serialize_field("$dbPointer", &body)?;
+        state.end()
+    }
+}
 
 impl Serialize for Binary {
     #[inline]
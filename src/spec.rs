@@ -28,9 +28,16 @@ const ELEMENT_TYPE_UTF8_STRING: u8 = 0x02;
 const ELEMENT_TYPE_EMBEDDED_DOCUMENT: u8 = 0x03;
 const ELEMENT_TYPE_ARRAY: u8 = 0x04;
 const ELEMENT_TYPE_BINARY: u8 = 0x05;
+const ELEMENT_TYPE_UNDEFINED: u8 = 0x06; // Deprecated
+const ELEMENT_TYPE_OBJECT_ID: u8 = 0x07;
 const ELEMENT_TYPE_BOOLEAN: u8 = 0x08;
 const ELEMENT_TYPE_UTC_DATETIME: u8 = 0x09;
 const ELEMENT_TYPE_NULL_VALUE: u8 = 0x0A;
+const ELEMENT_TYPE_REGULAR_EXPRESSION: u8 = 0x0B;
+const ELEMENT_TYPE_DBPOINTER: u8 = 0x0C; // Deprecated
+const ELEMENT_TYPE_JAVASCRIPT_CODE: u8 = 0x0D;
+const ELEMENT_TYPE_SYMBOL: u8 = 0x0E; // Deprecated
+const ELEMENT_TYPE_JAVASCRIPT_CODE_WITH_SCOPE: u8 = 0x0F;
 const ELEMENT_TYPE_32BIT_INTEGER: u8 = 0x10;
 const ELEMENT_TYPE_TIMESTAMP: u8 = 0x11;
 const ELEMENT_TYPE_64BIT_INTEGER: u8 = 0x12;
@@ -40,6 +47,9 @@ const ELEMENT_TYPE_128BIT_DECIMAL: u8 = 0x13;
 const ELEMENT_TYPE_32BIT_UINTEGER: u8 = 0x14;
 const ELEMENT_TYPE_64BIT_UINTEGER: u8 = 0x15;
 
+const ELEMENT_TYPE_MINKEY: u8 = 0xFF;
+const ELEMENT_TYPE_MAXKEY: u8 = 0x7F;
+
 const BINARY_SUBTYPE_GENERIC: u8 = 0x00;
 const BINARY_SUBTYPE_FUNCTION: u8 = 0x01;
 const BINARY_SUBTYPE_BINARY_OLD: u8 = 0x02;
@@ -66,12 +76,26 @@ pub enum ElementType {
     Array = ELEMENT_TYPE_ARRAY,
     /// Binary data
     Binary = ELEMENT_TYPE_BINARY,
+    /// Deprecated. Undefined (value)
+    Undefined = ELEMENT_TYPE_UNDEFINED,
+    /// [ObjectId](http://dochub.mongodb.org/core/objectids)
+    ObjectId = ELEMENT_TYPE_OBJECT_ID,
     /// Bool value
     Boolean = ELEMENT_TYPE_BOOLEAN,
     /// UTC datetime
     DateTime = ELEMENT_TYPE_UTC_DATETIME,
     /// Null value
     Null = ELEMENT_TYPE_NULL_VALUE,
+    /// Regular expression
+    RegularExpression = ELEMENT_TYPE_REGULAR_EXPRESSION,
+    /// Deprecated.
+    DbPointer = ELEMENT_TYPE_DBPOINTER,
+    /// JavaScript code
+    JavaScriptCode = ELEMENT_TYPE_JAVASCRIPT_CODE,
+    /// Deprecated.
+    Symbol = ELEMENT_TYPE_SYMBOL,
+    /// JavaScript code with scope
+    JavaScriptCodeWithScope = ELEMENT_TYPE_JAVASCRIPT_CODE_WITH_SCOPE,
     /// 32-bit integer
     Int32 = ELEMENT_TYPE_32BIT_INTEGER,
     /// Timestamp
@@ -84,6 +108,10 @@ pub enum ElementType {
     UInt64 = ELEMENT_TYPE_64BIT_UINTEGER,
     /// [128-bit decimal floating point](https://github.com/mongodb/specifications/blob/master/source/bson-decimal128/decimal128.rst)
     Decimal128 = ELEMENT_TYPE_128BIT_DECIMAL,
+    /// Max key
+    MaxKey = ELEMENT_TYPE_MAXKEY,
+    /// Min key
+    MinKey = ELEMENT_TYPE_MINKEY,
 }
 
 impl ElementType {
@@ -97,20 +125,44 @@ impl ElementType {
             ELEMENT_TYPE_EMBEDDED_DOCUMENT => EmbeddedDocument,
             ELEMENT_TYPE_ARRAY => Array,
             ELEMENT_TYPE_BINARY => Binary,
+            ELEMENT_TYPE_UNDEFINED => Undefined,
+            ELEMENT_TYPE_OBJECT_ID => ObjectId,
             ELEMENT_TYPE_BOOLEAN => Boolean,
             ELEMENT_TYPE_UTC_DATETIME => Self::DateTime,
             ELEMENT_TYPE_NULL_VALUE => Self::Null,
+            ELEMENT_TYPE_REGULAR_EXPRESSION => RegularExpression,
+            ELEMENT_TYPE_DBPOINTER => DbPointer,
+            ELEMENT_TYPE_JAVASCRIPT_CODE => JavaScriptCode,
+            ELEMENT_TYPE_SYMBOL => Symbol,
+            ELEMENT_TYPE_JAVASCRIPT_CODE_WITH_SCOPE => JavaScriptCodeWithScope,
             ELEMENT_TYPE_32BIT_INTEGER => Int32,
             ELEMENT_TYPE_TIMESTAMP => Timestamp,
             ELEMENT_TYPE_64BIT_INTEGER => Int64,
             ELEMENT_TYPE_32BIT_UINTEGER => UInt32,
             ELEMENT_TYPE_64BIT_UINTEGER => UInt64,
             ELEMENT_TYPE_128BIT_DECIMAL => Decimal128,
+            ELEMENT_TYPE_MAXKEY => MaxKey,
+            ELEMENT_TYPE_MINKEY => MinKey,
             _ => return None,
         })
     }
 }
 
+/// The on-the-wire size of an element value whose type is not known to this crate.
+///
+/// Without knowing the size of a value, a decoder cannot find where the next element begins, so
+/// the size of each unrecognized element type has to be supplied up front.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ElementSize {
+    /// The value is always exactly this many bytes long.
+    Fixed(usize),
+
+    /// The value starts with a little-endian `i32` giving its total length in bytes, including
+    /// the four bytes of the length itself, in the same way as an embedded document.
+    LengthPrefixed,
+}
+
 /// The available binary subtypes, plus a user-defined slot.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[non_exhaustive]
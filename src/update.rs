@@ -0,0 +1,1305 @@
+//! MongoDB update operators applied to [`Document`]s in memory.
+//!
+//! An [`Update`] is parsed from an update document such as `{ "$set": { "a.b": 1 } }` and can
+//! then be applied to any number of documents. The following operators are supported:
+//!
+//! | Operator       | Effect                                                                  |
+//! |----------------|-------------------------------------------------------------------------|
+//! | `$set`         | Set a field, creating embedded documents on the way                     |
+//! | `$unset`       | Remove a field, or set an array element to null                         |
+//! | `$inc`, `$mul` | Add to or multiply a number                                             |
+//! | `$min`, `$max` | Replace a value if the given one is smaller or greater                  |
+//! | `$rename`      | Move a field to another path                                            |
+//! | `$push`        | Append to an array; supports `$each`, `$position`, `$sort` and `$slice` |
+//! | `$addToSet`    | Append to an array unless the value is already present, with `$each`    |
+//! | `$pull`        | Remove the elements of an array that equal a value or match a filter    |
+//! | `$pop`         | Remove the first (`-1`) or last (`1`) element of an array               |
+//! | `$currentDate` | Set a field to the current date or timestamp                            |
+//!
+//! The semantics follow those of the server:
+//!
+//! * Fields are updated in the order of their paths rather than the order of the operators,
+//!   comparing numeric segments as numbers, so new fields are appended in that order.
+//! * Two operators may not update the same path, or a path and one of its prefixes.
+//! * Arithmetic promotes `Int32` to `Int64` on overflow and otherwise keeps the wider type of
+//!   `Int32`, `Int64`, `Double` and `Decimal128`; an `Int64` overflow is an error. Unsigned
+//!   integers, which the server does not have, widen to the narrowest of `UInt32`, `Int64` and
+//!   `UInt64` that holds the result.
+//! * `$min`, `$max`, `$addToSet`, `$pull` and the `$sort` modifier compare values in the server's
+//!   cross-type order, in which numbers of all types compare by value.
+//! * The all-positional segment `$[]` applies an operator to every element of an array.
+//! * Setting an element past the end of an array pads it with nulls, but not with more than
+//!   1,500,000 of them.
+//! * The `_id` field cannot be changed once it exists.
+//!
+//! An update is applied atomically: if it fails, the document is left unchanged.
+//!
+//! ```rust
+//! use bson::{doc, update::Update};
+//!
+//! let mut doc = doc! { "_id": 1, "n": 1, "scores": [1, 5, 3], "tags": ["a"] };
+//! let update = Update::parse(&doc! {
+//!     "$inc": { "n": 1_i64 },
+//!     "$push": { "scores": { "$each": [4, 2], "$sort": -1, "$slice": 3 } },
+//!     "$addToSet": { "tags": "a" },
+//!     "$set": { "meta.seen": true },
+//! })?;
+//!
+//! let result = update.apply(&mut doc)?;
+//! assert_eq!(
+//!     doc,
+//!     doc! { "_id": 1, "n": 2_i64, "scores": [5, 4, 3], "tags": ["a"], "meta": { "seen": true } }
+//! );
+//! let modified: Vec<String> = result.modified_paths().iter().map(|p| p.to_string()).collect();
+//! assert_eq!(modified, vec!["meta.seen", "n", "scores"]);
+//!
+//! let err = Update::parse(&doc! { "$inc": { "tags": 1 } })?.apply(&mut doc).unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "update failed in $inc at \"tags\": cannot apply $inc to a value of non-numeric type Array"
+//! );
+//! # Ok::<(), bson::update::Error>(())
+//! ```
+
+use std::{cmp::Ordering, convert::TryFrom, fmt};
+
+use crate::{
+    filter::ElementMatcher,
+    ordering::{self, Number},
+    path::{self, parse_index, KeyPath, TypeName},
+    Array,
+    Bson,
+    DateTime,
+    Decimal128,
+    Document,
+    Timestamp,
+};
+
+/// An error that occurs when parsing or applying an [`Update`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// The type of error that was encountered.
+    pub kind: ErrorKind,
+
+    /// The operator in which the error was encountered, if any.
+    operator: Option<String>,
+
+    /// The path at which the error was encountered, if any.
+    path: Option<String>,
+}
+
+impl Error {
+    fn new(kind: ErrorKind, operator: Option<&str>, path: Option<String>) -> Self {
+        Self {
+            kind,
+            operator: operator.map(String::from),
+            path,
+        }
+    }
+
+    /// The operator in which the error was encountered, if any.
+    pub fn operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+
+    /// The path at which the error was encountered, if any. Positional segments are replaced
+    /// with the index of the element being updated.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+}
+
+/// The different categories of errors that can occur when parsing or applying an [`Update`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The update document is malformed, e.g. it uses an unknown operator, gives an operator an
+    /// invalid argument or updates conflicting paths.
+    #[non_exhaustive]
+    InvalidUpdate { message: String },
+
+    /// The path cannot be created in the document, because it passes through a value that is
+    /// neither a document nor an array, names a field of an array, or applies `$[]` to a value
+    /// that is not an array.
+    #[non_exhaustive]
+    PathNotViable { message: String },
+
+    /// The operator cannot be applied to the type of the value found at the path.
+    #[non_exhaustive]
+    TypeMismatch { message: String },
+
+    /// The result of `$inc` or `$mul` cannot be represented.
+    #[non_exhaustive]
+    Overflow { message: String },
+
+    /// Setting an array element would pad the array with more than 1,500,000 nulls, which the
+    /// server refuses to do.
+    #[non_exhaustive]
+    BackfillLimit { message: String },
+
+    /// The update would change the `_id` field of the document.
+    ImmutableField,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("update failed")?;
+        if let Some(operator) = &self.operator {
+            write!(f, " in {}", operator)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, " at {:?}", path)?;
+        }
+        f.write_str(": ")?;
+
+        match &self.kind {
+            ErrorKind::InvalidUpdate { message }
+            | ErrorKind::PathNotViable { message }
+            | ErrorKind::TypeMismatch { message }
+            | ErrorKind::Overflow { message }
+            | ErrorKind::BackfillLimit { message } => f.write_str(message),
+            ErrorKind::ImmutableField => f.write_str("the _id field cannot be changed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Alias for `Result<T, update::Error>`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+fn invalid(message: impl Into<String>) -> ErrorKind {
+    ErrorKind::InvalidUpdate {
+        message: message.into(),
+    }
+}
+
+/// A parsed update document, which can be applied to documents with [`Update::apply`]. See the
+/// [`update`](crate::update) module for the supported operators.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Update {
+    operations: Vec<Operation>,
+}
+
+/// The outcome of applying an [`Update`] to a document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UpdateResult {
+    modified_paths: Vec<KeyPath>,
+}
+
+impl UpdateResult {
+    /// The paths whose values were changed, in the order they were updated. Positional segments
+    /// are replaced with the index of each element that was changed, and a `$rename` reports both
+    /// its source and its target.
+    pub fn modified_paths(&self) -> &[KeyPath] {
+        &self.modified_paths
+    }
+
+    /// Whether the update changed the document.
+    pub fn is_modified(&self) -> bool {
+        !self.modified_paths.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Operation {
+    operator: &'static str,
+    path: Vec<String>,
+    action: Action,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Action {
+    Set(Bson),
+    Unset,
+    Inc(Bson),
+    Mul(Bson),
+    Min(Bson),
+    Max(Bson),
+    Rename(Vec<String>),
+    Push(Push),
+    AddToSet(Array),
+    Pull(ElementMatcher),
+    Pop { first: bool },
+    CurrentDate { timestamp: bool },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Push {
+    each: Array,
+    position: Option<i64>,
+    sort: Option<PushSort>,
+    slice: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PushSort {
+    /// Sort the elements themselves, descending if true.
+    Values(bool),
+
+    /// Sort embedded documents by the values at these paths, each descending if true.
+    Fields(Vec<(Vec<String>, bool)>),
+}
+
+const OPERATORS: &[&str] = &[
+    "$set",
+    "$unset",
+    "$inc",
+    "$mul",
+    "$min",
+    "$max",
+    "$rename",
+    "$push",
+    "$addToSet",
+    "$pull",
+    "$pop",
+    "$currentDate",
+];
+
+impl Update {
+    /// Parse an update document, which must consist of update operators mapped to documents of
+    /// paths and arguments.
+    ///
+    /// This fails if an operator is unknown, an argument is invalid, or two paths conflict.
+    pub fn parse(update: &Document) -> Result<Self> {
+        if update.is_empty() {
+            return Err(Error::new(
+                invalid("an update document must contain at least one operator"),
+                None,
+                None,
+            ));
+        }
+
+        let mut operations = Vec::new();
+        for (name, fields) in update {
+            let operator = match OPERATORS.iter().find(|o| **o == name) {
+                Some(operator) => *operator,
+                None => {
+                    return Err(Error::new(
+                        invalid("unknown update operator"),
+                        Some(name),
+                        None,
+                    ))
+                }
+            };
+            let fields = match fields {
+                Bson::Document(fields) => fields,
+                other => {
+                    return Err(Error::new(
+                        invalid(format!(
+                            "expected a document of fields but found {}",
+                            TypeName(other.tag())
+                        )),
+                        Some(operator),
+                        None,
+                    ))
+                }
+            };
+
+            for (field, argument) in fields {
+                let error = |kind| Error::new(kind, Some(operator), Some(field.clone()));
+                let path = parse_path(field, operator != "$rename").map_err(error)?;
+                let action = parse_action(operator, &path, argument).map_err(error)?;
+                operations.push(Operation {
+                    operator,
+                    path,
+                    action,
+                });
+            }
+        }
+
+        check_conflicts(&operations)?;
+        operations.sort_by(|a, b| compare_paths(&a.path, &b.path));
+        Ok(Self { operations })
+    }
+
+    /// Apply this update to `doc`. If it fails, `doc` is left unchanged.
+    pub fn apply(&self, doc: &mut Document) -> Result<UpdateResult> {
+        let mut updated = doc.clone();
+        let mut result = UpdateResult::default();
+        for operation in &self.operations {
+            let id = updated.get("_id").cloned();
+            operation.apply(&mut updated, &mut result.modified_paths)?;
+            if let Some(id) = id {
+                if !matches!(updated.get("_id"), Some(new) if identical(&id, new)) {
+                    return Err(Error::new(
+                        ErrorKind::ImmutableField,
+                        Some(operation.operator),
+                        Some("_id".to_string()),
+                    ));
+                }
+            }
+        }
+        *doc = updated;
+        Ok(result)
+    }
+}
+
+fn parse_path(field: &str, positional: bool) -> std::result::Result<Vec<String>, ErrorKind> {
+    let mut path = Vec::new();
+    for segment in field.split('.') {
+        if segment.is_empty() {
+            return Err(invalid("update paths must not contain empty segments"));
+        } else if segment == "$[]" {
+            if !positional {
+                return Err(invalid("positional segments are not allowed in this path"));
+            }
+        } else if segment == "$" {
+            return Err(invalid(
+                "the positional operator '$' requires a query and is not supported",
+            ));
+        } else if segment.starts_with("$[") {
+            return Err(invalid(
+                "filtered positional operators require array filters and are not supported",
+            ));
+        } else if segment.starts_with('$') {
+            return Err(invalid(format!(
+                "the segment '{}' must not start with '$'",
+                segment
+            )));
+        }
+        path.push(segment.to_string());
+    }
+    Ok(path)
+}
+
+/// The value of `value` as an integer, if it is an integer or a double without a fractional
+/// part.
+fn as_integer(value: &Bson) -> Option<i64> {
+    match *value {
+        Bson::Int32(i) => Some(i.into()),
+        Bson::Int64(i) => Some(i),
+        Bson::Double(d) if d.fract() == 0.0 && d.abs() < i64::MAX as f64 => Some(d as i64),
+        _ => None,
+    }
+}
+
+fn parse_action(
+    operator: &str,
+    path: &[String],
+    argument: &Bson,
+) -> std::result::Result<Action, ErrorKind> {
+    let numeric = || match ordering::number(argument) {
+        Some(_) => Ok(argument.clone()),
+        None => Err(invalid(format!(
+            "{} requires a numeric argument but found {}",
+            operator, argument
+        ))),
+    };
+
+    let action = match operator {
+        "$set" => Action::Set(argument.clone()),
+        "$unset" => Action::Unset,
+        "$inc" => Action::Inc(numeric()?),
+        "$mul" => Action::Mul(numeric()?),
+        "$min" => Action::Min(argument.clone()),
+        "$max" => Action::Max(argument.clone()),
+        "$rename" => {
+            let target = match argument {
+                Bson::String(target) => parse_path(target, false)?,
+                other => {
+                    return Err(invalid(format!(
+                        "the new name must be a string but found {}",
+                        other
+                    )))
+                }
+            };
+            if target.starts_with(path) || path.starts_with(&target) {
+                return Err(invalid(
+                    "the source and target of $rename must not be on the same path",
+                ));
+            }
+            Action::Rename(target)
+        }
+        "$push" => Action::Push(parse_push(argument)?),
+        "$addToSet" => match argument {
+            Bson::Document(modifiers) if modifiers.contains_key("$each") => {
+                if modifiers.len() > 1 {
+                    return Err(invalid("$addToSet only supports the $each modifier"));
+                }
+                match modifiers.get("$each") {
+                    Some(Bson::Array(each)) => Action::AddToSet(each.clone()),
+                    _ => return Err(invalid("$each requires an array")),
+                }
+            }
+            value => Action::AddToSet(vec![value.clone()]),
+        },
+        "$pull" => match ElementMatcher::for_pull(argument) {
+            Ok(condition) => Action::Pull(condition),
+            Err(e) => return Err(invalid(e.to_string())),
+        },
+        "$pop" => match as_integer(argument) {
+            Some(1) => Action::Pop { first: false },
+            Some(-1) => Action::Pop { first: true },
+            _ => {
+                return Err(invalid(format!(
+                    "$pop expects 1 or -1 but found {}",
+                    argument
+                )))
+            }
+        },
+        "$currentDate" => match argument {
+            Bson::Boolean(_) => Action::CurrentDate { timestamp: false },
+            Bson::Document(spec) if spec.len() == 1 => match spec.get("$type") {
+                Some(Bson::String(t)) if t == "date" => Action::CurrentDate { timestamp: false },
+                Some(Bson::String(t)) if t == "timestamp" => {
+                    Action::CurrentDate { timestamp: true }
+                }
+                _ => {
+                    return Err(invalid(
+                        "$currentDate expects true or { $type: \"date\" | \"timestamp\" }",
+                    ))
+                }
+            },
+            _ => {
+                return Err(invalid(
+                    "$currentDate expects true or { $type: \"date\" | \"timestamp\" }",
+                ))
+            }
+        },
+        _ => unreachable!("operators are checked against OPERATORS"),
+    };
+    Ok(action)
+}
+
+fn parse_push(argument: &Bson) -> std::result::Result<Push, ErrorKind> {
+    let modifiers = match argument {
+        Bson::Document(modifiers) if modifiers.keys().any(|k| k.starts_with('$')) => modifiers,
+        value => {
+            return Ok(Push {
+                each: vec![value.clone()],
+                position: None,
+                sort: None,
+                slice: None,
+            })
+        }
+    };
+
+    let mut push = Push {
+        each: match modifiers.get("$each") {
+            Some(Bson::Array(each)) => each.clone(),
+            Some(_) => return Err(invalid("$each requires an array")),
+            None => return Err(invalid("the modifiers of $push require $each")),
+        },
+        position: None,
+        sort: None,
+        slice: None,
+    };
+    for (modifier, value) in modifiers {
+        match modifier.as_str() {
+            "$each" => {}
+            "$position" | "$slice" => {
+                let value = as_integer(value).ok_or_else(|| {
+                    invalid(format!(
+                        "{} requires an integer but found {}",
+                        modifier, value
+                    ))
+                })?;
+                if modifier == "$position" {
+                    push.position = Some(value);
+                } else {
+                    push.slice = Some(value);
+                }
+            }
+            "$sort" => push.sort = Some(parse_sort(value)?),
+            other => return Err(invalid(format!("unknown $push modifier {}", other))),
+        }
+    }
+    Ok(push)
+}
+
+fn parse_sort(value: &Bson) -> std::result::Result<PushSort, ErrorKind> {
+    let direction = |value: &Bson| match as_integer(value) {
+        Some(1) => Ok(false),
+        Some(-1) => Ok(true),
+        _ => Err(invalid(format!(
+            "$sort directions must be 1 or -1 but found {}",
+            value
+        ))),
+    };
+
+    match value {
+        Bson::Document(fields) if !fields.is_empty() => fields
+            .iter()
+            .map(|(field, value)| {
+                let path = parse_path(field, false)?;
+                Ok((path, direction(value)?))
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map(PushSort::Fields),
+        Bson::Document(_) => Err(invalid("$sort requires at least one field")),
+        value => direction(value).map(PushSort::Values),
+    }
+}
+
+/// Compare two paths segment by segment, comparing numeric segments as numbers.
+fn compare_paths(a: &[String], b: &[String]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        let ordering = match (parse_index(a), parse_index(b)) {
+            (Some(x), Some(y)) => x.cmp(&y).then_with(|| a.cmp(b)),
+            _ => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Fail if two operations update the same path or a path and one of its prefixes, treating
+/// `$[]` as any index.
+fn check_conflicts(operations: &[Operation]) -> Result<()> {
+    let mut paths: Vec<(&str, &[String])> = Vec::new();
+    for operation in operations {
+        paths.push((operation.operator, &operation.path));
+        if let Action::Rename(target) = &operation.action {
+            paths.push((operation.operator, target));
+        }
+    }
+
+    for (i, (operator, path)) in paths.iter().enumerate() {
+        for (_, other) in &paths[..i] {
+            let overlaps = path.iter().zip(other.iter()).all(|(a, b)| {
+                a == b
+                    || (a == "$[]" && (b == "$[]" || parse_index(b).is_some()))
+                    || (b == "$[]" && parse_index(a).is_some())
+            });
+            if overlaps {
+                let shorter = if path.len() <= other.len() {
+                    path
+                } else {
+                    other
+                };
+                return Err(Error::new(
+                    invalid(format!(
+                        "updating the path '{}' would create a conflict at '{}'",
+                        path.join("."),
+                        shorter.join(".")
+                    )),
+                    Some(operator),
+                    Some(path.join(".")),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether two values are the same, including their types and the order of their fields.
+fn identical(a: &Bson, b: &Bson) -> bool {
+    match (a, b) {
+        (Bson::Document(a), Bson::Document(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|((ka, va), (kb, vb))| ka == kb && identical(va, vb))
+        }
+        (Bson::Array(a), Bson::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| identical(a, b))
+        }
+        (Bson::Double(a), Bson::Double(b)) => a.to_bits() == b.to_bits(),
+        _ => a == b,
+    }
+}
+
+fn equal(a: &Bson, b: &Bson) -> bool {
+    ordering::compare(a, b) == Ordering::Equal
+}
+
+fn get<'a>(doc: &'a Document, path: &[String]) -> Option<&'a Bson> {
+    let (first, rest) = path.split_first()?;
+    let mut value = doc.get(first)?;
+    for segment in rest {
+        value = match value {
+            Bson::Document(doc) => doc.get(segment)?,
+            Bson::Array(array) => array.get(parse_index(segment)?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn get_mut<'a>(doc: &'a mut Document, path: &[String]) -> Option<&'a mut Bson> {
+    let (first, rest) = path.split_first()?;
+    let mut value = doc.get_mut(first)?;
+    for segment in rest {
+        value = match value {
+            Bson::Document(doc) => doc.get_mut(segment)?,
+            Bson::Array(array) => array.get_mut(parse_index(segment)?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn not_viable(segment: &str, parent: &[String], value: &Bson) -> ErrorKind {
+    ErrorKind::PathNotViable {
+        message: format!(
+            "cannot create field '{}' in element {{ {}: {} }}",
+            segment,
+            parent.last().map_or("", String::as_str),
+            value
+        ),
+    }
+}
+
+/// Check that setting element `index` of `array` pads it with few enough nulls.
+fn check_backfill(array: &Array, index: usize) -> std::result::Result<(), ErrorKind> {
+    if path::exceeds_padding(array.len(), index) {
+        return Err(ErrorKind::BackfillLimit {
+            message: path::padding_message(array.len()),
+        });
+    }
+    Ok(())
+}
+
+/// Store `value` at `path`, creating embedded documents for missing fields and padding arrays
+/// with nulls.
+fn set(doc: &mut Document, path: &[String], value: Bson) -> std::result::Result<(), ErrorKind> {
+    enum Container<'a> {
+        Document(&'a mut Document),
+        Array(&'a mut Array),
+    }
+
+    let (last, init) = path.split_last().expect("update paths are never empty");
+    let mut container = Container::Document(doc);
+    for (i, segment) in init.iter().enumerate() {
+        let next = match container {
+            Container::Document(doc) => doc
+                .entry(segment.clone())
+                .or_insert_with(|| Bson::Document(Document::new())),
+            Container::Array(array) => {
+                let index = match parse_index(segment) {
+                    Some(index) => index,
+                    None => {
+                        return Err(not_viable(segment, &path[..i], &Bson::Array(array.clone())))
+                    }
+                };
+                check_backfill(array, index)?;
+                path::element_or_pad(array, index, || Bson::Document(Document::new()))
+            }
+        };
+        container = match next {
+            Bson::Document(doc) => Container::Document(doc),
+            Bson::Array(array) => Container::Array(array),
+            other => return Err(not_viable(&path[i + 1], &path[..=i], other)),
+        };
+    }
+
+    match container {
+        Container::Document(doc) => {
+            doc.insert(last.clone(), value);
+        }
+        Container::Array(array) => {
+            let index = match parse_index(last) {
+                Some(index) => index,
+                None => return Err(not_viable(last, init, &Bson::Array(array.clone()))),
+            };
+            check_backfill(array, index)?;
+            *path::element_or_pad(array, index, || Bson::Null) = value;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the value at `path`, or set it to null if it is an array element, returning whether
+/// the document changed.
+fn unset(doc: &mut Document, path: &[String]) -> bool {
+    let (last, init) = path.split_last().expect("update paths are never empty");
+    if init.is_empty() {
+        return doc.remove(last).is_some();
+    }
+    match get_mut(doc, init) {
+        Some(Bson::Document(doc)) => doc.remove(last).is_some(),
+        Some(Bson::Array(array)) => match parse_index(last).and_then(|i| array.get_mut(i)) {
+            Some(Bson::Null) | None => false,
+            Some(element) => {
+                *element = Bson::Null;
+                true
+            }
+        },
+        _ => false,
+    }
+}
+
+impl Operation {
+    fn error(&self, kind: ErrorKind, path: &[String]) -> Error {
+        Error::new(kind, Some(self.operator), Some(path.join(".")))
+    }
+
+    fn apply(&self, doc: &mut Document, modified: &mut Vec<KeyPath>) -> Result<()> {
+        for path in self.expand(doc)? {
+            let changed = self
+                .apply_at(doc, &path)
+                .map_err(|kind| self.error(kind, &path))?;
+            if changed {
+                if let Action::Rename(target) = &self.action {
+                    modified.push(KeyPath::from_segments(path));
+                    modified.push(KeyPath::from_segments(target.clone()));
+                } else {
+                    modified.push(KeyPath::from_segments(path));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The concrete paths this operation updates, with each `$[]` replaced by every index of the
+    /// array it applies to.
+    fn expand(&self, doc: &Document) -> Result<Vec<Vec<String>>> {
+        let mut paths = vec![Vec::new()];
+        for segment in &self.path {
+            if segment != "$[]" {
+                for path in &mut paths {
+                    path.push(segment.clone());
+                }
+                continue;
+            }
+
+            let mut expanded = Vec::new();
+            for prefix in paths {
+                let length = match get(doc, &prefix) {
+                    Some(Bson::Array(array)) => array.len(),
+                    Some(other) => {
+                        let message = format!(
+                            "cannot apply array updates to non-array element {}: {}",
+                            prefix.join("."),
+                            other
+                        );
+                        return Err(self.error(ErrorKind::PathNotViable { message }, &prefix));
+                    }
+                    None => {
+                        let message = format!(
+                            "the path '{}' must exist in the document in order to apply array \
+                             updates",
+                            prefix.join(".")
+                        );
+                        return Err(self.error(ErrorKind::PathNotViable { message }, &prefix));
+                    }
+                };
+                for i in 0..length {
+                    let mut path = prefix.clone();
+                    path.push(i.to_string());
+                    expanded.push(path);
+                }
+            }
+            paths = expanded;
+        }
+        Ok(paths)
+    }
+
+    /// Apply this operation at a concrete path, returning whether the document changed.
+    fn apply_at(
+        &self,
+        doc: &mut Document,
+        path: &[String],
+    ) -> std::result::Result<bool, ErrorKind> {
+        let current = get(doc, path);
+        let new = match &self.action {
+            Action::Set(value) => Some(value.clone()),
+            Action::Unset => return Ok(unset(doc, path)),
+            Action::Inc(operand) => Some(match current {
+                Some(current) => self.arithmetic(current, operand, Arithmetic::Add)?,
+                None => operand.clone(),
+            }),
+            Action::Mul(operand) => Some(match current {
+                Some(current) => self.arithmetic(current, operand, Arithmetic::Multiply)?,
+                None => zero_like(operand),
+            }),
+            Action::Min(value) => match current {
+                Some(current) if ordering::compare(value, current) != Ordering::Less => None,
+                _ => Some(value.clone()),
+            },
+            Action::Max(value) => match current {
+                Some(current) if ordering::compare(value, current) != Ordering::Greater => None,
+                _ => Some(value.clone()),
+            },
+            Action::Rename(target) => return rename(doc, path, target),
+            Action::Push(push) => {
+                let mut array = self.array(current, path)?;
+                push.apply(&mut array);
+                Some(Bson::Array(array))
+            }
+            Action::AddToSet(values) => {
+                let mut array = self.array(current, path)?;
+                for value in values {
+                    if !array.iter().any(|element| equal(element, value)) {
+                        array.push(value.clone());
+                    }
+                }
+                Some(Bson::Array(array))
+            }
+            Action::Pull(condition) => match current {
+                None => None,
+                Some(_) => {
+                    let mut array = self.array(current, path)?;
+                    array.retain(|element| !condition.matches(element));
+                    Some(Bson::Array(array))
+                }
+            },
+            Action::Pop { first } => match current {
+                None => None,
+                Some(_) => {
+                    let mut array = self.array(current, path)?;
+                    if !*first {
+                        array.pop();
+                    } else if !array.is_empty() {
+                        array.remove(0);
+                    }
+                    Some(Bson::Array(array))
+                }
+            },
+            Action::CurrentDate { timestamp: false } => Some(Bson::DateTime(DateTime::now())),
+            Action::CurrentDate { timestamp: true } => Some(Bson::Timestamp(Timestamp {
+                time: (DateTime::now().timestamp_millis() / 1000) as u32,
+                increment: 1,
+            })),
+        };
+
+        match new {
+            Some(new) if !matches!(current, Some(current) if identical(current, &new)) => {
+                set(doc, path, new)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// A copy of the array at the path, or an empty array if the path is missing.
+    fn array(
+        &self,
+        current: Option<&Bson>,
+        path: &[String],
+    ) -> std::result::Result<Array, ErrorKind> {
+        match current {
+            None => Ok(Array::new()),
+            Some(Bson::Array(array)) => Ok(array.clone()),
+            Some(other) => Err(ErrorKind::TypeMismatch {
+                message: format!(
+                    "cannot apply {} to the field '{}' of non-array type {}",
+                    self.operator,
+                    path.join("."),
+                    TypeName(other.tag())
+                ),
+            }),
+        }
+    }
+
+    fn arithmetic(
+        &self,
+        current: &Bson,
+        operand: &Bson,
+        arithmetic: Arithmetic,
+    ) -> std::result::Result<Bson, ErrorKind> {
+        let current_number = ordering::number(current).ok_or_else(|| ErrorKind::TypeMismatch {
+            message: format!(
+                "cannot apply {} to a value of non-numeric type {}",
+                self.operator,
+                TypeName(current.tag())
+            ),
+        })?;
+        let operand_number = ordering::number(operand).expect("operands are checked when parsed");
+
+        let result = match (current_number, operand_number) {
+            (Number::Decimal(a), b) | (b, Number::Decimal(a)) => {
+                let b = match b {
+                    Number::Integer(i) => Decimal128::from_i128_rounded(i, Default::default()),
+                    Number::Double(d) => Decimal128::from(d),
+                    Number::Decimal(d) => d,
+                };
+                match arithmetic {
+                    Arithmetic::Add => a.checked_add(b),
+                    Arithmetic::Multiply => a.checked_mul(b),
+                }
+                .map(Bson::Decimal128)
+            }
+            (Number::Double(a), b) | (b, Number::Double(a)) => {
+                let b = match b {
+                    Number::Integer(i) => i as f64,
+                    Number::Double(d) => d,
+                    Number::Decimal(_) => unreachable!("decimals are handled above"),
+                };
+                Some(Bson::Double(match arithmetic {
+                    Arithmetic::Add => a + b,
+                    Arithmetic::Multiply => a * b,
+                }))
+            }
+            (Number::Integer(a), Number::Integer(b)) => match arithmetic {
+                Arithmetic::Add => a.checked_add(b),
+                Arithmetic::Multiply => a.checked_mul(b),
+            }
+            .and_then(|result| integer_result(result, current, operand)),
+        };
+
+        result.ok_or_else(|| ErrorKind::Overflow {
+            message: format!(
+                "applying {} with {} to {} overflows",
+                self.operator, operand, current
+            ),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Arithmetic {
+    Add,
+    Multiply,
+}
+
+/// Store an integer result in the narrowest type at least as wide as both operands' types that
+/// holds it, only using unsigned types if an operand is unsigned.
+fn integer_result(result: i128, a: &Bson, b: &Bson) -> Option<Bson> {
+    let rank = |value: &Bson| match value {
+        Bson::Int32(_) => 0,
+        Bson::UInt32(_) => 1,
+        Bson::Int64(_) => 2,
+        _ => 3,
+    };
+    let unsigned = matches!(a, Bson::UInt32(_) | Bson::UInt64(_))
+        || matches!(b, Bson::UInt32(_) | Bson::UInt64(_));
+    let start = rank(a).max(rank(b));
+
+    let candidates: Vec<Option<Bson>> = vec![
+        i32::try_from(result).ok().map(Bson::Int32),
+        u32::try_from(result)
+            .ok()
+            .filter(|_| unsigned)
+            .map(Bson::UInt32),
+        i64::try_from(result).ok().map(Bson::Int64),
+        u64::try_from(result)
+            .ok()
+            .filter(|_| unsigned)
+            .map(Bson::UInt64),
+    ];
+    candidates.into_iter().skip(start).flatten().next()
+}
+
+fn zero_like(operand: &Bson) -> Bson {
+    match operand {
+        Bson::Int64(_) => Bson::Int64(0),
+        Bson::UInt32(_) => Bson::UInt32(0),
+        Bson::UInt64(_) => Bson::UInt64(0),
+        Bson::Double(_) => Bson::Double(0.0),
+        Bson::Decimal128(_) => Bson::Decimal128(Decimal128::from(0_i32)),
+        _ => Bson::Int32(0),
+    }
+}
+
+/// Move the value at `source` to `target`, neither of which may pass through an array.
+fn rename(
+    doc: &mut Document,
+    source: &[String],
+    target: &[String],
+) -> std::result::Result<bool, ErrorKind> {
+    for path in &[source, target] {
+        for end in 1..path.len() {
+            if let Some(Bson::Array(_)) = get(doc, &path[..end]) {
+                return Err(ErrorKind::PathNotViable {
+                    message: format!(
+                        "$rename cannot move the array element '{}'",
+                        path[..=end].join(".")
+                    ),
+                });
+            }
+        }
+    }
+
+    let value = match get(doc, source) {
+        Some(value) => value.clone(),
+        None => return Ok(false),
+    };
+    unset(doc, source);
+    set(doc, target, value)?;
+    Ok(true)
+}
+
+impl Push {
+    fn apply(&self, array: &mut Array) {
+        let index = match self.position {
+            None => array.len(),
+            Some(position) if position < 0 => array.len().saturating_sub(magnitude(position)),
+            Some(position) => array.len().min(magnitude(position)),
+        };
+        array.splice(index..index, self.each.iter().cloned());
+
+        match &self.sort {
+            Some(PushSort::Values(descending)) => array.sort_by(|a, b| {
+                let ordering = ordering::compare(a, b);
+                if *descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }),
+            Some(PushSort::Fields(fields)) => array.sort_by(|a, b| {
+                let field = |value: &'_ Bson, path: &[String]| match value {
+                    Bson::Document(doc) => get(doc, path).cloned().unwrap_or(Bson::Null),
+                    _ => Bson::Null,
+                };
+                fields
+                    .iter()
+                    .map(|(path, descending)| {
+                        let ordering = ordering::compare(&field(a, path), &field(b, path));
+                        if *descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    })
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            }),
+            None => {}
+        }
+
+        match self.slice {
+            Some(slice) if slice >= 0 => array.truncate(magnitude(slice)),
+            Some(slice) => {
+                let keep = array.len().min(magnitude(slice));
+                array.drain(..array.len() - keep);
+            }
+            None => {}
+        }
+    }
+}
+
+/// The absolute value of `n` as a count of elements, saturating where it does not fit a `usize`.
+fn magnitude(n: i64) -> usize {
+    usize::try_from(i128::from(n).abs()).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ErrorKind, Update};
+    use crate::{Bson, Decimal128, Document};
+
+    fn apply(doc: &mut Document, update: Document) -> Vec<String> {
+        Update::parse(&update)
+            .unwrap()
+            .apply(doc)
+            .unwrap()
+            .modified_paths()
+            .iter()
+            .map(|path| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn field_operators() {
+        let mut doc = doc! {
+            "_id": 1,
+            "i": 1,
+            "l": i64::MAX - 1,
+            "d": 1.5,
+            "max": i32::MAX,
+            "u": Bson::UInt32(1),
+            "m": 5,
+            "old": { "x": 1 },
+            "same": "a",
+        };
+        let modified = apply(
+            &mut doc,
+            doc! {
+                "$inc": { "i": 2.5, "l": 1, "max": 1, "u": -2, "new": 3_i64 },
+                "$mul": { "d": 2, "missing": Decimal128::from(3_i32) },
+                "$min": { "m": 2.0 },
+                "$max": { "same": "a" },
+                "$rename": { "old.x": "renamed" },
+                "$set": { "z.1.y": true },
+                "$unset": { "gone": "" },
+                "$currentDate": { "date": true, "ts": { "$type": "timestamp" } },
+            },
+        );
+
+        assert_eq!(
+            modified,
+            vec![
+                "d", "date", "i", "l", "m", "max", "missing", "new", "old.x", "renamed", "ts", "u",
+                "z.1.y",
+            ]
+        );
+        assert_eq!(doc.get("i"), Some(&Bson::Double(3.5)));
+        assert_eq!(doc.get("l"), Some(&Bson::Int64(i64::MAX)));
+        assert_eq!(doc.get("max"), Some(&Bson::Int64(i32::MAX as i64 + 1)));
+        assert_eq!(doc.get("u"), Some(&Bson::Int64(-1)));
+        assert_eq!(doc.get("d"), Some(&Bson::Double(3.0)));
+        assert_eq!(doc.get("m"), Some(&Bson::Double(2.0)));
+        assert_eq!(
+            doc.get("missing"),
+            Some(&Bson::Decimal128(Decimal128::from(0_i32)))
+        );
+        assert_eq!(doc.get("old"), Some(&Bson::Document(doc! {})));
+        assert_eq!(doc.get("renamed"), Some(&Bson::Int32(1)));
+        assert_eq!(
+            doc.get("z"),
+            Some(&Bson::Document(doc! { "1": { "y": true } }))
+        );
+        assert!(matches!(doc.get("date"), Some(Bson::DateTime(_))));
+        assert!(matches!(doc.get("ts"), Some(Bson::Timestamp(_))));
+
+        let keys: Vec<&str> = doc.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "_id", "i", "l", "d", "max", "u", "m", "old", "same", "date", "missing", "new",
+                "renamed", "ts", "z",
+            ]
+        );
+
+        assert!(apply(
+            &mut doc,
+            doc! { "$inc": { "i": 0 }, "$set": { "same": "a" } }
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn array_operators() {
+        let mut doc = doc! {
+            "a": [1, 2, 3, 2],
+            "b": [{ "n": 3, "k": "x" }, { "n": 1, "k": "y" }],
+            "c": [1, 2],
+            "d": [[1, 2], [3]],
+            "e": [1, 2, 3],
+            "f": [1, 5, 8, 10],
+        };
+        let modified = apply(
+            &mut doc,
+            doc! {
+                "$pull": {
+                    "a": 2.0,
+                    "b": { "k": "y", "n": { "$lt": 2 } },
+                    "f": { "$gte": 5, "$lt": 10 },
+                },
+                "$addToSet": { "c": { "$each": [2_i64, 3, 3] }, "new": 1 },
+                "$push": {
+                    "d.$[]": { "$each": [0], "$position": 0, "$sort": -1 },
+                    "s": { "$each": [{ "n": 2 }, { "n": 1 }], "$sort": { "n": 1 }, "$slice": -1 },
+                },
+                "$pop": { "e": -1, "missing": 1 },
+            },
+        );
+
+        assert_eq!(modified, vec!["a", "b", "c", "d.0", "d.1", "e", "f", "new", "s"]);
+        assert_eq!(
+            doc,
+            doc! {
+                "a": [1, 3],
+                "b": [{ "n": 3, "k": "x" }],
+                "c": [1, 2, 3],
+                "d": [[2, 1, 0], [3, 0]],
+                "e": [2, 3],
+                "f": [1, 10],
+                "new": [1],
+                "s": [{ "n": 2 }],
+            }
+        );
+
+        let mut doc = doc! { "a": [{ "x": 1 }, { "x": 2 }], "b": [5, 5] };
+        let modified = apply(
+            &mut doc,
+            doc! { "$inc": { "a.$[].x": 10 }, "$unset": { "b.1": 1 }, "$set": { "b.3": 7 } },
+        );
+        assert_eq!(modified, vec!["a.0.x", "a.1.x", "b.1", "b.3"]);
+        assert_eq!(
+            doc,
+            doc! { "a": [{ "x": 11 }, { "x": 12 }], "b": [5, Bson::Null, Bson::Null, 7] }
+        );
+
+        let mut doc = doc! { "a": [1, 2], "b": [1, 2], "c": [1, 2] };
+        apply(
+            &mut doc,
+            doc! {
+                "$push": {
+                    "a": { "$each": [0], "$position": i64::MIN, "$slice": i64::MIN },
+                    "b": { "$each": [3], "$position": i64::MAX, "$slice": i64::MAX },
+                    "c": { "$each": [0], "$position": -1, "$slice": -2 },
+                },
+            },
+        );
+        assert_eq!(doc, doc! { "a": [0, 1, 2], "b": [1, 2, 3], "c": [0, 2] });
+    }
+
+    #[test]
+    fn errors() {
+        let parse_error = |update: Document| Update::parse(&update).unwrap_err();
+
+        let err = parse_error(doc! { "$foo": { "a": 1 } });
+        assert_eq!(err.operator(), Some("$foo"));
+        assert!(matches!(err.kind, ErrorKind::InvalidUpdate { .. }));
+        let err = parse_error(doc! { "$set": { "a.b": 1 }, "$inc": { "a": 1 } });
+        assert_eq!(
+            err.to_string(),
+            "update failed in $inc at \"a\": updating the path 'a' would create a conflict at 'a'"
+        );
+        assert!(matches!(
+            parse_error(doc! { "$set": { "a.$[].b": 1, "a.0": 1 } }).kind,
+            ErrorKind::InvalidUpdate { .. }
+        ));
+        assert!(matches!(
+            parse_error(doc! { "$inc": { "a": "1" } }).kind,
+            ErrorKind::InvalidUpdate { .. }
+        ));
+        assert_eq!(
+            parse_error(doc! { "$set": { "a.$.b": 1 } }).path(),
+            Some("a.$.b")
+        );
+        assert!(matches!(
+            parse_error(doc! { "$pop": { "a": 2 } }).kind,
+            ErrorKind::InvalidUpdate { .. }
+        ));
+
+        let original = doc! { "_id": 1, "s": "x", "a": [1], "n": i64::MAX, "u": Bson::UInt64(1) };
+        let apply_error = |update: Document| {
+            let mut doc = original.clone();
+            let err = Update::parse(&update).unwrap().apply(&mut doc).unwrap_err();
+            assert_eq!(doc, original);
+            err
+        };
+
+        let err = apply_error(doc! { "$set": { "z": 1, "s.x": 1 } });
+        assert_eq!(err.path(), Some("s.x"));
+        assert!(matches!(err.kind, ErrorKind::PathNotViable { .. }));
+        assert!(matches!(
+            apply_error(doc! { "$set": { "a.x": 1 } }).kind,
+            ErrorKind::PathNotViable { .. }
+        ));
+        assert!(matches!(
+            apply_error(doc! { "$set": { "s.$[]": 1 } }).kind,
+            ErrorKind::PathNotViable { .. }
+        ));
+        assert!(matches!(
+            apply_error(doc! { "$mul": { "s": 2 } }).kind,
+            ErrorKind::TypeMismatch { .. }
+        ));
+        assert!(matches!(
+            apply_error(doc! { "$push": { "s": 2 } }).kind,
+            ErrorKind::TypeMismatch { .. }
+        ));
+        assert!(matches!(
+            apply_error(doc! { "$inc": { "n": 1 } }).kind,
+            ErrorKind::Overflow { .. }
+        ));
+        assert!(matches!(
+            apply_error(doc! { "$inc": { "u": -2 } }).kind,
+            ErrorKind::Overflow { .. }
+        ));
+        assert_eq!(
+            apply_error(doc! { "$set": { "_id": 2 } }).kind,
+            ErrorKind::ImmutableField
+        );
+        assert!(matches!(
+            apply_error(doc! { "$rename": { "a.0": "b" } }).kind,
+            ErrorKind::PathNotViable { .. }
+        ));
+        let err = apply_error(doc! { "$set": { "a.100000000": 1 } });
+        assert_eq!(
+            err.to_string(),
+            "update failed in $set at \"a.100000000\": index would pad array of length 1 with more than 1500000 nulls"
+        );
+        assert!(matches!(
+            apply_error(doc! { "$set": { "a.1500002.b": 1 } }).kind,
+            ErrorKind::BackfillLimit { .. }
+        ));
+    }
+}